@@ -7,6 +7,9 @@ use std::{
 };
 
 use coinshift::{
+    chain_registry::{self, ChainRegistry},
+    l1_backend::snapshot::L1Snapshot,
+    l1_config::L1Config,
     miner::{self, Miner},
     node::{self, Node},
     types::{
@@ -33,6 +36,8 @@ use crate::cli::Config;
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
+    #[error("chain registry error: {0}")]
+    ChainRegistry(#[from] coinshift::chain_registry::Error),
     #[error("CUSF mainchain proto error")]
     CusfMainchain(#[from] coinshift::types::proto::Error),
     #[error("io error")]
@@ -374,209 +379,400 @@ impl App {
         )
     }
 
-    /// Periodic task to check and update swap confirmations dynamically
-    /// This works in both GUI and headless mode
-    async fn swap_confirmation_check_task(
-        node: Arc<Node>,
-    ) -> Result<(), Error> {
-        use coinshift::parent_chain_rpc::{ParentChainRpcClient, RpcConfig};
-        use coinshift::types::{ParentChainType, SwapState, SwapTxId};
-        use serde::{Deserialize, Serialize};
-        use std::collections::HashMap;
-        use std::path::PathBuf;
-        use std::time::Duration;
-
-        const CHECK_INTERVAL: Duration = Duration::from_secs(10);
-
-        tracing::info!(
-            "Swap confirmation check task started, will check every {} seconds",
-            CHECK_INTERVAL.as_secs()
+    /// Update confirmations of swaps waiting for L1 confirmations, and apply
+    /// L1 fills seen via ZMQ once they confirm. Only swaps whose parent
+    /// chain satisfies `check_parent_chain` are checked.
+    async fn check_swap_confirmations(
+        node: &Node,
+        check_parent_chain: impl Fn(types::ParentChainType) -> bool,
+    ) {
+        use coinshift::types::{SwapState, SwapTxId};
+
+        tracing::trace!(
+            "Swap confirmation check task: checking for swap confirmations"
         );
 
-        // Helper to load RPC config (same as in GUI)
-        fn load_rpc_config(parent_chain: ParentChainType) -> Option<RpcConfig> {
-            #[derive(Clone, Serialize, Deserialize)]
-            struct LocalRpcConfig {
-                url: String,
-                user: String,
-                password: String,
-            }
-
-            let config_path = dirs::data_dir()
-                .unwrap_or_else(|| PathBuf::from("."))
-                .join("coinshift")
-                .join("l1_rpc_configs.json");
-
-            if let Ok(file_content) = std::fs::read_to_string(&config_path)
-                && let Ok(configs) = serde_json::from_str::<
-                    HashMap<ParentChainType, LocalRpcConfig>,
-                >(&file_content)
-                && let Some(local_config) = configs.get(&parent_chain)
-            {
-                return Some(RpcConfig {
-                    url: local_config.url.clone(),
-                    user: local_config.user.clone(),
-                    password: local_config.password.clone(),
-                });
-            }
-            None
+        // Relay new parent chain headers, so that the next block can commit
+        // them and confirmations can advance
+        if let Err(err) = node.relay_l1_headers().await {
+            tracing::debug!("Failed to relay L1 headers: {err:#}");
         }
 
-        loop {
-            tokio::time::sleep(CHECK_INTERVAL).await;
-            tracing::trace!(
-                "Swap confirmation check task: checking for swap confirmations"
-            );
-
-            // Get swaps from database
+        // Get swaps from database
+        let swaps = {
             let rotxn = match node.env().read_txn() {
                 Ok(txn) => txn,
                 Err(err) => {
                     tracing::debug!("Failed to get read transaction: {err:#}");
-                    continue;
+                    return;
                 }
             };
-
-            let swaps = match node.state().load_all_swaps(&rotxn) {
+            match node.state().load_all_swaps(&rotxn) {
                 Ok(swaps) => swaps,
                 Err(err) => {
                     tracing::debug!("Failed to load swaps: {err:#}");
-                    continue;
+                    return;
                 }
-            };
-
-            // Filter swaps that are waiting for confirmations and have an L1 txid
-            let swaps_to_check: Vec<_> = swaps
-                .iter()
-                .filter(|swap| {
-                    matches!(swap.state, SwapState::WaitingConfirmations(..))
-                        && !matches!(swap.l1_txid, SwapTxId::Hash32(h) if h == [0u8; 32])
-                        && !matches!(swap.l1_txid, SwapTxId::Hash(ref v) if v.is_empty() || v.iter().all(|&b| b == 0))
-                })
-                .collect();
-
-            drop(rotxn);
+            }
+        };
 
-            if swaps_to_check.is_empty() {
-                continue;
+        // Filter swaps that are waiting for confirmations and have an L1 txid.
+        // Fills of partially fillable swaps are updated while connecting 2WPD.
+        let swaps_to_check: Vec<_> = swaps
+            .iter()
+            .filter(|swap| {
+                check_parent_chain(swap.parent_chain)
+                    && matches!(swap.state, SwapState::WaitingConfirmations(..))
+                    && !swap.allows_partial_fills()
+                    && !matches!(swap.l1_txid, SwapTxId::Hash32(h) if h == [0u8; 32])
+                    && !matches!(swap.l1_txid, SwapTxId::Hash(ref v) if v.is_empty() || v.iter().all(|&b| b == 0))
+            })
+            .collect();
+
+        // L1 fills seen via ZMQ, for swaps that are still pending
+        let mut fills_to_check = Vec::new();
+        for (swap_id, l1_txid_hex) in node.l1_fills() {
+            match swaps.iter().find(|swap| swap.id == swap_id) {
+                Some(swap) if swap.state == SwapState::Pending => {
+                    if check_parent_chain(swap.parent_chain) {
+                        fills_to_check.push((swap, l1_txid_hex));
+                    }
+                }
+                _ => node.remove_l1_fill(&swap_id),
             }
+        }
 
-            tracing::debug!(
-                swap_count = swaps_to_check.len(),
-                "Checking confirmations for {} swaps",
-                swaps_to_check.len()
-            );
+        if swaps_to_check.is_empty() && fills_to_check.is_empty() {
+            return;
+        }
 
-            let mut updated_count = 0;
-            let mut rwtxn = match node.env().write_txn() {
-                Ok(txn) => txn,
+        tracing::debug!(
+            swap_count = swaps_to_check.len(),
+            fill_count = fills_to_check.len(),
+            "Checking confirmations for {} swaps",
+            swaps_to_check.len() + fills_to_check.len()
+        );
+
+        // Fetch the L1 transactions of each parent chain before taking the
+        // write lock
+        let mut txids_by_chain =
+            HashMap::<types::ParentChainType, Vec<String>>::new();
+        for swap in &swaps_to_check {
+            txids_by_chain
+                .entry(swap.parent_chain)
+                .or_default()
+                .push(swap.l1_txid.to_hex());
+        }
+        for (swap, l1_txid_hex) in &fills_to_check {
+            txids_by_chain
+                .entry(swap.parent_chain)
+                .or_default()
+                .push(l1_txid_hex.clone());
+        }
+        let mut l1_snapshots = HashMap::new();
+        for (parent_chain, txids) in txids_by_chain {
+            let Some(backend) = node.l1_config().backend(parent_chain) else {
+                continue;
+            };
+            let mut snapshot = L1Snapshot::new(backend.as_ref(), Vec::new());
+            match snapshot.fetch_transactions(backend.as_ref(), &txids).await {
+                Ok(()) => {
+                    l1_snapshots.insert(parent_chain, snapshot);
+                }
                 Err(err) => {
-                    tracing::debug!("Failed to get write transaction: {err:#}");
-                    continue;
+                    tracing::debug!(
+                        ?parent_chain,
+                        error = %err,
+                        "Failed to fetch confirmations from RPC (this is normal if RPC is unavailable)"
+                    );
                 }
-            };
+            }
+        }
 
-            for swap in swaps_to_check {
-                // Get RPC config for this swap's parent chain
-                if let Some(rpc_config) = load_rpc_config(swap.parent_chain) {
-                    // L1 txid in canonical order for parent chain getrawtransaction
-                    let l1_txid_hex = swap.l1_txid.to_hex();
-
-                    // Fetch current confirmations from RPC
-                    let client = ParentChainRpcClient::new(rpc_config);
-                    match client.get_transaction_confirmations(&l1_txid_hex) {
-                        Ok(new_confirmations) => {
-                            // Get current confirmations from swap state
-                            let current_confirmations = match swap.state {
-                                SwapState::WaitingConfirmations(current, _) => {
-                                    current
-                                }
-                                _ => 0,
-                            };
-
-                            // Only update if confirmations have increased
-                            if new_confirmations > current_confirmations {
-                                tracing::info!(
-                                    swap_id = %swap.id,
-                                    old_confirmations = %current_confirmations,
-                                    new_confirmations = %new_confirmations,
-                                    required = %swap.required_confirmations,
-                                    "Updating swap confirmations dynamically (headless mode)"
-                                );
+        let mut updated_count = 0;
+        let mut rwtxn = match node.env().write_txn() {
+            Ok(txn) => txn,
+            Err(err) => {
+                tracing::debug!("Failed to get write transaction: {err:#}");
+                return;
+            }
+        };
 
-                                // Get current block info for reference
-                                let block_hash = match node
-                                    .state()
-                                    .try_get_tip(&rwtxn)
-                                {
-                                    Ok(Some(hash)) => hash,
-                                    Ok(None) | Err(_) => {
-                                        tracing::warn!(
-                                            "Could not get block hash for swap update"
-                                        );
-                                        continue;
-                                    }
-                                };
-                                let block_height = match node
-                                    .state()
-                                    .try_get_height(&rwtxn)
-                                {
-                                    Ok(Some(height)) => height,
-                                    Ok(None) | Err(_) => {
-                                        tracing::warn!(
-                                            "Could not get block height for swap update"
-                                        );
-                                        continue;
-                                    }
-                                };
-
-                                // Update swap with new confirmations
-                                if let Err(err) =
-                                    node.state().update_swap_l1_txid(
-                                        &mut rwtxn,
-                                        &swap.id,
-                                        swap.l1_txid.clone(),
-                                        new_confirmations,
-                                        None, // l1_claimer_address - not needed for confirmation updates
-                                        None, // l2_claimer_address - not changed on confirmation update
-                                        block_hash,
-                                        block_height,
-                                    )
-                                {
-                                    tracing::error!(
-                                        swap_id = %swap.id,
-                                        error = %err,
-                                        "Failed to update swap confirmations"
-                                    );
-                                } else {
-                                    updated_count += 1;
-                                }
+        // Get current block info for reference
+        let (block_hash, block_height) = match (
+            node.state().try_get_tip(&rwtxn),
+            node.state().try_get_height(&rwtxn),
+        ) {
+            (Ok(Some(hash)), Ok(Some(height))) => (hash, height),
+            _ => {
+                tracing::warn!(
+                    "Could not get block hash or height for swap update"
+                );
+                return;
+            }
+        };
+
+        for swap in swaps_to_check {
+            // L1 data fetched for this swap's parent chain
+            if let Some(snapshot) = l1_snapshots.get(&swap.parent_chain) {
+                // L1 txid in canonical order for parent chain getrawtransaction
+                let l1_txid_hex = swap.l1_txid.to_hex();
+
+                // Count confirmations against the consensus L1 header chain
+                match node.l1_headers().tx_confirmations(
+                    &rwtxn,
+                    swap.parent_chain,
+                    snapshot,
+                    &l1_txid_hex,
+                ) {
+                    Ok(new_confirmations) => {
+                        // Get current confirmations from swap state
+                        let current_confirmations = match swap.state {
+                            SwapState::WaitingConfirmations(current, _) => {
+                                current
                             }
-                        }
-                        Err(err) => {
-                            tracing::debug!(
+                            _ => 0,
+                        };
+
+                        // Only update if confirmations have increased
+                        if new_confirmations > current_confirmations {
+                            tracing::info!(
                                 swap_id = %swap.id,
-                                l1_txid = %l1_txid_hex,
-                                error = %err,
-                                "Failed to fetch confirmations from RPC (this is normal if RPC is unavailable)"
+                                old_confirmations = %current_confirmations,
+                                new_confirmations = %new_confirmations,
+                                required = %swap.required_confirmations,
+                                "Updating swap confirmations dynamically (headless mode)"
                             );
+
+                            // Update swap with new confirmations
+                            if let Err(err) = node.state().update_swap_l1_txid(
+                                &mut rwtxn,
+                                &swap.id,
+                                swap.l1_txid.clone(),
+                                new_confirmations,
+                                None, // l1_claimer_address - not needed for confirmation updates
+                                None, // l2_claimer_address - not changed on confirmation update
+                                block_hash,
+                                block_height,
+                            ) {
+                                tracing::error!(
+                                    swap_id = %swap.id,
+                                    error = %err,
+                                    "Failed to update swap confirmations"
+                                );
+                            } else {
+                                updated_count += 1;
+                            }
                         }
                     }
+                    Err(err) => {
+                        tracing::debug!(
+                            swap_id = %swap.id,
+                            l1_txid = %l1_txid_hex,
+                            error = %err,
+                            "Failed to fetch confirmations from RPC (this is normal if RPC is unavailable)"
+                        );
+                    }
                 }
             }
+        }
 
-            if updated_count > 0 {
-                if let Err(err) = rwtxn.commit() {
-                    tracing::error!("Failed to commit swap updates: {err:#}");
-                } else {
+        for (swap, l1_txid_hex) in fills_to_check {
+            let Some(snapshot) = l1_snapshots.get(&swap.parent_chain) else {
+                continue;
+            };
+            let confirmations = match node.l1_headers().tx_confirmations(
+                &rwtxn,
+                swap.parent_chain,
+                snapshot,
+                &l1_txid_hex,
+            ) {
+                Ok(confirmations) => confirmations,
+                Err(err) => {
+                    tracing::debug!(
+                        swap_id = %swap.id,
+                        l1_txid = %l1_txid_hex,
+                        error = %err,
+                        "Failed to fetch confirmations for L1 fill seen via ZMQ"
+                    );
+                    continue;
+                }
+            };
+            // Still in the L1 mempool
+            if confirmations == 0 {
+                continue;
+            }
+            let l1_txid = match SwapTxId::from_hex_rpc(&l1_txid_hex) {
+                Ok(l1_txid) => l1_txid,
+                Err(err) => {
+                    tracing::warn!(
+                        swap_id = %swap.id,
+                        l1_txid = %l1_txid_hex,
+                        error = %err,
+                        "Invalid L1 txid for fill seen via ZMQ"
+                    );
+                    node.remove_l1_fill(&swap.id);
+                    continue;
+                }
+            };
+            // The fill is applied, or rejected (e.g. the L1 tx is already
+            // used by another swap), either way it is not checked again
+            node.remove_l1_fill(&swap.id);
+            match node.state().update_swap_l1_txid(
+                &mut rwtxn,
+                &swap.id,
+                l1_txid,
+                confirmations,
+                None,
+                None,
+                block_hash,
+                block_height,
+            ) {
+                Ok(()) => {
                     tracing::info!(
-                        updated_swaps = updated_count,
-                        "Dynamically updated confirmations for {} swaps (headless mode)",
-                        updated_count
+                        swap_id = %swap.id,
+                        l1_txid = %l1_txid_hex,
+                        confirmations = %confirmations,
+                        "Applied L1 fill seen via ZMQ"
                     );
+                    updated_count += 1;
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        swap_id = %swap.id,
+                        l1_txid = %l1_txid_hex,
+                        error = %err,
+                        "Rejected L1 fill seen via ZMQ"
+                    );
+                }
+            }
+        }
+
+        if let Err(err) = rwtxn.commit() {
+            tracing::error!("Failed to commit swap updates: {err:#}");
+        } else if updated_count > 0 {
+            tracing::info!(
+                updated_swaps = updated_count,
+                "Dynamically updated confirmations for {} swaps (headless mode)",
+                updated_count
+            );
+        }
+    }
+
+    /// Rebuild the indexes of pending swap scripts that ZMQ notifications
+    /// are matched against
+    fn update_swap_script_indexes(
+        node: &Node,
+        indexes: &HashMap<
+            types::ParentChainType,
+            Arc<RwLock<coinshift::l1_backend::zmq::SwapScriptIndex>>,
+        >,
+    ) {
+        use coinshift::l1_backend::zmq::SwapScriptIndex;
+
+        let swaps_and_height = node.env().read_txn().ok().and_then(|rotxn| {
+            let swaps = node.state().load_all_swaps(&rotxn).ok()?;
+            let height = node.state().try_get_height(&rotxn).ok()?;
+            Some((swaps, height.unwrap_or(0)))
+        });
+        let Some((swaps, height)) = swaps_and_height else {
+            tracing::debug!("Failed to load swaps for ZMQ swap script index");
+            return;
+        };
+        for (parent_chain, index) in indexes {
+            *index.write() =
+                SwapScriptIndex::new(*parent_chain, &swaps, height);
+        }
+    }
+
+    /// Task to check and update swap confirmations dynamically.
+    /// This works in both GUI and headless mode.
+    ///
+    /// Parent chains with ZMQ endpoints in the L1 config file are checked
+    /// when the node publishes a new block, and L1 transactions paying
+    /// pending swaps are picked up from the relayed transactions. Other
+    /// parent chains are polled.
+    async fn swap_confirmation_check_task(
+        node: Arc<Node>,
+    ) -> Result<(), Error> {
+        use coinshift::{
+            l1_backend::zmq::{self, L1Event, SwapScriptIndex, ZmqSubscriber},
+            types::ParentChainType,
+        };
+        use std::collections::HashSet;
+        use std::time::Duration;
+
+        const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+        // Subscribe to the ZMQ endpoints of each parent chain. ZMQ endpoints
+        // are read at startup; other L1 config changes apply on the next
+        // check.
+        let (events_tx, mut events_rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut script_indexes = HashMap::new();
+        let mut block_notified_chains = HashSet::new();
+        for parent_chain in ParentChainType::all() {
+            let Some(entry) = node.l1_config().entry(*parent_chain) else {
+                continue;
+            };
+            let subscriptions = zmq::subscriptions(&entry);
+            if subscriptions.is_empty() {
+                continue;
+            }
+            let index = Arc::new(RwLock::new(SwapScriptIndex::default()));
+            for (endpoint, topics) in subscriptions {
+                if topics.contains(&zmq::HASHBLOCK_TOPIC) {
+                    block_notified_chains.insert(*parent_chain);
+                }
+                let subscriber = ZmqSubscriber::new(
+                    *parent_chain,
+                    endpoint,
+                    topics,
+                    index.clone(),
+                    events_tx.clone(),
+                );
+                let _task: JoinHandle<()> = spawn(subscriber.run());
+            }
+            script_indexes.insert(*parent_chain, index);
+        }
+        drop(events_tx);
+        Self::update_swap_script_indexes(&node, &script_indexes);
+
+        tracing::info!(
+            zmq_parent_chains = ?block_notified_chains,
+            "Swap confirmation check task started, will poll parent chains without ZMQ block notifications every {} seconds",
+            CHECK_INTERVAL.as_secs()
+        );
+
+        let mut state_changes = node.watch_state().boxed();
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    if block_notified_chains.len() < ParentChainType::all().len() {
+                        let () = Self::check_swap_confirmations(
+                            &node,
+                            |parent_chain| {
+                                !block_notified_chains.contains(&parent_chain)
+                            },
+                        )
+                        .await;
+                    }
+                }
+                Some(event) = events_rx.recv() => match event {
+                    L1Event::Fill { swap_id, txid, .. } => {
+                        node.notify_l1_fill(swap_id, txid.to_string());
+                    }
+                    L1Event::Block { parent_chain: block_chain, .. } => {
+                        let () = Self::check_swap_confirmations(
+                            &node,
+                            |parent_chain| parent_chain == block_chain,
+                        )
+                        .await;
+                    }
+                },
+                Some(()) = state_changes.next() => {
+                    Self::update_swap_script_indexes(&node, &script_indexes);
                 }
-            } else {
-                drop(rwtxn);
             }
         }
     }
@@ -667,14 +863,18 @@ impl App {
             config.datadir.display()
         );
 
+        // Register the parent chains of the chain registry file before any
+        // parent chain is looked up
+        let registry = ChainRegistry::load(&config.datadir)?;
+        tracing::info!(
+            hash = %hex::encode(registry.hash()),
+            "Peers must have the same chain registry"
+        );
+        let () = chain_registry::init(registry)?;
+
         // Validate L1 config file before start: test all configured networks
-        let l1_rpc_config_path = dirs::data_dir()
-            .unwrap_or_else(|| std::path::PathBuf::from("."))
-            .join("coinshift")
-            .join("l1_rpc_configs.json");
-        coinshift::parent_chain_rpc::validate_l1_config_file(
-            &l1_rpc_config_path,
-        )?;
+        let l1_config = L1Config::new(&config.datadir);
+        let () = runtime.block_on(l1_config.validate())?;
 
         let wallet = Wallet::new(&config.datadir.join("wallet.mdb"))?;
         if let Some(seed_phrase_path) = &config.mnemonic_seed_phrase_path {
@@ -733,7 +933,7 @@ impl App {
             cusf_mainchain_wallet: cusf_mainchain_wallet.clone(),
             network: config.network,
             wallet: Some(Arc::new(wallet.clone())),
-            l1_rpc_config_path: Some(l1_rpc_config_path),
+            l1_config,
         };
         let node = Node::new(node_config, &runtime)?;
         let node_elapsed = node_start.elapsed();
@@ -1025,6 +1225,8 @@ impl App {
         };
         let (bribe, header, body) = if prev_side_hash == tip_hash {
             const NUM_TRANSACTIONS: usize = 1000;
+            // Include new parent chain headers in the block
+            let () = self.node.relay_l1_headers().await?;
             let (txs, tx_fees) =
                 self.node.get_transactions(NUM_TRANSACTIONS)?;
             let coinbase = match tx_fees {
@@ -1,22 +1,11 @@
-use std::{
-    collections::HashMap,
-    path::PathBuf,
-    sync::{Arc, Mutex},
-};
+use std::sync::{Arc, Mutex};
 
-use coinshift::parent_chain_rpc;
+use coinshift::l1_backend::{self, L1BackendKind};
+use coinshift::l1_config::{self, L1ConfigEntries};
+use coinshift::parent_chain_rpc::{self, L1Endpoint};
 use coinshift::types::ParentChainType;
 use eframe::egui::{self, Button, Color32, ComboBox, RichText, TextEdit};
 use poll_promise::Promise;
-use serde::{Deserialize, Serialize};
-use serde_json::json;
-
-#[derive(Clone, Default, Deserialize, Serialize)]
-struct RpcConfig {
-    url: String,
-    user: String,
-    password: String,
-}
 
 #[derive(Clone)]
 enum ConnectionStatus {
@@ -27,79 +16,47 @@ enum ConnectionStatus {
 }
 
 pub struct L1Config {
+    l1_config: l1_config::L1Config,
     selected_parent_chain: ParentChainType,
     rpc_url: String,
     rpc_user: String,
     rpc_password: String,
-    configs: HashMap<ParentChainType, RpcConfig>,
+    configs: L1ConfigEntries,
     connection_status: Arc<Mutex<ConnectionStatus>>,
     status_promise: Option<Promise<anyhow::Result<u64>>>,
 }
 
-impl Default for L1Config {
-    fn default() -> Self {
+impl L1Config {
+    pub fn new(l1_config: l1_config::L1Config) -> Self {
         let supported = parent_chain_rpc::supported_l1_parent_chain_types();
         let first = supported
             .first()
             .copied()
             .unwrap_or(ParentChainType::Signet);
-        Self {
+        let mut config = Self {
+            configs: l1_config.entries(),
+            l1_config,
             selected_parent_chain: first,
             rpc_url: String::new(),
             rpc_user: String::new(),
             rpc_password: String::new(),
-            configs: HashMap::new(),
             connection_status: Arc::new(Mutex::new(ConnectionStatus::Unknown)),
             status_promise: None,
-        }
-    }
-}
-
-impl L1Config {
-    pub fn new(ctx: &egui::Context) -> Self {
-        let mut config = Self::default();
-        config.load(ctx);
+        };
+        config.load_saved_for_selected();
         config
     }
 
-    fn config_file_path() -> PathBuf {
-        dirs::data_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join("coinshift")
-            .join("l1_rpc_configs.json")
-    }
-
-    fn load(&mut self, _ctx: &egui::Context) {
-        let config_path = Self::config_file_path();
-        if let Ok(file_content) = std::fs::read_to_string(&config_path)
-            && let Ok(stored_configs) = serde_json::from_str::<
-                HashMap<ParentChainType, RpcConfig>,
-            >(&file_content)
+    /// Fill URL/user/password from the saved config for the selected chain.
+    fn load_saved_for_selected(&mut self) {
+        if let Some(config) = self
+            .configs
+            .get(&self.selected_parent_chain)
+            .map(|entry| &entry.endpoint)
         {
-            self.configs = stored_configs;
-            if let Some(config) = self.configs.get(&self.selected_parent_chain)
-            {
-                self.rpc_url = config.url.clone();
-                self.rpc_user = config.user.clone();
-                self.rpc_password = config.password.clone();
-            } else {
-                self.load_predefined_for_selected();
-            }
-        } else {
-            self.load_predefined_for_selected();
-        }
-    }
-
-    /// Fill URL/user/password from the predefined config for the selected chain.
-    fn load_predefined_for_selected(&mut self) {
-        let predefined = parent_chain_rpc::supported_l1_configs();
-        if let Some((_, rpc)) = predefined
-            .into_iter()
-            .find(|(c, _)| *c == self.selected_parent_chain)
-        {
-            self.rpc_url = rpc.url;
-            self.rpc_user = rpc.user;
-            self.rpc_password = rpc.password;
+            self.rpc_url = config.url.clone();
+            self.rpc_user = config.user.clone();
+            self.rpc_password = config.password.clone();
         } else {
             self.rpc_url.clear();
             self.rpc_user.clear();
@@ -107,13 +64,27 @@ impl L1Config {
         }
     }
 
-    fn save(&mut self, _ctx: &egui::Context) {
-        // Save the user's current input fields for the selected chain
-        let config = RpcConfig {
+    /// Endpoint from the input fields. Cookie file, password source and
+    /// TLS settings are kept from the saved endpoint.
+    fn current_endpoint(&self) -> L1Endpoint {
+        let saved = self
+            .configs
+            .get(&self.selected_parent_chain)
+            .map(|entry| entry.endpoint.clone())
+            .unwrap_or_default();
+        L1Endpoint {
+            backend: L1BackendKind::BitcoinCore,
             url: self.rpc_url.clone(),
             user: self.rpc_user.clone(),
             password: self.rpc_password.clone(),
-        };
+            ..saved
+        }
+    }
+
+    fn save(&mut self) {
+        // Save the user's current input fields for the selected chain,
+        // keeping any extra endpoints
+        let config = self.current_endpoint();
 
         tracing::info!(
             chain = ?self.selected_parent_chain,
@@ -122,121 +93,62 @@ impl L1Config {
             "L1 Config: saving configuration"
         );
 
-        self.configs
-            .insert(self.selected_parent_chain, config.clone());
-
-        // Persist to file
-        let config_path = Self::config_file_path();
-        if let Some(parent_dir) = config_path.parent() {
-            drop(std::fs::create_dir_all(parent_dir));
-        }
-        if let Ok(json) = serde_json::to_string_pretty(&self.configs) {
-            drop(std::fs::write(&config_path, json));
+        if let Err(err) = self
+            .l1_config
+            .set_endpoints(&[(self.selected_parent_chain, config)])
+        {
+            tracing::error!(error = %err, "L1 Config: failed to save");
         }
-        tracing::info!(
-            path = %config_path.display(),
-            "L1 Config: configuration persisted to file"
-        );
+        self.configs = self.l1_config.entries();
 
         // Auto-check connection when saving
-        if !config.url.is_empty() {
-            self.check_connection(&config.url, &config.user, &config.password);
-        }
+        self.check_connection();
     }
 
     fn load_selected_chain_config(&mut self) {
-        self.load_predefined_for_selected();
+        self.load_saved_for_selected();
         // Reset connection status when switching chains
         *self.connection_status.lock().unwrap() = ConnectionStatus::Unknown;
         self.status_promise = None;
     }
 
-    fn check_connection(&mut self, url: &str, user: &str, password: &str) {
-        if url.is_empty() {
+    fn check_connection(&mut self) {
+        let endpoint = self.current_endpoint();
+        if endpoint.url.is_empty() {
             return;
         }
 
         tracing::info!(
-            url = %url,
-            has_auth = !user.is_empty(),
+            url = %endpoint.url,
+            has_auth = !endpoint.user.is_empty() || endpoint.cookie_file.is_some(),
             "L1 Config: testing connection"
         );
 
-        let url = url.to_string();
-        let user = user.to_string();
-        let password = password.to_string();
+        let parent_chain = self.selected_parent_chain;
         let status = self.connection_status.clone();
 
         *status.lock().unwrap() = ConnectionStatus::Checking;
 
         let promise = Promise::spawn_thread("l1_rpc_check", move || {
-            Self::fetch_block_height(&url, &user, &password)
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?;
+            runtime.block_on(async {
+                // The node must also be on the selected chain
+                parent_chain_rpc::verify_l1_endpoint(parent_chain, &endpoint)
+                    .await?;
+                let block_height =
+                    l1_backend::from_endpoint(parent_chain, &endpoint)?
+                        .get_block_height()
+                        .await?;
+                tracing::info!(block_height, "L1 Config: connection test OK");
+                Ok(u64::from(block_height))
+            })
         });
 
         self.status_promise = Some(promise);
     }
 
-    fn fetch_block_height(
-        url: &str,
-        user: &str,
-        password: &str,
-    ) -> anyhow::Result<u64> {
-        use std::time::Duration;
-
-        // Use jsonrpc "1.0" to match nodes that accept curl-style requests (e.g. BCH test4)
-        let request = json!({
-            "jsonrpc": "1.0",
-            "id": "coinshift",
-            "method": "getblockchaininfo",
-            "params": []
-        });
-
-        tracing::info!(
-            url = %url,
-            request = %serde_json::to_string(&request).unwrap_or_default(),
-            "L1 Config: connection test request"
-        );
-
-        let client = reqwest::blocking::Client::builder()
-            .timeout(Duration::from_secs(10))
-            .build()?;
-
-        let mut request_builder = client.post(url).json(&request);
-
-        // Add HTTP basic authentication if user and password are provided
-        if !user.is_empty() {
-            request_builder = request_builder.basic_auth(user, Some(password));
-        }
-
-        let response = request_builder.send()?;
-        let status = response.status();
-        let json: serde_json::Value = response.json()?;
-
-        tracing::info!(
-            status = %status,
-            response = %serde_json::to_string_pretty(&json).unwrap_or_else(|_| json.to_string()),
-            "L1 Config: connection test response"
-        );
-
-        if let Some(error) = json.get("error")
-            && !error.is_null()
-        {
-            anyhow::bail!("RPC error: {}", error);
-        }
-
-        let result = json
-            .get("result")
-            .ok_or_else(|| anyhow::anyhow!("No result in response"))?;
-
-        let blocks = result
-            .get("blocks")
-            .and_then(|v| v.as_u64())
-            .ok_or_else(|| anyhow::anyhow!("No blocks field in response"))?;
-
-        tracing::info!(block_height = blocks, "L1 Config: connection test OK");
-        Ok(blocks)
-    }
-
     fn update_status(&mut self) {
         if let Some(promise) = &self.status_promise
             && let Some(result) = promise.ready()
@@ -264,7 +176,9 @@ impl L1Config {
         }
     }
 
-    pub fn show(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        // Pick up changes made through the RPC server or to the file
+        self.configs = self.l1_config.entries();
         ui.heading(format!(
             "{} Node RPC Configuration",
             self.selected_parent_chain.coin_name()
@@ -284,26 +198,19 @@ impl L1Config {
             ui.label("Parent Chain:");
             let previous_chain = self.selected_parent_chain;
             let supported = parent_chain_rpc::supported_l1_parent_chain_types();
-            let label = match self.selected_parent_chain {
-                ParentChainType::Signet => "Bitcoin Signet (sBTC)",
-                ParentChainType::BCH => "Bitcoin Cash Testnet 4 (BCH)",
-                _ => "Select network",
+            let label = if supported.contains(&self.selected_parent_chain) {
+                self.selected_parent_chain.display_name()
+            } else {
+                "Select network".to_owned()
             };
             ComboBox::from_id_salt("l1_config_parent_chain")
                 .selected_text(label)
                 .show_ui(ui, |ui| {
                     for chain in supported {
-                        let option_label = match chain {
-                            ParentChainType::Signet => "Bitcoin Signet (sBTC)",
-                            ParentChainType::BCH => {
-                                "Bitcoin Cash Testnet 4 (BCH)"
-                            }
-                            _ => continue,
-                        };
                         ui.selectable_value(
                             &mut self.selected_parent_chain,
                             *chain,
-                            option_label,
+                            chain.display_name(),
                         );
                     }
                 });
@@ -340,8 +247,7 @@ impl L1Config {
 
         ui.horizontal(|ui| {
             ui.label("RPC URL:");
-            ui.add_enabled(
-                false,
+            ui.add(
                 TextEdit::singleline(&mut self.rpc_url)
                     .hint_text(
                         self.selected_parent_chain.default_rpc_url_hint(),
@@ -350,7 +256,7 @@ impl L1Config {
             );
         });
         ui.label(
-            RichText::new("Only predefined networks are supported. URL cannot be changed.")
+            RichText::new("Endpoints must serve this chain's genesis block; they are checked on start.")
                 .small()
                 .color(Color32::GRAY),
         );
@@ -359,8 +265,7 @@ impl L1Config {
 
         ui.horizontal(|ui| {
             ui.label("RPC User:");
-            ui.add_enabled(
-                false,
+            ui.add(
                 TextEdit::singleline(&mut self.rpc_user)
                     .hint_text("rpcuser")
                     .desired_width(300.0),
@@ -371,8 +276,7 @@ impl L1Config {
 
         ui.horizontal(|ui| {
             ui.label("RPC Password:");
-            ui.add_enabled(
-                false,
+            ui.add(
                 TextEdit::singleline(&mut self.rpc_password)
                     .hint_text("rpcpassword")
                     .password(true)
@@ -381,9 +285,9 @@ impl L1Config {
         });
 
         // Show current saved configuration
-        if let Some(saved_config) =
-            self.configs.get(&self.selected_parent_chain)
+        if let Some(saved_entry) = self.configs.get(&self.selected_parent_chain)
         {
+            let saved_config = &saved_entry.endpoint;
             ui.horizontal(|ui| {
                 ui.label("Current saved URL:");
                 use crate::gui::util::UiExt;
@@ -402,6 +306,14 @@ impl L1Config {
                     );
                 });
             }
+            if !saved_entry.extra_endpoints.is_empty() {
+                ui.label(format!(
+                    "Extra endpoints: {} (quorum {} of {})",
+                    saved_entry.extra_endpoints.len(),
+                    saved_entry.quorum(),
+                    saved_entry.extra_endpoints.len() + 1,
+                ));
+            }
         } else {
             ui.label("No RPC URL configured for this parent chain");
         }
@@ -418,16 +330,13 @@ impl L1Config {
 
         match status {
             ConnectionStatus::Unknown => {
-                // Allow check using current URL (predefined when chain selected) even if not saved yet
+                // Allow check using current URL even if not saved yet
                 if !self.rpc_url.is_empty() {
-                    let url = self.rpc_url.clone();
-                    let user = self.rpc_user.clone();
-                    let password = self.rpc_password.clone();
                     ui.horizontal(|ui| {
                         ui.label(RichText::new("●").color(Color32::GRAY));
                         ui.label("Status: Unknown");
                         if ui.button("Check Connection").clicked() {
-                            self.check_connection(&url, &user, &password);
+                            self.check_connection();
                         }
                     });
                 }
@@ -451,13 +360,8 @@ impl L1Config {
                     );
                     ui.label(format!("Latest Block Height: {}", block_height));
                 });
-                if !self.rpc_url.is_empty() {
-                    let url = self.rpc_url.clone();
-                    let user = self.rpc_user.clone();
-                    let password = self.rpc_password.clone();
-                    if ui.button("Refresh").clicked() {
-                        self.check_connection(&url, &user, &password);
-                    }
+                if !self.rpc_url.is_empty() && ui.button("Refresh").clicked() {
+                    self.check_connection();
                 }
             }
             ConnectionStatus::Disconnected { error } => {
@@ -471,13 +375,8 @@ impl L1Config {
                 });
                 let error_msg = format!("Error: {}", error);
                 ui.label(RichText::new(error_msg).small().color(Color32::RED));
-                if !self.rpc_url.is_empty() {
-                    let url = self.rpc_url.clone();
-                    let user = self.rpc_user.clone();
-                    let password = self.rpc_password.clone();
-                    if ui.button("Retry").clicked() {
-                        self.check_connection(&url, &user, &password);
-                    }
+                if !self.rpc_url.is_empty() && ui.button("Retry").clicked() {
+                    self.check_connection();
                 }
             }
         }
@@ -495,7 +394,7 @@ impl L1Config {
                 )
                 .clicked()
             {
-                self.save(ctx);
+                self.save();
             }
 
             if ui.button("Clear").clicked() {
@@ -506,15 +405,12 @@ impl L1Config {
                 self.rpc_url.clear();
                 self.rpc_user.clear();
                 self.rpc_password.clear();
-                self.configs.remove(&self.selected_parent_chain);
-                // Persist the updated configs to file
-                let config_path = Self::config_file_path();
-                if let Some(parent_dir) = config_path.parent() {
-                    drop(std::fs::create_dir_all(parent_dir));
-                }
-                if let Ok(json) = serde_json::to_string_pretty(&self.configs) {
-                    drop(std::fs::write(&config_path, json));
+                if let Err(err) =
+                    self.l1_config.set_entry(self.selected_parent_chain, None)
+                {
+                    tracing::error!(error = %err, "L1 Config: failed to clear");
                 }
+                self.configs = self.l1_config.entries();
                 // Reset connection status
                 *self.connection_status.lock().unwrap() =
                     ConnectionStatus::Unknown;
@@ -561,6 +457,9 @@ impl L1Config {
             ParentChainType::Regtest => {
                 ui.label("Use Bitcoin Core with -regtest -txindex=1 flags for local testing.");
             }
+            ParentChainType::Custom(_) => {
+                ui.label("Use a node of this chain with -txindex=1 for full transaction lookup.");
+            }
         }
     }
 }
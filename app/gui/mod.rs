@@ -260,7 +260,10 @@ impl EguiApp {
         let bottom_panel = BottomPanel::new(app.clone());
         let coins = Coins::new(app.as_ref());
         let console_logs = ConsoleLogs::new(logs_capture, rpc_addr);
-        let l1_config = L1Config::new(&cc.egui_ctx);
+        let l1_config = L1Config::new(app.as_ref().map_or_else(
+            || coinshift::l1_config::L1Config::new(&config.datadir),
+            |app| app.node.l1_config().clone(),
+        ));
         let height = app
             .as_ref()
             .and_then(|app| app.node.try_get_height().ok().flatten())
@@ -383,7 +386,7 @@ impl eframe::App for EguiApp {
                         self.withdrawals.show(self.app.as_ref(), ui);
                     }
                     Tab::L1Config => {
-                        self.l1_config.show(ctx, ui);
+                        self.l1_config.show(ui);
                     }
                     Tab::ConsoleLogs => {
                         self.console_logs.show(self.app.as_ref(), ui);
@@ -1,8 +1,9 @@
 use coinshift::parent_chain_rpc;
-use coinshift::types::{Address, ParentChainType};
+use coinshift::types::{Address, ParentChainType, quote};
 use eframe::egui::{self, Button, Color32, ComboBox, RichText, TextEdit};
 
 use crate::app::App;
+use crate::gui::util::{show_confirmations_hint, show_l2_amount};
 
 #[derive(Debug)]
 pub struct CreateSwap {
@@ -11,7 +12,15 @@ pub struct CreateSwap {
     l1_amount: String,
     l2_recipient: Option<String>,
     l2_amount: String,
+    /// Set the L2 amount from `price` rather than directly
+    by_price: bool,
+    /// L2 per L1 coin
+    price: String,
     required_confirmations: String,
+    /// Minimum L1 fill, if the swap can be filled by several L1 payments
+    min_fill_l1_amount: String,
+    /// L2 blocks until the swap expires; empty for the chain default
+    expires_in_blocks: String,
     is_open_swap: bool,
     error_message: Option<String>,
 }
@@ -29,7 +38,11 @@ impl Default for CreateSwap {
             l1_amount: String::new(),
             l2_recipient: None,
             l2_amount: String::new(),
+            by_price: false,
+            price: String::new(),
             required_confirmations: String::new(),
+            min_fill_l1_amount: String::new(),
+            expires_in_blocks: String::new(),
             is_open_swap: false,
             error_message: None,
         }
@@ -37,6 +50,23 @@ impl Default for CreateSwap {
 }
 
 impl CreateSwap {
+    /// L2 amount for the entered L1 amount at the entered price, if both are
+    /// valid and the amount is not zero
+    fn l2_amount_at_price(&self) -> Option<bitcoin::Amount> {
+        let l1_amount = bitcoin::Amount::from_str_in(
+            &self.l1_amount,
+            bitcoin::Denomination::Bitcoin,
+        )
+        .ok()?;
+        let price = bitcoin::Amount::from_str_in(
+            &self.price,
+            bitcoin::Denomination::Bitcoin,
+        )
+        .ok()?;
+        quote::l2_amount_at_price(self.parent_chain, l1_amount, price)
+            .filter(|l2_amount| *l2_amount > bitcoin::Amount::ZERO)
+    }
+
     pub fn show(&mut self, app: Option<&App>, ui: &mut egui::Ui) {
         ui.heading("Create Swap (L2 → L1)");
         ui.add_space(4.0);
@@ -54,26 +84,19 @@ impl CreateSwap {
         ui.horizontal(|ui| {
             ui.label("Parent chain:");
             let supported = parent_chain_rpc::supported_l1_parent_chain_types();
-            let label = match self.parent_chain {
-                ParentChainType::Signet => "Bitcoin Signet (sBTC)",
-                ParentChainType::BCH => "Bitcoin Cash Testnet 4 (BCH)",
-                _ => "Select network",
+            let label = if supported.contains(&self.parent_chain) {
+                self.parent_chain.display_name()
+            } else {
+                "Select network".to_owned()
             };
             ComboBox::from_id_salt("parent_chain")
                 .selected_text(label)
                 .show_ui(ui, |ui| {
                     for chain in supported {
-                        let option_label = match chain {
-                            ParentChainType::Signet => "Bitcoin Signet (sBTC)",
-                            ParentChainType::BCH => {
-                                "Bitcoin Cash Testnet 4 (BCH)"
-                            }
-                            _ => continue,
-                        };
                         ui.selectable_value(
                             &mut self.parent_chain,
                             *chain,
-                            option_label,
+                            chain.display_name(),
                         );
                     }
                 });
@@ -132,13 +155,33 @@ impl CreateSwap {
             self.l2_recipient = None;
         }
 
-        ui.horizontal(|ui| {
-            ui.label("L2 amount you offer:");
-            ui.add(
-                TextEdit::singleline(&mut self.l2_amount)
-                    .hint_text("e.g. 0.001"),
-            );
-        });
+        ui.checkbox(&mut self.by_price, "Set the L2 amount by price");
+        if self.by_price {
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "Price (L2 per {}):",
+                    self.parent_chain.ticker()
+                ));
+                ui.add(
+                    TextEdit::singleline(&mut self.price)
+                        .hint_text("e.g. 1.05"),
+                );
+                if let Some(l2_amount) = self.l2_amount_at_price() {
+                    ui.label(format!(
+                        "(you offer {})",
+                        show_l2_amount(l2_amount)
+                    ));
+                }
+            });
+        } else {
+            ui.horizontal(|ui| {
+                ui.label("L2 amount you offer:");
+                ui.add(
+                    TextEdit::singleline(&mut self.l2_amount)
+                        .hint_text("e.g. 0.001"),
+                );
+            });
+        }
 
         ui.add_space(8.0);
         ui.label(RichText::new("Options").strong());
@@ -147,11 +190,37 @@ impl CreateSwap {
             ui.label("Required L1 confirmations:");
             ui.add(
                 TextEdit::singleline(&mut self.required_confirmations)
+                    .hint_text("leave empty for suggested"),
+            );
+            let l1_amount = bitcoin::Amount::from_str_in(
+                &self.l1_amount,
+                bitcoin::Denomination::Bitcoin,
+            );
+            ui.label(show_confirmations_hint(
+                self.parent_chain,
+                l1_amount.ok(),
+            ));
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(format!("Minimum fill ({}):", self.parent_chain.ticker()));
+            ui.add(
+                TextEdit::singleline(&mut self.min_fill_l1_amount)
+                    .hint_text("leave empty for a single fill"),
+            );
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Expires in (L2 blocks):");
+            ui.add(
+                TextEdit::singleline(&mut self.expires_in_blocks)
                     .hint_text("leave empty for default"),
             );
             ui.label(format!(
-                "(default: {})",
-                self.parent_chain.default_confirmations()
+                "(default: {}, {}-{})",
+                self.parent_chain.default_swap_expiration_blocks(),
+                self.parent_chain.min_swap_expiration_blocks(),
+                self.parent_chain.max_swap_expiration_blocks()
             ));
         });
 
@@ -173,15 +242,51 @@ impl CreateSwap {
             &self.l1_amount,
             bitcoin::Denomination::Bitcoin,
         );
-        let l2_amount = bitcoin::Amount::from_str_in(
-            &self.l2_amount,
-            bitcoin::Denomination::Bitcoin,
-        );
-        let required_confirmations = self
-            .required_confirmations
-            .parse::<u32>()
+        let l2_amount = if self.by_price {
+            self.l2_amount_at_price()
+        } else {
+            bitcoin::Amount::from_str_in(
+                &self.l2_amount,
+                bitcoin::Denomination::Bitcoin,
+            )
             .ok()
-            .or_else(|| Some(self.parent_chain.default_confirmations()));
+        };
+        // Swaps must require at least the minimum for their L1 amount
+        let required_confirmations = match self.required_confirmations.trim() {
+            "" => Some(None),
+            confirmations => confirmations
+                .parse::<u32>()
+                .ok()
+                .filter(|confirmations| {
+                    l1_amount.as_ref().is_ok_and(|l1_amount| {
+                        *confirmations
+                            >= self.parent_chain.min_confirmations(*l1_amount)
+                    })
+                })
+                .map(Some),
+        };
+
+        let min_fill_l1_amount = match self.min_fill_l1_amount.trim() {
+            "" => Ok(None),
+            min_fill => bitcoin::Amount::from_str_in(
+                min_fill,
+                bitcoin::Denomination::Bitcoin,
+            )
+            .map(Some),
+        };
+
+        let expires_in_blocks = match self.expires_in_blocks.trim() {
+            "" => Some(None),
+            expires_in => expires_in
+                .parse::<u32>()
+                .ok()
+                .filter(|expires_in| {
+                    (self.parent_chain.min_swap_expiration_blocks()
+                        ..=self.parent_chain.max_swap_expiration_blocks())
+                        .contains(expires_in)
+                })
+                .map(Some),
+        };
 
         let l2_recipient: Option<Address> = if self.is_open_swap {
             None
@@ -191,8 +296,11 @@ impl CreateSwap {
 
         let is_valid = app.is_some()
             && (l2_recipient.is_some() || self.is_open_swap)
-            && l2_amount.is_ok()
+            && l2_amount.is_some()
             && l1_amount.is_ok()
+            && required_confirmations.is_some()
+            && min_fill_l1_amount.is_ok()
+            && expires_in_blocks.is_some()
             && !self.l1_recipient_address.is_empty();
 
         if ui
@@ -217,6 +325,12 @@ impl CreateSwap {
             // Extract amounts for logging (before they're moved)
             let l1_amount_val = l1_amount.expect("should not happen");
             let l2_amount_val = l2_amount.expect("should not happen");
+            let min_fill_l1_amount =
+                min_fill_l1_amount.expect("should not happen");
+            let expires_in_blocks =
+                expires_in_blocks.expect("should not happen");
+            let required_confirmations =
+                required_confirmations.expect("should not happen");
 
             // Create a closure that checks if an outpoint is locked to a swap
             // We create a new read transaction each time to avoid lifetime issues
@@ -241,6 +355,8 @@ impl CreateSwap {
                 l2_recipient,
                 l2_amount_val,
                 required_confirmations,
+                min_fill_l1_amount,
+                expires_in_blocks,
                 bitcoin::Amount::ZERO,
                 is_locked,
             ) {
@@ -255,6 +371,7 @@ impl CreateSwap {
                         l2_recipient = ?l2_recipient,
                         l2_amount = %l2_amount_val,
                         required_confirmations = ?required_confirmations,
+                        ?expires_in_blocks,
                         is_open_swap = %self.is_open_swap,
                         "Successfully created swap transaction"
                     );
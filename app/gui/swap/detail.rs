@@ -1,7 +1,8 @@
 use std::collections::HashMap;
 
-use coinshift::parent_chain_rpc::ParentChainRpcClient;
-use coinshift::types::{Address, Swap, SwapId, SwapState, SwapTxId};
+use coinshift::types::{
+    Address, Swap, SwapDirection, SwapId, SwapState, SwapTxId,
+};
 use eframe::egui::{self, Button, ScrollArea};
 
 use crate::app::App;
@@ -223,6 +224,26 @@ impl SwapDetail {
                             ui.label(addr.to_string());
                             ui.end_row();
                         }
+
+                        if let Some(reservation) = &swap.reservation {
+                            ui.label(
+                                egui::RichText::new("Reserved by:").strong(),
+                            );
+                            ui.label(
+                                reservation.l2_claimer_address.to_string(),
+                            );
+                            ui.end_row();
+                            ui.label(
+                                egui::RichText::new("Reservation ends at:")
+                                    .strong(),
+                            );
+                            ui.label(format!(
+                                "{} (bond {})",
+                                reservation.expires_at_height,
+                                show_l2_amount(reservation.bond)
+                            ));
+                            ui.end_row();
+                        }
                     });
             });
 
@@ -247,6 +268,19 @@ impl SwapDetail {
                         self.cancel_swap(app, &swap, list);
                     }
 
+                    if Self::can_reserve(app, &swap)
+                        && ui
+                            .button("Reserve Swap")
+                            .on_hover_text(format!(
+                                "Lock a bond of {} for exclusive fill rights",
+                                show_l2_amount(swap.reservation_bond())
+                            ))
+                            .clicked()
+                        && let Some(app) = app
+                    {
+                        self.reserve_swap(app, &swap, list);
+                    }
+
                     if matches!(
                         swap.state,
                         SwapState::Pending | SwapState::Cancelled
@@ -285,6 +319,15 @@ impl SwapDetail {
             ui.add_space(4.0);
 
             // ── state-specific actions ─────────────────────────────
+            if swap.allows_partial_fills() {
+                if !matches!(
+                    swap.state,
+                    SwapState::Completed | SwapState::Cancelled
+                ) {
+                    self.show_partial_fill_actions(app, &swap, ui, list);
+                }
+                return;
+            }
             match &swap.state {
                 SwapState::Pending => {
                     self.show_pending_actions(app, &swap, ui, list);
@@ -377,7 +420,7 @@ impl SwapDetail {
                     )
                     .clicked()
                 && let Some(app) = app {
-                    self.fetch_confirmations_from_rpc(app, swap, list);
+                    self.fetch_confirmations_from_rpc(app, swap);
                 }
             });
 
@@ -469,6 +512,112 @@ impl SwapDetail {
         });
     }
 
+    // ── partial fills ──────────────────────────────────────────────
+
+    fn show_partial_fill_actions(
+        &mut self,
+        app: Option<&App>,
+        swap: &Swap,
+        ui: &mut egui::Ui,
+        list: &mut SwapList,
+    ) {
+        let fills = app
+            .and_then(|app| {
+                let rotxn = app.node.env().read_txn().ok()?;
+                app.node.state().get_swap_fills(&rotxn, &swap.id).ok()
+            })
+            .unwrap_or_default();
+
+        ui.group(|ui| {
+            ui.heading("Fills");
+            if let Some(min_fill) = swap.min_fill_l1_amount {
+                ui.label(format!(
+                    "Minimum fill: {}",
+                    show_l1_amount(min_fill, swap.parent_chain)
+                ));
+            }
+            ui.label(
+                egui::RichText::new(
+                    "Each L1 payment to the L1 recipient must declare the L2 \
+                     address that claims its share in an OP_RETURN output. \
+                     Claims prove their fills once confirmed.",
+                )
+                .small()
+                .color(egui::Color32::GRAY),
+            );
+
+            if fills.is_empty() {
+                ui.label("No fills claimed yet.");
+            } else {
+                egui::Grid::new("swap_fills_grid")
+                    .num_columns(4)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label(egui::RichText::new("L1 TxID").strong());
+                        ui.label(egui::RichText::new("L1 Amount").strong());
+                        ui.label(egui::RichText::new("L2 Share").strong());
+                        ui.label(egui::RichText::new("Claimed By").strong());
+                        ui.end_row();
+                        for fill in &fills {
+                            let txid = fill.l1_txid.to_hex_rpc();
+                            ui.label(
+                                egui::RichText::new(format!(
+                                    "{}...",
+                                    &txid[..txid.len().min(10)]
+                                ))
+                                .monospace(),
+                            )
+                            .on_hover_text(&txid);
+                            ui.label(show_l1_amount(
+                                fill.l1_amount,
+                                swap.parent_chain,
+                            ));
+                            ui.label(show_l2_amount(fill.l2_amount));
+                            ui.label(
+                                egui::RichText::new(
+                                    fill.l2_claimer_address.to_string(),
+                                )
+                                .monospace(),
+                            );
+                            ui.end_row();
+                        }
+                    });
+            }
+
+            if !swap.is_accepting_fills(&fills) {
+                return;
+            }
+            ui.add_space(4.0);
+            ui.horizontal(|ui| {
+                ui.label("Claimer Address:");
+                ui.text_edit_singleline(&mut self.claimer_address_input);
+            });
+            if ui
+                .add_enabled(
+                    app.is_some()
+                        && (swap.l2_recipient.is_some()
+                            || !self.claimer_address_input.is_empty()),
+                    Button::new("Claim Fills"),
+                )
+                .clicked()
+                && let Some(app) = app
+            {
+                let claimer_addr = match self.claimer_address_input.trim() {
+                    "" => None,
+                    addr => match addr.parse::<Address>() {
+                        Ok(addr) => Some(addr),
+                        Err(err) => {
+                            self.claim_error =
+                                Some(format!("Invalid address: {err}"));
+                            return;
+                        }
+                    },
+                };
+                self.claim_swap(app, &swap.id, claimer_addr, list);
+            }
+        });
+    }
+
     // ── confirmation progress ──────────────────────────────────────
 
     fn show_confirmation_progress(
@@ -599,14 +748,21 @@ impl SwapDetail {
             return;
         }
 
-        let recipient = swap
-            .l2_recipient
-            .or(swap.l2_claimer_address)
-            .or(l2_claimer_address)
-            .ok_or_else(|| {
-                tracing::error!("Open swap requires claimer address");
-            })
-            .ok();
+        let recipient = if swap.allows_partial_fills() {
+            l2_claimer_address.or(swap.l2_recipient)
+        } else {
+            swap.l2_recipient
+                .or(swap
+                    .reservation
+                    .as_ref()
+                    .map(|reservation| reservation.l2_claimer_address))
+                .or(l2_claimer_address)
+                .or(swap.l2_claimer_address)
+        }
+        .ok_or_else(|| {
+            tracing::error!("Open swap requires claimer address");
+        })
+        .ok();
 
         let recipient = match recipient {
             Some(addr) => addr,
@@ -617,15 +773,79 @@ impl SwapDetail {
             }
         };
 
-        let l2_claimer_for_tx =
-            swap.l2_recipient.is_none().then_some(recipient);
-        let tx = match app.wallet.create_swap_claim_tx(
-            &accumulator,
-            *swap_id,
-            recipient,
-            locked_outputs,
-            l2_claimer_for_tx,
-        ) {
+        // Prove the L1 fills from the L1 backend's copies of their blocks
+        let Some(backend) = app.node.l1_config().backend(swap.parent_chain)
+        else {
+            self.claim_error = Some(format!(
+                "No L1 backend configured for {:?}",
+                swap.parent_chain
+            ));
+            return;
+        };
+        let tx = if swap.allows_partial_fills() {
+            let proven = app
+                .runtime
+                .block_on(coinshift::state::fetch_swap_fill_proofs(
+                    backend.as_ref(),
+                    &swap,
+                ))
+                .and_then(|candidates| {
+                    coinshift::state::prove_swap_fills(
+                        app.node.state(),
+                        &rotxn,
+                        &swap,
+                        &recipient,
+                        candidates,
+                    )
+                });
+            let (l1_proofs, fills) = match proven {
+                Ok(proven) => proven,
+                Err(err) => {
+                    self.claim_error =
+                        Some(format!("Failed to prove L1 fills: {err:#}"));
+                    return;
+                }
+            };
+            if fills.is_empty() {
+                self.claim_error = Some(format!(
+                    "No confirmed L1 fills provable for {recipient}"
+                ));
+                return;
+            }
+            let release: bitcoin::Amount =
+                fills.iter().map(|fill| fill.l2_amount).sum();
+            app.wallet.create_partial_swap_claim_tx(
+                &accumulator,
+                *swap_id,
+                recipient,
+                locked_outputs,
+                release,
+                l2_claimer_address,
+                &l1_proofs,
+            )
+        } else {
+            let l1_proof = match app.runtime.block_on(
+                backend.get_inclusion_proof(&swap.l1_txid.to_hex_rpc()),
+            ) {
+                Ok(l1_proof) => l1_proof,
+                Err(err) => {
+                    self.claim_error =
+                        Some(format!("Failed to prove L1 fill: {err:#}"));
+                    return;
+                }
+            };
+            let l2_claimer_for_tx =
+                swap.l2_recipient.is_none().then_some(recipient);
+            app.wallet.create_swap_claim_tx(
+                &accumulator,
+                *swap_id,
+                recipient,
+                locked_outputs,
+                l2_claimer_for_tx,
+                &l1_proof,
+            )
+        };
+        let tx = match tx {
             Ok(tx) => tx,
             Err(err) => {
                 self.claim_error =
@@ -682,31 +902,149 @@ impl SwapDetail {
             }
             self.claim_error = Some("Pending swap not found in mempool".into());
         } else {
-            let creator = swap.l2_creator_address.as_ref();
-            let mut rwtxn = match app.node.env().write_txn() {
-                Ok(txn) => txn,
+            let Some(creator) = swap.l2_creator_address else {
+                self.claim_error =
+                    Some("Only the swap creator can cancel".into());
+                return;
+            };
+            let accumulator = match app.node.get_tip_accumulator() {
+                Ok(acc) => acc,
                 Err(err) => {
                     self.claim_error =
-                        Some(format!("Failed to get write txn: {err:#}"));
+                        Some(format!("Failed to get accumulator: {err:#}"));
                     return;
                 }
             };
-
-            if let Err(err) =
-                app.node.state().cancel_swap(&mut rwtxn, &swap_id, creator)
-            {
+            let tx = match app.wallet.create_swap_cancel_tx(
+                &accumulator,
+                swap_id,
+                creator,
+                bitcoin::Amount::ZERO,
+                |outpoint| {
+                    app.node
+                        .env()
+                        .read_txn()
+                        .ok()
+                        .and_then(|rotxn| {
+                            app.node
+                                .state()
+                                .is_output_locked_to_swap(&rotxn, outpoint)
+                                .ok()
+                        })
+                        .flatten()
+                        .is_some()
+                },
+            ) {
+                Ok(tx) => tx,
+                Err(err) => {
+                    self.claim_error = Some(format!(
+                        "Failed to create cancel transaction: {err:#}"
+                    ));
+                    return;
+                }
+            };
+            let txid = tx.txid();
+            if let Err(err) = app.sign_and_send(tx) {
                 self.claim_error = Some(format!("Failed to cancel: {err:#}"));
                 return;
             }
 
-            if let Err(err) = rwtxn.commit() {
-                self.claim_error = Some(format!("Failed to commit: {err:#}"));
+            self.success_message = Some(format!(
+                "Swap cancel sent (txid {txid}). The swap is cancelled once \
+                 the transaction is mined."
+            ));
+            list.refresh_swaps(app);
+        }
+    }
+
+    /// Whether an open swap can be reserved by us, as a taker
+    fn can_reserve(app: Option<&App>, swap: &Swap) -> bool {
+        let Some(app) = app else {
+            return false;
+        };
+        if swap.created_at_height == 0
+            || swap.direction != SwapDirection::L2ToL1
+            || swap.l2_recipient.is_some()
+            || swap.allows_partial_fills()
+            || !matches!(swap.state, SwapState::Pending)
+            || swap.has_l1_txid()
+            || Self::can_manage(Some(app), swap)
+        {
+            return false;
+        }
+        let next_height = match app.node.try_get_height() {
+            Ok(Some(height)) => height + 1,
+            Ok(None) => 0,
+            Err(_) => return false,
+        };
+        swap.live_reservation(next_height).is_none()
+    }
+
+    fn reserve_swap(&mut self, app: &App, swap: &Swap, list: &mut SwapList) {
+        let swap_id = swap.id;
+        let Some(creator) = swap.l2_creator_address else {
+            self.claim_error = Some("Swap has no creator".into());
+            return;
+        };
+        let l2_claimer_address = match app.wallet.get_new_address() {
+            Ok(address) => address,
+            Err(err) => {
+                self.claim_error =
+                    Some(format!("Failed to get address: {err:#}"));
                 return;
             }
-
-            self.success_message = Some("Swap cancelled".into());
-            list.refresh_swaps(app);
+        };
+        let accumulator = match app.node.get_tip_accumulator() {
+            Ok(acc) => acc,
+            Err(err) => {
+                self.claim_error =
+                    Some(format!("Failed to get accumulator: {err:#}"));
+                return;
+            }
+        };
+        let reserve_blocks = swap.parent_chain.max_swap_reservation_blocks();
+        let tx = match app.wallet.create_swap_reserve_tx(
+            &accumulator,
+            swap_id,
+            creator,
+            l2_claimer_address,
+            reserve_blocks,
+            swap.reservation_bond(),
+            bitcoin::Amount::ZERO,
+            |outpoint| {
+                app.node
+                    .env()
+                    .read_txn()
+                    .ok()
+                    .and_then(|rotxn| {
+                        app.node
+                            .state()
+                            .is_output_locked_to_swap(&rotxn, outpoint)
+                            .ok()
+                    })
+                    .flatten()
+                    .is_some()
+            },
+        ) {
+            Ok(tx) => tx,
+            Err(err) => {
+                self.claim_error = Some(format!(
+                    "Failed to create reserve transaction: {err:#}"
+                ));
+                return;
+            }
+        };
+        let txid = tx.txid();
+        if let Err(err) = app.sign_and_send(tx) {
+            self.claim_error = Some(format!("Failed to reserve: {err:#}"));
+            return;
         }
+        self.success_message = Some(format!(
+            "Swap reserve sent (txid {txid}). Once mined, send the L1 coins \
+             within {reserve_blocks} blocks; otherwise the bond goes to the \
+             swap creator."
+        ));
+        list.refresh_swaps(app);
     }
 
     fn delete_swap(&mut self, app: &App, swap: &Swap, list: &mut SwapList) {
@@ -772,17 +1110,12 @@ impl SwapDetail {
         }
     }
 
-    fn fetch_confirmations_from_rpc(
-        &mut self,
-        _app: &App,
-        swap: &Swap,
-        list: &SwapList,
-    ) {
+    fn fetch_confirmations_from_rpc(&mut self, app: &App, swap: &Swap) {
         if self.l1_txid_input.is_empty() {
             return;
         }
 
-        if let Some(rpc_config) = list.load_rpc_config(swap.parent_chain) {
+        if let Some(backend) = app.node.l1_config().backend(swap.parent_chain) {
             self.fetching_confirmations = true;
             let txid_hex = self.l1_txid_input.clone();
             let txid_for_rpc = SwapTxId::from_hex(&txid_hex)
@@ -792,8 +1125,10 @@ impl SwapDetail {
                 })
                 .unwrap_or(txid_hex);
 
-            let client = ParentChainRpcClient::new(rpc_config);
-            match client.get_transaction_confirmations(&txid_for_rpc) {
+            match app
+                .runtime
+                .block_on(backend.get_transaction_confirmations(&txid_for_rpc))
+            {
                 Ok(confirmations) => {
                     tracing::info!(
                         swap_id = %swap.id,
@@ -862,37 +1197,25 @@ impl SwapDetail {
         let l1_txid_hex = l1_txid.to_hex();
 
         // Fetch and validate from RPC
-        let confirmations = if let Some(rpc_config) =
-            list.load_rpc_config(swap.parent_chain)
+        let confirmations = if let Some(backend) =
+            app.node.l1_config().backend(swap.parent_chain)
         {
-            let client = ParentChainRpcClient::new(rpc_config);
-            match client.get_transaction(&l1_txid_hex) {
+            match app.runtime.block_on(backend.get_transaction(&l1_txid_hex)) {
                 Ok(tx_info) => {
                     let conf = tx_info.confirmations;
 
                     // Validate outputs if expected recipient/amount known
-                    if let (Some(expected_recipient), Some(expected_amount)) =
-                        (&swap.l1_recipient_address, swap.l1_amount)
+                    if let (Some(expected_script), Some(expected_amount)) =
+                        (&swap.l1_recipient_script, swap.l1_amount)
                     {
                         let expected_sats = expected_amount.to_sat();
-                        let found = tx_info.vout.iter().any(|vout| {
-                            let sats = (vout.value * 100_000_000.0) as u64;
-                            let addr_match = vout
-                                .script_pub_key
-                                .address
-                                .as_ref()
-                                .map(|a| a == expected_recipient)
-                                .unwrap_or(false)
-                                || vout
-                                    .script_pub_key
-                                    .addresses
-                                    .as_ref()
-                                    .map(|addrs| {
-                                        addrs.contains(expected_recipient)
-                                    })
-                                    .unwrap_or(false);
-                            addr_match && sats == expected_sats
-                        });
+                        let found = tx_info
+                            .amount_paid_to(
+                                expected_script,
+                                expected_sats,
+                                expected_sats,
+                            )
+                            .is_some();
 
                         if !found {
                             self.claim_error = Some(
@@ -1010,5 +1333,9 @@ fn state_display(state: &SwapState) -> (String, egui::Color32) {
         SwapState::Cancelled => {
             ("Cancelled".into(), egui::Color32::from_rgb(150, 150, 150))
         }
+        SwapState::PartiallyFilled(filled, total) => (
+            format!("Filled {}/{} sats", filled, total),
+            egui::Color32::from_rgb(255, 200, 120),
+        ),
     }
 }
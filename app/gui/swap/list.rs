@@ -1,12 +1,12 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::HashSet,
     time::{Duration, Instant},
 };
 
-use coinshift::parent_chain_rpc::{ParentChainRpcClient, RpcConfig};
-use coinshift::types::{ParentChainType, Swap, SwapId, SwapState, SwapTxId};
+use coinshift::types::{Swap, SwapId, SwapState, SwapTxId, quote};
 use eframe::egui::{self, Button, ScrollArea};
 
+use super::quote::SwapQuotes;
 use crate::app::App;
 use crate::gui::util::{show_l1_amount, show_l2_amount};
 
@@ -42,6 +42,7 @@ pub struct SwapList {
     ownership_filter: OwnershipFilter,
     swap_id_search: String,
     search_error: Option<String>,
+    quotes: SwapQuotes,
     // confirmation checking (kept here since it's background work)
     last_confirmation_check: Option<Instant>,
     checking_confirmations: bool,
@@ -92,15 +93,22 @@ impl SwapList {
                     l2_amount,
                     l1_recipient_address,
                     l1_amount,
+                    min_fill_l1_amount,
+                    expires_in_blocks: _,
                 } = &tx.transaction.data
                 {
                     let swap_id_obj = coinshift::types::SwapId(*swap_id);
                     if !swaps_result.iter().any(|s| s.id == swap_id_obj) {
                         let l1_txid =
                             coinshift::types::SwapTxId::from_bytes(&[0u8; 32]);
-                        let swap = coinshift::types::Swap::new(
+                        let direction = if l1_recipient_address.is_some() {
+                            coinshift::types::SwapDirection::L2ToL1
+                        } else {
+                            coinshift::types::SwapDirection::L1ToL2
+                        };
+                        let mut swap = coinshift::types::Swap::new(
                             swap_id_obj,
-                            coinshift::types::SwapDirection::L2ToL1,
+                            direction,
                             *parent_chain,
                             l1_txid,
                             Some(*required_confirmations),
@@ -112,6 +120,8 @@ impl SwapList {
                             None,
                             None,
                         );
+                        swap.min_fill_l1_amount =
+                            min_fill_l1_amount.map(bitcoin::Amount::from_sat);
                         swaps_result.push(swap);
                     }
                 }
@@ -119,6 +129,7 @@ impl SwapList {
         }
 
         self.swaps = Some(swaps_result);
+        self.quotes.refresh(app);
     }
 
     // ── ownership helper ───────────────────────────────────────────
@@ -181,6 +192,17 @@ impl SwapList {
 
         ui.add_space(4.0);
 
+        // ── open swaps by price ────────────────────────────────────
+        if let Some(swap_id) = self.quotes.show(app, ui)
+            && let Some(swap) =
+                self.swaps.iter().flatten().find(|swap| swap.id == swap_id)
+        {
+            self.selected_swap_id = Some(swap_id);
+            navigate_to = Some(swap.clone());
+        }
+
+        ui.add_space(4.0);
+
         // ── filters row ────────────────────────────────────────────
         ui.horizontal(|ui| {
             // Status filter
@@ -311,6 +333,7 @@ impl SwapList {
                     SwapStatusFilter::WaitingConfirmations => matches!(
                         swap.state,
                         SwapState::WaitingConfirmations(..)
+                            | SwapState::PartiallyFilled(..)
                     ),
                     SwapStatusFilter::ReadyToClaim => {
                         matches!(swap.state, SwapState::ReadyToClaim)
@@ -366,7 +389,7 @@ impl SwapList {
 
         ScrollArea::vertical().show(ui, |ui| {
             egui::Grid::new("swap_list_grid")
-                .num_columns(8)
+                .num_columns(9)
                 .spacing([12.0, 0.0])
                 .min_col_width(0.0)
                 .striped(false) // we handle striping manually for selection highlight
@@ -378,6 +401,7 @@ impl SwapList {
                     ui.label(egui::RichText::new("State").color(header_color).strong().size(11.0));
                     ui.label(egui::RichText::new("L2 Amount").color(header_color).strong().size(11.0));
                     ui.label(egui::RichText::new("L1 Amount").color(header_color).strong().size(11.0));
+                    ui.label(egui::RichText::new("Price").color(header_color).strong().size(11.0));
                     ui.label(egui::RichText::new("Tags").color(header_color).strong().size(11.0));
                     ui.label(""); // action col
                     ui.end_row();
@@ -391,6 +415,7 @@ impl SwapList {
                     ui.separator();
                     ui.separator();
                     ui.separator();
+                    ui.separator();
                     ui.end_row();
 
                     // ── data rows ──────────────────────────────────
@@ -489,7 +514,25 @@ impl SwapList {
                             .unwrap_or_else(|| "--".into());
                         ui.label(egui::RichText::new(l1_text).size(11.0));
 
-                        // Col 7: Tags
+                        // Col 7: Price (L2 per L1 coin)
+                        let price_text = swap
+                            .l1_amount
+                            .and_then(|l1_amount| {
+                                quote::price_of(
+                                    swap.parent_chain,
+                                    l1_amount,
+                                    swap.l2_amount,
+                                )
+                            })
+                            .map(show_l2_amount)
+                            .unwrap_or_else(|| "--".into());
+                        ui.label(egui::RichText::new(price_text).size(11.0))
+                            .on_hover_text(format!(
+                                "L2 per {}",
+                                swap.parent_chain.ticker()
+                            ));
+
+                        // Col 8: Tags
                         ui.horizontal(|ui| {
                             ui.spacing_mut().item_spacing.x = 4.0;
                             if swap.created_at_height == 0 {
@@ -518,7 +561,7 @@ impl SwapList {
                             }
                         });
 
-                        // Col 8: View button
+                        // Col 9: View button
                         if ui
                             .add(
                                 Button::new(
@@ -542,49 +585,6 @@ impl SwapList {
 
     // ── background confirmation checking ───────────────────────────
 
-    pub(crate) fn load_rpc_config(
-        &self,
-        parent_chain: ParentChainType,
-    ) -> Option<RpcConfig> {
-        use dirs;
-        use serde::{Deserialize, Serialize};
-        use std::path::PathBuf;
-
-        #[derive(Clone, Serialize, Deserialize)]
-        struct LocalRpcConfig {
-            url: String,
-            user: String,
-            password: String,
-        }
-
-        let config_path = dirs::data_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join("coinshift")
-            .join("l1_rpc_configs.json");
-
-        if let Ok(file_content) = std::fs::read_to_string(&config_path)
-            && let Ok(configs) = serde_json::from_str::<
-                HashMap<ParentChainType, LocalRpcConfig>,
-            >(&file_content)
-            && let Some(local_config) = configs.get(&parent_chain)
-        {
-            return Some(RpcConfig {
-                url: local_config.url.clone(),
-                user: local_config.user.clone(),
-                password: local_config.password.clone(),
-            });
-        }
-
-        coinshift::parent_chain_rpc::supported_l1_configs()
-            .into_iter()
-            .find(|(c, _)| *c == parent_chain)
-            .map(|(_, rpc)| RpcConfig {
-                url: rpc.url,
-                user: rpc.user,
-                password: rpc.password,
-            })
-    }
-
     fn check_confirmations_dynamically(&mut self, app: &App) {
         if self.checking_confirmations {
             return;
@@ -615,6 +615,7 @@ impl SwapList {
             .iter()
             .filter(|swap| {
                 matches!(swap.state, SwapState::WaitingConfirmations(..))
+                    && !swap.allows_partial_fills()
                     && !matches!(swap.l1_txid, SwapTxId::Hash32(h) if h == [0u8; 32])
                     && !matches!(swap.l1_txid, SwapTxId::Hash(ref v) if v.is_empty() || v.iter().all(|&b| b == 0))
             })
@@ -630,25 +631,24 @@ impl SwapList {
         let work: Vec<_> = swaps_to_check
             .iter()
             .filter_map(|swap| {
-                self.load_rpc_config(swap.parent_chain).map(|rpc_config| {
-                    (swap.id, rpc_config, swap.l1_txid.to_hex())
-                })
+                app.node
+                    .l1_config()
+                    .backend(swap.parent_chain)
+                    .map(|backend| (swap.id, backend, swap.l1_txid.to_hex()))
             })
             .collect();
 
-        let results: Vec<(SwapId, u32)> = std::thread::spawn(move || {
-            work.into_iter()
-                .filter_map(|(swap_id, rpc_config, l1_txid_hex)| {
-                    let client = ParentChainRpcClient::new(rpc_config);
-                    client
-                        .get_transaction_confirmations(&l1_txid_hex)
-                        .ok()
-                        .map(|c| (swap_id, c))
-                })
-                .collect()
-        })
-        .join()
-        .unwrap_or_default();
+        let results: Vec<(SwapId, u32)> = app.runtime.block_on(async move {
+            let mut results = Vec::new();
+            for (swap_id, backend, l1_txid_hex) in work {
+                if let Ok(confirmations) =
+                    backend.get_transaction_confirmations(&l1_txid_hex).await
+                {
+                    results.push((swap_id, confirmations));
+                }
+            }
+            results
+        });
 
         let mut updated_count = 0;
         let mut rwtxn = match app.node.env().write_txn() {
@@ -744,5 +744,9 @@ fn state_display(state: &SwapState) -> (String, egui::Color32) {
         SwapState::Cancelled => {
             ("Cancelled".into(), egui::Color32::from_rgb(150, 150, 150))
         }
+        SwapState::PartiallyFilled(filled, total) => (
+            format!("Filled {}/{} sats", filled, total),
+            egui::Color32::from_rgb(255, 200, 120),
+        ),
     }
 }
@@ -6,16 +6,21 @@ use crate::app::App;
 mod create;
 mod detail;
 mod list;
+mod offers;
+mod quote;
 
 use create::CreateSwap;
 use detail::SwapDetail;
 use list::SwapList;
+use offers::SwapOffers;
 
 #[derive(Default, EnumIter, Eq, PartialEq, strum::Display)]
 enum Tab {
     #[default]
     #[strum(to_string = "Create Swap")]
     Create,
+    #[strum(to_string = "L1 → L2 Offers")]
+    Offers,
     #[strum(to_string = "Swap List")]
     List,
     #[strum(to_string = "Swap Detail")]
@@ -24,6 +29,7 @@ enum Tab {
 
 pub struct Swap {
     create: CreateSwap,
+    offers: SwapOffers,
     list: SwapList,
     detail: SwapDetail,
     tab: Tab,
@@ -33,6 +39,7 @@ impl Swap {
     pub fn new(app: Option<&App>) -> Self {
         Self {
             create: CreateSwap::default(),
+            offers: SwapOffers::default(),
             list: SwapList::new(app),
             detail: SwapDetail::default(),
             tab: Tab::default(),
@@ -52,6 +59,9 @@ impl Swap {
             Tab::Create => {
                 self.create.show(app, ui);
             }
+            Tab::Offers => {
+                self.offers.show(app, ui);
+            }
             Tab::List => {
                 if let Some(swap) = self.list.show(app, ui) {
                     self.detail.set_swap(swap);
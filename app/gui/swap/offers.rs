@@ -0,0 +1,402 @@
+use coinshift::parent_chain_rpc;
+use coinshift::types::{Address, OutPoint, ParentChainType, Swap, SwapId};
+use eframe::egui::{self, Button, Color32, ComboBox, RichText, TextEdit};
+
+use crate::app::App;
+use crate::gui::util::{
+    show_confirmations_hint, show_l1_amount, show_l2_amount,
+};
+
+/// L1 → L2 swap offers: offer L1 coins for L2 coins, or accept someone
+/// else's offer by locking L2 coins.
+#[derive(Debug)]
+pub struct SwapOffers {
+    parent_chain: ParentChainType,
+    l1_amount: String,
+    l2_recipient: String,
+    l2_amount: String,
+    required_confirmations: String,
+    /// L1 address used when accepting an offer
+    accept_l1_address: String,
+    /// Open offers, awaiting acceptance
+    offers: Option<Vec<Swap>>,
+    error_message: Option<String>,
+    success_message: Option<String>,
+}
+
+impl Default for SwapOffers {
+    fn default() -> Self {
+        let supported = parent_chain_rpc::supported_l1_parent_chain_types();
+        let first = supported
+            .first()
+            .copied()
+            .unwrap_or(ParentChainType::Signet);
+        Self {
+            parent_chain: first,
+            l1_amount: String::new(),
+            l2_recipient: String::new(),
+            l2_amount: String::new(),
+            required_confirmations: String::new(),
+            accept_l1_address: String::new(),
+            offers: None,
+            error_message: None,
+            success_message: None,
+        }
+    }
+}
+
+/// Check whether an outpoint is locked to a swap, for coin selection
+fn is_locked(app: &App, outpoint: &OutPoint) -> bool {
+    let rotxn = match app.node.env().read_txn() {
+        Ok(txn) => txn,
+        Err(_) => return false,
+    };
+    app.node
+        .state()
+        .is_output_locked_to_swap(&rotxn, outpoint)
+        .map(|opt| opt.is_some())
+        .unwrap_or(false)
+}
+
+impl SwapOffers {
+    fn refresh_offers(&mut self, app: &App) {
+        let rotxn = match app.node.env().read_txn() {
+            Ok(txn) => txn,
+            Err(err) => {
+                tracing::error!("Failed to get read transaction: {err:#}");
+                return;
+            }
+        };
+        match app.node.state().load_all_swaps(&rotxn) {
+            Ok(swaps) => {
+                self.offers = Some(
+                    swaps
+                        .into_iter()
+                        .filter(Swap::is_awaiting_acceptance)
+                        .collect(),
+                );
+            }
+            Err(err) => {
+                tracing::error!("Failed to list swaps: {err:#}");
+            }
+        }
+    }
+
+    pub fn show(&mut self, app: Option<&App>, ui: &mut egui::Ui) {
+        if self.offers.is_none()
+            && let Some(app) = app
+        {
+            self.refresh_offers(app);
+        }
+
+        ui.heading("Swap Offers (L1 → L2)");
+        ui.add_space(4.0);
+        ui.label(
+            RichText::new("Offer L1 coins for L2 coins. Whoever accepts your offer locks their L2 coins and gives you an L1 address; once you have sent the L1 coins, claim the L2 coins from the swap detail.")
+                .small()
+                .color(Color32::GRAY),
+        );
+
+        if let Some(error_msg) = &self.error_message {
+            ui.label(
+                RichText::new(format!("Error: {}", error_msg))
+                    .small()
+                    .color(Color32::RED),
+            );
+        }
+        if let Some(success_msg) = &self.success_message {
+            ui.label(
+                RichText::new(success_msg)
+                    .small()
+                    .color(Color32::from_rgb(100, 200, 100)),
+            );
+        }
+        ui.separator();
+
+        self.show_create(app, ui);
+        ui.separator();
+        self.show_open_offers(app, ui);
+    }
+
+    fn show_create(&mut self, app: Option<&App>, ui: &mut egui::Ui) {
+        ui.label(RichText::new("Create offer").strong());
+
+        ui.horizontal(|ui| {
+            ui.label("Parent chain:");
+            let supported = parent_chain_rpc::supported_l1_parent_chain_types();
+            let label = if supported.contains(&self.parent_chain) {
+                self.parent_chain.display_name()
+            } else {
+                "Select network".to_owned()
+            };
+            ComboBox::from_id_salt("offer_parent_chain")
+                .selected_text(label)
+                .show_ui(ui, |ui| {
+                    for chain in supported {
+                        ui.selectable_value(
+                            &mut self.parent_chain,
+                            *chain,
+                            chain.display_name(),
+                        );
+                    }
+                });
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "Amount you offer ({})",
+                self.parent_chain.ticker()
+            ));
+            ui.add(
+                TextEdit::singleline(&mut self.l1_amount)
+                    .hint_text("e.g. 0.001"),
+            );
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("L2 amount you want:");
+            ui.add(
+                TextEdit::singleline(&mut self.l2_amount)
+                    .hint_text("e.g. 0.001"),
+            );
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Your L2 address:");
+            ui.add(
+                TextEdit::singleline(&mut self.l2_recipient)
+                    .hint_text("leave empty for a new wallet address"),
+            );
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Required L1 confirmations:");
+            ui.add(
+                TextEdit::singleline(&mut self.required_confirmations)
+                    .hint_text("leave empty for suggested"),
+            );
+            let l1_amount = bitcoin::Amount::from_str_in(
+                &self.l1_amount,
+                bitcoin::Denomination::Bitcoin,
+            );
+            ui.label(show_confirmations_hint(
+                self.parent_chain,
+                l1_amount.ok(),
+            ));
+        });
+
+        let l1_amount = bitcoin::Amount::from_str_in(
+            &self.l1_amount,
+            bitcoin::Denomination::Bitcoin,
+        );
+        let l2_amount = bitcoin::Amount::from_str_in(
+            &self.l2_amount,
+            bitcoin::Denomination::Bitcoin,
+        );
+        let l2_recipient = match self.l2_recipient.trim() {
+            "" => Ok(None),
+            l2_recipient => l2_recipient.parse::<Address>().map(Some),
+        };
+        // Swaps must require at least the minimum for their L1 amount
+        let required_confirmations = match self.required_confirmations.trim() {
+            "" => Some(None),
+            confirmations => confirmations
+                .parse::<u32>()
+                .ok()
+                .filter(|confirmations| {
+                    l1_amount.as_ref().is_ok_and(|l1_amount| {
+                        *confirmations
+                            >= self.parent_chain.min_confirmations(*l1_amount)
+                    })
+                })
+                .map(Some),
+        };
+
+        let is_valid = app.is_some()
+            && l1_amount.is_ok()
+            && l2_amount.is_ok()
+            && l2_recipient.is_ok()
+            && required_confirmations.is_some();
+
+        if ui
+            .add_enabled(is_valid, Button::new("Create Offer"))
+            .clicked()
+        {
+            let app = app.unwrap();
+            self.error_message = None;
+            self.success_message = None;
+            let l2_recipient = match l2_recipient.expect("should not happen") {
+                Some(addr) => addr,
+                None => match app.wallet.get_new_address() {
+                    Ok(addr) => addr,
+                    Err(err) => {
+                        self.error_message =
+                            Some(format!("Failed to get address: {err:#}"));
+                        return;
+                    }
+                },
+            };
+            let accumulator = match app.node.get_tip_accumulator() {
+                Ok(acc) => acc,
+                Err(err) => {
+                    self.error_message =
+                        Some(format!("Failed to get accumulator: {err:#}"));
+                    return;
+                }
+            };
+            let (tx, swap_id) = match app.wallet.create_l1_to_l2_swap_create_tx(
+                &accumulator,
+                self.parent_chain,
+                l1_amount.expect("should not happen"),
+                l2_recipient,
+                l2_amount.expect("should not happen"),
+                required_confirmations.expect("should not happen"),
+                bitcoin::Amount::ZERO,
+                |outpoint| is_locked(app, outpoint),
+            ) {
+                Ok(result) => result,
+                Err(err) => {
+                    self.error_message = Some(format!(
+                        "Failed to create offer transaction: {err:#}"
+                    ));
+                    return;
+                }
+            };
+            let txid = tx.txid();
+            if let Err(err) = app.sign_and_send(tx) {
+                self.error_message =
+                    Some(format!("Failed to send transaction: {err:#}"));
+                return;
+            }
+            app.node.add_created_pending_swap(swap_id);
+            tracing::info!(%swap_id, %txid, "Swap offer created");
+            self.l1_amount.clear();
+            self.l2_amount.clear();
+            self.l2_recipient.clear();
+            self.required_confirmations.clear();
+            self.success_message =
+                Some(format!("Offer created: {swap_id} (txid {txid})"));
+        }
+    }
+
+    fn show_open_offers(&mut self, app: Option<&App>, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(RichText::new("Open offers").strong());
+            if ui.button("Refresh").clicked()
+                && let Some(app) = app
+            {
+                self.refresh_offers(app);
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Your L1 address:");
+            ui.add(
+                TextEdit::singleline(&mut self.accept_l1_address)
+                    .hint_text("Where you receive L1 coins when accepting"),
+            );
+        });
+
+        let Some(offers) = self.offers.clone() else {
+            return;
+        };
+        if offers.is_empty() {
+            ui.label("No open offers.");
+            return;
+        }
+
+        let mut accept: Option<Swap> = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("swap_offers_grid")
+                .num_columns(5)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label(RichText::new("Swap ID").strong());
+                    ui.label(RichText::new("You send (L2)").strong());
+                    ui.label(RichText::new("You receive (L1)").strong());
+                    ui.label(RichText::new("Expires at").strong());
+                    ui.label("");
+                    ui.end_row();
+
+                    for swap in &offers {
+                        let id_hex = hex::encode(swap.id.0);
+                        ui.label(
+                            RichText::new(format!("{}...", &id_hex[..10]))
+                                .monospace(),
+                        )
+                        .on_hover_text(&id_hex);
+                        ui.label(show_l2_amount(swap.l2_amount));
+                        ui.label(
+                            swap.l1_amount
+                                .map(|amount| {
+                                    show_l1_amount(amount, swap.parent_chain)
+                                })
+                                .unwrap_or_else(|| "N/A".to_string()),
+                        );
+                        ui.label(
+                            swap.expires_at_height
+                                .map(|height| height.to_string())
+                                .unwrap_or_else(|| "never".to_string()),
+                        );
+                        let can_accept = app.is_some()
+                            && !self.accept_l1_address.trim().is_empty();
+                        if ui
+                            .add_enabled(can_accept, Button::new("Accept"))
+                            .clicked()
+                        {
+                            accept = Some(swap.clone());
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+
+        if let Some(swap) = accept
+            && let Some(app) = app
+        {
+            self.accept_offer(app, &swap);
+        }
+    }
+
+    fn accept_offer(&mut self, app: &App, swap: &Swap) {
+        self.error_message = None;
+        self.success_message = None;
+        let swap_id: SwapId = swap.id;
+        let accumulator = match app.node.get_tip_accumulator() {
+            Ok(acc) => acc,
+            Err(err) => {
+                self.error_message =
+                    Some(format!("Failed to get accumulator: {err:#}"));
+                return;
+            }
+        };
+        let tx = match app.wallet.create_swap_accept_tx(
+            &accumulator,
+            swap_id,
+            self.accept_l1_address.trim().to_owned(),
+            swap.l2_amount,
+            bitcoin::Amount::ZERO,
+            |outpoint| is_locked(app, outpoint),
+        ) {
+            Ok(tx) => tx,
+            Err(err) => {
+                self.error_message = Some(format!(
+                    "Failed to create accept transaction: {err:#}"
+                ));
+                return;
+            }
+        };
+        let txid = tx.txid();
+        if let Err(err) = app.sign_and_send(tx) {
+            self.error_message =
+                Some(format!("Failed to send transaction: {err:#}"));
+            return;
+        }
+        tracing::info!(%swap_id, %txid, "Swap offer accepted");
+        self.success_message = Some(format!(
+            "Offer {swap_id} accepted (txid {txid}). Wait for the creator \
+             to send the L1 coins."
+        ));
+        self.refresh_offers(app);
+    }
+}
@@ -0,0 +1,201 @@
+use coinshift::parent_chain_rpc;
+use coinshift::types::{ParentChainType, SwapId, SwapOffer, SwapQuote};
+use eframe::egui::{self, Button, Color32, ComboBox, RichText, TextEdit};
+
+use crate::app::App;
+use crate::gui::util::{show_l1_amount, show_l2_amount};
+
+/// Open L2 → L1 swaps of a parent chain by price, and the best fill of an
+/// L1 amount from them
+pub struct SwapQuotes {
+    parent_chain: ParentChainType,
+    offers: Option<Vec<SwapOffer>>,
+    /// L1 amount to quote
+    l1_amount: String,
+}
+
+impl Default for SwapQuotes {
+    fn default() -> Self {
+        let supported = parent_chain_rpc::supported_l1_parent_chain_types();
+        let first = supported
+            .first()
+            .copied()
+            .unwrap_or(ParentChainType::Signet);
+        Self {
+            parent_chain: first,
+            offers: None,
+            l1_amount: String::new(),
+        }
+    }
+}
+
+impl SwapQuotes {
+    pub fn refresh(&mut self, app: &App) {
+        let rotxn = match app.node.env().read_txn() {
+            Ok(txn) => txn,
+            Err(err) => {
+                tracing::error!("Failed to get read transaction: {err:#}");
+                return;
+            }
+        };
+        match app.node.state().get_swap_offers(&rotxn, self.parent_chain) {
+            Ok(offers) => self.offers = Some(offers),
+            Err(err) => {
+                tracing::error!("Failed to list swap offers: {err:#}");
+            }
+        }
+    }
+
+    /// Returns `Some(swap_id)` when the user clicks "View" on an offer.
+    pub fn show(
+        &mut self,
+        app: Option<&App>,
+        ui: &mut egui::Ui,
+    ) -> Option<SwapId> {
+        let mut view = None;
+        egui::CollapsingHeader::new("Open swaps by price").show(ui, |ui| {
+            let parent_chain = self.parent_chain;
+            ui.horizontal(|ui| {
+                ui.label("Parent chain:");
+                ComboBox::from_id_salt("quote_parent_chain")
+                    .selected_text(self.parent_chain.display_name())
+                    .show_ui(ui, |ui| {
+                        for chain in
+                            parent_chain_rpc::supported_l1_parent_chain_types()
+                        {
+                            ui.selectable_value(
+                                &mut self.parent_chain,
+                                *chain,
+                                chain.display_name(),
+                            );
+                        }
+                    });
+            });
+            if self.parent_chain != parent_chain
+                && let Some(app) = app
+            {
+                self.refresh(app);
+            }
+            let Some(offers) = &self.offers else {
+                ui.label("No offers loaded. Click Refresh.");
+                return;
+            };
+            if offers.is_empty() {
+                ui.label("No open swaps can be filled.");
+                return;
+            }
+            let ticker = self.parent_chain.ticker();
+            egui::Grid::new("swap_quotes_grid")
+                .num_columns(6)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label(RichText::new("Swap ID").strong());
+                    ui.label(
+                        RichText::new(format!("Price (L2 per {ticker})"))
+                            .strong(),
+                    );
+                    ui.label(RichText::new("L1 Outstanding").strong());
+                    ui.label(RichText::new("L2 Outstanding").strong());
+                    ui.label(RichText::new("Min Fill").strong());
+                    ui.label("");
+                    ui.end_row();
+                    for offer in offers {
+                        let id_hex = hex::encode(offer.swap_id.0);
+                        ui.monospace(format!("{}...", &id_hex[..10]))
+                            .on_hover_text(&id_hex);
+                        ui.label(show_l2_amount(offer.price));
+                        ui.label(show_l1_amount(
+                            offer.outstanding_l1_amount(),
+                            offer.parent_chain,
+                        ));
+                        ui.label(show_l2_amount(offer.outstanding_l2_amount()));
+                        let min_fill = if offer.allows_partial_fills {
+                            show_l1_amount(
+                                offer.min_fill_l1_amount,
+                                offer.parent_chain,
+                            )
+                        } else {
+                            "single fill".to_owned()
+                        };
+                        ui.label(min_fill);
+                        if ui.add(Button::new("View")).clicked() {
+                            view = Some(offer.swap_id);
+                        }
+                        ui.end_row();
+                    }
+                });
+
+            ui.add_space(4.0);
+            ui.horizontal(|ui| {
+                ui.label(format!("Best fill of up to ({ticker}):"));
+                ui.add(
+                    TextEdit::singleline(&mut self.l1_amount)
+                        .hint_text("e.g. 0.5"),
+                );
+            });
+            let Ok(l1_amount) = bitcoin::Amount::from_str_in(
+                &self.l1_amount,
+                bitcoin::Denomination::Bitcoin,
+            ) else {
+                return;
+            };
+            let quote = SwapQuote::new(self.parent_chain, offers, l1_amount);
+            let Some(price) = quote.price else {
+                ui.label(
+                    RichText::new(
+                        "No open swaps can be filled with this amount",
+                    )
+                    .color(Color32::GRAY),
+                );
+                return;
+            };
+            ui.label(format!(
+                "Pay {} for {} L2 (average price {}) across {} swap(s)",
+                show_l1_amount(quote.l1_amount, quote.parent_chain),
+                show_l2_amount(quote.l2_amount),
+                show_l2_amount(price),
+                quote.fills.len()
+            ));
+            for fill in &quote.fills {
+                ui.label(
+                    RichText::new(format!(
+                        "{}: pay {} for {} L2",
+                        hex::encode(fill.swap_id.0),
+                        show_l1_amount(fill.l1_amount, quote.parent_chain),
+                        show_l2_amount(fill.l2_amount)
+                    ))
+                    .small()
+                    .monospace(),
+                );
+            }
+            if quote.fills.iter().any(|fill| {
+                offers.iter().any(|offer| {
+                    offer.swap_id == fill.swap_id && !offer.allows_partial_fills
+                })
+            }) {
+                ui.label(
+                    RichText::new(
+                        "Single-fill swaps must be reserved before paying.",
+                    )
+                    .small()
+                    .color(Color32::GRAY),
+                );
+            }
+            if quote.fills.iter().any(|fill| {
+                offers.iter().any(|offer| {
+                    offer.swap_id == fill.swap_id && offer.allows_partial_fills
+                })
+            }) {
+                ui.label(
+                    RichText::new(
+                        "Partial fills must declare your L2 address in an \
+                         OP_RETURN output of the L1 payment.",
+                    )
+                    .small()
+                    .color(Color32::GRAY),
+                );
+            }
+        });
+        view
+    }
+}
@@ -31,21 +31,29 @@ pub fn show_l1_amount(
     amount: bitcoin::Amount,
     chain: coinshift::types::ParentChainType,
 ) -> String {
-    use coinshift::types::ParentChainType;
-    let prefix = match chain {
-        ParentChainType::BTC => "BTC",
-        ParentChainType::BCH => "BCH",
-        ParentChainType::LTC => "LTC",
-        ParentChainType::Signet => "sBTC",
-        ParentChainType::Regtest => "rBTC",
-    };
     format!(
         "{} {}",
-        prefix,
+        chain.ticker(),
         amount.to_string_in(bitcoin::Denomination::Bitcoin)
     )
 }
 
+/// Hint for the required L1 confirmations of a swap: suggested and minimum
+/// confirmations for its L1 amount if valid, or the chain's default
+pub fn show_confirmations_hint(
+    parent_chain: coinshift::types::ParentChainType,
+    l1_amount: Option<bitcoin::Amount>,
+) -> String {
+    match l1_amount {
+        Some(l1_amount) => format!(
+            "(suggested: {}, minimum: {})",
+            parent_chain.suggested_confirmations(l1_amount),
+            parent_chain.min_confirmations(l1_amount)
+        ),
+        None => format!("(default: {})", parent_chain.default_confirmations()),
+    }
+}
+
 // extension for InnerResponse<Response> and InnerResponse<Option<Response>>
 pub trait InnerResponseExt {
     #[allow(dead_code)]
@@ -1,8 +1,12 @@
-use std::path::{Path, PathBuf};
+use std::path::Path;
 
 use clap::Parser as _;
-use coinshift::parent_chain_rpc;
 use coinshift::types::ParentChainType;
+use coinshift::{
+    chain_registry::{BCH_TESTNET4_ID, builtin_network},
+    l1_config::L1Config,
+    parent_chain_rpc::L1Endpoint,
+};
 use mimalloc::MiMalloc;
 use tokio::{signal::ctrl_c, sync::oneshot};
 use tracing_subscriber::{
@@ -199,26 +203,24 @@ fn run_egui_app(
     )
 }
 
-fn l1_config_path() -> PathBuf {
-    dirs::data_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("coinshift")
-        .join("l1_rpc_configs.json")
-}
-
+/// Write the L1 config file with Bitcoin Core RPC endpoints given by URL
 fn write_l1_config_from_flags(
-    l1_signet: bool,
-    l1_bch_testnet4: bool,
+    l1_config: &L1Config,
+    l1_signet: Option<&str>,
+    l1_bch_testnet4: Option<&str>,
 ) -> anyhow::Result<()> {
-    let path = l1_config_path();
-    let mut chains = Vec::new();
-    if l1_signet {
-        chains.push(ParentChainType::Signet);
+    let mut endpoints = Vec::new();
+    if let Some(url) = l1_signet {
+        endpoints
+            .push((ParentChainType::Signet, L1Endpoint::bitcoin_core(url)?));
     }
-    if l1_bch_testnet4 {
-        chains.push(ParentChainType::BCH);
+    if let Some(url) = l1_bch_testnet4 {
+        endpoints.push((
+            builtin_network(BCH_TESTNET4_ID),
+            L1Endpoint::bitcoin_core(url)?,
+        ));
     }
-    parent_chain_rpc::write_l1_config_file(&path, &chains)?;
+    let () = l1_config.set_endpoints(&endpoints)?;
     Ok(())
 }
 
@@ -226,6 +228,7 @@ fn main() -> anyhow::Result<()> {
     // Configure the allocator before Tokio spins up worker threads.
     configure_mimalloc();
     let cli = cli::Cli::parse();
+    let l1_config = L1Config::new(&cli.run.datadir());
 
     // Handle init subcommand: write L1 config and exit
     if let Some(cli::AppSubcommand::Init {
@@ -233,15 +236,11 @@ fn main() -> anyhow::Result<()> {
         l1_bch_testnet4,
     }) = &cli.command
     {
-        let path = l1_config_path();
-        let mut chains = Vec::new();
-        if *l1_signet {
-            chains.push(ParentChainType::Signet);
-        }
-        if *l1_bch_testnet4 {
-            chains.push(ParentChainType::BCH);
-        }
-        parent_chain_rpc::write_l1_config_file(&path, &chains)?;
+        write_l1_config_from_flags(
+            &l1_config,
+            l1_signet.as_deref(),
+            l1_bch_testnet4.as_deref(),
+        )?;
         tracing_subscriber::fmt()
             .with_writer(std::io::stdout)
             .with_ansi(std::io::IsTerminal::is_terminal(&std::io::stdout()))
@@ -249,15 +248,19 @@ fn main() -> anyhow::Result<()> {
             .init();
         tracing::info!(
             "L1 config written to {} (Signet: {}, BCH Testnet4: {})",
-            path.display(),
-            l1_signet,
-            l1_bch_testnet4
+            l1_config.path().display(),
+            l1_signet.is_some(),
+            l1_bch_testnet4.is_some()
         );
         return Ok(());
     }
 
-    if cli.run.l1_signet || cli.run.l1_bch_testnet4 {
-        write_l1_config_from_flags(cli.run.l1_signet, cli.run.l1_bch_testnet4)?;
+    if cli.run.l1_signet.is_some() || cli.run.l1_bch_testnet4.is_some() {
+        write_l1_config_from_flags(
+            &l1_config,
+            cli.run.l1_signet.as_deref(),
+            cli.run.l1_bch_testnet4.as_deref(),
+        )?;
     }
     let config = cli.run.get_config()?;
     let (line_buffer, _rolling_log_guard) = set_tracing_subscriber(
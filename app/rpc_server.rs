@@ -1,12 +1,15 @@
-use std::net::SocketAddr;
+use std::{collections::HashMap, net::SocketAddr};
 
 use bitcoin::Amount;
 use coinshift::{
+    l1_headers::L1Tip,
     net::Peer,
+    parent_chain_rpc::{self, L1ConfigEntry, L1Endpoint},
     state,
     types::{
-        Address, ParentChainType, PointedOutput, Swap, SwapId, SwapState,
-        SwapTxId, Txid, WithdrawalBundle,
+        Address, L1InclusionProof, OutPoint, ParentChainType, PointedOutput,
+        Swap, SwapFill, SwapId, SwapOffer, SwapQuote, SwapState, SwapTxId,
+        Txid, VerifiedL1Fill, WithdrawalBundle, quote,
     },
     wallet::Balance,
 };
@@ -57,6 +60,45 @@ impl RpcServerImpl {
             Err(state::Error::SwapNotCreator)
         }
     }
+
+    /// Get an unspent HTLC output
+    fn get_htlc(
+        &self,
+        outpoint: OutPoint,
+    ) -> RpcResult<(OutPoint, coinshift::types::Output)> {
+        let output = self
+            .app
+            .node
+            .get_all_utxos()
+            .map_err(custom_err)?
+            .remove(&outpoint)
+            .ok_or_else(|| custom_err_msg(format!("No UTXO {outpoint}")))?;
+        if !output.content.is_htlc() {
+            return Err(custom_err_msg(format!("{outpoint} is not an HTLC")));
+        }
+        Ok((outpoint, output))
+    }
+
+    /// Check whether an outpoint is locked to a swap, for coin selection
+    fn is_output_locked(&self, outpoint: &coinshift::types::OutPoint) -> bool {
+        let node = &self.app.node;
+        let rotxn = match node.env().read_txn() {
+            Ok(txn) => txn,
+            Err(_) => {
+                tracing::warn!(
+                    "Failed to create read transaction for locked output check"
+                );
+                return false;
+            }
+        };
+        match node.state().is_output_locked_to_swap(&rotxn, outpoint) {
+            Ok(locked_swap_id) => locked_swap_id.is_some(),
+            Err(err) => {
+                tracing::warn!(outpoint = ?outpoint, error = %err, "Error checking if output is locked");
+                false
+            }
+        }
+    }
 }
 
 fn custom_err_msg(err_msg: impl Into<String>) -> ErrorObject<'static> {
@@ -70,6 +112,18 @@ where
     let error = anyhow::Error::from(error);
     custom_err_msg(format!("{error:#}"))
 }
+
+/// Parse a hex-encoded 32-byte value, such as an HTLC hash or preimage
+fn parse_hex32(name: &str, value_hex: &str) -> RpcResult<[u8; 32]> {
+    let bytes = hex::decode(value_hex.trim())
+        .map_err(|err| custom_err_msg(format!("Invalid {name} hex: {err}")))?;
+    bytes.try_into().map_err(|bytes: Vec<u8>| {
+        custom_err_msg(format!(
+            "{name} must be 32 bytes, got {} bytes",
+            bytes.len()
+        ))
+    })
+}
 #[async_trait]
 impl RpcServer for RpcServerImpl {
     async fn balance(&self) -> RpcResult<Balance> {
@@ -322,6 +376,89 @@ impl RpcServer for RpcServerImpl {
         Ok(txid)
     }
 
+    async fn create_htlc(
+        &self,
+        recipient: Address,
+        value_sats: u64,
+        payment_hash_hex: String,
+        refund_height: u32,
+        fee_sats: u64,
+    ) -> RpcResult<Txid> {
+        let payment_hash = parse_hex32("payment hash", &payment_hash_hex)?;
+        let accumulator =
+            self.app.node.get_tip_accumulator().map_err(custom_err)?;
+        let (tx, refund_address) = self
+            .app
+            .wallet
+            .create_htlc_tx(
+                &accumulator,
+                recipient,
+                Amount::from_sat(value_sats),
+                payment_hash,
+                refund_height,
+                Amount::from_sat(fee_sats),
+                |outpoint| self.is_output_locked(outpoint),
+            )
+            .map_err(custom_err)?;
+        let txid = tx.txid();
+        self.app.sign_and_send(tx).map_err(custom_err)?;
+        tracing::info!(%txid, %refund_address, refund_height, "Created HTLC");
+        Ok(txid)
+    }
+
+    async fn redeem_htlc(
+        &self,
+        outpoint: OutPoint,
+        preimage_hex: String,
+        fee_sats: u64,
+    ) -> RpcResult<Txid> {
+        let preimage = parse_hex32("preimage", &preimage_hex)?;
+        let htlc = self.get_htlc(outpoint)?;
+        let accumulator =
+            self.app.node.get_tip_accumulator().map_err(custom_err)?;
+        let tx = self
+            .app
+            .wallet
+            .create_htlc_redeem_tx(
+                &accumulator,
+                htlc,
+                preimage,
+                Amount::from_sat(fee_sats),
+            )
+            .map_err(custom_err)?;
+        let txid = tx.txid();
+        self.app.sign_and_send(tx).map_err(custom_err)?;
+        Ok(txid)
+    }
+
+    async fn refund_htlc(
+        &self,
+        outpoint: OutPoint,
+        fee_sats: u64,
+    ) -> RpcResult<Txid> {
+        let htlc = self.get_htlc(outpoint)?;
+        // The HTLC is owned by its recipient, so it is not in our wallet
+        // UTXOs; add it so that authorize() can find it
+        self.app
+            .wallet
+            .put_utxos(&HashMap::from([htlc.clone()]))
+            .map_err(custom_err)?;
+        let accumulator =
+            self.app.node.get_tip_accumulator().map_err(custom_err)?;
+        let tx = self
+            .app
+            .wallet
+            .create_htlc_refund_tx(
+                &accumulator,
+                htlc,
+                Amount::from_sat(fee_sats),
+            )
+            .map_err(custom_err)?;
+        let txid = tx.txid();
+        self.app.sign_and_send(tx).map_err(custom_err)?;
+        Ok(txid)
+    }
+
     async fn create_swap(
         &self,
         parent_chain: ParentChainType,
@@ -330,6 +467,8 @@ impl RpcServer for RpcServerImpl {
         l2_recipient: Option<Address>, // Optional - None = open swap
         l2_amount_sats: u64,
         required_confirmations: Option<u32>,
+        min_fill_l1_amount_sats: Option<u64>,
+        expires_in_blocks: Option<u32>,
         fee_sats: u64,
     ) -> RpcResult<(SwapId, Txid)> {
         let accumulator =
@@ -374,6 +513,8 @@ impl RpcServer for RpcServerImpl {
                 l2_recipient, // Optional
                 Amount::from_sat(l2_amount_sats),
                 required_confirmations,
+                min_fill_l1_amount_sats.map(Amount::from_sat),
+                expires_in_blocks,
                 Amount::from_sat(fee_sats),
                 is_locked,
             )
@@ -384,6 +525,156 @@ impl RpcServer for RpcServerImpl {
         Ok((swap_id, txid))
     }
 
+    async fn create_l1_to_l2_swap(
+        &self,
+        parent_chain: ParentChainType,
+        l1_amount_sats: u64,
+        l2_recipient: Option<Address>,
+        l2_amount_sats: u64,
+        required_confirmations: Option<u32>,
+        fee_sats: u64,
+    ) -> RpcResult<(SwapId, Txid)> {
+        let accumulator =
+            self.app.node.get_tip_accumulator().map_err(custom_err)?;
+        let l2_recipient = match l2_recipient {
+            Some(l2_recipient) => l2_recipient,
+            None => self.app.wallet.get_new_address().map_err(custom_err)?,
+        };
+        let (tx, swap_id) = self
+            .app
+            .wallet
+            .create_l1_to_l2_swap_create_tx(
+                &accumulator,
+                parent_chain,
+                Amount::from_sat(l1_amount_sats),
+                l2_recipient,
+                Amount::from_sat(l2_amount_sats),
+                required_confirmations,
+                Amount::from_sat(fee_sats),
+                |outpoint| self.is_output_locked(outpoint),
+            )
+            .map_err(custom_err)?;
+        let txid = tx.txid();
+        self.app.sign_and_send(tx).map_err(custom_err)?;
+        self.app.node.add_created_pending_swap(swap_id);
+        Ok((swap_id, txid))
+    }
+
+    async fn get_swap_confirmations(
+        &self,
+        parent_chain: ParentChainType,
+        l1_amount_sats: u64,
+    ) -> RpcResult<u32> {
+        Ok(parent_chain
+            .suggested_confirmations(Amount::from_sat(l1_amount_sats)))
+    }
+
+    async fn create_swap_at_price(
+        &self,
+        parent_chain: ParentChainType,
+        l1_recipient_address: String,
+        l1_amount_sats: u64,
+        l2_recipient: Option<Address>,
+        price_sats: u64,
+        required_confirmations: Option<u32>,
+        min_fill_l1_amount_sats: Option<u64>,
+        expires_in_blocks: Option<u32>,
+        fee_sats: u64,
+    ) -> RpcResult<(SwapId, Txid)> {
+        let l2_amount = quote::l2_amount_at_price(
+            parent_chain,
+            Amount::from_sat(l1_amount_sats),
+            Amount::from_sat(price_sats),
+        )
+        .filter(|l2_amount| *l2_amount > Amount::ZERO)
+        .ok_or_else(|| {
+            custom_err_msg(format!(
+                "Invalid L2 amount for {l1_amount_sats} L1 sats at a price \
+                 of {price_sats} sats"
+            ))
+        })?;
+        self.create_swap(
+            parent_chain,
+            l1_recipient_address,
+            l1_amount_sats,
+            l2_recipient,
+            l2_amount.to_sat(),
+            required_confirmations,
+            min_fill_l1_amount_sats,
+            expires_in_blocks,
+            fee_sats,
+        )
+        .await
+    }
+
+    async fn list_swap_offers(
+        &self,
+        parent_chain: ParentChainType,
+    ) -> RpcResult<Vec<SwapOffer>> {
+        let rotxn = self.app.node.env().read_txn().map_err(custom_err)?;
+        let offers = self
+            .app
+            .node
+            .state()
+            .get_swap_offers(&rotxn, parent_chain)
+            .map_err(custom_err)?;
+        Ok(offers)
+    }
+
+    async fn quote_swap(
+        &self,
+        parent_chain: ParentChainType,
+        l1_amount_sats: u64,
+    ) -> RpcResult<SwapQuote> {
+        let offers = self.list_swap_offers(parent_chain).await?;
+        Ok(SwapQuote::new(
+            parent_chain,
+            &offers,
+            Amount::from_sat(l1_amount_sats),
+        ))
+    }
+
+    async fn accept_swap(
+        &self,
+        swap_id: SwapId,
+        l1_recipient_address: String,
+        fee_sats: u64,
+    ) -> RpcResult<Txid> {
+        let swap = {
+            let rotxn = self.app.node.env().read_txn().map_err(custom_err)?;
+            self.app
+                .node
+                .state()
+                .get_swap(&rotxn, &swap_id)
+                .map_err(custom_err)?
+                .ok_or_else(|| custom_err_msg("Swap not found"))?
+        };
+        if !swap.is_awaiting_acceptance() {
+            return Err(custom_err_msg(format!(
+                "Swap {swap_id} is not an L1 → L2 offer awaiting acceptance \
+                 (direction: {:?}, state: {:?})",
+                swap.direction, swap.state
+            )));
+        }
+        let accumulator =
+            self.app.node.get_tip_accumulator().map_err(custom_err)?;
+        let tx = self
+            .app
+            .wallet
+            .create_swap_accept_tx(
+                &accumulator,
+                swap_id,
+                l1_recipient_address,
+                swap.l2_amount,
+                Amount::from_sat(fee_sats),
+                |outpoint| self.is_output_locked(outpoint),
+            )
+            .map_err(custom_err)?;
+        let txid = tx.txid();
+        self.app.sign_and_send(tx).map_err(custom_err)?;
+        Ok(txid)
+    }
+
     async fn reconstruct_swaps(&self) -> RpcResult<u32> {
         let mut rwtxn = self.app.node.env().write_txn().map_err(custom_err)?;
         let count = self
@@ -446,6 +737,20 @@ impl RpcServer for RpcServerImpl {
         Ok(())
     }
 
+    async fn get_swap_fills(
+        &self,
+        swap_id: SwapId,
+    ) -> RpcResult<Vec<SwapFill>> {
+        let rotxn = self.app.node.env().read_txn().map_err(custom_err)?;
+        let fills = self
+            .app
+            .node
+            .state()
+            .get_swap_fills(&rotxn, &swap_id)
+            .map_err(custom_err)?;
+        Ok(fills)
+    }
+
     async fn get_swap_status(
         &self,
         swap_id: SwapId,
@@ -464,23 +769,137 @@ impl RpcServer for RpcServerImpl {
         &self,
         swap_id: SwapId,
         l2_claimer_address: Option<Address>,
+        l1_proof_hex: Option<String>,
     ) -> RpcResult<Txid> {
+        let l1_proof_bytes = l1_proof_hex
+            .map(|proof_hex| {
+                hex::decode(proof_hex.trim()).map_err(|err| {
+                    custom_err_msg(format!("Invalid L1 proof hex: {err}"))
+                })
+            })
+            .transpose()?;
+
         // Get swap to verify it's ready and get recipient
-        let rotxn = self.app.node.env().read_txn().map_err(custom_err)?;
-        let swap = self
-            .app
-            .node
-            .state()
-            .get_swap(&rotxn, &swap_id)
-            .map_err(custom_err)?
-            .ok_or_else(|| custom_err_msg("Swap not found"))?;
+        let state = self.app.node.state();
+        let swap = {
+            let rotxn = self.app.node.env().read_txn().map_err(custom_err)?;
+            state
+                .get_swap(&rotxn, &swap_id)
+                .map_err(custom_err)?
+                .ok_or_else(|| custom_err_msg("Swap not found"))?
+        };
 
-        if !matches!(swap.state, SwapState::ReadyToClaim) {
-            return Err(custom_err_msg(format!(
-                "Swap is not ready to claim (state: {:?})",
-                swap.state
-            )));
+        // Determine recipient: pre-specified uses swap.l2_recipient; open uses stored or provided claimer address.
+        // A partial claim pays the provided claimer address, if any.
+        let recipient = if swap.allows_partial_fills() {
+            l2_claimer_address.or(swap.l2_recipient)
+        } else {
+            swap.l2_recipient
+                .or(swap
+                    .reservation
+                    .as_ref()
+                    .map(|reservation| reservation.l2_claimer_address))
+                .or(l2_claimer_address)
+                .or(swap.l2_claimer_address)
         }
+        .ok_or_else(|| {
+            custom_err_msg("Open swap requires l2_claimer_address (or set when L1 tx was submitted)")
+        })?;
+
+        // Claims must prove their L1 fills. Without proofs from the caller,
+        // build them from the L1 backend's copy of the fills' blocks.
+        let l1_backend = || {
+            self.app
+                .node
+                .l1_config()
+                .backend(swap.parent_chain)
+                .ok_or_else(|| {
+                    custom_err_msg(format!(
+                        "No L1 backend configured for {:?}; \
+                         provide an L1 inclusion proof",
+                        swap.parent_chain
+                    ))
+                })
+        };
+        let l1_proofs_provided = l1_proof_bytes.is_some();
+        let l1_proofs = match l1_proof_bytes {
+            Some(bytes) if swap.allows_partial_fills() => {
+                L1InclusionProof::decode_many(&bytes).map_err(custom_err)?
+            }
+            Some(bytes) => {
+                vec![L1InclusionProof::decode(&bytes).map_err(custom_err)?]
+            }
+            // Candidate proofs of the fills, selected below
+            None if swap.allows_partial_fills() => {
+                state::fetch_swap_fill_proofs(l1_backend()?.as_ref(), &swap)
+                    .await
+                    .map_err(custom_err)?
+            }
+            None => {
+                if !matches!(
+                    swap.state,
+                    SwapState::WaitingConfirmations(..)
+                        | SwapState::ReadyToClaim
+                ) {
+                    return Err(custom_err_msg(format!(
+                        "Swap has no detected L1 fill (state: {:?}); \
+                         provide an L1 inclusion proof",
+                        swap.state
+                    )));
+                }
+                let l1_proof = l1_backend()?
+                    .get_inclusion_proof(&swap.l1_txid.to_hex_rpc())
+                    .await
+                    .map_err(custom_err)?;
+                vec![l1_proof]
+            }
+        };
+
+        let rotxn = self.app.node.env().read_txn().map_err(custom_err)?;
+        // For partially fillable swaps, release the share of every fill
+        // that is proven for the recipient
+        let (l1_proofs, partial_release) = if swap.allows_partial_fills() {
+            let (l1_proofs, fills) = if l1_proofs_provided {
+                let mut fills = Vec::new();
+                for (fill, l1_fill) in state::proven_swap_fills(
+                    state, &rotxn, &swap, &recipient, &l1_proofs,
+                )
+                .map_err(custom_err)?
+                {
+                    let _: u32 = state::verify_l1_fill_depth(
+                        state, &rotxn, &swap, &l1_fill,
+                    )
+                    .map_err(custom_err)?;
+                    fills.push(fill);
+                }
+                (l1_proofs, fills)
+            } else {
+                state::prove_swap_fills(
+                    state, &rotxn, &swap, &recipient, l1_proofs,
+                )
+                .map_err(custom_err)?
+            };
+            if fills.is_empty() {
+                return Err(custom_err_msg(format!(
+                    "Swap has no confirmed L1 fills provable for {recipient}"
+                )));
+            }
+            let release: Amount = fills.iter().map(|fill| fill.l2_amount).sum();
+            (l1_proofs, Some(release))
+        } else {
+            // Reject a bad proof here rather than broadcasting a claim that
+            // every node will refuse
+            for l1_proof in &l1_proofs {
+                let _: VerifiedL1Fill = state::verify_swap_claim_proof(
+                    state,
+                    &rotxn,
+                    &swap,
+                    Some(&l1_proof.encode()),
+                )
+                .map_err(custom_err)?;
+            }
+            (l1_proofs, None)
+        };
 
         // Get locked outputs for this swap
         // Note: We must query the node directly, not the wallet, because the wallet
@@ -496,7 +915,17 @@ impl RpcServer for RpcServerImpl {
                     swap_id: locked_swap_id,
                     ..
                 } => {
-                    if *locked_swap_id == swap_id.0 {
+                    // A forfeited reservation bond keeps its SwapPending
+                    // content, but is no longer locked to the swap
+                    let is_locked_to_swap = *locked_swap_id == swap_id.0
+                        && self
+                            .app
+                            .node
+                            .state()
+                            .is_output_locked_to_swap(&rotxn, &outpoint)
+                            .map_err(custom_err)?
+                            == Some(swap_id);
+                    if is_locked_to_swap {
                         tracing::info!(
                             "Found locked output for swap {}: {:?}",
                             swap_id,
@@ -528,19 +957,9 @@ impl RpcServer for RpcServerImpl {
             )));
         }
 
-        // Determine recipient: pre-specified uses swap.l2_recipient; open uses stored or provided claimer address
-        let recipient = swap
-            .l2_recipient
-            .or(swap.l2_claimer_address)
-            .or(l2_claimer_address)
-            .ok_or_else(|| {
-                custom_err_msg("Open swap requires l2_claimer_address (or set when L1 tx was submitted)")
-            })?;
-
         // Add locked outputs to wallet temporarily so they can be used for signing
         // SwapPending outputs are normally filtered out, but we need them in the wallet
         // for the authorize() call to find the address and signing key
-        use std::collections::HashMap;
         let locked_utxos: HashMap<_, _> =
             locked_outputs.iter().cloned().collect();
         self.app
@@ -555,19 +974,35 @@ impl RpcServer for RpcServerImpl {
 
         let accumulator =
             self.app.node.get_tip_accumulator().map_err(custom_err)?;
-        let l2_claimer_for_tx =
-            swap.l2_recipient.is_none().then_some(recipient);
-        let tx = self
-            .app
-            .wallet
-            .create_swap_claim_tx(
+        let tx = match (partial_release, l1_proofs.as_slice()) {
+            (Some(release), _) => self.app.wallet.create_partial_swap_claim_tx(
                 &accumulator,
                 swap_id,
                 recipient,
                 locked_outputs,
-                l2_claimer_for_tx,
-            )
-            .map_err(custom_err)?;
+                release,
+                l2_claimer_address,
+                &l1_proofs,
+            ),
+            (None, [l1_proof]) => {
+                let l2_claimer_for_tx =
+                    swap.l2_recipient.is_none().then_some(recipient);
+                self.app.wallet.create_swap_claim_tx(
+                    &accumulator,
+                    swap_id,
+                    recipient,
+                    locked_outputs,
+                    l2_claimer_for_tx,
+                    l1_proof,
+                )
+            }
+            (None, _) => {
+                return Err(custom_err_msg(
+                    "Swap claim requires an L1 inclusion proof",
+                ));
+            }
+        }
+        .map_err(custom_err)?;
         let txid = tx.txid();
         self.app.sign_and_send(tx).map_err(custom_err)?;
         Ok(txid)
@@ -598,17 +1033,77 @@ impl RpcServer for RpcServerImpl {
         Ok(swaps)
     }
 
-    async fn cancel_swap(&self, swap_id: SwapId) -> RpcResult<()> {
-        let creator =
-            self.resolve_swap_creator(&swap_id).map_err(custom_err)?;
-        let mut rwtxn = self.app.node.env().write_txn().map_err(custom_err)?;
-        self.app
-            .node
-            .state()
-            .cancel_swap(&mut rwtxn, &swap_id, creator.as_ref())
+    async fn cancel_swap(
+        &self,
+        swap_id: SwapId,
+        fee_sats: u64,
+    ) -> RpcResult<Txid> {
+        let creator = self
+            .resolve_swap_creator(&swap_id)
+            .map_err(custom_err)?
+            .ok_or_else(|| custom_err_msg("Swap has no creator"))?;
+        let accumulator =
+            self.app.node.get_tip_accumulator().map_err(custom_err)?;
+        let tx = self
+            .app
+            .wallet
+            .create_swap_cancel_tx(
+                &accumulator,
+                swap_id,
+                creator,
+                Amount::from_sat(fee_sats),
+                |outpoint| self.is_output_locked(outpoint),
+            )
             .map_err(custom_err)?;
-        rwtxn.commit().map_err(custom_err)?;
-        Ok(())
+        let txid = tx.txid();
+        self.app.sign_and_send(tx).map_err(custom_err)?;
+        Ok(txid)
+    }
+
+    async fn reserve_swap(
+        &self,
+        swap_id: SwapId,
+        l2_claimer_address: Option<Address>,
+        reserve_blocks: Option<u32>,
+        fee_sats: u64,
+    ) -> RpcResult<Txid> {
+        let swap = {
+            let rotxn = self.app.node.env().read_txn().map_err(custom_err)?;
+            self.app
+                .node
+                .state()
+                .get_swap(&rotxn, &swap_id)
+                .map_err(custom_err)?
+                .ok_or_else(|| custom_err_msg("Swap not found"))?
+        };
+        let creator = swap
+            .l2_creator_address
+            .ok_or_else(|| custom_err_msg("Swap has no creator"))?;
+        let l2_claimer_address = match l2_claimer_address {
+            Some(address) => address,
+            None => self.app.wallet.get_new_address().map_err(custom_err)?,
+        };
+        let reserve_blocks = reserve_blocks
+            .unwrap_or_else(|| swap.parent_chain.max_swap_reservation_blocks());
+        let accumulator =
+            self.app.node.get_tip_accumulator().map_err(custom_err)?;
+        let tx = self
+            .app
+            .wallet
+            .create_swap_reserve_tx(
+                &accumulator,
+                swap_id,
+                creator,
+                l2_claimer_address,
+                reserve_blocks,
+                swap.reservation_bond(),
+                Amount::from_sat(fee_sats),
+                |outpoint| self.is_output_locked(outpoint),
+            )
+            .map_err(custom_err)?;
+        let txid = tx.txid();
+        self.app.sign_and_send(tx).map_err(custom_err)?;
+        Ok(txid)
     }
 
     async fn delete_swap(&self, swap_id: SwapId) -> RpcResult<()> {
@@ -623,6 +1118,70 @@ impl RpcServer for RpcServerImpl {
         rwtxn.commit().map_err(custom_err)?;
         Ok(())
     }
+
+    async fn get_l1_header_tip(
+        &self,
+        parent_chain: ParentChainType,
+    ) -> RpcResult<Option<L1Tip>> {
+        self.app
+            .node
+            .try_get_l1_header_tip(parent_chain)
+            .map_err(custom_err)
+    }
+
+    async fn get_l1_config(
+        &self,
+        parent_chain: Option<ParentChainType>,
+    ) -> RpcResult<HashMap<ParentChainType, L1ConfigEntry>> {
+        let entries = self
+            .app
+            .node
+            .l1_config()
+            .entries()
+            .into_iter()
+            .filter(|(chain, _)| parent_chain.is_none_or(|c| c == *chain))
+            .map(|(chain, entry)| (chain, entry.redacted()))
+            .collect();
+        Ok(entries)
+    }
+
+    async fn set_l1_config(
+        &self,
+        parent_chain: ParentChainType,
+        endpoint: L1Endpoint,
+    ) -> RpcResult<()> {
+        let () = parent_chain_rpc::verify_l1_endpoint(parent_chain, &endpoint)
+            .await
+            .map_err(custom_err)?;
+        let l1_config = self.app.node.l1_config().clone();
+        tokio::task::spawn_blocking(move || {
+            l1_config
+                .set_endpoints(&[(parent_chain, endpoint)])
+                .map_err(custom_err)
+        })
+        .await
+        .unwrap()
+    }
+
+    async fn submit_l1_headers(
+        &self,
+        parent_chain: ParentChainType,
+        headers_hex: Vec<String>,
+    ) -> RpcResult<Txid> {
+        let headers = headers_hex
+            .iter()
+            .map(|header_hex| {
+                bitcoin::consensus::encode::deserialize_hex(header_hex.trim())
+                    .map_err(|err| {
+                        custom_err_msg(format!("Invalid L1 header hex: {err}"))
+                    })
+            })
+            .collect::<Result<Vec<bitcoin::block::Header>, _>>()?;
+        self.app
+            .node
+            .submit_l1_headers(parent_chain, &headers)
+            .map_err(custom_err)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -1,23 +1,15 @@
-use std::{
-    collections::HashMap, net::SocketAddr, path::PathBuf, time::Duration,
-};
+use std::{net::SocketAddr, path::PathBuf, time::Duration};
 
 use clap::{Parser, Subcommand};
 use http::HeaderMap;
 use jsonrpsee::{core::client::ClientT, http_client::HttpClientBuilder};
 
-use coinshift::parent_chain_rpc::RpcConfig;
-use coinshift::types::{Address, ParentChainType, SwapId, Txid};
+use coinshift::l1_backend::{L1BackendKind, tls::TlsConfig};
+use coinshift::parent_chain_rpc::L1Endpoint;
+use coinshift::types::{Address, OutPoint, ParentChainType, SwapId, Txid};
 use coinshift_app_rpc_api::RpcClient;
 use tracing_subscriber::{filter::Targets, layer::SubscriberExt as _};
 
-fn l1_config_path() -> PathBuf {
-    dirs::data_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("coinshift")
-        .join("l1_rpc_configs.json")
-}
-
 fn parse_swap_id(s: &str) -> anyhow::Result<SwapId> {
     let bytes = hex::decode(s)
         .map_err(|e| anyhow::anyhow!("invalid swap_id hex: {}", e))?;
@@ -34,8 +26,23 @@ fn parse_parent_chain(s: &str) -> anyhow::Result<ParentChainType> {
         "ltc" => Ok(ParentChainType::LTC),
         "signet" => Ok(ParentChainType::Signet),
         "regtest" => Ok(ParentChainType::Regtest),
+        // Chain registry ID
+        _ => s.parse().map_err(|_| {
+            anyhow::anyhow!(
+                "unknown parent_chain '{}', use: btc, bch, ltc, signet, regtest or a chain registry ID (e.g. BCH-testnet4)",
+                s
+            )
+        }),
+    }
+}
+
+fn parse_l1_backend(s: &str) -> anyhow::Result<L1BackendKind> {
+    match s.to_lowercase().as_str() {
+        "bitcoin_core" | "bitcoin-core" => Ok(L1BackendKind::BitcoinCore),
+        "electrum" => Ok(L1BackendKind::Electrum),
+        "esplora" => Ok(L1BackendKind::Esplora),
         _ => Err(anyhow::anyhow!(
-            "unknown parent_chain '{}', use: btc, bch, ltc, signet, regtest",
+            "unknown L1 backend '{}', use: bitcoin-core, electrum, esplora",
             s
         )),
     }
@@ -44,11 +51,22 @@ fn parse_parent_chain(s: &str) -> anyhow::Result<ParentChainType> {
 #[derive(Clone, Debug, Subcommand)]
 #[command(arg_required_else_help(true))]
 pub enum Command {
+    /// Accept an L1 → L2 swap offer, locking the swap's L2 amount.
+    /// The swap creator then sends L1 coins to l1_recipient_address.
+    AcceptSwap {
+        #[arg(long, value_parser = parse_swap_id)]
+        swap_id: SwapId,
+        #[arg(long)]
+        l1_recipient_address: String,
+        #[arg(long)]
+        fee_sats: u64,
+    },
     /// Get balance in sats
     Balance,
     /// Connect to a peer
     ConnectPeer { addr: SocketAddr },
     /// Create a swap (L2 → L1). Optional l2_recipient = open swap.
+    /// Optional min_fill_l1_amount_sats = partially fillable swap.
     CreateSwap {
         #[arg(long, value_parser = parse_parent_chain)]
         parent_chain: ParentChainType,
@@ -60,6 +78,52 @@ pub enum Command {
         l2_recipient: Option<Address>,
         #[arg(long)]
         l2_amount_sats: u64,
+        /// L1 confirmations required to claim the swap (default: suggested
+        /// for the L1 amount, see get-swap-confirmations)
+        #[arg(long)]
+        required_confirmations: Option<u32>,
+        #[arg(long)]
+        min_fill_l1_amount_sats: Option<u64>,
+        /// L2 blocks until the swap expires (default: parent chain default)
+        #[arg(long)]
+        expires_in_blocks: Option<u32>,
+        #[arg(long)]
+        fee_sats: u64,
+    },
+    /// Create a swap (L2 → L1) by price: offer l1_amount_sats worth of L2
+    /// coins at price_sats L2 sats per L1 coin
+    CreateSwapAtPrice {
+        #[arg(long, value_parser = parse_parent_chain)]
+        parent_chain: ParentChainType,
+        #[arg(long)]
+        l1_recipient_address: String,
+        #[arg(long)]
+        l1_amount_sats: u64,
+        #[arg(long)]
+        l2_recipient: Option<Address>,
+        /// L2 sats per L1 coin
+        #[arg(long)]
+        price_sats: u64,
+        #[arg(long)]
+        required_confirmations: Option<u32>,
+        #[arg(long)]
+        min_fill_l1_amount_sats: Option<u64>,
+        #[arg(long)]
+        expires_in_blocks: Option<u32>,
+        #[arg(long)]
+        fee_sats: u64,
+    },
+    /// Create an L1 → L2 swap offer: offer L1 coins for L2 coins.
+    /// The L2 coins are paid to l2_recipient, or to a new wallet address.
+    CreateL1ToL2Swap {
+        #[arg(long, value_parser = parse_parent_chain)]
+        parent_chain: ParentChainType,
+        #[arg(long)]
+        l1_amount_sats: u64,
+        #[arg(long)]
+        l2_recipient: Option<Address>,
+        #[arg(long)]
+        l2_amount_sats: u64,
         #[arg(long)]
         required_confirmations: Option<u32>,
         #[arg(long)]
@@ -73,6 +137,21 @@ pub enum Command {
         #[arg(long)]
         fee_sats: u64,
     },
+    /// Lock funds in an HTLC, redeemable by the recipient with the SHA256
+    /// preimage of payment_hash before refund_height, and refundable to this
+    /// wallet from refund_height onwards
+    CreateHtlc {
+        recipient: Address,
+        #[arg(long)]
+        value_sats: u64,
+        /// SHA256 payment hash (64 hex chars)
+        #[arg(long)]
+        payment_hash: String,
+        #[arg(long)]
+        refund_height: u32,
+        #[arg(long)]
+        fee_sats: u64,
+    },
     /// Format a deposit address
     FormatDepositAddress { address: Address },
     /// Delete peer from known_peers DB.
@@ -80,11 +159,17 @@ pub enum Command {
     ForgetPeer { addr: SocketAddr },
     /// Generate a mnemonic seed phrase
     GenerateMnemonic,
-    /// Show L1 RPC config (all chains or one if --chain is set)
+    /// Show L1 RPC config (all chains or one if --chain is set). Stored
+    /// passwords are hidden.
     GetL1Config {
         #[arg(long, value_parser = parse_parent_chain)]
         chain: Option<ParentChainType>,
     },
+    /// Get the tip of the L1 header chain for a parent chain
+    GetL1HeaderTip {
+        #[arg(long, value_parser = parse_parent_chain)]
+        chain: ParentChainType,
+    },
     /// Get the best mainchain block hash
     GetBestMainchainBlockHash,
     /// Get the best sidechain block hash
@@ -111,6 +196,24 @@ pub enum Command {
         swap_id: SwapId,
         #[arg(long)]
         l2_claimer_address: Option<Address>,
+        /// Hex-encoded L1 inclusion proof of the fill, or a list of proofs
+        /// for a partially fillable swap. If omitted, the node builds them
+        /// from its L1 backend.
+        #[arg(long)]
+        l1_proof: Option<String>,
+    },
+    /// Get the confirmations suggested for a swap of an L1 amount: the
+    /// parent chain's default, or more if the swap's size requires it
+    GetSwapConfirmations {
+        #[arg(long, value_parser = parse_parent_chain)]
+        parent_chain: ParentChainType,
+        #[arg(long)]
+        l1_amount_sats: u64,
+    },
+    /// Get the L1 payments claimed for a partially fillable swap
+    GetSwapFills {
+        #[arg(long, value_parser = parse_swap_id)]
+        swap_id: SwapId,
     },
     /// Get status of a swap by ID
     GetSwapStatus {
@@ -127,15 +230,78 @@ pub enum Command {
     ListSwaps,
     /// List swaps for a specific recipient address
     ListSwapsByRecipient { recipient: Address },
+    /// List open swaps that can be filled, best price first
+    ListSwapOffers {
+        #[arg(long, value_parser = parse_parent_chain)]
+        parent_chain: ParentChainType,
+    },
+    /// Get the best fill of up to l1_amount_sats from open swaps
+    QuoteSwap {
+        #[arg(long, value_parser = parse_parent_chain)]
+        parent_chain: ParentChainType,
+        #[arg(long)]
+        l1_amount_sats: u64,
+    },
     /// Recover wallet from mnemonic phrase (sets seed, then shows addresses and balance)
     RecoverFromMnemonic { mnemonic: String },
     /// Reconstruct all swaps from the blockchain
     ReconstructSwaps,
-    /// Cancel a swap (only Pending swaps). Unlocks outputs and marks as cancelled.
+    /// Redeem an HTLC paid to this wallet, before its refund height
+    RedeemHtlc {
+        /// Txid of the transaction that created the HTLC
+        #[arg(long)]
+        txid: Txid,
+        #[arg(long)]
+        vout: u32,
+        /// Preimage of the HTLC's payment hash (64 hex chars)
+        #[arg(long)]
+        preimage: String,
+        #[arg(long)]
+        fee_sats: u64,
+    },
+    /// Refund an HTLC created by this wallet, from its refund height onwards
+    RefundHtlc {
+        /// Txid of the transaction that created the HTLC
+        #[arg(long)]
+        txid: Txid,
+        #[arg(long)]
+        vout: u32,
+        #[arg(long)]
+        fee_sats: u64,
+    },
+    /// Submit hex-encoded L1 headers for a parent chain, in ascending height
+    /// order, as a header relay transaction
+    SubmitL1Headers {
+        #[arg(long, value_parser = parse_parent_chain)]
+        chain: ParentChainType,
+        #[arg(required = true)]
+        headers_hex: Vec<String>,
+    },
+    /// Cancel a swap we created (only Pending swaps), by sending a SwapCancel
+    /// transaction. Unlocks outputs and marks as cancelled once mined.
     CancelSwap {
         /// Swap ID (64 hex chars)
         #[arg(value_parser = parse_swap_id)]
         swap_id: SwapId,
+        #[arg(long)]
+        fee_sats: u64,
+    },
+    /// Reserve an open swap, locking a bond of 1% of its L2 amount. Only the
+    /// reserving taker's L1 payment fills the swap while the reservation is
+    /// live.
+    ReserveSwap {
+        /// Swap ID (64 hex chars)
+        #[arg(value_parser = parse_swap_id)]
+        swap_id: SwapId,
+        /// L2 address to claim to (default: a new wallet address)
+        #[arg(long)]
+        l2_claimer_address: Option<Address>,
+        /// Reservation length in L2 blocks (default: the maximum for the
+        /// swap's parent chain)
+        #[arg(long)]
+        reserve_blocks: Option<u32>,
+        #[arg(long)]
+        fee_sats: u64,
     },
     /// Delete a swap from the database (only Pending or Cancelled).
     DeleteSwap {
@@ -157,16 +323,36 @@ pub enum Command {
     RemoveFromMempool { txid: Txid },
     /// Set the wallet seed from a mnemonic seed phrase
     SetSeedFromMnemonic { mnemonic: String },
-    /// Set L1 RPC config for a parent chain (url required; user/password optional)
+    /// Set L1 RPC config for a parent chain (url required; user/password optional).
+    /// The node checks that the endpoint serves the parent chain.
+    /// With `--backend electrum`, the url is an Electrum server (`tcp://host:port`); with
+    /// `--backend esplora`, it is the base URL of an Esplora API.
     SetL1Config {
         #[arg(long, value_parser = parse_parent_chain)]
         parent_chain: ParentChainType,
+        #[arg(long, value_parser = parse_l1_backend, default_value = "bitcoin-core")]
+        backend: L1BackendKind,
         #[arg(long)]
         url: String,
         #[arg(long, default_value = "")]
         user: String,
         #[arg(long, default_value = "")]
         password: String,
+        /// Bitcoin Core cookie file, used instead of user/password
+        #[arg(long)]
+        cookie_file: Option<PathBuf>,
+        /// Environment variable to read the password from when the app starts
+        #[arg(long)]
+        password_env: Option<String>,
+        /// File to read the password from when the app starts
+        #[arg(long)]
+        password_file: Option<PathBuf>,
+        /// PEM file with the CA certificates to trust for https:// and ssl:// URLs
+        #[arg(long)]
+        tls_ca_cert: Option<PathBuf>,
+        /// SHA-256 fingerprint of the server certificate to pin (hex)
+        #[arg(long)]
+        tls_cert_sha256: Option<String>,
     },
     /// Get total sidechain wealth
     SidechainWealth,
@@ -232,6 +418,16 @@ where
     RpcClient: ClientT + Sync,
 {
     Ok(match command {
+        Command::AcceptSwap {
+            swap_id,
+            l1_recipient_address,
+            fee_sats,
+        } => {
+            let txid = rpc_client
+                .accept_swap(swap_id, l1_recipient_address, fee_sats)
+                .await?;
+            format!("Swap accepted: txid={}", txid)
+        }
         Command::Balance => {
             let balance = rpc_client.balance().await?;
             serde_json::to_string_pretty(&balance)?
@@ -247,6 +443,8 @@ where
             l2_recipient,
             l2_amount_sats,
             required_confirmations,
+            min_fill_l1_amount_sats,
+            expires_in_blocks,
             fee_sats,
         } => {
             let (swap_id, txid) = rpc_client
@@ -257,17 +455,67 @@ where
                     l2_recipient,
                     l2_amount_sats,
                     required_confirmations,
+                    min_fill_l1_amount_sats,
+                    expires_in_blocks,
                     fee_sats,
                 )
                 .await?;
             format!("Swap created: id={} txid={}", swap_id, txid)
         }
+        Command::CreateSwapAtPrice {
+            parent_chain,
+            l1_recipient_address,
+            l1_amount_sats,
+            l2_recipient,
+            price_sats,
+            required_confirmations,
+            min_fill_l1_amount_sats,
+            expires_in_blocks,
+            fee_sats,
+        } => {
+            let (swap_id, txid) = rpc_client
+                .create_swap_at_price(
+                    parent_chain,
+                    l1_recipient_address,
+                    l1_amount_sats,
+                    l2_recipient,
+                    price_sats,
+                    required_confirmations,
+                    min_fill_l1_amount_sats,
+                    expires_in_blocks,
+                    fee_sats,
+                )
+                .await?;
+            format!("Swap created: id={} txid={}", swap_id, txid)
+        }
+        Command::CreateL1ToL2Swap {
+            parent_chain,
+            l1_amount_sats,
+            l2_recipient,
+            l2_amount_sats,
+            required_confirmations,
+            fee_sats,
+        } => {
+            let (swap_id, txid) = rpc_client
+                .create_l1_to_l2_swap(
+                    parent_chain,
+                    l1_amount_sats,
+                    l2_recipient,
+                    l2_amount_sats,
+                    required_confirmations,
+                    fee_sats,
+                )
+                .await?;
+            format!("Swap offer created: id={} txid={}", swap_id, txid)
+        }
         Command::ClaimSwap {
             swap_id,
             l2_claimer_address,
+            l1_proof,
         } => {
-            let txid =
-                rpc_client.claim_swap(swap_id, l2_claimer_address).await?;
+            let txid = rpc_client
+                .claim_swap(swap_id, l2_claimer_address, l1_proof)
+                .await?;
             format!("Swap claimed: txid={}", txid)
         }
         Command::CreateDeposit {
@@ -280,6 +528,24 @@ where
                 .await?;
             format!("{txid}")
         }
+        Command::CreateHtlc {
+            recipient,
+            value_sats,
+            payment_hash,
+            refund_height,
+            fee_sats,
+        } => {
+            let txid = rpc_client
+                .create_htlc(
+                    recipient,
+                    value_sats,
+                    payment_hash,
+                    refund_height,
+                    fee_sats,
+                )
+                .await?;
+            format!("{txid}")
+        }
         Command::FormatDepositAddress { address } => {
             rpc_client.format_deposit_address(address).await?
         }
@@ -306,23 +572,12 @@ where
         }
         Command::GenerateMnemonic => rpc_client.generate_mnemonic().await?,
         Command::GetL1Config { chain } => {
-            let path = l1_config_path();
-            let configs: HashMap<ParentChainType, RpcConfig> = if path.exists()
-            {
-                let s = std::fs::read_to_string(&path).map_err(|e| {
-                    anyhow::anyhow!("read config: {}: {}", path.display(), e)
-                })?;
-                serde_json::from_str(&s).unwrap_or_default()
-            } else {
-                HashMap::new()
-            };
-            let out: HashMap<ParentChainType, RpcConfig> = match chain {
-                Some(c) => {
-                    configs.into_iter().filter(|(k, _)| *k == c).collect()
-                }
-                None => configs,
-            };
-            serde_json::to_string_pretty(&out)?
+            let configs = rpc_client.get_l1_config(chain).await?;
+            serde_json::to_string_pretty(&configs)?
+        }
+        Command::GetL1HeaderTip { chain } => {
+            let tip = rpc_client.get_l1_header_tip(chain).await?;
+            serde_json::to_string_pretty(&tip)?
         }
         Command::GetNewAddress => {
             let address = rpc_client.get_new_address().await?;
@@ -340,6 +595,19 @@ where
             let blockcount = rpc_client.getblockcount().await?;
             format!("{blockcount}")
         }
+        Command::GetSwapConfirmations {
+            parent_chain,
+            l1_amount_sats,
+        } => {
+            let confirmations = rpc_client
+                .get_swap_confirmations(parent_chain, l1_amount_sats)
+                .await?;
+            format!("{confirmations}")
+        }
+        Command::GetSwapFills { swap_id } => {
+            let fills = rpc_client.get_swap_fills(swap_id).await?;
+            serde_json::to_string_pretty(&fills)?
+        }
         Command::GetSwapStatus { swap_id } => {
             let status = rpc_client.get_swap_status(swap_id).await?;
             serde_json::to_string_pretty(&status)?
@@ -365,6 +633,18 @@ where
             let swaps = rpc_client.list_swaps_by_recipient(recipient).await?;
             serde_json::to_string_pretty(&swaps)?
         }
+        Command::ListSwapOffers { parent_chain } => {
+            let offers = rpc_client.list_swap_offers(parent_chain).await?;
+            serde_json::to_string_pretty(&offers)?
+        }
+        Command::QuoteSwap {
+            parent_chain,
+            l1_amount_sats,
+        } => {
+            let quote =
+                rpc_client.quote_swap(parent_chain, l1_amount_sats).await?;
+            serde_json::to_string_pretty(&quote)?
+        }
         Command::RecoverFromMnemonic { mnemonic } => {
             rpc_client.set_seed_from_mnemonic(mnemonic).await?;
             let addresses = rpc_client.get_wallet_addresses().await?;
@@ -381,9 +661,49 @@ where
             let count = rpc_client.reconstruct_swaps().await?;
             format!("Reconstructed {} swaps from blockchain", count)
         }
-        Command::CancelSwap { swap_id } => {
-            rpc_client.cancel_swap(swap_id).await?;
-            "Swap cancelled".to_string()
+        Command::RedeemHtlc {
+            txid,
+            vout,
+            preimage,
+            fee_sats,
+        } => {
+            let outpoint = OutPoint::Regular { txid, vout };
+            let txid =
+                rpc_client.redeem_htlc(outpoint, preimage, fee_sats).await?;
+            format!("{txid}")
+        }
+        Command::RefundHtlc {
+            txid,
+            vout,
+            fee_sats,
+        } => {
+            let outpoint = OutPoint::Regular { txid, vout };
+            let txid = rpc_client.refund_htlc(outpoint, fee_sats).await?;
+            format!("{txid}")
+        }
+        Command::SubmitL1Headers { chain, headers_hex } => {
+            let txid = rpc_client.submit_l1_headers(chain, headers_hex).await?;
+            format!("{txid}")
+        }
+        Command::CancelSwap { swap_id, fee_sats } => {
+            let txid = rpc_client.cancel_swap(swap_id, fee_sats).await?;
+            format!("Swap cancel sent: txid={}", txid)
+        }
+        Command::ReserveSwap {
+            swap_id,
+            l2_claimer_address,
+            reserve_blocks,
+            fee_sats,
+        } => {
+            let txid = rpc_client
+                .reserve_swap(
+                    swap_id,
+                    l2_claimer_address,
+                    reserve_blocks,
+                    fee_sats,
+                )
+                .await?;
+            format!("Swap reserve sent: txid={}", txid)
         }
         Command::DeleteSwap { swap_id } => {
             rpc_client.delete_swap(swap_id).await?;
@@ -429,41 +749,31 @@ where
         }
         Command::SetL1Config {
             parent_chain,
+            backend,
             url,
             user,
             password,
+            cookie_file,
+            password_env,
+            password_file,
+            tls_ca_cert,
+            tls_cert_sha256,
         } => {
-            let path = l1_config_path();
-            let mut configs: HashMap<ParentChainType, RpcConfig> = if path
-                .exists()
-            {
-                let s = std::fs::read_to_string(&path).map_err(|e| {
-                    anyhow::anyhow!("read config: {}: {}", path.display(), e)
-                })?;
-                serde_json::from_str(&s).unwrap_or_default()
-            } else {
-                HashMap::new()
-            };
-            configs.insert(
-                parent_chain,
-                RpcConfig {
-                    url: url.clone(),
-                    user: user.clone(),
-                    password: password.clone(),
+            let endpoint = L1Endpoint {
+                backend,
+                url,
+                user,
+                password,
+                cookie_file,
+                password_env,
+                password_file,
+                tls: TlsConfig {
+                    ca_cert: tls_ca_cert,
+                    cert_sha256: tls_cert_sha256,
                 },
-            );
-            if let Some(parent) = path.parent() {
-                drop(std::fs::create_dir_all(parent));
-            }
-            std::fs::write(&path, serde_json::to_string_pretty(&configs)?)
-                .map_err(|e| {
-                    anyhow::anyhow!("write config: {}: {}", path.display(), e)
-                })?;
-            format!(
-                "L1 RPC config saved for {} at {}",
-                parent_chain.coin_name(),
-                path.display()
-            )
+            };
+            let () = rpc_client.set_l1_config(parent_chain, endpoint).await?;
+            format!("L1 RPC config saved for {}", parent_chain.coin_name())
         }
         Command::SidechainWealth => {
             let sidechain_wealth = rpc_client.sidechain_wealth_sats().await?;
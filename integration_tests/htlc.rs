@@ -0,0 +1,192 @@
+//! Test redeeming and refunding HTLC outputs.
+//!
+//! Alice locks funds in two HTLCs paying a new address of her own. The
+//! first can only be redeemed with the right preimage, and is redeemed
+//! before its refund height. The second cannot be refunded before its
+//! refund height nor redeemed after it, and is refunded.
+
+use bip300301_enforcer_integration_tests::{
+    integration_test::deposit,
+    setup::{PostSetup as EnforcerPostSetup, Sidechain as _},
+    util::{AbortOnDrop, AsyncTrial, TestFailureCollector, TestFileRegistry},
+};
+use bitcoin::hashes::{Hash as _, sha256};
+use coinshift::types::{OutPoint, OutputContent};
+use coinshift_app_rpc_api::RpcClient as _;
+use futures::{
+    FutureExt as _, StreamExt as _, channel::mpsc, future::BoxFuture,
+};
+use tokio::time::sleep;
+use tracing::Instrument as _;
+
+use crate::{setup::PostSetup, util::BinPaths};
+
+const DEPOSIT_AMOUNT: bitcoin::Amount = bitcoin::Amount::from_sat(21_000_000);
+const DEPOSIT_FEE: bitcoin::Amount = bitcoin::Amount::from_sat(1_000_000);
+const HTLC_VALUE: u64 = 1_000_000;
+const HTLC_FEE: u64 = 1_000;
+/// Number of blocks after creation at which the HTLCs become refundable
+const HTLC_REFUND_DELAY: u32 = 3;
+
+/// Find the unspent HTLC with a payment hash
+async fn find_htlc(
+    sidechain: &PostSetup,
+    payment_hash: [u8; 32],
+) -> anyhow::Result<Option<OutPoint>> {
+    let utxos = sidechain.rpc_client.list_utxos().await?;
+    Ok(utxos.iter().find_map(|utxo| match &utxo.output.content {
+        OutputContent::Htlc {
+            payment_hash: utxo_payment_hash,
+            ..
+        } if *utxo_payment_hash == payment_hash => Some(utxo.outpoint),
+        _ => None,
+    }))
+}
+
+/// Create an HTLC paying a new wallet address, and mine it
+async fn create_htlc(
+    sidechain: &mut PostSetup,
+    enforcer_post_setup: &mut EnforcerPostSetup,
+    payment_hash: [u8; 32],
+    refund_height: u32,
+) -> anyhow::Result<OutPoint> {
+    let recipient = sidechain.rpc_client.get_new_address().await?;
+    let txid = sidechain
+        .rpc_client
+        .create_htlc(
+            recipient,
+            HTLC_VALUE,
+            hex::encode(payment_hash),
+            refund_height,
+            HTLC_FEE,
+        )
+        .await?;
+    tracing::info!(%txid, refund_height, "Created HTLC");
+    sidechain.bmm_single(enforcer_post_setup).await?;
+    sleep(std::time::Duration::from_millis(500)).await;
+    find_htlc(sidechain, payment_hash)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("HTLC not found after creation"))
+}
+
+async fn htlc_task(
+    bin_paths: BinPaths,
+    res_tx: mpsc::UnboundedSender<anyhow::Result<()>>,
+) -> anyhow::Result<()> {
+    let (mut alice, mut enforcer_post_setup) =
+        crate::swap_creation::setup_swapper(&bin_paths, res_tx, "htlc-alice")
+            .await?;
+    let deposit_address = alice.get_deposit_address().await?;
+    let () = deposit(
+        &mut enforcer_post_setup,
+        &mut alice,
+        &deposit_address,
+        DEPOSIT_AMOUNT,
+        DEPOSIT_FEE,
+    )
+    .await?;
+
+    // Redeem an HTLC with its preimage
+    let preimage = [7u8; 32];
+    let payment_hash = sha256::Hash::hash(&preimage).to_byte_array();
+    let refund_height =
+        alice.rpc_client.getblockcount().await? + HTLC_REFUND_DELAY;
+    let outpoint = create_htlc(
+        &mut alice,
+        &mut enforcer_post_setup,
+        payment_hash,
+        refund_height,
+    )
+    .await?;
+    anyhow::ensure!(
+        alice
+            .rpc_client
+            .refund_htlc(outpoint, HTLC_FEE)
+            .await
+            .is_err(),
+        "HTLC should not be refundable before its refund height"
+    );
+    anyhow::ensure!(
+        alice
+            .rpc_client
+            .redeem_htlc(outpoint, hex::encode([8u8; 32]), HTLC_FEE)
+            .await
+            .is_err(),
+        "HTLC should not be redeemable with the wrong preimage"
+    );
+    let redeem_txid = alice
+        .rpc_client
+        .redeem_htlc(outpoint, hex::encode(preimage), HTLC_FEE)
+        .await?;
+    tracing::info!(%redeem_txid, "Redeemed HTLC");
+    alice.bmm_single(&mut enforcer_post_setup).await?;
+    sleep(std::time::Duration::from_millis(500)).await;
+    anyhow::ensure!(
+        find_htlc(&alice, payment_hash).await?.is_none(),
+        "Redeemed HTLC should be spent"
+    );
+
+    // Refund an HTLC after its refund height
+    let preimage = [9u8; 32];
+    let payment_hash = sha256::Hash::hash(&preimage).to_byte_array();
+    let refund_height =
+        alice.rpc_client.getblockcount().await? + HTLC_REFUND_DELAY;
+    let outpoint = create_htlc(
+        &mut alice,
+        &mut enforcer_post_setup,
+        payment_hash,
+        refund_height,
+    )
+    .await?;
+    alice
+        .bmm(&mut enforcer_post_setup, HTLC_REFUND_DELAY)
+        .await?;
+    sleep(std::time::Duration::from_millis(500)).await;
+    anyhow::ensure!(
+        alice
+            .rpc_client
+            .redeem_htlc(outpoint, hex::encode(preimage), HTLC_FEE)
+            .await
+            .is_err(),
+        "HTLC should not be redeemable after its refund height"
+    );
+    let refund_txid = alice.rpc_client.refund_htlc(outpoint, HTLC_FEE).await?;
+    tracing::info!(%refund_txid, "Refunded HTLC");
+    alice.bmm_single(&mut enforcer_post_setup).await?;
+    sleep(std::time::Duration::from_millis(500)).await;
+    anyhow::ensure!(
+        find_htlc(&alice, payment_hash).await?.is_none(),
+        "Refunded HTLC should be spent"
+    );
+
+    crate::swap_creation::cleanup_swapper(alice, enforcer_post_setup).await
+}
+
+async fn htlc(bin_paths: BinPaths) -> anyhow::Result<()> {
+    let (res_tx, mut res_rx) = mpsc::unbounded();
+    let _test_task: AbortOnDrop<()> = tokio::task::spawn({
+        let res_tx = res_tx.clone();
+        async move {
+            let res = htlc_task(bin_paths, res_tx.clone()).await;
+            let _send_err: Result<(), _> = res_tx.unbounded_send(res);
+        }
+        .in_current_span()
+    })
+    .into();
+    res_rx.next().await.ok_or_else(|| {
+        anyhow::anyhow!("Unexpected end of test task result stream")
+    })?
+}
+
+pub fn htlc_trial(
+    bin_paths: BinPaths,
+    file_registry: TestFileRegistry,
+    failure_collector: TestFailureCollector,
+) -> AsyncTrial<BoxFuture<'static, anyhow::Result<()>>> {
+    AsyncTrial::new(
+        "htlc",
+        htlc(bin_paths).boxed(),
+        file_registry,
+        failure_collector,
+    )
+}
@@ -6,16 +6,22 @@ use futures::{FutureExt, future::BoxFuture};
 
 use crate::{
     confirmations_block_inclusion::confirmations_block_inclusion_trial,
+    htlc::htlc_trial,
     ibd::ibd_trial,
     l1_rpc_dependency::l1_rpc_dependency_trial,
+    l1_to_l2_swap::l1_to_l2_swap_trial,
     l1_txid_uniqueness::l1_txid_uniqueness_trial,
     l1_verification_rpc_only::l1_verification_rpc_only_trial,
     multi_node_verification::multi_node_verification_trial,
+    partial_fill_swap::partial_fill_swap_trial,
     setup::{Init, PostSetup},
+    swap_cancel::swap_cancel_trial,
     swap_creation::{
         swap_creation_fixed_trial, swap_creation_open_fill_trial,
         swap_creation_open_trial,
     },
+    swap_reorg::swap_reorg_trial,
+    swap_reserve::swap_reserve_trial,
     unknown_withdrawal::unknown_withdrawal_trial,
     util::BinPaths,
 };
@@ -105,6 +111,36 @@ pub fn tests(
             file_registry.clone(),
             failure_collector.clone(),
         ),
+        swap_reorg_trial(
+            bin_paths.clone(),
+            file_registry.clone(),
+            failure_collector.clone(),
+        ),
+        l1_to_l2_swap_trial(
+            bin_paths.clone(),
+            file_registry.clone(),
+            failure_collector.clone(),
+        ),
+        partial_fill_swap_trial(
+            bin_paths.clone(),
+            file_registry.clone(),
+            failure_collector.clone(),
+        ),
+        swap_cancel_trial(
+            bin_paths.clone(),
+            file_registry.clone(),
+            failure_collector.clone(),
+        ),
+        swap_reserve_trial(
+            bin_paths.clone(),
+            file_registry.clone(),
+            failure_collector.clone(),
+        ),
+        htlc_trial(
+            bin_paths.clone(),
+            file_registry.clone(),
+            failure_collector.clone(),
+        ),
         unknown_withdrawal_trial(bin_paths, file_registry, failure_collector),
     ]
 }
@@ -0,0 +1,197 @@
+//! Test the L1 → L2 swap flow: an offer of L1 coins for L2 coins is created
+//! and accepted by locking L2 coins. A fill that is only known to the node
+//! cannot be claimed by the offer's creator.
+
+use bip300301_enforcer_integration_tests::{
+    integration_test::deposit,
+    setup::{PostSetup as EnforcerPostSetup, Sidechain as _},
+    util::{AbortOnDrop, AsyncTrial, TestFailureCollector, TestFileRegistry},
+};
+use coinshift::types::{
+    OutputContent, ParentChainType, Swap, SwapDirection, SwapId, SwapState,
+};
+use coinshift_app_rpc_api::RpcClient as _;
+use futures::{
+    FutureExt as _, StreamExt as _, channel::mpsc, future::BoxFuture,
+};
+use tokio::time::sleep;
+use tracing::Instrument as _;
+
+use crate::{setup::PostSetup, util::BinPaths};
+
+const DEPOSIT_AMOUNT: bitcoin::Amount = bitcoin::Amount::from_sat(21_000_000);
+const DEPOSIT_FEE: bitcoin::Amount = bitcoin::Amount::from_sat(1_000_000);
+const SWAP_L2_AMOUNT: u64 = 10_000_000;
+const SWAP_L1_AMOUNT: u64 = 5_000_000;
+const SWAP_FEE: u64 = 1_000;
+
+/// Total value of outputs locked to a swap
+async fn locked_to_swap(
+    sidechain: &PostSetup,
+    swap_id: SwapId,
+) -> anyhow::Result<u64> {
+    let utxos = sidechain.rpc_client.list_utxos().await?;
+    Ok(utxos
+        .iter()
+        .filter_map(|utxo| match &utxo.output.content {
+            OutputContent::SwapPending {
+                value,
+                swap_id: locked_swap_id,
+            } if *locked_swap_id == swap_id.0 => Some(value.to_sat()),
+            _ => None,
+        })
+        .sum())
+}
+
+/// Mine a block and return the swap's status
+async fn mine_and_get_swap(
+    sidechain: &mut PostSetup,
+    enforcer: &mut EnforcerPostSetup,
+    swap_id: SwapId,
+) -> anyhow::Result<Swap> {
+    sidechain.bmm_single(enforcer).await?;
+    sleep(std::time::Duration::from_millis(500)).await;
+    sidechain
+        .rpc_client
+        .get_swap_status(swap_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Swap {swap_id} not found"))
+}
+
+async fn l1_to_l2_swap_task(
+    bin_paths: BinPaths,
+    res_tx: mpsc::UnboundedSender<anyhow::Result<()>>,
+) -> anyhow::Result<()> {
+    let (mut sidechain, mut enforcer_post_setup) =
+        crate::swap_creation::setup_swapper(
+            &bin_paths,
+            res_tx.clone(),
+            "l1-to-l2",
+        )
+        .await?;
+
+    let deposit_address = sidechain.get_deposit_address().await?;
+    let () = deposit(
+        &mut enforcer_post_setup,
+        &mut sidechain,
+        &deposit_address,
+        DEPOSIT_AMOUNT,
+        DEPOSIT_FEE,
+    )
+    .await?;
+
+    // Create the offer
+    let l2_recipient = sidechain.rpc_client.get_new_address().await?;
+    let (swap_id, _txid) = sidechain
+        .rpc_client
+        .create_l1_to_l2_swap(
+            ParentChainType::Regtest,
+            SWAP_L1_AMOUNT,
+            Some(l2_recipient),
+            SWAP_L2_AMOUNT,
+            Some(1),
+            SWAP_FEE,
+        )
+        .await?;
+    let swap =
+        mine_and_get_swap(&mut sidechain, &mut enforcer_post_setup, swap_id)
+            .await?;
+    anyhow::ensure!(swap.direction == SwapDirection::L1ToL2);
+    anyhow::ensure!(
+        swap.is_awaiting_acceptance(),
+        "Offer should await acceptance: {swap:?}"
+    );
+    anyhow::ensure!(
+        locked_to_swap(&sidechain, swap_id).await? == 0,
+        "Offer should not lock any outputs"
+    );
+    tracing::info!(%swap_id, "L1 → L2 offer created");
+
+    // Accept the offer
+    let l1_recipient = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+    let _txid = sidechain
+        .rpc_client
+        .accept_swap(swap_id, l1_recipient.to_string(), SWAP_FEE)
+        .await?;
+    let swap =
+        mine_and_get_swap(&mut sidechain, &mut enforcer_post_setup, swap_id)
+            .await?;
+    anyhow::ensure!(
+        swap.l1_recipient_address.as_deref() == Some(l1_recipient),
+        "Accepted swap should have the L1 recipient set: {swap:?}"
+    );
+    anyhow::ensure!(
+        matches!(swap.state, SwapState::Pending),
+        "Accepted swap should be Pending, got {:?}",
+        swap.state
+    );
+    anyhow::ensure!(
+        locked_to_swap(&sidechain, swap_id).await? == SWAP_L2_AMOUNT,
+        "Accepting should lock the swap's L2 amount"
+    );
+    sidechain
+        .rpc_client
+        .accept_swap(swap_id, l1_recipient.to_string(), SWAP_FEE)
+        .await
+        .expect_err("accepting an accepted swap should fail");
+    tracing::info!(%swap_id, "L1 → L2 offer accepted");
+
+    // A fill recorded from the node's own view of L1 cannot be proven in
+    // the consensus L1 header chain, so it cannot be claimed
+    let fake_l1_txid_hex = "cc".repeat(32);
+    sidechain
+        .rpc_client
+        .update_swap_l1_txid(swap_id, fake_l1_txid_hex, 1, None)
+        .await?;
+    sidechain
+        .rpc_client
+        .claim_swap(swap_id, None, None)
+        .await
+        .expect_err(
+            "claiming a fill without an L1 inclusion proof should fail",
+        );
+    let swap =
+        mine_and_get_swap(&mut sidechain, &mut enforcer_post_setup, swap_id)
+            .await?;
+    anyhow::ensure!(
+        !matches!(swap.state, SwapState::Completed),
+        "Unproven fill should not complete the swap, got {:?}",
+        swap.state
+    );
+    anyhow::ensure!(
+        locked_to_swap(&sidechain, swap_id).await? == SWAP_L2_AMOUNT,
+        "The accepted L2 amount should stay locked"
+    );
+
+    tracing::info!("L1 → L2 swap test passed");
+    crate::swap_creation::cleanup_swapper(sidechain, enforcer_post_setup).await
+}
+
+async fn l1_to_l2_swap(bin_paths: BinPaths) -> anyhow::Result<()> {
+    let (res_tx, mut res_rx) = mpsc::unbounded();
+    let _test_task: AbortOnDrop<()> = tokio::task::spawn({
+        let res_tx = res_tx.clone();
+        async move {
+            let res = l1_to_l2_swap_task(bin_paths, res_tx.clone()).await;
+            let _send_err: Result<(), _> = res_tx.unbounded_send(res);
+        }
+        .in_current_span()
+    })
+    .into();
+    res_rx.next().await.ok_or_else(|| {
+        anyhow::anyhow!("Unexpected end of test task result stream")
+    })?
+}
+
+pub fn l1_to_l2_swap_trial(
+    bin_paths: BinPaths,
+    file_registry: TestFileRegistry,
+    failure_collector: TestFailureCollector,
+) -> AsyncTrial<BoxFuture<'static, anyhow::Result<()>>> {
+    AsyncTrial::new(
+        "l1_to_l2_swap",
+        l1_to_l2_swap(bin_paths).boxed(),
+        file_registry,
+        failure_collector,
+    )
+}
@@ -6,14 +6,20 @@ use std::collections::HashSet;
 use tracing_subscriber::{filter as tracing_filter, layer::SubscriberExt};
 
 mod confirmations_block_inclusion;
+mod htlc;
 mod ibd;
 mod integration_test;
 mod l1_rpc_dependency;
+mod l1_to_l2_swap;
 mod l1_txid_uniqueness;
 mod l1_verification_rpc_only;
 mod multi_node_verification;
+mod partial_fill_swap;
 mod setup;
+mod swap_cancel;
 mod swap_creation;
+mod swap_reorg;
+mod swap_reserve;
 mod unknown_withdrawal;
 mod util;
 
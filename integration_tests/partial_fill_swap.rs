@@ -0,0 +1,172 @@
+//! Test a partially fillable swap: it locks its L2 amount, and its fills
+//! can only be claimed with L1 inclusion proofs. The integration test
+//! harness has no L1 backend for swaps, so no fill can be proven and the
+//! L2 amount stays locked.
+
+use bip300301_enforcer_integration_tests::{
+    integration_test::deposit,
+    setup::{PostSetup as EnforcerPostSetup, Sidechain as _},
+    util::{AbortOnDrop, AsyncTrial, TestFailureCollector, TestFileRegistry},
+};
+use coinshift::types::{
+    OutputContent, ParentChainType, Swap, SwapId, SwapState,
+};
+use coinshift_app_rpc_api::RpcClient as _;
+use futures::{
+    FutureExt as _, StreamExt as _, channel::mpsc, future::BoxFuture,
+};
+use tokio::time::sleep;
+use tracing::Instrument as _;
+
+use crate::{setup::PostSetup, util::BinPaths};
+
+const DEPOSIT_AMOUNT: bitcoin::Amount = bitcoin::Amount::from_sat(21_000_000);
+const DEPOSIT_FEE: bitcoin::Amount = bitcoin::Amount::from_sat(1_000_000);
+const SWAP_L2_AMOUNT: u64 = 10_000_000;
+const SWAP_L1_AMOUNT: u64 = 5_000_000;
+const SWAP_MIN_FILL_L1_AMOUNT: u64 = 2_000_000;
+const SWAP_FEE: u64 = 1_000;
+
+/// Total value of outputs locked to a swap
+async fn locked_to_swap(
+    sidechain: &PostSetup,
+    swap_id: SwapId,
+) -> anyhow::Result<u64> {
+    let utxos = sidechain.rpc_client.list_utxos().await?;
+    Ok(utxos
+        .iter()
+        .filter_map(|utxo| match &utxo.output.content {
+            OutputContent::SwapPending {
+                value,
+                swap_id: locked_swap_id,
+            } if *locked_swap_id == swap_id.0 => Some(value.to_sat()),
+            _ => None,
+        })
+        .sum())
+}
+
+/// Mine a block and return the swap's status
+async fn mine_and_get_swap(
+    sidechain: &mut PostSetup,
+    enforcer: &mut EnforcerPostSetup,
+    swap_id: SwapId,
+) -> anyhow::Result<Swap> {
+    sidechain.bmm_single(enforcer).await?;
+    sleep(std::time::Duration::from_millis(500)).await;
+    sidechain
+        .rpc_client
+        .get_swap_status(swap_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Swap {swap_id} not found"))
+}
+
+async fn partial_fill_swap_task(
+    bin_paths: BinPaths,
+    res_tx: mpsc::UnboundedSender<anyhow::Result<()>>,
+) -> anyhow::Result<()> {
+    let (mut sidechain, mut enforcer_post_setup) =
+        crate::swap_creation::setup_swapper(
+            &bin_paths,
+            res_tx.clone(),
+            "partial-fill",
+        )
+        .await?;
+
+    let deposit_address = sidechain.get_deposit_address().await?;
+    let () = deposit(
+        &mut enforcer_post_setup,
+        &mut sidechain,
+        &deposit_address,
+        DEPOSIT_AMOUNT,
+        DEPOSIT_FEE,
+    )
+    .await?;
+
+    // Create an open, partially fillable swap
+    let l1_recipient = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+    let (swap_id, _txid) = sidechain
+        .rpc_client
+        .create_swap(
+            ParentChainType::Regtest,
+            l1_recipient.to_string(),
+            SWAP_L1_AMOUNT,
+            None,
+            SWAP_L2_AMOUNT,
+            Some(1),
+            Some(SWAP_MIN_FILL_L1_AMOUNT),
+            None,
+            SWAP_FEE,
+        )
+        .await?;
+    let swap =
+        mine_and_get_swap(&mut sidechain, &mut enforcer_post_setup, swap_id)
+            .await?;
+    anyhow::ensure!(
+        swap.min_fill_l1_amount
+            == Some(bitcoin::Amount::from_sat(SWAP_MIN_FILL_L1_AMOUNT)),
+        "Swap should allow partial fills: {swap:?}"
+    );
+    anyhow::ensure!(
+        locked_to_swap(&sidechain, swap_id).await? == SWAP_L2_AMOUNT,
+        "Swap should lock its L2 amount"
+    );
+
+    // Without provable fills, nobody can claim a share
+    let alice = sidechain.rpc_client.get_new_address().await?;
+    sidechain
+        .rpc_client
+        .claim_swap(swap_id, Some(alice), None)
+        .await
+        .expect_err("claiming without provable fills should fail");
+    sidechain
+        .rpc_client
+        .claim_swap(swap_id, Some(alice), Some("dd".repeat(32)))
+        .await
+        .expect_err("claiming with an invalid proof should fail");
+    let swap =
+        mine_and_get_swap(&mut sidechain, &mut enforcer_post_setup, swap_id)
+            .await?;
+    anyhow::ensure!(
+        swap.state == SwapState::Pending,
+        "Swap without claimed fills should stay pending, got {:?}",
+        swap.state
+    );
+    let fills = sidechain.rpc_client.get_swap_fills(swap_id).await?;
+    anyhow::ensure!(fills.is_empty(), "Expected no fills, got {fills:?}");
+    anyhow::ensure!(
+        locked_to_swap(&sidechain, swap_id).await? == SWAP_L2_AMOUNT,
+        "The L2 amount should stay locked"
+    );
+
+    tracing::info!("Partial fill swap test passed");
+    crate::swap_creation::cleanup_swapper(sidechain, enforcer_post_setup).await
+}
+
+async fn partial_fill_swap(bin_paths: BinPaths) -> anyhow::Result<()> {
+    let (res_tx, mut res_rx) = mpsc::unbounded();
+    let _test_task: AbortOnDrop<()> = tokio::task::spawn({
+        let res_tx = res_tx.clone();
+        async move {
+            let res = partial_fill_swap_task(bin_paths, res_tx.clone()).await;
+            let _send_err: Result<(), _> = res_tx.unbounded_send(res);
+        }
+        .in_current_span()
+    })
+    .into();
+    res_rx.next().await.ok_or_else(|| {
+        anyhow::anyhow!("Unexpected end of test task result stream")
+    })?
+}
+
+pub fn partial_fill_swap_trial(
+    bin_paths: BinPaths,
+    file_registry: TestFileRegistry,
+    failure_collector: TestFailureCollector,
+) -> AsyncTrial<BoxFuture<'static, anyhow::Result<()>>> {
+    AsyncTrial::new(
+        "partial_fill_swap",
+        partial_fill_swap(bin_paths).boxed(),
+        file_registry,
+        failure_collector,
+    )
+}
@@ -216,7 +216,8 @@ impl Sidechain for PostSetup {
                 && match &utxo.output.content {
                     OutputContent::Value(utxo_value) => *utxo_value == value,
                     OutputContent::Withdrawal { .. }
-                    | OutputContent::SwapPending { .. } => false,
+                    | OutputContent::SwapPending { .. }
+                    | OutputContent::Htlc { .. } => false,
                 }
                 && match utxo.outpoint {
                     OutPoint::Deposit(outpoint) => outpoint.txid == txid,
@@ -0,0 +1,210 @@
+//! Test that a swap cancelled by its creator is cancelled on every node.
+//!
+//! Alice creates a swap and BMMs it. Bob, connected to Alice, cannot cancel
+//! it. Alice cancels it with a SwapCancel transaction; once mined, both
+//! nodes see the swap as Cancelled.
+
+use bip300301_enforcer_integration_tests::{
+    integration_test::deposit,
+    setup::{PostSetup as EnforcerPostSetup, Sidechain as _},
+    util::{AbortOnDrop, AsyncTrial, TestFailureCollector, TestFileRegistry},
+};
+use coinshift::types::{OutputContent, ParentChainType, SwapId, SwapState};
+use coinshift_app_rpc_api::RpcClient as _;
+use futures::{
+    FutureExt as _, StreamExt as _, channel::mpsc, future::BoxFuture,
+};
+use tokio::time::sleep;
+use tracing::Instrument as _;
+
+use crate::{
+    setup::{Init, PostSetup},
+    util::BinPaths,
+};
+
+const DEPOSIT_AMOUNT: bitcoin::Amount = bitcoin::Amount::from_sat(21_000_000);
+const DEPOSIT_FEE: bitcoin::Amount = bitcoin::Amount::from_sat(1_000_000);
+const SWAP_L2_AMOUNT: u64 = 10_000_000;
+const SWAP_L1_AMOUNT: u64 = 5_000_000;
+const SWAP_FEE: u64 = 1_000;
+const CANCEL_FEE: u64 = 1_000;
+
+/// Total value of outputs locked to a swap
+async fn locked_to_swap(
+    sidechain: &PostSetup,
+    swap_id: SwapId,
+) -> anyhow::Result<u64> {
+    let utxos = sidechain.rpc_client.list_utxos().await?;
+    Ok(utxos
+        .iter()
+        .filter_map(|utxo| match &utxo.output.content {
+            OutputContent::SwapPending {
+                value,
+                swap_id: locked_swap_id,
+            } if *locked_swap_id == swap_id.0 => Some(value.to_sat()),
+            _ => None,
+        })
+        .sum())
+}
+
+/// Wait (with retries) for a node to reach a block count
+async fn wait_for_block_count(
+    sidechain: &PostSetup,
+    expected: u32,
+) -> anyhow::Result<()> {
+    const MAX_RETRIES: usize = 30;
+    const RETRY_DELAY_MS: u64 = 500;
+    for _ in 0..MAX_RETRIES {
+        if sidechain.rpc_client.getblockcount().await? == expected {
+            return Ok(());
+        }
+        sleep(std::time::Duration::from_millis(RETRY_DELAY_MS)).await;
+    }
+    let block_count = sidechain.rpc_client.getblockcount().await?;
+    anyhow::bail!(
+        "Expected block count {expected}, found {block_count} after timeout"
+    )
+}
+
+async fn swap_state(
+    sidechain: &PostSetup,
+    swap_id: SwapId,
+) -> anyhow::Result<SwapState> {
+    let swap = sidechain
+        .rpc_client
+        .get_swap_status(swap_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Swap {swap_id} not found"))?;
+    Ok(swap.state)
+}
+
+async fn swap_cancel_task(
+    bin_paths: BinPaths,
+    res_tx: mpsc::UnboundedSender<anyhow::Result<()>>,
+) -> anyhow::Result<()> {
+    let (mut alice, mut enforcer_post_setup) =
+        crate::swap_creation::setup_swapper(
+            &bin_paths,
+            res_tx.clone(),
+            "cancel-alice",
+        )
+        .await?;
+    let bob = PostSetup::setup(
+        Init {
+            coinshift_app: bin_paths.coinshift_app.clone(),
+            data_dir_suffix: Some("cancel-bob".to_owned()),
+        },
+        &enforcer_post_setup,
+        res_tx,
+    )
+    .await?;
+    tracing::info!("Setup Bob's node successfully");
+    bob.rpc_client.connect_peer(alice.net_addr().into()).await?;
+
+    let deposit_address = alice.get_deposit_address().await?;
+    let () = deposit(
+        &mut enforcer_post_setup,
+        &mut alice,
+        &deposit_address,
+        DEPOSIT_AMOUNT,
+        DEPOSIT_FEE,
+    )
+    .await?;
+
+    let l1_recipient = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+    let (swap_id, _txid) = alice
+        .rpc_client
+        .create_swap(
+            ParentChainType::Regtest,
+            l1_recipient.to_string(),
+            SWAP_L1_AMOUNT,
+            None,
+            SWAP_L2_AMOUNT,
+            Some(1),
+            None,
+            None,
+            SWAP_FEE,
+        )
+        .await?;
+    alice.bmm_single(&mut enforcer_post_setup).await?;
+    let () =
+        wait_for_block_count(&bob, alice.rpc_client.getblockcount().await?)
+            .await?;
+    sleep(std::time::Duration::from_millis(500)).await;
+    anyhow::ensure!(
+        locked_to_swap(&alice, swap_id).await? == SWAP_L2_AMOUNT,
+        "Swap outputs should be locked after creation"
+    );
+
+    // Only the swap creator may cancel
+    anyhow::ensure!(
+        bob.rpc_client
+            .cancel_swap(swap_id, CANCEL_FEE)
+            .await
+            .is_err(),
+        "Bob should not be able to cancel Alice's swap"
+    );
+
+    let cancel_txid = alice.rpc_client.cancel_swap(swap_id, CANCEL_FEE).await?;
+    tracing::info!(%swap_id, %cancel_txid, "Sent swap cancel");
+    anyhow::ensure!(
+        matches!(swap_state(&alice, swap_id).await?, SwapState::Pending),
+        "Swap should stay Pending until the cancel is mined"
+    );
+
+    alice.bmm_single(&mut enforcer_post_setup).await?;
+    let () =
+        wait_for_block_count(&bob, alice.rpc_client.getblockcount().await?)
+            .await?;
+    sleep(std::time::Duration::from_millis(500)).await;
+
+    for (name, node) in [("Alice", &alice), ("Bob", &bob)] {
+        let state = swap_state(node, swap_id).await?;
+        anyhow::ensure!(
+            matches!(state, SwapState::Cancelled),
+            "{name} should see the swap as Cancelled, got {state:?}"
+        );
+    }
+
+    // A cancelled swap cannot be cancelled again
+    anyhow::ensure!(
+        alice
+            .rpc_client
+            .cancel_swap(swap_id, CANCEL_FEE)
+            .await
+            .is_err(),
+        "Cancelling a cancelled swap should fail"
+    );
+
+    drop(bob);
+    crate::swap_creation::cleanup_swapper(alice, enforcer_post_setup).await
+}
+
+async fn swap_cancel(bin_paths: BinPaths) -> anyhow::Result<()> {
+    let (res_tx, mut res_rx) = mpsc::unbounded();
+    let _test_task: AbortOnDrop<()> = tokio::task::spawn({
+        let res_tx = res_tx.clone();
+        async move {
+            let res = swap_cancel_task(bin_paths, res_tx.clone()).await;
+            let _send_err: Result<(), _> = res_tx.unbounded_send(res);
+        }
+        .in_current_span()
+    })
+    .into();
+    res_rx.next().await.ok_or_else(|| {
+        anyhow::anyhow!("Unexpected end of test task result stream")
+    })?
+}
+
+pub fn swap_cancel_trial(
+    bin_paths: BinPaths,
+    file_registry: TestFileRegistry,
+    failure_collector: TestFailureCollector,
+) -> AsyncTrial<BoxFuture<'static, anyhow::Result<()>>> {
+    AsyncTrial::new(
+        "swap_cancel",
+        swap_cancel(bin_paths).boxed(),
+        file_registry,
+        failure_collector,
+    )
+}
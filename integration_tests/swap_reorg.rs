@@ -0,0 +1,215 @@
+//! Test that swap state is reverted when the block that created it is
+//! disconnected in a sidechain reorg.
+//!
+//! Alice creates a swap and BMMs it into her chain. Bob, who is not connected
+//! to Alice, BMMs a longer chain without the swap. When Alice connects to Bob
+//! she reorgs to Bob's chain, which must remove the swap and unlock its
+//! outputs. Re-mining the swap transaction from Alice's mempool restores it.
+
+use bip300301_enforcer_integration_tests::{
+    integration_test::deposit,
+    setup::{PostSetup as EnforcerPostSetup, Sidechain as _},
+    util::{AbortOnDrop, AsyncTrial, TestFailureCollector, TestFileRegistry},
+};
+use coinshift::types::{OutputContent, ParentChainType, SwapId, SwapState};
+use coinshift_app_rpc_api::RpcClient as _;
+use futures::{
+    FutureExt as _, StreamExt as _, channel::mpsc, future::BoxFuture,
+};
+use tokio::time::sleep;
+use tracing::Instrument as _;
+
+use crate::{
+    setup::{Init, PostSetup},
+    util::BinPaths,
+};
+
+const DEPOSIT_AMOUNT: bitcoin::Amount = bitcoin::Amount::from_sat(21_000_000);
+const DEPOSIT_FEE: bitcoin::Amount = bitcoin::Amount::from_sat(1_000_000);
+const SWAP_L2_AMOUNT: u64 = 10_000_000;
+const SWAP_L1_AMOUNT: u64 = 5_000_000;
+const SWAP_FEE: u64 = 1_000;
+
+/// Total value of outputs locked to a swap
+async fn locked_to_swap(
+    sidechain: &PostSetup,
+    swap_id: SwapId,
+) -> anyhow::Result<u64> {
+    let utxos = sidechain.rpc_client.list_utxos().await?;
+    Ok(utxos
+        .iter()
+        .filter_map(|utxo| match &utxo.output.content {
+            OutputContent::SwapPending {
+                value,
+                swap_id: locked_swap_id,
+            } if *locked_swap_id == swap_id.0 => Some(value.to_sat()),
+            _ => None,
+        })
+        .sum())
+}
+
+/// Wait (with retries) for a node to reach a block count
+async fn wait_for_block_count(
+    sidechain: &PostSetup,
+    expected: u32,
+) -> anyhow::Result<()> {
+    const MAX_RETRIES: usize = 30;
+    const RETRY_DELAY_MS: u64 = 500;
+    for _ in 0..MAX_RETRIES {
+        if sidechain.rpc_client.getblockcount().await? == expected {
+            return Ok(());
+        }
+        sleep(std::time::Duration::from_millis(RETRY_DELAY_MS)).await;
+    }
+    let block_count = sidechain.rpc_client.getblockcount().await?;
+    anyhow::bail!(
+        "Expected block count {expected}, found {block_count} after timeout"
+    )
+}
+
+async fn swap_reorg_task(
+    bin_paths: BinPaths,
+    res_tx: mpsc::UnboundedSender<anyhow::Result<()>>,
+) -> anyhow::Result<()> {
+    let (mut alice, mut enforcer_post_setup) =
+        crate::swap_creation::setup_swapper(
+            &bin_paths,
+            res_tx.clone(),
+            "reorg-alice",
+        )
+        .await?;
+    let bob = PostSetup::setup(
+        Init {
+            coinshift_app: bin_paths.coinshift_app.clone(),
+            data_dir_suffix: Some("reorg-bob".to_owned()),
+        },
+        &enforcer_post_setup,
+        res_tx,
+    )
+    .await?;
+    tracing::info!("Setup Bob's node successfully");
+
+    let deposit_address = alice.get_deposit_address().await?;
+    let () = deposit(
+        &mut enforcer_post_setup,
+        &mut alice,
+        &deposit_address,
+        DEPOSIT_AMOUNT,
+        DEPOSIT_FEE,
+    )
+    .await?;
+
+    let l1_recipient = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+    let (swap_id, _txid) = alice
+        .rpc_client
+        .create_swap(
+            ParentChainType::Regtest,
+            l1_recipient.to_string(),
+            SWAP_L1_AMOUNT,
+            Some(alice.rpc_client.get_new_address().await?),
+            SWAP_L2_AMOUNT,
+            Some(1),
+            None,
+            None,
+            SWAP_FEE,
+        )
+        .await?;
+    alice.bmm_single(&mut enforcer_post_setup).await?;
+    sleep(std::time::Duration::from_millis(500)).await;
+    anyhow::ensure!(
+        alice.rpc_client.get_swap_status(swap_id).await?.is_some(),
+        "Swap {swap_id} not found after block inclusion"
+    );
+    anyhow::ensure!(
+        locked_to_swap(&alice, swap_id).await? == SWAP_L2_AMOUNT,
+        "Swap outputs should be locked before reorg"
+    );
+    let alice_blocks = alice.rpc_client.getblockcount().await?;
+    tracing::info!(%swap_id, alice_blocks, "Swap included in Alice's chain");
+
+    // Bob mines a longer chain that does not include the swap
+    let bob_blocks = alice_blocks + 2;
+    bob.bmm(&mut enforcer_post_setup, bob_blocks).await?;
+    anyhow::ensure!(bob.rpc_client.getblockcount().await? == bob_blocks);
+    anyhow::ensure!(
+        bob.rpc_client.get_swap_status(swap_id).await?.is_none(),
+        "Bob's chain should not include the swap"
+    );
+
+    tracing::info!("Connecting Alice to Bob");
+    alice.rpc_client.connect_peer(bob.net_addr().into()).await?;
+    let () = wait_for_block_count(&alice, bob_blocks).await?;
+    anyhow::ensure!(
+        alice.rpc_client.get_best_sidechain_block_hash().await?
+            == bob.rpc_client.get_best_sidechain_block_hash().await?,
+        "Alice should have reorged to Bob's tip"
+    );
+    sleep(std::time::Duration::from_millis(500)).await;
+
+    // The swap and its output locks must be gone from Alice's state
+    anyhow::ensure!(
+        alice.rpc_client.get_swap_status(swap_id).await?.is_none(),
+        "Swap {swap_id} should be removed by the reorg"
+    );
+    anyhow::ensure!(
+        !alice
+            .rpc_client
+            .list_swaps()
+            .await?
+            .iter()
+            .any(|swap| swap.id == swap_id),
+        "Swap {swap_id} should not be listed after the reorg"
+    );
+    tracing::info!(%swap_id, "Swap reverted by reorg");
+
+    // The swap transaction was returned to Alice's mempool; re-mining it
+    // recreates the swap
+    alice.bmm_single(&mut enforcer_post_setup).await?;
+    sleep(std::time::Duration::from_millis(500)).await;
+    let swap = alice
+        .rpc_client
+        .get_swap_status(swap_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Swap {swap_id} not re-mined"))?;
+    anyhow::ensure!(
+        matches!(swap.state, SwapState::Pending),
+        "Re-mined swap should be Pending, got {:?}",
+        swap.state
+    );
+    anyhow::ensure!(
+        locked_to_swap(&alice, swap_id).await? == SWAP_L2_AMOUNT,
+        "Swap outputs should be locked after re-mining"
+    );
+
+    drop(bob);
+    crate::swap_creation::cleanup_swapper(alice, enforcer_post_setup).await
+}
+
+async fn swap_reorg(bin_paths: BinPaths) -> anyhow::Result<()> {
+    let (res_tx, mut res_rx) = mpsc::unbounded();
+    let _test_task: AbortOnDrop<()> = tokio::task::spawn({
+        let res_tx = res_tx.clone();
+        async move {
+            let res = swap_reorg_task(bin_paths, res_tx.clone()).await;
+            let _send_err: Result<(), _> = res_tx.unbounded_send(res);
+        }
+        .in_current_span()
+    })
+    .into();
+    res_rx.next().await.ok_or_else(|| {
+        anyhow::anyhow!("Unexpected end of test task result stream")
+    })?
+}
+
+pub fn swap_reorg_trial(
+    bin_paths: BinPaths,
+    file_registry: TestFileRegistry,
+    failure_collector: TestFailureCollector,
+) -> AsyncTrial<BoxFuture<'static, anyhow::Result<()>>> {
+    AsyncTrial::new(
+        "swap_reorg",
+        swap_reorg(bin_paths).boxed(),
+        file_registry,
+        failure_collector,
+    )
+}
@@ -0,0 +1,279 @@
+//! Test that a taker can reserve an open swap, and that the reservation
+//! lapses.
+//!
+//! Alice creates an open swap. Bob reserves it with a SwapReserve
+//! transaction, locking a bond to the swap; both nodes see the reservation,
+//! and Alice cannot cancel the swap while it is live. Once the reservation
+//! lapses, the bond stays with Alice and she can cancel the swap.
+
+use bip300301_enforcer_integration_tests::{
+    integration_test::deposit,
+    setup::{PostSetup as EnforcerPostSetup, Sidechain as _},
+    util::{AbortOnDrop, AsyncTrial, TestFailureCollector, TestFileRegistry},
+};
+use coinshift::types::{
+    OutputContent, ParentChainType, Swap, SwapId, SwapState,
+};
+use coinshift_app_rpc_api::RpcClient as _;
+use futures::{
+    FutureExt as _, StreamExt as _, channel::mpsc, future::BoxFuture,
+};
+use tokio::time::sleep;
+use tracing::Instrument as _;
+
+use crate::{
+    setup::{Init, PostSetup},
+    util::BinPaths,
+};
+
+const DEPOSIT_AMOUNT: bitcoin::Amount = bitcoin::Amount::from_sat(21_000_000);
+const DEPOSIT_FEE: bitcoin::Amount = bitcoin::Amount::from_sat(1_000_000);
+const SWAP_L2_AMOUNT: u64 = 10_000_000;
+const SWAP_L1_AMOUNT: u64 = 5_000_000;
+const SWAP_EXPIRES_IN_BLOCKS: u32 = 20;
+const SWAP_FEE: u64 = 1_000;
+const RESERVE_BLOCKS: u32 = 3;
+const RESERVE_FEE: u64 = 1_000;
+const CANCEL_FEE: u64 = 1_000;
+
+/// Outputs with SwapPending content for a swap, as (address, value)
+async fn swap_pending_outputs(
+    sidechain: &PostSetup,
+    swap_id: SwapId,
+) -> anyhow::Result<Vec<(coinshift::types::Address, u64)>> {
+    let utxos = sidechain.rpc_client.list_utxos().await?;
+    Ok(utxos
+        .iter()
+        .filter_map(|utxo| match &utxo.output.content {
+            OutputContent::SwapPending {
+                value,
+                swap_id: locked_swap_id,
+            } if *locked_swap_id == swap_id.0 => {
+                Some((utxo.output.address, value.to_sat()))
+            }
+            _ => None,
+        })
+        .collect())
+}
+
+/// Wait (with retries) for a node to reach a block count
+async fn wait_for_block_count(
+    sidechain: &PostSetup,
+    expected: u32,
+) -> anyhow::Result<()> {
+    const MAX_RETRIES: usize = 30;
+    const RETRY_DELAY_MS: u64 = 500;
+    for _ in 0..MAX_RETRIES {
+        if sidechain.rpc_client.getblockcount().await? == expected {
+            return Ok(());
+        }
+        sleep(std::time::Duration::from_millis(RETRY_DELAY_MS)).await;
+    }
+    let block_count = sidechain.rpc_client.getblockcount().await?;
+    anyhow::bail!(
+        "Expected block count {expected}, found {block_count} after timeout"
+    )
+}
+
+async fn get_swap(
+    sidechain: &PostSetup,
+    swap_id: SwapId,
+) -> anyhow::Result<Swap> {
+    sidechain
+        .rpc_client
+        .get_swap_status(swap_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Swap {swap_id} not found"))
+}
+
+async fn swap_reserve_task(
+    bin_paths: BinPaths,
+    res_tx: mpsc::UnboundedSender<anyhow::Result<()>>,
+) -> anyhow::Result<()> {
+    let (mut alice, mut enforcer_post_setup) =
+        crate::swap_creation::setup_swapper(
+            &bin_paths,
+            res_tx.clone(),
+            "reserve-alice",
+        )
+        .await?;
+    let mut bob = PostSetup::setup(
+        Init {
+            coinshift_app: bin_paths.coinshift_app.clone(),
+            data_dir_suffix: Some("reserve-bob".to_owned()),
+        },
+        &enforcer_post_setup,
+        res_tx,
+    )
+    .await?;
+    tracing::info!("Setup Bob's node successfully");
+    bob.rpc_client.connect_peer(alice.net_addr().into()).await?;
+
+    let deposit_address = alice.get_deposit_address().await?;
+    let () = deposit(
+        &mut enforcer_post_setup,
+        &mut alice,
+        &deposit_address,
+        DEPOSIT_AMOUNT,
+        DEPOSIT_FEE,
+    )
+    .await?;
+    let deposit_address = bob.get_deposit_address().await?;
+    let () = deposit(
+        &mut enforcer_post_setup,
+        &mut bob,
+        &deposit_address,
+        DEPOSIT_AMOUNT,
+        DEPOSIT_FEE,
+    )
+    .await?;
+    let () =
+        wait_for_block_count(&alice, bob.rpc_client.getblockcount().await?)
+            .await?;
+
+    let l1_recipient = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+    let (swap_id, _txid) = alice
+        .rpc_client
+        .create_swap(
+            ParentChainType::Regtest,
+            l1_recipient.to_string(),
+            SWAP_L1_AMOUNT,
+            None,
+            SWAP_L2_AMOUNT,
+            Some(1),
+            None,
+            Some(SWAP_EXPIRES_IN_BLOCKS),
+            SWAP_FEE,
+        )
+        .await?;
+    alice.bmm_single(&mut enforcer_post_setup).await?;
+    let () =
+        wait_for_block_count(&bob, alice.rpc_client.getblockcount().await?)
+            .await?;
+    sleep(std::time::Duration::from_millis(500)).await;
+    let swap = get_swap(&bob, swap_id).await?;
+    let creator = swap
+        .l2_creator_address
+        .ok_or_else(|| anyhow::anyhow!("Swap {swap_id} has no creator"))?;
+    let bond = swap.reservation_bond().to_sat();
+
+    let l2_claimer_address = bob.rpc_client.get_new_address().await?;
+    let reserve_txid = bob
+        .rpc_client
+        .reserve_swap(
+            swap_id,
+            Some(l2_claimer_address),
+            Some(RESERVE_BLOCKS),
+            RESERVE_FEE,
+        )
+        .await?;
+    tracing::info!(%swap_id, %reserve_txid, "Sent swap reserve");
+    bob.bmm_single(&mut enforcer_post_setup).await?;
+    let () =
+        wait_for_block_count(&alice, bob.rpc_client.getblockcount().await?)
+            .await?;
+    sleep(std::time::Duration::from_millis(500)).await;
+
+    for (name, node) in [("Alice", &alice), ("Bob", &bob)] {
+        let swap = get_swap(node, swap_id).await?;
+        let reservation = swap.reservation.as_ref().ok_or_else(|| {
+            anyhow::anyhow!("{name} should see the swap as reserved")
+        })?;
+        anyhow::ensure!(
+            reservation.l2_claimer_address == l2_claimer_address
+                && swap.l2_claimer_address == Some(l2_claimer_address),
+            "{name} should see Bob's claimer address on the reservation"
+        );
+        anyhow::ensure!(
+            reservation.bond.to_sat() == bond,
+            "{name} should see a bond of {bond} sats, got {}",
+            reservation.bond.to_sat()
+        );
+    }
+    let pending_outputs = swap_pending_outputs(&alice, swap_id).await?;
+    anyhow::ensure!(
+        pending_outputs.contains(&(creator, bond)),
+        "The bond should be owned by the swap creator"
+    );
+
+    // The swap cannot be cancelled or reserved again while the reservation
+    // is live
+    anyhow::ensure!(
+        alice
+            .rpc_client
+            .cancel_swap(swap_id, CANCEL_FEE)
+            .await
+            .is_err(),
+        "Alice should not be able to cancel a reserved swap"
+    );
+    anyhow::ensure!(
+        bob.rpc_client
+            .reserve_swap(swap_id, None, Some(RESERVE_BLOCKS), RESERVE_FEE)
+            .await
+            .is_err(),
+        "A reserved swap should not be reservable again"
+    );
+
+    // Let the reservation lapse without filling the swap
+    alice.bmm(&mut enforcer_post_setup, RESERVE_BLOCKS).await?;
+    let () =
+        wait_for_block_count(&bob, alice.rpc_client.getblockcount().await?)
+            .await?;
+    sleep(std::time::Duration::from_millis(500)).await;
+    for (name, node) in [("Alice", &alice), ("Bob", &bob)] {
+        let swap = get_swap(node, swap_id).await?;
+        anyhow::ensure!(
+            swap.reservation.is_none() && swap.l2_claimer_address.is_none(),
+            "{name} should see the reservation lapsed"
+        );
+        anyhow::ensure!(
+            matches!(swap.state, SwapState::Pending),
+            "{name} should see the swap Pending again, got {:?}",
+            swap.state
+        );
+    }
+
+    // With the reservation lapsed, Alice can cancel
+    let cancel_txid = alice.rpc_client.cancel_swap(swap_id, CANCEL_FEE).await?;
+    tracing::info!(%swap_id, %cancel_txid, "Sent swap cancel");
+    alice.bmm_single(&mut enforcer_post_setup).await?;
+    sleep(std::time::Duration::from_millis(500)).await;
+    let swap = get_swap(&alice, swap_id).await?;
+    anyhow::ensure!(
+        matches!(swap.state, SwapState::Cancelled),
+        "Swap should be Cancelled, got {:?}",
+        swap.state
+    );
+
+    drop(bob);
+    crate::swap_creation::cleanup_swapper(alice, enforcer_post_setup).await
+}
+
+async fn swap_reserve(bin_paths: BinPaths) -> anyhow::Result<()> {
+    let (res_tx, mut res_rx) = mpsc::unbounded();
+    let _test_task: AbortOnDrop<()> = tokio::task::spawn({
+        let res_tx = res_tx.clone();
+        async move {
+            let res = swap_reserve_task(bin_paths, res_tx.clone()).await;
+            let _send_err: Result<(), _> = res_tx.unbounded_send(res);
+        }
+        .in_current_span()
+    })
+    .into();
+    res_rx.next().await.ok_or_else(|| {
+        anyhow::anyhow!("Unexpected end of test task result stream")
+    })?
+}
+
+pub fn swap_reserve_trial(
+    bin_paths: BinPaths,
+    file_registry: TestFileRegistry,
+    failure_collector: TestFailureCollector,
+) -> AsyncTrial<BoxFuture<'static, anyhow::Result<()>>> {
+    AsyncTrial::new(
+        "swap_reserve",
+        swap_reserve(bin_paths).boxed(),
+        file_registry,
+        failure_collector,
+    )
+}
@@ -65,6 +65,8 @@ impl CoinshiftApp {
             format!("http://127.0.0.1:{}", self.mainchain_grpc_port),
             "--net-addr".to_owned(),
             format!("127.0.0.1:{}", self.net_port),
+            "--network".to_owned(),
+            "regtest".to_owned(),
             "--rpc-addr".to_owned(),
             format!("127.0.0.1:{}", self.rpc_port),
         ];
@@ -13,8 +13,8 @@ use sneed::{
 };
 
 use crate::types::{
-    Accumulator, BlockHash, BmmResult, Body, Header, Tip, VERSION, Version,
-    proto::mainchain,
+    Accumulator, BlockHash, BmmResult, Body, Header, Network, Tip, VERSION,
+    Version, proto::mainchain,
 };
 
 #[derive(Debug, thiserror::Error)]
@@ -33,6 +33,13 @@ pub enum Error {
     IncompatibleVersion { version: Version, db_path: PathBuf },
     #[error("invalid merkle root")]
     InvalidMerkleRoot,
+    #[error(
+        "mainchain block {block_hash} has no parent, but is not the genesis block of {network:?}"
+    )]
+    InvalidMainGenesis {
+        network: Network,
+        block_hash: bitcoin::BlockHash,
+    },
     #[error("invalid previous side hash")]
     InvalidPrevSideHash,
     #[error("no accumulator for block {0}")]
@@ -64,6 +71,17 @@ pub enum Error {
     NoMainHeaderInfo(bitcoin::BlockHash),
     #[error("no height info for mainchain block hash {0}")]
     NoMainHeight(bitcoin::BlockHash),
+    #[error(
+        "DB (`{}`) was created for network {:?}, not {:?}",
+        .db_path.display(),
+        .db_network,
+        .network
+    )]
+    WrongNetwork {
+        network: Network,
+        db_network: Network,
+        db_path: PathBuf,
+    },
 }
 
 #[derive(Clone)]
@@ -136,13 +154,15 @@ pub struct Archive {
         SerdeBincode<bitcoin::BlockHash>,
         SerdeBincode<bitcoin::Work>,
     >,
+    network: Network,
+    _network: DatabaseUnique<UnitKey, SerdeBincode<Network>>,
     _version: DatabaseUnique<UnitKey, SerdeBincode<Version>>,
 }
 
 impl Archive {
-    pub const NUM_DBS: u32 = 14;
+    pub const NUM_DBS: u32 = 15;
 
-    pub fn new(env: &sneed::Env) -> Result<Self, Error> {
+    pub fn new(env: &sneed::Env, network: Network) -> Result<Self, Error> {
         tracing::debug!("Archive::new: Acquiring write transaction");
         let mut rwtxn = env.write_txn().map_err(EnvError::from)?;
         tracing::debug!("Archive::new: Write transaction acquired");
@@ -197,6 +217,26 @@ impl Archive {
                 tracing::debug!("Archive::new: Initial database version set");
             }
         }
+        // The DB belongs to the network that created it. DBs created
+        // before the network was recorded are assumed to be for `network`.
+        let network_db =
+            DatabaseUnique::create(env, &mut rwtxn, "archive_network")
+                .map_err(EnvError::from)?;
+        match network_db.try_get(&rwtxn, &()).map_err(DbError::from)? {
+            Some(db_network) if db_network != network => {
+                return Err(Error::WrongNetwork {
+                    network,
+                    db_network,
+                    db_path: env.path().to_path_buf(),
+                });
+            }
+            Some(_) => (),
+            None => {
+                network_db
+                    .put(&mut rwtxn, &(), &network)
+                    .map_err(DbError::from)?;
+            }
+        }
         tracing::debug!("Archive::new: Creating accumulators database");
         let accumulators =
             DatabaseUnique::create(env, &mut rwtxn, "accumulators")
@@ -312,6 +352,8 @@ impl Archive {
             main_successors,
             successors,
             total_work,
+            network,
+            _network: network_db,
             _version: version,
         })
     }
@@ -963,6 +1005,15 @@ impl Archive {
         rwtxn: &mut RwTxn,
         header_info: &mainchain::BlockHeaderInfo,
     ) -> Result<(), Error> {
+        if header_info.prev_block_hash == bitcoin::BlockHash::all_zeros()
+            && header_info.block_hash
+                != self.network.mainchain_genesis_block_hash()
+        {
+            return Err(Error::InvalidMainGenesis {
+                network: self.network,
+                block_hash: header_info.block_hash,
+            });
+        }
         if self
             .try_get_main_header_info(rwtxn, &header_info.prev_block_hash)?
             .is_none()
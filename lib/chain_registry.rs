@@ -0,0 +1,907 @@
+//! Parent chain registry
+//!
+//! Every [`ParentChainType`] is described by a [`ChainDescriptor`]. The
+//! built-in chains are always registered, as are the test networks of
+//! Bitcoin Cash and Litecoin, which are referenced by swaps as
+//! [`ParentChainType::Custom`] chains with the [`BUILTIN_NETWORK_IDS`].
+//! Further chains (e.g. Bitcoin testnet4, Dogecoin-style chains or custom
+//! regtests) are loaded at startup from `parent_chains.json` in the data
+//! directory, a map from [`ChainId`] to descriptor, and are likewise
+//! referenced by swaps as [`ParentChainType::Custom`].
+//!
+//! Swap validation depends on the descriptors, so every node of a network
+//! must load the same chains, and a chain must not be removed while swaps
+//! reference it. The [hash](ChainRegistry::hash) of the registry prefixes
+//! every P2P message, so nodes with different registries refuse each other.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+    sync::{LazyLock, OnceLock},
+    time::Duration,
+};
+
+use bitcoin::hashes::Hash as _;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::types::{
+    AddressParams, ChainId, ChainNetwork, ChainParams, DifficultyAdjustment,
+    Hash, ParentChainType, PowAlgorithm,
+};
+
+/// Name of the chain registry file in the data directory
+pub const PARENT_CHAINS_FILE_NAME: &str = "parent_chains.json";
+
+/// Chain ID of Bitcoin Cash testnet4
+pub const BCH_TESTNET4_ID: &str = "BCH-testnet4";
+
+/// Chain ID of Bitcoin Cash regtest
+pub const BCH_REGTEST_ID: &str = "BCH-regtest";
+
+/// Chain ID of Litecoin testnet
+pub const LTC_TESTNET_ID: &str = "LTC-testnet";
+
+/// Chain ID of Litecoin regtest
+pub const LTC_REGTEST_ID: &str = "LTC-regtest";
+
+/// IDs of the built-in networks that are registered as custom chains, in
+/// registry order
+pub const BUILTIN_NETWORK_IDS: [&str; 4] = [
+    BCH_TESTNET4_ID,
+    BCH_REGTEST_ID,
+    LTC_TESTNET_ID,
+    LTC_REGTEST_ID,
+];
+
+/// The always registered custom chain with one of the
+/// [`BUILTIN_NETWORK_IDS`]
+pub fn builtin_network(id: &str) -> ParentChainType {
+    ParentChainType::Custom(id.parse().expect("valid built-in chain ID"))
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("chain ID `{0}` is reserved for a built-in parent chain")]
+    ReservedChainId(ChainId),
+    #[error("invalid descriptor for parent chain `{id}`: {reason}")]
+    InvalidDescriptor { id: ChainId, reason: String },
+    #[error("chain registry is already initialized")]
+    AlreadyInitialized,
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Swap expiration limits, in L2 blocks
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct SwapExpirationBlocks {
+    /// Expiration of swaps that do not choose one
+    pub default: u32,
+    /// Minimum expiration that a swap creator may choose
+    pub min: u32,
+    /// Maximum expiration that a swap creator may choose
+    pub max: u32,
+}
+
+/// Minimum number of confirmations for swaps of at least an L1 amount
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConfirmationTier {
+    pub min_l1_amount_sats: u64,
+    pub confirmations: u32,
+}
+
+fn default_supported() -> bool {
+    true
+}
+
+/// Description of a parent chain
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ChainDescriptor {
+    pub ticker: String,
+    pub coin_name: String,
+    /// Chain name reported by `getblockchaininfo`. If set, Bitcoin Core
+    /// endpoints must report it as well as serving the genesis block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_name: Option<String>,
+    pub default_confirmations: u32,
+    /// Minimum confirmations by swap L1 amount, ordered by increasing L1
+    /// amount. The first tier starts at an L1 amount of 0, so that every
+    /// swap has a minimum.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub confirmation_tiers: Vec<ConfirmationTier>,
+    pub swap_expiration_blocks: SwapExpirationBlocks,
+    /// Maximum number of L2 blocks for which a taker may reserve an open
+    /// swap
+    pub max_swap_reservation_blocks: u32,
+    /// Maximum L1 confirmation age (in L1 blocks) for an L1 transaction to
+    /// be accepted as a swap fill
+    pub max_l1_tx_age_blocks: u32,
+    /// Whether the GUI offers L1 config and swap creation for this chain
+    #[serde(default = "default_supported")]
+    pub supported: bool,
+    #[serde(flatten)]
+    pub params: ChainParams,
+}
+
+/// Confirmation tier of a built-in chain
+fn tier(min_l1_amount_sats: u64, confirmations: u32) -> ConfirmationTier {
+    ConfirmationTier {
+        min_l1_amount_sats,
+        confirmations,
+    }
+}
+
+/// Confirmation tiers of the built-in regtests. Small swaps may be tested
+/// with a single confirmation.
+fn regtest_confirmation_tiers() -> Vec<ConfirmationTier> {
+    vec![tier(0, 1), tier(1_000_000_000, 3), tier(100_000_000_000, 6)]
+}
+
+impl ChainDescriptor {
+    /// Descriptor of a built-in chain
+    fn builtin(parent_chain: ParentChainType) -> Option<Self> {
+        let descriptor = match parent_chain {
+            ParentChainType::BTC => Self {
+                ticker: "BTC".to_owned(),
+                coin_name: "Bitcoin".to_owned(),
+                chain_name: Some("main".to_owned()),
+                default_confirmations: 6,
+                confirmation_tiers: vec![
+                    tier(0, 3),
+                    tier(10_000_000, 6),
+                    tier(1_000_000_000, 12),
+                    tier(10_000_000_000, 24),
+                ],
+                // ~1 week / 1 hour / ~4 weeks at 10min L2 blocks
+                swap_expiration_blocks: SwapExpirationBlocks {
+                    default: 1008,
+                    min: 6,
+                    max: 4032,
+                },
+                // ~1 day at 10min L2 blocks
+                max_swap_reservation_blocks: 144,
+                // ~2 weeks of Bitcoin blocks
+                max_l1_tx_age_blocks: 2016,
+                supported: false,
+                params: ChainParams::bitcoin(),
+            },
+            // Bitcoin Cash shares its genesis block and chain name with
+            // Bitcoin, so endpoints of either chain pass as the other
+            ParentChainType::BCH => Self {
+                ticker: "BCH".to_owned(),
+                coin_name: "Bitcoin Cash".to_owned(),
+                chain_name: Some("main".to_owned()),
+                default_confirmations: 6,
+                // Bitcoin Cash has a small fraction of Bitcoin's hash rate
+                confirmation_tiers: vec![
+                    tier(0, 6),
+                    tier(100_000_000, 12),
+                    tier(10_000_000_000, 24),
+                    tier(100_000_000_000, 48),
+                ],
+                swap_expiration_blocks: SwapExpirationBlocks {
+                    default: 1008,
+                    min: 6,
+                    max: 4032,
+                },
+                max_swap_reservation_blocks: 144,
+                max_l1_tx_age_blocks: 2016,
+                supported: false,
+                params: ChainParams::bitcoin_cash(),
+            },
+            ParentChainType::LTC => Self {
+                ticker: "LTC".to_owned(),
+                coin_name: "Litecoin".to_owned(),
+                chain_name: Some("main".to_owned()),
+                default_confirmations: 3,
+                // Confirmations are 4x as frequent as on Bitcoin, and
+                // scrypt proof of work is not verified
+                confirmation_tiers: vec![
+                    tier(0, 3),
+                    tier(1_000_000_000, 6),
+                    tier(10_000_000_000, 12),
+                    tier(100_000_000_000, 24),
+                    tier(1_000_000_000_000, 48),
+                ],
+                swap_expiration_blocks: SwapExpirationBlocks {
+                    default: 432,
+                    min: 6,
+                    max: 2016,
+                },
+                max_swap_reservation_blocks: 72,
+                // ~2 weeks of 2.5min blocks
+                max_l1_tx_age_blocks: 8064,
+                supported: false,
+                params: ChainParams::litecoin(),
+            },
+            ParentChainType::Signet => Self {
+                ticker: "sBTC".to_owned(),
+                coin_name: "Bitcoin Signet".to_owned(),
+                chain_name: Some("signet".to_owned()),
+                default_confirmations: 3,
+                confirmation_tiers: vec![
+                    tier(0, 2),
+                    tier(100_000_000, 3),
+                    tier(10_000_000_000, 6),
+                ],
+                swap_expiration_blocks: SwapExpirationBlocks {
+                    default: 432,
+                    min: 6,
+                    max: 2016,
+                },
+                max_swap_reservation_blocks: 72,
+                max_l1_tx_age_blocks: 2016,
+                // Signet headers cannot be verified, so swaps cannot be
+                // proven
+                supported: false,
+                params: ChainParams::bitcoin_signet(),
+            },
+            // Short limits for testing
+            ParentChainType::Regtest => Self {
+                ticker: "rBTC".to_owned(),
+                coin_name: "Bitcoin Regtest".to_owned(),
+                chain_name: Some("regtest".to_owned()),
+                default_confirmations: 3,
+                confirmation_tiers: regtest_confirmation_tiers(),
+                swap_expiration_blocks: SwapExpirationBlocks {
+                    default: 50,
+                    min: 1,
+                    max: 1000,
+                },
+                max_swap_reservation_blocks: 20,
+                max_l1_tx_age_blocks: 500,
+                supported: false,
+                params: ChainParams::bitcoin_regtest(),
+            },
+            ParentChainType::Custom(_) => return None,
+        };
+        Some(descriptor)
+    }
+
+    /// Descriptor of a built-in network with one of the
+    /// [`BUILTIN_NETWORK_IDS`]
+    fn builtin_network(id: &str) -> Option<Self> {
+        let descriptor = match id {
+            // Node implementations report different chain names for
+            // testnet4, so only the genesis block is checked
+            BCH_TESTNET4_ID => Self {
+                ticker: "tBCH".to_owned(),
+                coin_name: "Bitcoin Cash Testnet4".to_owned(),
+                chain_name: None,
+                default_confirmations: 3,
+                // Minimum difficulty blocks make testnet4 reorgs cheap
+                confirmation_tiers: vec![
+                    tier(0, 3),
+                    tier(100_000_000, 6),
+                    tier(10_000_000_000, 12),
+                ],
+                // ~3 days / ~2 weeks for faster chains / testnets
+                swap_expiration_blocks: SwapExpirationBlocks {
+                    default: 432,
+                    min: 6,
+                    max: 2016,
+                },
+                // ~12 hours for faster chains / testnets
+                max_swap_reservation_blocks: 72,
+                max_l1_tx_age_blocks: 2016,
+                supported: true,
+                params: ChainParams::bitcoin_cash_testnet4(),
+            },
+            // Shares its genesis block and chain name with Bitcoin regtest
+            BCH_REGTEST_ID => Self {
+                ticker: "rBCH".to_owned(),
+                coin_name: "Bitcoin Cash Regtest".to_owned(),
+                chain_name: None,
+                default_confirmations: 3,
+                confirmation_tiers: regtest_confirmation_tiers(),
+                swap_expiration_blocks: SwapExpirationBlocks {
+                    default: 50,
+                    min: 1,
+                    max: 1000,
+                },
+                max_swap_reservation_blocks: 20,
+                max_l1_tx_age_blocks: 500,
+                supported: false,
+                params: ChainParams::bitcoin_cash_regtest(),
+            },
+            LTC_TESTNET_ID => Self {
+                ticker: "tLTC".to_owned(),
+                coin_name: "Litecoin Testnet".to_owned(),
+                chain_name: Some("test".to_owned()),
+                default_confirmations: 3,
+                confirmation_tiers: vec![
+                    tier(0, 3),
+                    tier(1_000_000_000, 6),
+                    tier(100_000_000_000, 12),
+                ],
+                swap_expiration_blocks: SwapExpirationBlocks {
+                    default: 432,
+                    min: 6,
+                    max: 2016,
+                },
+                max_swap_reservation_blocks: 72,
+                // ~2 weeks of 2.5min blocks
+                max_l1_tx_age_blocks: 8064,
+                supported: false,
+                params: ChainParams::litecoin_testnet(),
+            },
+            LTC_REGTEST_ID => Self {
+                ticker: "rLTC".to_owned(),
+                coin_name: "Litecoin Regtest".to_owned(),
+                chain_name: Some("regtest".to_owned()),
+                default_confirmations: 3,
+                confirmation_tiers: regtest_confirmation_tiers(),
+                swap_expiration_blocks: SwapExpirationBlocks {
+                    default: 50,
+                    min: 1,
+                    max: 1000,
+                },
+                max_swap_reservation_blocks: 20,
+                max_l1_tx_age_blocks: 500,
+                supported: false,
+                params: ChainParams::litecoin_regtest(),
+            },
+            _ => return None,
+        };
+        Some(descriptor)
+    }
+
+    /// Descriptor used for custom chains that are not in the registry, e.g.
+    /// when a chain is removed from the registry, or by the CLI for chains
+    /// reported by a node. No address decodes, no header is valid and no
+    /// swap expiration can be chosen, so swaps on such chains can be
+    /// neither created nor filled.
+    pub fn unregistered() -> &'static Self {
+        static UNREGISTERED: LazyLock<ChainDescriptor> =
+            LazyLock::new(|| ChainDescriptor {
+                ticker: String::new(),
+                coin_name: String::new(),
+                chain_name: None,
+                default_confirmations: 6,
+                confirmation_tiers: Vec::new(),
+                swap_expiration_blocks: SwapExpirationBlocks {
+                    default: 0,
+                    min: 1,
+                    max: 0,
+                },
+                max_swap_reservation_blocks: 0,
+                max_l1_tx_age_blocks: 0,
+                supported: false,
+                params: ChainParams {
+                    network: ChainNetwork::Mainnet,
+                    genesis_block_hash: bitcoin::BlockHash::all_zeros(),
+                    target_block_interval: Duration::from_secs(600),
+                    pow_algorithm: PowAlgorithm::Sha256d,
+                    pow_limit_bits: 0,
+                    difficulty_adjustment: DifficultyAdjustment::Fixed,
+                    address: AddressParams::default(),
+                    default_rpc_port: 0,
+                },
+            });
+        &UNREGISTERED
+    }
+
+    /// Minimum confirmations for a swap of `l1_amount`, from the highest
+    /// confirmation tier that it reaches. Only the
+    /// [unregistered](Self::unregistered) descriptor has no tiers, and
+    /// requires 1 confirmation.
+    pub fn min_confirmations(&self, l1_amount: bitcoin::Amount) -> u32 {
+        self.confirmation_tiers
+            .iter()
+            .rev()
+            .find(|tier| l1_amount.to_sat() >= tier.min_l1_amount_sats)
+            .map_or(1, |tier| tier.confirmations)
+    }
+
+    /// Check that the descriptor is consistent
+    fn validate(&self, id: ChainId) -> Result<(), Error> {
+        let invalid = |reason: &str| Error::InvalidDescriptor {
+            id,
+            reason: reason.to_owned(),
+        };
+        if self.ticker.is_empty() || self.coin_name.is_empty() {
+            return Err(invalid("ticker and coin name must not be empty"));
+        }
+        let SwapExpirationBlocks { default, min, max } =
+            self.swap_expiration_blocks;
+        if min == 0 || !(min..=max).contains(&default) {
+            return Err(invalid(
+                "swap expiration must satisfy 0 < min <= default <= max",
+            ));
+        }
+        if self
+            .confirmation_tiers
+            .first()
+            .is_none_or(|tier| tier.min_l1_amount_sats != 0)
+        {
+            return Err(invalid(
+                "confirmation tiers must start at an L1 amount of 0",
+            ));
+        }
+        let tiers_ordered = self.confirmation_tiers.windows(2).all(|tiers| {
+            tiers[0].min_l1_amount_sats < tiers[1].min_l1_amount_sats
+                && tiers[0].confirmations <= tiers[1].confirmations
+        });
+        if !tiers_ordered
+            || self
+                .confirmation_tiers
+                .iter()
+                .any(|tier| tier.confirmations == 0)
+        {
+            return Err(invalid(
+                "confirmation tiers must require at least 1 confirmation, \
+                 ordered by increasing L1 amount and confirmations",
+            ));
+        }
+        if self.params.target_block_interval.is_zero() {
+            return Err(invalid("target block interval must not be zero"));
+        }
+        let AddressParams {
+            segwit_hrp,
+            cashaddr_prefix,
+            p2pkh_versions,
+            p2sh_versions,
+        } = &self.params.address;
+        if segwit_hrp.is_none()
+            && cashaddr_prefix.is_none()
+            && p2pkh_versions.is_empty()
+            && p2sh_versions.is_empty()
+        {
+            return Err(invalid("at least one address encoding is required"));
+        }
+        Ok(())
+    }
+}
+
+/// Descriptors of the known parent chains
+#[derive(Debug)]
+pub struct ChainRegistry {
+    /// Built-in chains and networks, followed by custom chains ordered by ID
+    chains: Vec<ParentChainType>,
+    /// Chains for which the GUI offers L1 config and swap creation
+    supported: Vec<ParentChainType>,
+    descriptors: HashMap<ParentChainType, ChainDescriptor>,
+    /// Hash of the registered chains and their descriptors
+    hash: Hash,
+}
+
+impl ChainRegistry {
+    /// Registry of the built-in chains and networks and the given custom
+    /// chains
+    pub fn new(
+        custom: BTreeMap<ChainId, ChainDescriptor>,
+    ) -> Result<Self, Error> {
+        let mut chains = Vec::new();
+        let mut descriptors = HashMap::new();
+        for parent_chain in ParentChainType::BUILTIN {
+            let descriptor = ChainDescriptor::builtin(parent_chain)
+                .expect("built-in chains have descriptors");
+            chains.push(parent_chain);
+            descriptors.insert(parent_chain, descriptor);
+        }
+        for id in BUILTIN_NETWORK_IDS {
+            let descriptor = ChainDescriptor::builtin_network(id)
+                .expect("built-in networks have descriptors");
+            let parent_chain = builtin_network(id);
+            chains.push(parent_chain);
+            descriptors.insert(parent_chain, descriptor);
+        }
+        for (id, descriptor) in custom {
+            // Built-in IDs parse to built-in chains, and IDs differing only
+            // in case would be confused with them
+            if ParentChainType::BUILTIN
+                .iter()
+                .map(ParentChainType::to_string)
+                .chain(BUILTIN_NETWORK_IDS.map(str::to_owned))
+                .any(|builtin_id| builtin_id.eq_ignore_ascii_case(id.as_str()))
+            {
+                return Err(Error::ReservedChainId(id));
+            }
+            let () = descriptor.validate(id)?;
+            let parent_chain = ParentChainType::Custom(id);
+            chains.push(parent_chain);
+            descriptors.insert(parent_chain, descriptor);
+        }
+        let supported = chains
+            .iter()
+            .copied()
+            .filter(|parent_chain| descriptors[parent_chain].supported)
+            .collect();
+        let hash = Self::compute_hash(&chains, &descriptors);
+        Ok(Self {
+            chains,
+            supported,
+            descriptors,
+            hash,
+        })
+    }
+
+    /// Hash of the registered chains and their descriptors, in registry
+    /// order. Whether the GUI supports a chain does not affect consensus,
+    /// so it is not committed to.
+    fn compute_hash(
+        chains: &[ParentChainType],
+        descriptors: &HashMap<ParentChainType, ChainDescriptor>,
+    ) -> Hash {
+        let committed: Vec<_> = chains
+            .iter()
+            .map(|parent_chain| {
+                let mut descriptor =
+                    serde_json::to_value(&descriptors[parent_chain])
+                        .expect("failed to serialize chain descriptor");
+                if let serde_json::Value::Object(fields) = &mut descriptor {
+                    let _: Option<serde_json::Value> =
+                        fields.remove("supported");
+                }
+                (parent_chain, descriptor)
+            })
+            .collect();
+        let committed = serde_json::to_vec(&committed)
+            .expect("failed to serialize chain registry");
+        blake3::hash(&committed).into()
+    }
+
+    /// Registry of the built-in chains and networks only
+    pub fn builtin() -> Self {
+        Self::new(BTreeMap::new()).expect("built-in chains are valid")
+    }
+
+    /// Load the chain registry file from a data directory. Without a file,
+    /// only the built-in chains and networks are registered.
+    pub fn load(datadir: &Path) -> Result<Self, Error> {
+        Self::load_from_path(&datadir.join(PARENT_CHAINS_FILE_NAME))
+    }
+
+    pub fn load_from_path(path: &Path) -> Result<Self, Error> {
+        match std::fs::read_to_string(path) {
+            Ok(file_content) => {
+                let custom = serde_json::from_str(&file_content)?;
+                let registry = Self::new(custom)?;
+                tracing::info!(
+                    path = %path.display(),
+                    parent_chains = ?registry.chains,
+                    "Loaded chain registry"
+                );
+                Ok(registry)
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                Ok(Self::builtin())
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Registered chains: the built-in chains and networks, followed by
+    /// custom chains ordered by ID
+    pub fn chains(&self) -> &[ParentChainType] {
+        &self.chains
+    }
+
+    /// Chains for which the GUI offers L1 config and swap creation
+    pub fn supported(&self) -> &[ParentChainType] {
+        &self.supported
+    }
+
+    /// Hash of the registry. Nodes of a network must have the same hash.
+    pub fn hash(&self) -> Hash {
+        self.hash
+    }
+
+    /// Descriptor of a chain, or `None` if it is not registered
+    pub fn descriptor(
+        &self,
+        parent_chain: ParentChainType,
+    ) -> Option<&ChainDescriptor> {
+        self.descriptors.get(&parent_chain)
+    }
+}
+
+static REGISTRY: OnceLock<ChainRegistry> = OnceLock::new();
+
+/// The chain registry set with [`init`], or that of the built-in chains if
+/// none was set
+pub fn registry() -> &'static ChainRegistry {
+    REGISTRY.get_or_init(ChainRegistry::builtin)
+}
+
+/// Set the chain registry. Must be called at startup, before parent chains
+/// are looked up.
+pub fn init(registry: ChainRegistry) -> Result<(), Error> {
+    REGISTRY
+        .set(registry)
+        .map_err(|_| Error::AlreadyInitialized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        BCH_REGTEST_ID, BCH_TESTNET4_ID, BUILTIN_NETWORK_IDS, ChainDescriptor,
+        ChainRegistry, Error, LTC_REGTEST_ID, LTC_TESTNET_ID, builtin_network,
+    };
+    use crate::types::{ChainId, ChainNetwork, ParentChainType};
+
+    /// Dogecoin-style chain and a second regtest
+    fn custom_chains() -> serde_json::Value {
+        serde_json::json!({
+            "DOGE": {
+                "ticker": "DOGE",
+                "coin_name": "Dogecoin",
+                "chain_name": "main",
+                "default_confirmations": 40,
+                "confirmation_tiers": [
+                    { "min_l1_amount_sats": 0, "confirmations": 40 },
+                    { "min_l1_amount_sats": 100_000_000_000u64, "confirmations": 60 }
+                ],
+                "swap_expiration_blocks": { "default": 432, "min": 6, "max": 2016 },
+                "max_swap_reservation_blocks": 72,
+                "max_l1_tx_age_blocks": 20160,
+                "network": "mainnet",
+                "genesis_block_hash": "1a91e3dace36e2be3bf030a65679fe821aa1d6ef92e7c9902eb318182c355691",
+                "target_block_interval": 60,
+                "pow_algorithm": "scrypt",
+                "pow_limit_bits": 0x1e0fffff,
+                "difficulty_adjustment": "per_block",
+                "address": { "p2pkh_versions": [0x1e], "p2sh_versions": [0x16] },
+                "default_rpc_port": 22555
+            },
+            "regtest-b": {
+                "ticker": "rBTC-b",
+                "coin_name": "Bitcoin Regtest B",
+                "default_confirmations": 1,
+                "confirmation_tiers": [
+                    { "min_l1_amount_sats": 0, "confirmations": 1 }
+                ],
+                "swap_expiration_blocks": { "default": 10, "min": 1, "max": 100 },
+                "max_swap_reservation_blocks": 5,
+                "max_l1_tx_age_blocks": 100,
+                "supported": false,
+                "network": "regtest",
+                "genesis_block_hash": "0f9188f13cb7b2c71f2a335e3a4fc328bf5beb436012afca590b1a11466e2206",
+                "target_block_interval": 600,
+                "pow_algorithm": "sha256d",
+                "pow_limit_bits": 0x207fffff,
+                "difficulty_adjustment": "fixed",
+                "address": { "segwit_hrp": "bcrt", "p2pkh_versions": [0x6f], "p2sh_versions": [0xc4] },
+                "default_rpc_port": 18543
+            }
+        })
+    }
+
+    /// Built-in chains, followed by the built-in networks
+    fn builtin_chains() -> Vec<ParentChainType> {
+        ParentChainType::BUILTIN
+            .into_iter()
+            .chain(BUILTIN_NETWORK_IDS.map(builtin_network))
+            .collect()
+    }
+
+    #[test]
+    fn builtin_chains_are_registered() {
+        let registry = ChainRegistry::builtin();
+        assert_eq!(registry.chains(), builtin_chains());
+        assert_eq!(registry.supported(), [builtin_network(BCH_TESTNET4_ID)]);
+        for parent_chain in builtin_chains() {
+            let descriptor = registry.descriptor(parent_chain).unwrap();
+            let id: ChainId = "builtin".parse().unwrap();
+            assert!(descriptor.validate(id).is_ok());
+        }
+        // Each coin has a mainnet, a testnet and a regtest
+        let network = |parent_chain: ParentChainType| {
+            registry.descriptor(parent_chain).unwrap().params.network
+        };
+        assert_eq!(network(ParentChainType::BCH), ChainNetwork::Mainnet);
+        assert_eq!(
+            network(builtin_network(BCH_TESTNET4_ID)),
+            ChainNetwork::Testnet
+        );
+        assert_eq!(
+            network(builtin_network(BCH_REGTEST_ID)),
+            ChainNetwork::Regtest
+        );
+        assert_eq!(network(ParentChainType::LTC), ChainNetwork::Mainnet);
+        assert_eq!(
+            network(builtin_network(LTC_TESTNET_ID)),
+            ChainNetwork::Testnet
+        );
+        assert_eq!(
+            network(builtin_network(LTC_REGTEST_ID)),
+            ChainNetwork::Regtest
+        );
+    }
+
+    #[test]
+    fn custom_chains_are_registered() {
+        let registry = ChainRegistry::new(
+            serde_json::from_value(custom_chains()).unwrap(),
+        )
+        .unwrap();
+        let doge = ParentChainType::Custom("DOGE".parse().unwrap());
+        let regtest_b = ParentChainType::Custom("regtest-b".parse().unwrap());
+        assert_eq!(&registry.chains()[9..], [doge, regtest_b]);
+        assert_eq!(
+            registry.supported(),
+            [builtin_network(BCH_TESTNET4_ID), doge]
+        );
+        let descriptor = registry.descriptor(doge).unwrap();
+        assert_eq!(descriptor.ticker, "DOGE");
+        assert_eq!(descriptor.params.target_block_interval.as_secs(), 60);
+        let script = descriptor
+            .params
+            .address
+            .script_pubkey("DFxLFMAJWaNYA7TVTUstzPMFRSevAwTSLq")
+            .unwrap();
+        assert!(script.is_p2pkh());
+        assert!(
+            descriptor
+                .params
+                .address
+                .script_pubkey("mrLC19Je2BuWQDkWSTriGYPyQJXKkkBmCx")
+                .is_err()
+        );
+        // Custom regtests share the genesis block of the built-in regtest
+        assert_eq!(
+            registry
+                .descriptor(regtest_b)
+                .unwrap()
+                .params
+                .genesis_block_hash,
+            ParentChainType::Regtest.genesis_block_hash()
+        );
+        let unknown = ParentChainType::Custom("LTC-test".parse().unwrap());
+        assert!(registry.descriptor(unknown).is_none());
+    }
+
+    #[test]
+    fn registry_hash_commits_to_consensus_descriptors() {
+        let registry = |chains: serde_json::Value| {
+            ChainRegistry::new(serde_json::from_value(chains).unwrap()).unwrap()
+        };
+        let custom = registry(custom_chains());
+        assert_eq!(custom.hash(), registry(custom_chains()).hash());
+        assert_ne!(custom.hash(), ChainRegistry::builtin().hash());
+
+        // Nodes must agree on consensus parameters...
+        let mut chains = custom_chains();
+        chains["DOGE"]["default_confirmations"] = 20.into();
+        assert_ne!(custom.hash(), registry(chains).hash());
+        // ...but not on which chains their GUI supports
+        let mut chains = custom_chains();
+        chains["DOGE"]["supported"] = false.into();
+        assert_eq!(custom.hash(), registry(chains).hash());
+    }
+
+    #[test]
+    fn invalid_custom_chains_are_rejected() {
+        let mut chains = custom_chains();
+        let doge = chains["DOGE"].take();
+        let registry_with = |id: &str,
+                             descriptor: serde_json::Value|
+         -> Result<ChainRegistry, Error> {
+            let chains = serde_json::json!({ id: descriptor });
+            ChainRegistry::new(serde_json::from_value(chains)?)
+        };
+        assert!(matches!(
+            registry_with("btc", doge.clone()),
+            Err(Error::ReservedChainId(_))
+        ));
+        assert!(matches!(
+            registry_with("bch-testnet4", doge.clone()),
+            Err(Error::ReservedChainId(_))
+        ));
+        assert!(matches!(
+            registry_with("not a chain ID", doge.clone()),
+            Err(Error::Json(_))
+        ));
+        let mut invalid_expiration = doge.clone();
+        invalid_expiration["swap_expiration_blocks"]["default"] = 1.into();
+        assert!(matches!(
+            registry_with("DOGE", invalid_expiration),
+            Err(Error::InvalidDescriptor { .. })
+        ));
+        let mut unordered_tiers = doge.clone();
+        unordered_tiers["confirmation_tiers"] = serde_json::json!([
+            { "min_l1_amount_sats": 0, "confirmations": 40 },
+            { "min_l1_amount_sats": 1_000_000_000u64, "confirmations": 60 },
+            { "min_l1_amount_sats": 100_000_000u64, "confirmations": 80 }
+        ]);
+        assert!(matches!(
+            registry_with("DOGE", unordered_tiers),
+            Err(Error::InvalidDescriptor { .. })
+        ));
+        // Every swap amount must reach a tier
+        let mut no_tiers = doge.clone();
+        no_tiers["confirmation_tiers"] = serde_json::json!([]);
+        assert!(matches!(
+            registry_with("DOGE", no_tiers),
+            Err(Error::InvalidDescriptor { .. })
+        ));
+        let mut untiered_small_swaps = doge.clone();
+        untiered_small_swaps["confirmation_tiers"] = serde_json::json!([
+            { "min_l1_amount_sats": 100_000_000u64, "confirmations": 40 }
+        ]);
+        assert!(matches!(
+            registry_with("DOGE", untiered_small_swaps),
+            Err(Error::InvalidDescriptor { .. })
+        ));
+        let mut zero_confirmations = doge.clone();
+        zero_confirmations["confirmation_tiers"] = serde_json::json!([
+            { "min_l1_amount_sats": 0, "confirmations": 0 }
+        ]);
+        assert!(matches!(
+            registry_with("DOGE", zero_confirmations),
+            Err(Error::InvalidDescriptor { .. })
+        ));
+        let mut no_addresses = doge;
+        no_addresses["address"] = serde_json::json!({});
+        assert!(matches!(
+            registry_with("DOGE", no_addresses),
+            Err(Error::InvalidDescriptor { .. })
+        ));
+    }
+
+    #[test]
+    fn min_confirmations_follow_tiers() {
+        let registry = ChainRegistry::builtin();
+        let btc = registry.descriptor(ParentChainType::BTC).unwrap();
+        let min_confirmations = |btc_amount: f64| {
+            btc.min_confirmations(
+                bitcoin::Amount::from_btc(btc_amount).unwrap(),
+            )
+        };
+        assert_eq!(min_confirmations(0.0), 3);
+        assert_eq!(min_confirmations(0.01), 3);
+        assert_eq!(min_confirmations(0.1), 6);
+        assert_eq!(min_confirmations(5.0), 6);
+        assert_eq!(min_confirmations(10.0), 12);
+        assert_eq!(min_confirmations(1000.0), 24);
+        // The smallest swaps of every registered chain fall in its first
+        // tier, and only regtests accept a single confirmation
+        for parent_chain in registry.chains() {
+            let descriptor = registry.descriptor(*parent_chain).unwrap();
+            let smallest = descriptor.min_confirmations(bitcoin::Amount::ZERO);
+            assert_eq!(
+                smallest,
+                descriptor.confirmation_tiers[0].confirmations
+            );
+            if descriptor.params.network == ChainNetwork::Regtest {
+                assert_eq!(smallest, 1);
+            } else {
+                assert!(smallest > 1, "{parent_chain}");
+            }
+            let largest =
+                descriptor.min_confirmations(bitcoin::Amount::MAX_MONEY);
+            assert!(largest > smallest, "{parent_chain}");
+        }
+        // Only unregistered chains fall back to a single confirmation
+        assert_eq!(
+            ChainDescriptor::unregistered()
+                .min_confirmations(bitcoin::Amount::MAX_MONEY),
+            1
+        );
+    }
+
+    #[test]
+    fn load_missing_file_registers_builtin_chains() {
+        let registry = ChainRegistry::load_from_path(
+            "/nonexistent/parent_chains.json".as_ref(),
+        )
+        .unwrap();
+        assert_eq!(registry.chains(), builtin_chains());
+    }
+
+    #[test]
+    fn unregistered_chains_cannot_be_used() {
+        let descriptor = ChainDescriptor::unregistered();
+        let expiration = descriptor.swap_expiration_blocks;
+        assert!(expiration.min > expiration.max);
+        assert!(
+            descriptor
+                .params
+                .address
+                .script_pubkey("bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080")
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,574 @@
+//! Electrum protocol client
+//!
+//! Queries an Electrum server (ElectrumX, Fulcrum, electrs) over plain TCP
+//! or TLS.
+//! Unlike [`crate::parent_chain_rpc::ParentChainRpcClient`], this does not
+//! need a wallet on the L1 node: address history comes from the server's
+//! script hash index.
+
+use std::{
+    collections::HashMap,
+    io::{BufRead as _, BufReader, Read, Write},
+    net::TcpStream,
+    sync::Arc,
+    time::Duration,
+};
+
+use bitcoin::{
+    BlockHash,
+    block::Header,
+    hashes::{Hash as _, sha256},
+};
+use futures::future::{self, BoxFuture};
+use parking_lot::Mutex;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::{L1Backend, tls::TlsConfig};
+use crate::{
+    parent_chain_rpc::{Error, TransactionInfo},
+    types::{ParentChainType, l1_recipient_script},
+};
+
+/// Protocol version requested in `server.version`
+const PROTOCOL_VERSION: &str = "1.4";
+
+/// Read/write timeout for server requests
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Message sent by Electrum servers for unknown transactions
+const TX_NOT_FOUND_MESSAGE: &str = "No such mempool or blockchain transaction";
+
+#[derive(Debug, Deserialize)]
+struct ElectrumError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ElectrumResponse {
+    /// Absent for notifications
+    id: Option<u64>,
+    result: Option<serde_json::Value>,
+    error: Option<ElectrumError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HistoryEntry {
+    tx_hash: String,
+    /// Zero or negative for mempool transactions
+    height: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct HeadersTip {
+    height: u32,
+}
+
+/// Connection to an Electrum server, over plain TCP or TLS
+trait Stream: Read + Write + Send + std::fmt::Debug {}
+
+impl<T> Stream for T where T: Read + Write + Send + std::fmt::Debug {}
+
+#[derive(Debug)]
+struct Connection {
+    reader: BufReader<Box<dyn Stream>>,
+    next_id: u64,
+}
+
+/// Electrum script hash: SHA256 of the output script, hex-encoded in
+/// reverse byte order
+fn script_hash(script: &bitcoin::Script) -> String {
+    let mut hash = sha256::Hash::hash(script.as_bytes()).to_byte_array();
+    hash.reverse();
+    hex::encode(hash)
+}
+
+/// Wrap a connection to `addr` in TLS
+fn tls_stream(
+    addr: &str,
+    config: Arc<rustls::ClientConfig>,
+    stream: TcpStream,
+) -> Result<rustls::StreamOwned<rustls::ClientConnection, TcpStream>, Error> {
+    let host = addr.rsplit_once(':').map_or(addr, |(host, _port)| host);
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let server_name = rustls::pki_types::ServerName::try_from(host.to_owned())
+        .map_err(|err| Error::InvalidUrl(format!("{addr}: {err}")))?;
+    let connection = rustls::ClientConnection::new(config, server_name)?;
+    Ok(rustls::StreamOwned::new(connection, stream))
+}
+
+/// Blocking connection state of an [`ElectrumClient`]
+#[derive(Debug)]
+struct Client {
+    addr: String,
+    /// Set for `ssl://` servers
+    tls: Option<Arc<rustls::ClientConfig>>,
+    parent_chain: ParentChainType,
+    /// Open connection, re-established after I/O errors
+    connection: Mutex<Option<Connection>>,
+    /// Headers fetched by height, so that [`L1Backend::get_block_header`]
+    /// can look them up by hash
+    headers: Mutex<HashMap<BlockHash, Header>>,
+}
+
+impl Client {
+    fn connect(&self) -> Result<Connection, Error> {
+        let stream = TcpStream::connect(&self.addr)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+        let stream: Box<dyn Stream> = match &self.tls {
+            Some(config) => {
+                Box::new(tls_stream(&self.addr, config.clone(), stream)?)
+            }
+            None => Box::new(stream),
+        };
+        let mut connection = Connection {
+            reader: BufReader::new(stream),
+            next_id: 0,
+        };
+        let server_version = Self::request(
+            &mut connection,
+            "server.version",
+            json!(["coinshift", PROTOCOL_VERSION]),
+        )?;
+        tracing::debug!(
+            addr = %self.addr,
+            server_version = %server_version,
+            "Connected to Electrum server"
+        );
+        Ok(connection)
+    }
+
+    /// Send a request and wait for its response, skipping notifications
+    fn request(
+        connection: &mut Connection,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value, Error> {
+        let id = connection.next_id;
+        connection.next_id += 1;
+        let mut line = serde_json::to_string(&json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        }))?;
+        line.push('\n');
+        connection.reader.get_mut().write_all(line.as_bytes())?;
+        loop {
+            let mut line = String::new();
+            if connection.reader.read_line(&mut line)? == 0 {
+                return Err(std::io::Error::from(
+                    std::io::ErrorKind::UnexpectedEof,
+                )
+                .into());
+            }
+            let response: ElectrumResponse = serde_json::from_str(&line)?;
+            if response.id != Some(id) {
+                continue;
+            }
+            if let Some(error) = response.error {
+                return Err(Error::Rpc(format!(
+                    "{}: {}",
+                    error.code, error.message
+                )));
+            }
+            return response.result.ok_or(Error::InvalidResponse);
+        }
+    }
+
+    fn call<T: for<'de> Deserialize<'de>>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<T, Error> {
+        tracing::debug!(
+            addr = %self.addr,
+            method = %method,
+            params = %params,
+            "Making Electrum request"
+        );
+        let mut connection = self.connection.lock();
+        if connection.is_none() {
+            *connection = Some(self.connect()?);
+        }
+        let conn = connection.as_mut().expect("connection was just opened");
+        let result = match Self::request(conn, method, params) {
+            Ok(result) => result,
+            Err(err) => {
+                // Reconnect on the next request, unless the server replied
+                if !matches!(err, Error::Rpc(_)) {
+                    *connection = None;
+                }
+                tracing::error!(
+                    addr = %self.addr,
+                    method = %method,
+                    error = %err,
+                    "Electrum request failed"
+                );
+                return Err(err);
+            }
+        };
+        Ok(serde_json::from_value(result)?)
+    }
+
+    fn get_history(
+        &self,
+        script: &bitcoin::Script,
+    ) -> Result<Vec<HistoryEntry>, Error> {
+        self.call(
+            "blockchain.scripthash.get_history",
+            json!([script_hash(script)]),
+        )
+    }
+
+    fn get_header(&self, height: u32) -> Result<Header, Error> {
+        let header_hex: String =
+            self.call("blockchain.block.header", json!([height]))?;
+        let header_bytes =
+            hex::decode(header_hex).map_err(|_| Error::InvalidResponse)?;
+        let header: Header = bitcoin::consensus::deserialize(&header_bytes)
+            .map_err(|_| Error::InvalidResponse)?;
+        self.headers.lock().insert(header.block_hash(), header);
+        Ok(header)
+    }
+
+    /// Get transaction by ID. The containing block is found from the
+    /// history of the transaction's first output script, since Electrum
+    /// servers do not index transactions by block.
+    fn get_transaction(&self, txid: &str) -> Result<TransactionInfo, Error> {
+        let tx_hex: String = self
+            .call("blockchain.transaction.get", json!([txid]))
+            .map_err(|err| match err {
+                Error::Rpc(message)
+                    if message.contains(TX_NOT_FOUND_MESSAGE) =>
+                {
+                    Error::TransactionNotFound
+                }
+                err => err,
+            })?;
+        let tx_bytes =
+            hex::decode(tx_hex).map_err(|_| Error::InvalidResponse)?;
+        let tx: bitcoin::Transaction =
+            bitcoin::consensus::deserialize(&tx_bytes)
+                .map_err(|_| Error::InvalidResponse)?;
+        let mut blockheight = None;
+        if let Some(output) = tx
+            .output
+            .iter()
+            .find(|output| !output.script_pubkey.is_op_return())
+        {
+            blockheight = self
+                .get_history(&output.script_pubkey)?
+                .into_iter()
+                .find(|entry| entry.tx_hash == txid && entry.height > 0)
+                .map(|entry| entry.height as u32);
+        }
+        let (confirmations, blockhash) = match blockheight {
+            Some(height) => {
+                let tip_height = self.get_block_height()?;
+                let block_hash = self.get_header(height)?.block_hash();
+                (
+                    tip_height.saturating_sub(height) + 1,
+                    Some(block_hash.to_string()),
+                )
+            }
+            None => (0, None),
+        };
+        Ok(TransactionInfo {
+            txid: txid.to_owned(),
+            confirmations,
+            blockhash,
+            blockheight,
+            ..TransactionInfo::from_transaction(&tx)
+        })
+    }
+
+    /// Get transactions paying to an address, including mempool
+    /// transactions
+    fn list_transactions(&self, address: &str) -> Result<Vec<String>, Error> {
+        let script = l1_recipient_script(self.parent_chain, address)
+            .map_err(|err| Error::Rpc(err.to_string()))?;
+        let history = self.get_history(&script)?;
+        Ok(history.into_iter().map(|entry| entry.tx_hash).collect())
+    }
+
+    fn get_block_height(&self) -> Result<u32, Error> {
+        let tip: HeadersTip =
+            self.call("blockchain.headers.subscribe", json!([]))?;
+        Ok(tip.height)
+    }
+}
+
+/// Client for an Electrum server, configured as `tcp://host:port` (or
+/// `host:port`), or `ssl://host:port` for TLS, in the L1 config file.
+/// Requests block on the connection, so they are made on Tokio's blocking
+/// thread pool.
+#[derive(Debug)]
+pub struct ElectrumClient {
+    client: Arc<Client>,
+}
+
+impl ElectrumClient {
+    pub fn new(
+        url: &str,
+        parent_chain: ParentChainType,
+        tls: &TlsConfig,
+    ) -> Result<Self, Error> {
+        let (addr, tls) = match url.strip_prefix("ssl://") {
+            Some(addr) => (addr, Some(Arc::new(tls.client_config()?))),
+            None => (url.strip_prefix("tcp://").unwrap_or(url), None),
+        };
+        let client = Client {
+            addr: addr.to_owned(),
+            tls,
+            parent_chain,
+            connection: Mutex::new(None),
+            headers: Mutex::new(HashMap::new()),
+        };
+        Ok(Self {
+            client: Arc::new(client),
+        })
+    }
+
+    /// Make a blocking request on Tokio's blocking thread pool
+    fn spawn_request<T: Send + 'static>(
+        &self,
+        request: impl FnOnce(&Client) -> Result<T, Error> + Send + 'static,
+    ) -> BoxFuture<'static, Result<T, Error>> {
+        let client = self.client.clone();
+        Box::pin(async move {
+            tokio::task::spawn_blocking(move || request(&client)).await?
+        })
+    }
+}
+
+impl L1Backend for ElectrumClient {
+    fn name(&self) -> String {
+        format!("electrum://{}", self.client.addr)
+    }
+
+    fn get_transaction<'a>(
+        &'a self,
+        txid: &'a str,
+    ) -> BoxFuture<'a, Result<TransactionInfo, Error>> {
+        let txid = txid.to_owned();
+        self.spawn_request(move |client| client.get_transaction(&txid))
+    }
+
+    fn list_transactions<'a>(
+        &'a self,
+        address: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<String>, Error>> {
+        let address = address.to_owned();
+        self.spawn_request(move |client| client.list_transactions(&address))
+    }
+
+    fn get_block_height(&self) -> BoxFuture<'_, Result<u32, Error>> {
+        self.spawn_request(Client::get_block_height)
+    }
+
+    fn get_block_hash(
+        &self,
+        height: u32,
+    ) -> BoxFuture<'_, Result<BlockHash, Error>> {
+        self.spawn_request(move |client| {
+            Ok(client.get_header(height)?.block_hash())
+        })
+    }
+
+    /// Electrum servers index headers by height, so only headers previously
+    /// fetched with [`L1Backend::get_block_hash`] are available
+    fn get_block_header<'a>(
+        &'a self,
+        block_hash: &'a BlockHash,
+    ) -> BoxFuture<'a, Result<Header, Error>> {
+        let header = self
+            .client
+            .headers
+            .lock()
+            .get(block_hash)
+            .copied()
+            .ok_or(Error::InvalidResponse);
+        Box::pin(future::ready(header))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead as _, BufReader, Write as _},
+        net::TcpListener,
+    };
+
+    use bitcoin::{
+        Amount, OutPoint, Sequence, Transaction, TxIn, TxOut, Witness,
+        absolute::LockTime, transaction::Version,
+    };
+    use serde_json::json;
+
+    use super::{ElectrumClient, TX_NOT_FOUND_MESSAGE, script_hash};
+    use crate::{
+        l1_backend::{L1Backend as _, mock::MockL1Backend},
+        parent_chain_rpc::Error,
+        types::{ParentChainType, l1_recipient_script},
+    };
+
+    const ADDRESS: &str = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+
+    /// Serve Electrum requests on a local port from a fixed chain of
+    /// headers, and one transaction paying `script` at height 1
+    fn spawn_server(
+        headers: Vec<bitcoin::block::Header>,
+        tx: Transaction,
+        script: bitcoin::ScriptBuf,
+    ) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let txid = tx.compute_txid().to_string();
+        let tx_hex = bitcoin::consensus::encode::serialize_hex(&tx);
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut writer = stream.try_clone().unwrap();
+            for line in BufReader::new(stream).lines() {
+                let Ok(line) = line else { break };
+                let request: serde_json::Value =
+                    serde_json::from_str(&line).unwrap();
+                let params = &request["params"];
+                let result = match request["method"].as_str().unwrap() {
+                    "server.version" => Ok(json!(["stand-in", "1.4"])),
+                    "blockchain.headers.subscribe" => {
+                        let tip = headers.last().unwrap();
+                        // Servers may send notifications at any time
+                        let notification = json!({
+                            "jsonrpc": "2.0",
+                            "method": "blockchain.headers.subscribe",
+                            "params": [{ "height": 0, "hex": "" }],
+                        });
+                        writeln!(writer, "{notification}").unwrap();
+                        Ok(json!({
+                            "height": headers.len() - 1,
+                            "hex": bitcoin::consensus::encode::serialize_hex(tip),
+                        }))
+                    }
+                    "blockchain.block.header" => {
+                        let height = params[0].as_u64().unwrap() as usize;
+                        Ok(json!(bitcoin::consensus::encode::serialize_hex(
+                            &headers[height]
+                        )))
+                    }
+                    "blockchain.scripthash.get_history" => {
+                        if params[0] == script_hash(&script) {
+                            Ok(json!([{ "tx_hash": txid, "height": 1 }]))
+                        } else {
+                            Ok(json!([]))
+                        }
+                    }
+                    "blockchain.transaction.get" => {
+                        if params[0] == txid.as_str() {
+                            Ok(json!(tx_hex))
+                        } else {
+                            Err(json!({
+                                "code": 2,
+                                "message": TX_NOT_FOUND_MESSAGE,
+                            }))
+                        }
+                    }
+                    method => panic!("unexpected method {method}"),
+                };
+                let response = match result {
+                    Ok(result) => json!({
+                        "jsonrpc": "2.0",
+                        "id": request["id"],
+                        "result": result,
+                    }),
+                    Err(error) => json!({
+                        "jsonrpc": "2.0",
+                        "id": request["id"],
+                        "error": error,
+                    }),
+                };
+                writeln!(writer, "{response}").unwrap();
+            }
+        });
+        addr
+    }
+
+    #[test]
+    fn tracks_payment_through_stand_in_server() {
+        let chain = MockL1Backend::new_regtest();
+        let _tip = chain.mine_blocks(2);
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let headers = (0..=2)
+            .map(|height| {
+                runtime.block_on(async {
+                    let block_hash = chain.get_block_hash(height).await?;
+                    chain.get_block_header(&block_hash).await
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let script =
+            l1_recipient_script(ParentChainType::Regtest, ADDRESS).unwrap();
+        let amount = Amount::from_sat(5_000_000);
+        let tx = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint {
+                    txid: bitcoin::Txid::from_byte_array([1; 32]),
+                    vout: 0,
+                },
+                script_sig: bitcoin::ScriptBuf::new(),
+                sequence: Sequence::MAX,
+                witness: Witness::new(),
+            }],
+            output: vec![TxOut {
+                value: amount,
+                script_pubkey: script.clone(),
+            }],
+        };
+        let txid = tx.compute_txid().to_string();
+        let addr = spawn_server(headers.clone(), tx, script.clone());
+        let client = ElectrumClient::new(
+            &format!("tcp://{addr}"),
+            ParentChainType::Regtest,
+            &Default::default(),
+        )
+        .unwrap();
+
+        assert_eq!(runtime.block_on(client.get_block_height()).unwrap(), 2);
+        let block_hash = runtime.block_on(client.get_block_hash(1)).unwrap();
+        assert_eq!(block_hash, headers[1].block_hash());
+        assert_eq!(
+            runtime
+                .block_on(client.get_block_header(&block_hash))
+                .unwrap(),
+            headers[1]
+        );
+
+        assert_eq!(
+            runtime.block_on(client.list_transactions(ADDRESS)).unwrap(),
+            vec![txid.clone()]
+        );
+        let tx_info = runtime.block_on(client.get_transaction(&txid)).unwrap();
+        assert_eq!(tx_info.confirmations, 2);
+        assert_eq!(tx_info.blockheight, Some(1));
+        assert_eq!(tx_info.blockhash, Some(block_hash.to_string()));
+        let matches = runtime
+            .block_on(client.find_transactions_by_script_and_amount(
+                ADDRESS,
+                &script,
+                amount.to_sat(),
+            ))
+            .unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].1.txid, txid);
+
+        assert!(matches!(
+            runtime.block_on(client.get_transaction(&"00".repeat(32))),
+            Err(Error::TransactionNotFound)
+        ));
+    }
+}
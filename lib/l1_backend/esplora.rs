@@ -0,0 +1,398 @@
+//! Esplora REST client
+//!
+//! Queries an Esplora-compatible HTTP API (electrs/esplora, mempool.space).
+//! Like the Electrum backend, this does not need a wallet on the L1 node.
+
+use std::time::Duration;
+
+use bitcoin::{Block, BlockHash};
+use futures::future::BoxFuture;
+use serde::Deserialize;
+
+use super::{L1Backend, tls::TlsConfig};
+use crate::parent_chain_rpc::{
+    Error, ScriptPubKey, TransactionInfo, Vin, Vout, redact_url,
+};
+
+/// Timeout for Esplora requests
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Deserialize)]
+struct EsploraTxStatus {
+    confirmed: bool,
+    block_height: Option<u32>,
+    block_hash: Option<BlockHash>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EsploraVin {
+    txid: bitcoin::Txid,
+    vout: u32,
+    #[serde(default)]
+    is_coinbase: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct EsploraVout {
+    scriptpubkey: bitcoin::ScriptBuf,
+    scriptpubkey_address: Option<String>,
+    /// Value in sats
+    value: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct EsploraTx {
+    txid: bitcoin::Txid,
+    vin: Vec<EsploraVin>,
+    vout: Vec<EsploraVout>,
+    status: EsploraTxStatus,
+}
+
+/// Client for an Esplora API, configured by its base URL (e.g.
+/// `http://localhost:3000` or `https://mempool.space/signet/api`) in the
+/// L1 config file
+#[derive(Debug)]
+pub struct EsploraClient {
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl EsploraClient {
+    pub fn new(base_url: &str, tls: &TlsConfig) -> Result<Self, Error> {
+        Ok(Self {
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            client: tls.http_client()?,
+        })
+    }
+
+    /// GET a path relative to the base URL. A 404 is reported as
+    /// [`Error::TransactionNotFound`].
+    async fn get(&self, path: &str) -> Result<reqwest::Response, Error> {
+        let url = format!("{}{path}", self.base_url);
+        tracing::debug!(url = %redact_url(&url), "Making Esplora request");
+        let response =
+            self.client.get(&url).timeout(HTTP_TIMEOUT).send().await?;
+        match response.status() {
+            reqwest::StatusCode::NOT_FOUND => Err(Error::TransactionNotFound),
+            status if !status.is_success() => {
+                let body = response.text().await.unwrap_or_default();
+                tracing::error!(
+                    url = %redact_url(&url),
+                    status = %status,
+                    response_body = %body,
+                    "Esplora request failed"
+                );
+                Err(Error::Rpc(format!("{status}: {body}")))
+            }
+            _ => Ok(response),
+        }
+    }
+
+    async fn get_json<T: for<'de> Deserialize<'de>>(
+        &self,
+        path: &str,
+    ) -> Result<T, Error> {
+        let body = self.get(path).await?.text().await?;
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    async fn get_text(&self, path: &str) -> Result<String, Error> {
+        let body = self.get(path).await?.text().await?;
+        Ok(body.trim().to_owned())
+    }
+
+    async fn confirmations(
+        &self,
+        status: &EsploraTxStatus,
+    ) -> Result<u32, Error> {
+        match status.block_height {
+            Some(height) if status.confirmed => {
+                let tip_height = self.get_block_height().await?;
+                Ok(tip_height.saturating_sub(height) + 1)
+            }
+            _ => Ok(0),
+        }
+    }
+}
+
+impl L1Backend for EsploraClient {
+    fn name(&self) -> String {
+        redact_url(&self.base_url)
+    }
+
+    fn get_transaction<'a>(
+        &'a self,
+        txid: &'a str,
+    ) -> BoxFuture<'a, Result<TransactionInfo, Error>> {
+        Box::pin(async move {
+            let tx: EsploraTx = self.get_json(&format!("/tx/{txid}")).await?;
+            let confirmations = self.confirmations(&tx.status).await?;
+            let vin = tx
+                .vin
+                .into_iter()
+                .map(|vin| {
+                    if vin.is_coinbase {
+                        Vin {
+                            txid: None,
+                            vout: None,
+                        }
+                    } else {
+                        Vin {
+                            txid: Some(vin.txid.to_string()),
+                            vout: Some(vin.vout),
+                        }
+                    }
+                })
+                .collect();
+            let vout = tx
+                .vout
+                .into_iter()
+                .map(|vout| Vout {
+                    value: bitcoin::Amount::from_sat(vout.value),
+                    script_pub_key: ScriptPubKey {
+                        hex: vout.scriptpubkey,
+                        address: vout.scriptpubkey_address,
+                        addresses: None,
+                    },
+                })
+                .collect();
+            let (blockhash, blockheight) = if tx.status.confirmed {
+                (
+                    tx.status
+                        .block_hash
+                        .map(|block_hash| block_hash.to_string()),
+                    tx.status.block_height,
+                )
+            } else {
+                (None, None)
+            };
+            Ok(TransactionInfo {
+                txid: tx.txid.to_string(),
+                confirmations,
+                blockhash,
+                blockheight,
+                vout,
+                vin,
+            })
+        })
+    }
+
+    fn get_transaction_confirmations<'a>(
+        &'a self,
+        txid: &'a str,
+    ) -> BoxFuture<'a, Result<u32, Error>> {
+        Box::pin(async move {
+            let status: EsploraTxStatus =
+                self.get_json(&format!("/tx/{txid}/status")).await?;
+            self.confirmations(&status).await
+        })
+    }
+
+    /// Get transactions for an address. Esplora returns mempool transactions
+    /// and the most recent confirmed transactions (25 on electrs).
+    fn list_transactions<'a>(
+        &'a self,
+        address: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<String>, Error>> {
+        Box::pin(async move {
+            let txs: Vec<EsploraTx> =
+                self.get_json(&format!("/address/{address}/txs")).await?;
+            Ok(txs.into_iter().map(|tx| tx.txid.to_string()).collect())
+        })
+    }
+
+    fn get_block_height(&self) -> BoxFuture<'_, Result<u32, Error>> {
+        Box::pin(async move {
+            self.get_text("/blocks/tip/height")
+                .await?
+                .parse()
+                .map_err(|_| Error::InvalidResponse)
+        })
+    }
+
+    fn get_block_hash(
+        &self,
+        height: u32,
+    ) -> BoxFuture<'_, Result<BlockHash, Error>> {
+        Box::pin(async move {
+            self.get_text(&format!("/block-height/{height}"))
+                .await?
+                .parse()
+                .map_err(|_| Error::InvalidResponse)
+        })
+    }
+
+    fn get_block_header<'a>(
+        &'a self,
+        block_hash: &'a BlockHash,
+    ) -> BoxFuture<'a, Result<bitcoin::block::Header, Error>> {
+        Box::pin(async move {
+            let header_hex = self
+                .get_text(&format!("/block/{block_hash}/header"))
+                .await?;
+            let header_bytes =
+                hex::decode(header_hex).map_err(|_| Error::InvalidResponse)?;
+            bitcoin::consensus::deserialize(&header_bytes)
+                .map_err(|_| Error::InvalidResponse)
+        })
+    }
+
+    fn get_block<'a>(
+        &'a self,
+        block_hash: &'a BlockHash,
+    ) -> BoxFuture<'a, Result<Block, Error>> {
+        Box::pin(async move {
+            let response =
+                self.get(&format!("/block/{block_hash}/raw")).await?;
+            let block_bytes = response.bytes().await?;
+            bitcoin::consensus::deserialize(&block_bytes)
+                .map_err(|_| Error::InvalidResponse)
+        })
+    }
+
+    fn get_block_transactions<'a>(
+        &'a self,
+        block_hash: &'a BlockHash,
+    ) -> BoxFuture<'a, Result<Vec<TransactionInfo>, Error>> {
+        Box::pin(async move {
+            super::block_transactions(
+                self.get_block(block_hash).await?,
+                block_hash,
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead as _, BufReader, Write as _},
+        net::TcpListener,
+    };
+
+    use serde_json::json;
+
+    use super::EsploraClient;
+    use crate::{
+        l1_backend::{L1Backend as _, mock::MockL1Backend},
+        parent_chain_rpc::Error,
+        types::{ParentChainType, l1_recipient_script},
+    };
+
+    const ADDRESS: &str = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+
+    /// Serve fixed Esplora responses over HTTP on a local port. Unknown
+    /// paths get a 404.
+    fn spawn_server(routes: Vec<(String, String)>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { break };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                // Skip headers
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap() <= 2 {
+                        break;
+                    }
+                }
+                let path = request_line.split_whitespace().nth(1).unwrap();
+                let (status, body) =
+                    routes.iter().find(|(route, _)| route == path).map_or(
+                        ("404 Not Found", "Transaction not found"),
+                        |(_, body)| ("200 OK", body.as_str()),
+                    );
+                write!(
+                    stream,
+                    "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+                .unwrap();
+            }
+        });
+        base_url
+    }
+
+    #[test]
+    fn tracks_payment_through_fixture_server() {
+        let chain = MockL1Backend::new_regtest();
+        let script =
+            l1_recipient_script(ParentChainType::Regtest, ADDRESS).unwrap();
+        let txid = "ab".repeat(32);
+        let block_hash = chain.mine_blocks(1);
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let header = runtime
+            .block_on(chain.get_block_header(&block_hash))
+            .unwrap();
+        let status = json!({
+            "confirmed": true,
+            "block_height": 1,
+            "block_hash": block_hash.to_string(),
+        });
+        let tx = json!({
+            "txid": txid,
+            "vin": [{ "txid": "00".repeat(32), "vout": 0, "is_coinbase": true }],
+            "vout": [{
+                "scriptpubkey": script.to_hex_string(),
+                "scriptpubkey_address": ADDRESS,
+                "value": 5_000_000,
+            }],
+            "status": status,
+        });
+        let routes = vec![
+            ("/blocks/tip/height".to_owned(), "3".to_owned()),
+            ("/block-height/1".to_owned(), block_hash.to_string()),
+            (
+                format!("/block/{block_hash}/header"),
+                bitcoin::consensus::encode::serialize_hex(&header),
+            ),
+            (format!("/address/{ADDRESS}/txs"), json!([tx]).to_string()),
+            (format!("/tx/{txid}"), tx.to_string()),
+            (format!("/tx/{txid}/status"), status.to_string()),
+        ];
+        let client = EsploraClient::new(
+            &format!("{}/", spawn_server(routes)),
+            &Default::default(),
+        )
+        .unwrap();
+
+        assert_eq!(runtime.block_on(client.get_block_height()).unwrap(), 3);
+        assert_eq!(
+            runtime.block_on(client.get_block_hash(1)).unwrap(),
+            block_hash
+        );
+        assert_eq!(
+            runtime
+                .block_on(client.get_block_header(&block_hash))
+                .unwrap(),
+            header
+        );
+        assert_eq!(
+            runtime
+                .block_on(client.get_transaction_confirmations(&txid))
+                .unwrap(),
+            3
+        );
+
+        let matches = runtime
+            .block_on(client.find_transactions_by_script_and_amount(
+                ADDRESS, &script, 5_000_000,
+            ))
+            .unwrap();
+        assert_eq!(matches.len(), 1);
+        let (sender, tx_info) = &matches[0];
+        assert_eq!(sender, "unknown");
+        assert_eq!(tx_info.txid, txid);
+        assert_eq!(tx_info.confirmations, 3);
+        assert_eq!(tx_info.blockheight, Some(1));
+        assert_eq!(tx_info.blockhash, Some(block_hash.to_string()));
+
+        assert!(matches!(
+            runtime.block_on(client.get_transaction(&"00".repeat(32))),
+            Err(Error::TransactionNotFound)
+        ));
+    }
+}
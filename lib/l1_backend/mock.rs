@@ -0,0 +1,340 @@
+//! In-memory parent chain, for testing swap processing without a node
+
+use std::collections::HashMap;
+
+use bitcoin::{
+    Block, BlockHash, OutPoint, Sequence, Transaction, TxIn, TxMerkleNode,
+    TxOut, Witness,
+    absolute::LockTime,
+    block::{Header, Version},
+    hashes::{Hash as _, sha256d},
+    pow::CompactTarget,
+    transaction,
+};
+use futures::future::BoxFuture;
+use parking_lot::RwLock;
+
+use super::L1Backend;
+use crate::parent_chain_rpc::{Error, TransactionInfo};
+
+/// Difficulty bits of mined regtest blocks
+const REGTEST_BITS: u32 = 0x207fffff;
+
+/// Time between mined blocks, in seconds
+const BLOCK_INTERVAL_SECS: u32 = 600;
+
+#[derive(Debug)]
+struct MockTx {
+    tx: Transaction,
+    info: TransactionInfo,
+    /// Height of the block containing the transaction, if confirmed
+    height: Option<u32>,
+}
+
+#[derive(Debug)]
+struct MockChain {
+    /// Best chain blocks, indexed by height
+    blocks: Vec<Block>,
+    transactions: HashMap<String, MockTx>,
+    /// Transactions paying to each address, in the order they were sent
+    address_history: HashMap<String, Vec<String>>,
+    /// Number of transactions sent, used to derive distinct inputs
+    sent: u64,
+}
+
+/// Regtest chain held in memory. Transactions are sent to the mempool with
+/// [`Self::send_to_script`] and confirmed by [`Self::mine_blocks`]. Mined
+/// blocks commit to their transactions and satisfy regtest proof of work,
+/// so their headers can be relayed into an
+/// [`crate::l1_headers::L1HeaderChain`] and their transactions proven with
+/// an [`crate::types::L1InclusionProof`].
+#[derive(Debug)]
+pub struct MockL1Backend {
+    chain: RwLock<MockChain>,
+}
+
+impl MockL1Backend {
+    /// New chain containing only the regtest genesis block
+    pub fn new_regtest() -> Self {
+        let genesis =
+            bitcoin::constants::genesis_block(bitcoin::Network::Regtest);
+        Self {
+            chain: RwLock::new(MockChain {
+                blocks: vec![genesis],
+                transactions: HashMap::new(),
+                address_history: HashMap::new(),
+                sent: 0,
+            }),
+        }
+    }
+
+    /// Send an unconfirmed transaction paying `amount` to `script`, which is
+    /// listed in the history of `address`. Returns the txid.
+    pub fn send_to_script(
+        &self,
+        address: &str,
+        script: bitcoin::ScriptBuf,
+        amount: bitcoin::Amount,
+    ) -> String {
+        self.send_outputs(
+            address,
+            vec![TxOut {
+                value: amount,
+                script_pubkey: script,
+            }],
+        )
+    }
+
+    /// Send an unconfirmed transaction with `outputs`, which is listed in
+    /// the history of `address`. The first output pays to `address`.
+    /// Returns the txid.
+    pub fn send_outputs(&self, address: &str, outputs: Vec<TxOut>) -> String {
+        let mut chain = self.chain.write();
+        chain.sent += 1;
+        let prev_txid = bitcoin::Txid::from_raw_hash(sha256d::Hash::hash(
+            &chain.sent.to_le_bytes(),
+        ));
+        let tx = Transaction {
+            version: transaction::Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint {
+                    txid: prev_txid,
+                    vout: 0,
+                },
+                script_sig: bitcoin::ScriptBuf::new(),
+                sequence: Sequence::MAX,
+                witness: Witness::new(),
+            }],
+            output: outputs,
+        };
+        let txid = tx.compute_txid().to_string();
+        let mut info = TransactionInfo::from_transaction(&tx);
+        info.vout[0].script_pub_key.address = Some(address.to_owned());
+        chain.transactions.insert(
+            txid.clone(),
+            MockTx {
+                tx,
+                info,
+                height: None,
+            },
+        );
+        chain
+            .address_history
+            .entry(address.to_owned())
+            .or_default()
+            .push(txid.clone());
+        txid
+    }
+
+    /// Mine `count` blocks. Unconfirmed transactions are included in the
+    /// first block, after its coinbase, in txid order. Returns the new tip
+    /// hash.
+    pub fn mine_blocks(&self, count: u32) -> BlockHash {
+        let mut chain = self.chain.write();
+        for _ in 0..count {
+            let height = chain.blocks.len() as u32;
+            let coinbase = Transaction {
+                version: transaction::Version::TWO,
+                lock_time: LockTime::ZERO,
+                input: vec![TxIn {
+                    previous_output: OutPoint::null(),
+                    script_sig: bitcoin::script::Builder::new()
+                        .push_int(i64::from(height))
+                        .into_script(),
+                    sequence: Sequence::MAX,
+                    witness: Witness::new(),
+                }],
+                output: Vec::new(),
+            };
+            let mut mempool: Vec<(&String, &mut MockTx)> = chain
+                .transactions
+                .iter_mut()
+                .filter(|(_, tx)| tx.height.is_none())
+                .collect();
+            mempool.sort_by(|(a, _), (b, _)| a.cmp(b));
+            let mut txdata = vec![coinbase];
+            for (_, tx) in &mut mempool {
+                tx.height = Some(height);
+                txdata.push(tx.tx.clone());
+            }
+            let prev = &chain
+                .blocks
+                .last()
+                .expect("genesis is never removed")
+                .header;
+            let mut block = Block {
+                header: Header {
+                    version: Version::TWO,
+                    prev_blockhash: prev.block_hash(),
+                    merkle_root: TxMerkleNode::all_zeros(),
+                    time: prev.time + BLOCK_INTERVAL_SECS,
+                    bits: CompactTarget::from_consensus(REGTEST_BITS),
+                    nonce: 0,
+                },
+                txdata,
+            };
+            block.header.merkle_root =
+                block.compute_merkle_root().expect("block has a coinbase");
+            while block.header.validate_pow(block.header.target()).is_err() {
+                block.header.nonce += 1;
+            }
+            let block_hash = block.block_hash().to_string();
+            for tx in chain.transactions.values_mut() {
+                if tx.height == Some(height) {
+                    tx.info.blockhash = Some(block_hash.clone());
+                    tx.info.blockheight = Some(height);
+                }
+            }
+            chain.blocks.push(block);
+        }
+        chain
+            .blocks
+            .last()
+            .expect("genesis is never removed")
+            .block_hash()
+    }
+}
+
+impl L1Backend for MockL1Backend {
+    fn name(&self) -> String {
+        "mock".to_owned()
+    }
+
+    fn get_transaction<'a>(
+        &'a self,
+        txid: &'a str,
+    ) -> BoxFuture<'a, Result<TransactionInfo, Error>> {
+        Box::pin(async move {
+            let chain = self.chain.read();
+            let tx = chain
+                .transactions
+                .get(txid)
+                .ok_or(Error::TransactionNotFound)?;
+            let tip_height = chain.blocks.len() as u32 - 1;
+            let mut info = tx.info.clone();
+            info.confirmations =
+                tx.height.map_or(0, |height| tip_height - height + 1);
+            Ok(info)
+        })
+    }
+
+    fn list_transactions<'a>(
+        &'a self,
+        address: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<String>, Error>> {
+        Box::pin(async move {
+            Ok(self
+                .chain
+                .read()
+                .address_history
+                .get(address)
+                .cloned()
+                .unwrap_or_default())
+        })
+    }
+
+    fn get_block_height(&self) -> BoxFuture<'_, Result<u32, Error>> {
+        Box::pin(async move { Ok(self.chain.read().blocks.len() as u32 - 1) })
+    }
+
+    fn get_block_hash(
+        &self,
+        height: u32,
+    ) -> BoxFuture<'_, Result<BlockHash, Error>> {
+        Box::pin(async move {
+            self.chain
+                .read()
+                .blocks
+                .get(height as usize)
+                .map(Block::block_hash)
+                .ok_or(Error::InvalidResponse)
+        })
+    }
+
+    fn get_block_header<'a>(
+        &'a self,
+        block_hash: &'a BlockHash,
+    ) -> BoxFuture<'a, Result<Header, Error>> {
+        Box::pin(async move {
+            self.get_block(block_hash).await.map(|block| block.header)
+        })
+    }
+
+    fn get_block<'a>(
+        &'a self,
+        block_hash: &'a BlockHash,
+    ) -> BoxFuture<'a, Result<Block, Error>> {
+        Box::pin(async move {
+            self.chain
+                .read()
+                .blocks
+                .iter()
+                .find(|block| block.block_hash() == *block_hash)
+                .cloned()
+                .ok_or(Error::InvalidResponse)
+        })
+    }
+
+    fn get_block_transactions<'a>(
+        &'a self,
+        block_hash: &'a BlockHash,
+    ) -> BoxFuture<'a, Result<Vec<TransactionInfo>, Error>> {
+        Box::pin(async move {
+            super::block_transactions(
+                self.get_block(block_hash).await?,
+                block_hash,
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+
+    use super::MockL1Backend;
+    use crate::{
+        l1_backend::L1Backend as _,
+        types::{ParentChainType, l1_recipient_script},
+    };
+
+    const ADDRESS: &str = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+
+    #[test]
+    fn sent_transactions_confirm_when_mined() {
+        let backend = MockL1Backend::new_regtest();
+        let script =
+            l1_recipient_script(ParentChainType::Regtest, ADDRESS).unwrap();
+        let amount = bitcoin::Amount::from_sat(5_000_000);
+        let txid = backend.send_to_script(ADDRESS, script.clone(), amount);
+        assert_eq!(
+            block_on(backend.get_transaction_confirmations(&txid)).unwrap(),
+            0
+        );
+
+        let _tip = backend.mine_blocks(3);
+        assert_eq!(block_on(backend.get_block_height()).unwrap(), 3);
+        assert_eq!(
+            block_on(backend.get_transaction_confirmations(&txid)).unwrap(),
+            3
+        );
+        let matches = block_on(backend.find_transactions_by_script_and_amount(
+            ADDRESS,
+            &script,
+            amount.to_sat(),
+        ))
+        .unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].1.txid, txid);
+        assert!(
+            block_on(backend.find_transactions_by_script_and_amount(
+                ADDRESS,
+                &script,
+                amount.to_sat() + 1,
+            ))
+            .unwrap()
+            .is_empty()
+        );
+    }
+}
@@ -0,0 +1,429 @@
+//! Backends for querying swap parent chains
+//!
+//! Swap processing, confirmation checks and the GUI look up L1 transactions
+//! through the [`L1Backend`] trait rather than a concrete client. Bitcoin
+//! Core JSON-RPC ([`ParentChainRpcClient`]), the Electrum protocol
+//! ([`electrum::ElectrumClient`]) and Esplora REST APIs
+//! ([`esplora::EsploraClient`]) are selected per parent chain in the L1
+//! config file; [`mock::MockL1Backend`] is an in-memory chain for tests.
+//! Swap fills and new blocks can also be pushed by a node's ZMQ
+//! notifications ([`zmq::ZmqSubscriber`]).
+//!
+//! Backends are queried asynchronously: their methods return boxed futures.
+//! While connecting 2WPD, backends are only queried before the write
+//! transaction, and the results are served from an
+//! [`snapshot::L1Snapshot`].
+
+use std::ops::RangeInclusive;
+
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    parent_chain_rpc::{self, Error, ParentChainRpcClient, TransactionInfo},
+    types::{L1InclusionProof, ParentChainType},
+};
+
+pub mod electrum;
+pub mod esplora;
+pub mod mock;
+pub mod quorum;
+pub mod snapshot;
+pub mod tls;
+pub mod zmq;
+
+/// Kind of backend configured for a parent chain in the L1 config file
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum L1BackendKind {
+    /// Bitcoin Core compatible JSON-RPC. Address history requires the
+    /// node's wallet.
+    #[default]
+    BitcoinCore,
+    /// Electrum protocol server, at `tcp://host:port`
+    Electrum,
+    /// Esplora REST API, at its base URL
+    Esplora,
+}
+
+/// Source of parent chain transactions, address history and headers
+pub trait L1Backend: Send + Sync {
+    /// Description of the backend, for logs (e.g. the node URL)
+    fn name(&self) -> String;
+
+    /// Get a transaction by ID (in RPC byte order)
+    fn get_transaction<'a>(
+        &'a self,
+        txid: &'a str,
+    ) -> BoxFuture<'a, Result<TransactionInfo, Error>>;
+
+    /// Get confirmations for a transaction by ID, as reported by the backend
+    fn get_transaction_confirmations<'a>(
+        &'a self,
+        txid: &'a str,
+    ) -> BoxFuture<'a, Result<u32, Error>> {
+        Box::pin(
+            async move { Ok(self.get_transaction(txid).await?.confirmations) },
+        )
+    }
+
+    /// Get transactions by ID. Lookups that fail individually (e.g.
+    /// [`Error::TransactionNotFound`]) are returned in place. Backends that
+    /// can batch requests override this.
+    fn get_transactions<'a>(
+        &'a self,
+        txids: &'a [String],
+    ) -> BoxFuture<'a, Result<Vec<Result<TransactionInfo, Error>>, Error>> {
+        Box::pin(async move {
+            let mut txs = Vec::with_capacity(txids.len());
+            for txid in txids {
+                txs.push(self.get_transaction(txid).await);
+            }
+            Ok(txs)
+        })
+    }
+
+    /// IDs of transactions paying to an address
+    fn list_transactions<'a>(
+        &'a self,
+        address: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<String>, Error>>;
+
+    /// Current tip height
+    fn get_block_height(&self) -> BoxFuture<'_, Result<u32, Error>>;
+
+    /// Hash of the block at the specified height in the best chain
+    fn get_block_hash(
+        &self,
+        height: u32,
+    ) -> BoxFuture<'_, Result<bitcoin::BlockHash, Error>>;
+
+    /// Header of the specified block
+    fn get_block_header<'a>(
+        &'a self,
+        block_hash: &'a bitcoin::BlockHash,
+    ) -> BoxFuture<'a, Result<bitcoin::block::Header, Error>>;
+
+    /// Hashes and headers of the blocks at `heights` in the best chain.
+    /// Backends that can batch requests override this.
+    fn get_best_chain_headers(
+        &self,
+        heights: RangeInclusive<u32>,
+    ) -> BoxFuture<
+        '_,
+        Result<Vec<(bitcoin::BlockHash, bitcoin::block::Header)>, Error>,
+    > {
+        Box::pin(async move {
+            let mut headers = Vec::new();
+            for height in heights {
+                let block_hash = self.get_block_hash(height).await?;
+                let header = self.get_block_header(&block_hash).await?;
+                headers.push((block_hash, header));
+            }
+            Ok(headers)
+        })
+    }
+
+    /// Raw block, used to prove the inclusion of its transactions. Backends
+    /// that cannot fetch blocks return [`Error::BlocksNotSupported`].
+    fn get_block<'a>(
+        &'a self,
+        _block_hash: &'a bitcoin::BlockHash,
+    ) -> BoxFuture<'a, Result<bitcoin::Block, Error>> {
+        Box::pin(async { Err(Error::BlocksNotSupported) })
+    }
+
+    /// Inclusion proof for a confirmed transaction, built from the block
+    /// containing it. The block must have the expected hash and commit to
+    /// its transactions, so the proof is only as trusted as the header it
+    /// is later anchored to.
+    fn get_inclusion_proof<'a>(
+        &'a self,
+        txid: &'a str,
+    ) -> BoxFuture<'a, Result<L1InclusionProof, Error>> {
+        Box::pin(async move {
+            let tx = self.get_transaction(txid).await?;
+            let block_hash: bitcoin::BlockHash = tx
+                .blockhash
+                .ok_or(Error::TransactionNotConfirmed)?
+                .parse()
+                .map_err(|_| Error::InvalidResponse)?;
+            let block = self.get_block(&block_hash).await?;
+            if block.block_hash() != block_hash || !block.check_merkle_root() {
+                return Err(Error::InvalidResponse);
+            }
+            let tx_index = block
+                .txdata
+                .iter()
+                .position(|tx| tx.compute_txid().to_string() == txid)
+                .ok_or(Error::InvalidResponse)?;
+            L1InclusionProof::from_block(&block, tx_index)
+                .ok_or(Error::InvalidResponse)
+        })
+    }
+
+    /// Transactions of a block in the best chain, used to scan new blocks
+    /// for swap payments. Backends that cannot fetch blocks return
+    /// [`Error::BlocksNotSupported`].
+    fn get_block_transactions<'a>(
+        &'a self,
+        _block_hash: &'a bitcoin::BlockHash,
+    ) -> BoxFuture<'a, Result<Vec<TransactionInfo>, Error>> {
+        Box::pin(async { Err(Error::BlocksNotSupported) })
+    }
+
+    /// Find transactions paying a specific amount to a script.
+    /// `address` is the address for the script, used to look up candidate
+    /// transactions. Returns (sender_address, tx_info).
+    fn find_transactions_by_script_and_amount<'a>(
+        &'a self,
+        address: &'a str,
+        script: &'a bitcoin::Script,
+        amount_sats: u64,
+    ) -> BoxFuture<'a, Result<Vec<(String, TransactionInfo)>, Error>> {
+        Box::pin(async move {
+            let matches = self
+                .find_transactions_by_script_and_amount_range(
+                    address,
+                    script,
+                    amount_sats,
+                    amount_sats,
+                )
+                .await?;
+            Ok(matches
+                .into_iter()
+                .map(|(sender, tx, _amount_sats)| (sender, tx))
+                .collect())
+        })
+    }
+
+    /// Find transactions paying between `min_sats` and `max_sats`
+    /// (inclusive) to a script. `address` is the address for the script,
+    /// used to look up candidate transactions. Returns the sender address,
+    /// the transaction, and the amount paid to the script.
+    fn find_transactions_by_script_and_amount_range<'a>(
+        &'a self,
+        address: &'a str,
+        script: &'a bitcoin::Script,
+        min_sats: u64,
+        max_sats: u64,
+    ) -> BoxFuture<'a, Result<Vec<(String, TransactionInfo, u64)>, Error>> {
+        Box::pin(async move {
+            let txids = self.list_transactions(address).await?;
+            let txs = self.get_transactions(&txids).await?;
+            let mut matches = Vec::new();
+            for (txid, tx) in txids.iter().zip(txs) {
+                match tx {
+                    Ok(tx) => {
+                        // Check if any output matches the script and amount
+                        if let Some(amount_sats) =
+                            tx.amount_paid_to(script, min_sats, max_sats)
+                        {
+                            let sender = self
+                                .sender_address(&tx)
+                                .await
+                                .unwrap_or_else(|| "unknown".to_string());
+                            matches.push((sender, tx, amount_sats));
+                        }
+                    }
+                    Err(Error::TransactionNotFound) => {
+                        // Transaction might have been spent, skip it
+                        continue;
+                    }
+                    Err(e) => {
+                        tracing::warn!(
+                            "Error getting transaction {}: {}",
+                            txid,
+                            e
+                        );
+                        continue;
+                    }
+                }
+            }
+            Ok(matches)
+        })
+    }
+
+    /// Address spent by the first input of a transaction, if it can be
+    /// determined
+    fn sender_address<'a>(
+        &'a self,
+        tx: &'a TransactionInfo,
+    ) -> BoxFuture<'a, Option<String>> {
+        Box::pin(async move {
+            let vin = tx.vin.first()?;
+            let (Some(input_txid), Some(input_vout)) = (&vin.txid, &vin.vout)
+            else {
+                return None;
+            };
+            // Get the input transaction to find sender
+            let input_tx = self.get_transaction(input_txid).await.ok()?;
+            let input_vout_data = input_tx.vout.get(*input_vout as usize)?;
+            input_vout_data.script_pub_key.address.clone().or_else(|| {
+                input_vout_data
+                    .script_pub_key
+                    .addresses
+                    .as_ref()
+                    .and_then(|addrs| addrs.first().cloned())
+            })
+        })
+    }
+}
+
+impl<B: L1Backend + ?Sized> L1Backend for &B {
+    fn name(&self) -> String {
+        (**self).name()
+    }
+
+    fn get_transaction<'a>(
+        &'a self,
+        txid: &'a str,
+    ) -> BoxFuture<'a, Result<TransactionInfo, Error>> {
+        (**self).get_transaction(txid)
+    }
+
+    fn get_transactions<'a>(
+        &'a self,
+        txids: &'a [String],
+    ) -> BoxFuture<'a, Result<Vec<Result<TransactionInfo, Error>>, Error>> {
+        (**self).get_transactions(txids)
+    }
+
+    fn get_transaction_confirmations<'a>(
+        &'a self,
+        txid: &'a str,
+    ) -> BoxFuture<'a, Result<u32, Error>> {
+        (**self).get_transaction_confirmations(txid)
+    }
+
+    fn list_transactions<'a>(
+        &'a self,
+        address: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<String>, Error>> {
+        (**self).list_transactions(address)
+    }
+
+    fn get_block_height(&self) -> BoxFuture<'_, Result<u32, Error>> {
+        (**self).get_block_height()
+    }
+
+    fn get_block_hash(
+        &self,
+        height: u32,
+    ) -> BoxFuture<'_, Result<bitcoin::BlockHash, Error>> {
+        (**self).get_block_hash(height)
+    }
+
+    fn get_block_header<'a>(
+        &'a self,
+        block_hash: &'a bitcoin::BlockHash,
+    ) -> BoxFuture<'a, Result<bitcoin::block::Header, Error>> {
+        (**self).get_block_header(block_hash)
+    }
+
+    fn get_best_chain_headers(
+        &self,
+        heights: RangeInclusive<u32>,
+    ) -> BoxFuture<
+        '_,
+        Result<Vec<(bitcoin::BlockHash, bitcoin::block::Header)>, Error>,
+    > {
+        (**self).get_best_chain_headers(heights)
+    }
+
+    fn get_block<'a>(
+        &'a self,
+        block_hash: &'a bitcoin::BlockHash,
+    ) -> BoxFuture<'a, Result<bitcoin::Block, Error>> {
+        (**self).get_block(block_hash)
+    }
+
+    fn get_inclusion_proof<'a>(
+        &'a self,
+        txid: &'a str,
+    ) -> BoxFuture<'a, Result<L1InclusionProof, Error>> {
+        (**self).get_inclusion_proof(txid)
+    }
+
+    fn get_block_transactions<'a>(
+        &'a self,
+        block_hash: &'a bitcoin::BlockHash,
+    ) -> BoxFuture<'a, Result<Vec<TransactionInfo>, Error>> {
+        (**self).get_block_transactions(block_hash)
+    }
+
+    fn find_transactions_by_script_and_amount_range<'a>(
+        &'a self,
+        address: &'a str,
+        script: &'a bitcoin::Script,
+        min_sats: u64,
+        max_sats: u64,
+    ) -> BoxFuture<'a, Result<Vec<(String, TransactionInfo, u64)>, Error>> {
+        (**self).find_transactions_by_script_and_amount_range(
+            address, script, min_sats, max_sats,
+        )
+    }
+}
+
+/// Transactions of a raw block, which must have the expected hash and commit
+/// to its transactions
+pub(crate) fn block_transactions(
+    block: bitcoin::Block,
+    block_hash: &bitcoin::BlockHash,
+) -> Result<Vec<TransactionInfo>, Error> {
+    if block.block_hash() != *block_hash || !block.check_merkle_root() {
+        return Err(Error::InvalidResponse);
+    }
+    let confirmed_in = block_hash.to_string();
+    Ok(block
+        .txdata
+        .iter()
+        .map(|tx| TransactionInfo {
+            blockhash: Some(confirmed_in.clone()),
+            ..TransactionInfo::from_transaction(tx)
+        })
+        .collect())
+}
+
+/// Backend for a single endpoint in the L1 config file. Fails if the
+/// endpoint's password or TLS config cannot be read.
+pub fn from_endpoint(
+    parent_chain: ParentChainType,
+    endpoint: &parent_chain_rpc::L1Endpoint,
+) -> Result<Box<dyn L1Backend>, Error> {
+    let backend: Box<dyn L1Backend> = match endpoint.backend {
+        L1BackendKind::BitcoinCore => {
+            Box::new(ParentChainRpcClient::new(endpoint.rpc_config()?)?)
+        }
+        L1BackendKind::Electrum => Box::new(electrum::ElectrumClient::new(
+            &endpoint.url,
+            parent_chain,
+            &endpoint.tls,
+        )?),
+        L1BackendKind::Esplora => {
+            Box::new(esplora::EsploraClient::new(&endpoint.url, &endpoint.tls)?)
+        }
+    };
+    Ok(backend)
+}
+
+/// Backend for a parent chain's L1 config file entry. Entries with extra
+/// endpoints are queried through a [`quorum::QuorumBackend`].
+pub fn from_config(
+    parent_chain: ParentChainType,
+    entry: &parent_chain_rpc::L1ConfigEntry,
+) -> Result<Box<dyn L1Backend>, Error> {
+    if entry.extra_endpoints.is_empty() {
+        return from_endpoint(parent_chain, &entry.endpoint);
+    }
+    let backends = entry
+        .endpoints()
+        .map(|endpoint| from_endpoint(parent_chain, endpoint))
+        .collect::<Result<_, _>>()?;
+    Ok(Box::new(quorum::QuorumBackend::new(
+        backends,
+        entry.quorum(),
+    )))
+}
@@ -0,0 +1,513 @@
+//! M-of-N agreement across parent chain endpoints
+//!
+//! With a single endpoint per parent chain, one lying or lagging L1 node can
+//! move swaps to `ReadyToClaim`. [`QuorumBackend`] queries every endpoint
+//! configured for a chain, and only returns responses that at least `quorum`
+//! of them agree on. Endpoints that fail or disagree are logged.
+
+use std::collections::HashMap;
+
+use bitcoin::{Block, BlockHash, block::Header};
+use futures::future::{self, BoxFuture};
+
+use super::L1Backend;
+use crate::parent_chain_rpc::{Error, TransactionInfo};
+
+/// Backend that requires `quorum` of its backends to agree. `quorum` should
+/// be a majority to protect against a lying endpoint; if several responses
+/// reach a smaller quorum, the most common one is used, preferring earlier
+/// endpoints.
+pub struct QuorumBackend {
+    backends: Vec<Box<dyn L1Backend>>,
+    quorum: usize,
+}
+
+impl QuorumBackend {
+    /// `quorum` is clamped to between 1 and the number of backends
+    pub fn new(backends: Vec<Box<dyn L1Backend>>, quorum: usize) -> Self {
+        let quorum = quorum.clamp(1, backends.len().max(1));
+        Self { backends, quorum }
+    }
+
+    /// Query every backend concurrently, and return the responses in the
+    /// largest group of responses with equal `key`, if it reaches quorum. If
+    /// no response reaches quorum but enough backends do not know the
+    /// transaction, returns [`Error::TransactionNotFound`]. If too few
+    /// backends serve blocks to reach quorum, returns
+    /// [`Error::BlocksNotSupported`].
+    async fn agree<'a, T, K: PartialEq>(
+        &'a self,
+        method: &str,
+        query: impl Fn(&'a dyn L1Backend) -> BoxFuture<'a, Result<T, Error>>,
+        key: impl Fn(&T) -> K,
+    ) -> Result<Vec<T>, Error> {
+        let responses = future::join_all(
+            self.backends.iter().map(|backend| query(backend.as_ref())),
+        )
+        .await;
+        let mut groups: Vec<(K, Vec<(usize, T)>)> = Vec::new();
+        let mut failed = Vec::new();
+        let mut not_found = 0;
+        let mut blocks_not_supported = 0;
+        for (idx, (backend, response)) in
+            self.backends.iter().zip(responses).enumerate()
+        {
+            match response {
+                Ok(response) => {
+                    let response_key = key(&response);
+                    match groups.iter_mut().find(|(k, _)| *k == response_key) {
+                        Some((_, group)) => group.push((idx, response)),
+                        None => {
+                            groups.push((response_key, vec![(idx, response)]))
+                        }
+                    }
+                }
+                Err(Error::TransactionNotFound) => not_found += 1,
+                Err(Error::BlocksNotSupported) => {
+                    blocks_not_supported += 1;
+                    failed.push(idx);
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        endpoint = %backend.name(),
+                        method = %method,
+                        error = %err,
+                        "L1 endpoint request failed"
+                    );
+                    failed.push(idx);
+                }
+            }
+        }
+        // Largest group, preferring earlier endpoints on ties
+        let best = groups
+            .into_iter()
+            .map(|(_, group)| group)
+            .reduce(|best, group| {
+                if group.len() > best.len() {
+                    group
+                } else {
+                    best
+                }
+            })
+            .unwrap_or_default();
+        if best.len() < self.quorum {
+            if not_found >= self.quorum {
+                return Err(Error::TransactionNotFound);
+            }
+            if self.backends.len() - blocks_not_supported < self.quorum {
+                return Err(Error::BlocksNotSupported);
+            }
+            return Err(Error::QuorumNotReached {
+                agreed: best.len(),
+                required: self.quorum,
+            });
+        }
+        for (idx, backend) in self.backends.iter().enumerate() {
+            if !failed.contains(&idx)
+                && !best.iter().any(|(best_idx, _)| *best_idx == idx)
+            {
+                tracing::warn!(
+                    endpoint = %backend.name(),
+                    method = %method,
+                    agreed = %best.len(),
+                    "L1 endpoint disagrees with quorum"
+                );
+            }
+        }
+        Ok(best.into_iter().map(|(_, response)| response).collect())
+    }
+
+    /// Highest value that at least `quorum` of `values` reach
+    fn quorum_value(&self, mut values: Vec<u32>) -> Result<u32, Error> {
+        values.sort_unstable_by(|a, b| b.cmp(a));
+        values
+            .get(self.quorum - 1)
+            .copied()
+            .ok_or(Error::QuorumNotReached {
+                agreed: values.len(),
+                required: self.quorum,
+            })
+    }
+}
+
+impl L1Backend for QuorumBackend {
+    fn name(&self) -> String {
+        let names: Vec<String> =
+            self.backends.iter().map(|backend| backend.name()).collect();
+        format!("quorum {} of [{}]", self.quorum, names.join(", "))
+    }
+
+    /// Backends must agree on the containing block and the outputs.
+    /// Confirmations are the highest count reported by at least `quorum`
+    /// backends.
+    fn get_transaction<'a>(
+        &'a self,
+        txid: &'a str,
+    ) -> BoxFuture<'a, Result<TransactionInfo, Error>> {
+        Box::pin(async move {
+            let txs = self
+                .agree(
+                    "get_transaction",
+                    |backend| backend.get_transaction(txid),
+                    |tx| {
+                        let outputs: Vec<_> = tx
+                            .vout
+                            .iter()
+                            .map(|vout| {
+                                (vout.script_pub_key.hex.clone(), vout.value)
+                            })
+                            .collect();
+                        (tx.blockhash.clone(), outputs)
+                    },
+                )
+                .await?;
+            let confirmations = self.quorum_value(
+                txs.iter().map(|tx| tx.confirmations).collect(),
+            )?;
+            let mut tx = txs
+                .into_iter()
+                .next()
+                .expect("quorum is at least one response");
+            tx.confirmations = confirmations;
+            Ok(tx)
+        })
+    }
+
+    /// Transactions listed by at least `quorum` backends
+    fn list_transactions<'a>(
+        &'a self,
+        address: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<String>, Error>> {
+        Box::pin(async move {
+            let responses = future::join_all(
+                self.backends
+                    .iter()
+                    .map(|backend| backend.list_transactions(address)),
+            )
+            .await;
+            let mut responded = 0;
+            let mut counts = HashMap::<String, usize>::new();
+            let mut txids = Vec::new();
+            for (backend, response) in self.backends.iter().zip(responses) {
+                match response {
+                    Ok(backend_txids) => {
+                        responded += 1;
+                        for txid in backend_txids {
+                            let count = counts.entry(txid.clone()).or_default();
+                            *count += 1;
+                            if *count == 1 {
+                                txids.push(txid);
+                            }
+                        }
+                    }
+                    Err(err) => {
+                        tracing::warn!(
+                            endpoint = %backend.name(),
+                            method = "list_transactions",
+                            error = %err,
+                            "L1 endpoint request failed"
+                        );
+                    }
+                }
+            }
+            if responded < self.quorum {
+                return Err(Error::QuorumNotReached {
+                    agreed: responded,
+                    required: self.quorum,
+                });
+            }
+            txids.retain(|txid| {
+                let count = counts[txid];
+                if count < self.quorum {
+                    tracing::warn!(
+                        address = %address,
+                        txid = %txid,
+                        endpoints = %count,
+                        "L1 transaction listed by too few endpoints"
+                    );
+                }
+                count >= self.quorum
+            });
+            Ok(txids)
+        })
+    }
+
+    /// Highest tip height reached by at least `quorum` backends
+    fn get_block_height(&self) -> BoxFuture<'_, Result<u32, Error>> {
+        Box::pin(async move {
+            let responses = future::join_all(
+                self.backends
+                    .iter()
+                    .map(|backend| backend.get_block_height()),
+            )
+            .await;
+            let mut heights = Vec::with_capacity(self.backends.len());
+            for (backend, response) in self.backends.iter().zip(responses) {
+                match response {
+                    Ok(height) => heights.push(height),
+                    Err(err) => {
+                        tracing::warn!(
+                            endpoint = %backend.name(),
+                            method = "get_block_height",
+                            error = %err,
+                            "L1 endpoint request failed"
+                        );
+                    }
+                }
+            }
+            self.quorum_value(heights)
+        })
+    }
+
+    fn get_block_hash(
+        &self,
+        height: u32,
+    ) -> BoxFuture<'_, Result<BlockHash, Error>> {
+        Box::pin(async move {
+            let block_hashes = self
+                .agree(
+                    "get_block_hash",
+                    |backend| backend.get_block_hash(height),
+                    |block_hash| *block_hash,
+                )
+                .await?;
+            Ok(block_hashes[0])
+        })
+    }
+
+    /// First block served with the expected hash and a valid merkle root.
+    /// Such a block cannot be forged, so a single backend suffices.
+    fn get_block<'a>(
+        &'a self,
+        block_hash: &'a BlockHash,
+    ) -> BoxFuture<'a, Result<Block, Error>> {
+        Box::pin(async move {
+            let mut served = 0;
+            for backend in &self.backends {
+                match backend.get_block(block_hash).await {
+                    Ok(block)
+                        if block.block_hash() == *block_hash
+                            && block.check_merkle_root() =>
+                    {
+                        return Ok(block);
+                    }
+                    Ok(_) => {
+                        served += 1;
+                        tracing::warn!(
+                            endpoint = %backend.name(),
+                            method = "get_block",
+                            "L1 endpoint served an invalid block"
+                        );
+                    }
+                    Err(Error::BlocksNotSupported) => (),
+                    Err(err) => {
+                        served += 1;
+                        tracing::warn!(
+                            endpoint = %backend.name(),
+                            method = "get_block",
+                            error = %err,
+                            "L1 endpoint request failed"
+                        );
+                    }
+                }
+            }
+            if served == 0 {
+                return Err(Error::BlocksNotSupported);
+            }
+            Err(Error::InvalidResponse)
+        })
+    }
+
+    /// Backends must agree on the transactions and their outputs
+    fn get_block_transactions<'a>(
+        &'a self,
+        block_hash: &'a BlockHash,
+    ) -> BoxFuture<'a, Result<Vec<TransactionInfo>, Error>> {
+        Box::pin(async move {
+            let blocks = self
+                .agree(
+                    "get_block_transactions",
+                    |backend| backend.get_block_transactions(block_hash),
+                    |txs| {
+                        txs.iter()
+                            .map(|tx| {
+                                let outputs: Vec<_> = tx
+                                    .vout
+                                    .iter()
+                                    .map(|vout| {
+                                        (
+                                            vout.script_pub_key.hex.clone(),
+                                            vout.value,
+                                        )
+                                    })
+                                    .collect();
+                                (tx.txid.clone(), outputs)
+                            })
+                            .collect::<Vec<_>>()
+                    },
+                )
+                .await?;
+            Ok(blocks
+                .into_iter()
+                .next()
+                .expect("quorum is at least one response"))
+        })
+    }
+
+    /// Headers are checked against their hash, so any backend will do
+    fn get_block_header<'a>(
+        &'a self,
+        block_hash: &'a BlockHash,
+    ) -> BoxFuture<'a, Result<Header, Error>> {
+        Box::pin(async move {
+            for backend in &self.backends {
+                match backend.get_block_header(block_hash).await {
+                    Ok(header) if header.block_hash() == *block_hash => {
+                        return Ok(header);
+                    }
+                    Ok(_) => {
+                        tracing::warn!(
+                            endpoint = %backend.name(),
+                            block_hash = %block_hash,
+                            "L1 endpoint returned a header with the wrong hash"
+                        );
+                    }
+                    Err(err) => {
+                        tracing::warn!(
+                            endpoint = %backend.name(),
+                            method = "get_block_header",
+                            error = %err,
+                            "L1 endpoint request failed"
+                        );
+                    }
+                }
+            }
+            Err(Error::InvalidResponse)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+
+    use super::QuorumBackend;
+    use crate::{
+        l1_backend::{L1Backend, mock::MockL1Backend},
+        parent_chain_rpc::Error,
+        types::{ParentChainType, l1_recipient_script},
+    };
+
+    const ADDRESS: &str = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+
+    /// Mock chain with `blocks` blocks, in the first of which `amount_sats`
+    /// is paid to [`ADDRESS`]. Mock txids and block hashes are
+    /// deterministic, so mocks built alike agree.
+    fn mock(amount_sats: Option<u64>, blocks: u32) -> Box<dyn L1Backend> {
+        let backend = MockL1Backend::new_regtest();
+        if let Some(amount_sats) = amount_sats {
+            let script =
+                l1_recipient_script(ParentChainType::Regtest, ADDRESS).unwrap();
+            let _txid = backend.send_to_script(
+                ADDRESS,
+                script,
+                bitcoin::Amount::from_sat(amount_sats),
+            );
+        }
+        let _tip = backend.mine_blocks(blocks);
+        Box::new(backend)
+    }
+
+    /// Txid of a first payment of 5_000_000 sats to [`ADDRESS`] on a mock
+    /// chain
+    fn payment_txid() -> String {
+        let backend = MockL1Backend::new_regtest();
+        let script =
+            l1_recipient_script(ParentChainType::Regtest, ADDRESS).unwrap();
+        backend.send_to_script(
+            ADDRESS,
+            script,
+            bitcoin::Amount::from_sat(5_000_000),
+        )
+    }
+
+    #[test]
+    fn lying_endpoint_cannot_fake_a_payment() {
+        let backend = QuorumBackend::new(
+            vec![mock(None, 2), mock(None, 2), mock(Some(5_000_000), 2)],
+            2,
+        );
+        let script =
+            l1_recipient_script(ParentChainType::Regtest, ADDRESS).unwrap();
+        assert!(
+            block_on(backend.find_transactions_by_script_and_amount(
+                ADDRESS, &script, 5_000_000
+            ))
+            .unwrap()
+            .is_empty()
+        );
+        assert!(matches!(
+            block_on(backend.get_transaction(&payment_txid())),
+            Err(Error::TransactionNotFound)
+        ));
+    }
+
+    #[test]
+    fn lagging_endpoint_is_outvoted() {
+        let endpoints = || {
+            vec![
+                mock(Some(5_000_000), 3),
+                mock(Some(5_000_000), 2),
+                mock(Some(5_000_000), 1),
+            ]
+        };
+        let backend = QuorumBackend::new(endpoints(), 2);
+        let script =
+            l1_recipient_script(ParentChainType::Regtest, ADDRESS).unwrap();
+        let matches = block_on(backend.find_transactions_by_script_and_amount(
+            ADDRESS, &script, 5_000_000,
+        ))
+        .unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].1.confirmations, 2);
+        assert_eq!(block_on(backend.get_block_height()).unwrap(), 2);
+        assert!(block_on(backend.get_block_hash(2)).is_ok());
+        assert!(matches!(
+            block_on(backend.get_block_hash(3)),
+            Err(Error::QuorumNotReached {
+                agreed: 1,
+                required: 2
+            })
+        ));
+
+        let backend = QuorumBackend::new(endpoints(), 3);
+        assert_eq!(
+            block_on(backend.get_transaction_confirmations(&payment_txid()))
+                .unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn conflicting_outputs_need_quorum() {
+        let endpoints = || {
+            vec![
+                mock(Some(5_000_000), 1),
+                mock(Some(5_000_000), 1),
+                mock(Some(6_000_000), 1),
+            ]
+        };
+        let txid = payment_txid();
+        let tx =
+            block_on(QuorumBackend::new(endpoints(), 2).get_transaction(&txid))
+                .unwrap();
+        assert_eq!(tx.vout[0].value.to_sat(), 5_000_000);
+        assert!(matches!(
+            block_on(QuorumBackend::new(endpoints(), 3).get_transaction(&txid)),
+            Err(Error::QuorumNotReached {
+                agreed: 2,
+                required: 3
+            })
+        ));
+    }
+}
@@ -0,0 +1,246 @@
+//! Parent chain data fetched ahead of a database write
+//!
+//! Connecting 2WPD updates swaps and the L1 scan cache in one write
+//! transaction. So that the write lock is not held during
+//! network I/O, the L1 data that the update needs is fetched beforehand
+//! with a read transaction (see [`crate::l1_scan::L1ScanCache::plan`]),
+//! and the update queries an [`L1Snapshot`] rather than the backend.
+//! Queries for data that was not fetched fail with
+//! [`Error::NotInSnapshot`].
+
+use std::collections::{HashMap, HashSet};
+
+use bitcoin::{BlockHash, ScriptBuf};
+
+use super::L1Backend;
+use crate::parent_chain_rpc::{Error, TransactionInfo};
+
+/// L1 data fetched from a backend for a set of watched scripts
+#[derive(Clone, Debug)]
+pub struct L1Snapshot {
+    name: String,
+    /// Transactions paying watched scripts in each fetched block, or `None`
+    /// if the backend cannot serve blocks
+    blocks: Option<HashMap<BlockHash, Vec<TransactionInfo>>>,
+    /// IDs of transactions paying each fetched address
+    address_txids: HashMap<String, Vec<String>>,
+    /// Fetched transactions, or `None` if the backend did not find them
+    transactions: HashMap<String, Option<TransactionInfo>>,
+    /// Scripts (with their addresses) that the snapshot was taken for
+    watched: Vec<(ScriptBuf, String)>,
+}
+
+impl L1Snapshot {
+    /// Empty snapshot of `backend`. Headers are not part of a snapshot:
+    /// they are read from the consensus header chain.
+    pub fn new(
+        backend: &dyn L1Backend,
+        watched: Vec<(ScriptBuf, String)>,
+    ) -> Self {
+        Self {
+            name: format!("snapshot of {}", backend.name()),
+            blocks: Some(HashMap::new()),
+            address_txids: HashMap::new(),
+            transactions: HashMap::new(),
+            watched,
+        }
+    }
+
+    /// Whether payments to `script` were fetched
+    pub fn watches(&self, script: &ScriptBuf) -> bool {
+        self.watched
+            .iter()
+            .any(|(watched_script, _)| watched_script == script)
+    }
+
+    /// Fetch a block, keeping the transactions that pay `scripts`. Fails
+    /// with [`Error::BlocksNotSupported`] if the backend cannot serve
+    /// blocks, which the snapshot then reports as well.
+    pub async fn fetch_block(
+        &mut self,
+        backend: &dyn L1Backend,
+        block_hash: BlockHash,
+        scripts: &HashSet<&ScriptBuf>,
+    ) -> Result<(), Error> {
+        if self.blocks.is_none() {
+            return Err(Error::BlocksNotSupported);
+        }
+        let txs = match backend.get_block_transactions(&block_hash).await {
+            Ok(txs) => txs,
+            Err(Error::BlocksNotSupported) => {
+                self.blocks = None;
+                return Err(Error::BlocksNotSupported);
+            }
+            Err(err) => return Err(err),
+        };
+        let txs = txs
+            .into_iter()
+            .filter(|tx| {
+                tx.vout
+                    .iter()
+                    .any(|vout| scripts.contains(&vout.script_pub_key.hex))
+            })
+            .collect();
+        if let Some(blocks) = &mut self.blocks {
+            blocks.insert(block_hash, txs);
+        }
+        Ok(())
+    }
+
+    /// Fetch transactions by ID
+    pub async fn fetch_transactions(
+        &mut self,
+        backend: &dyn L1Backend,
+        txids: &[String],
+    ) -> Result<(), Error> {
+        let txids: Vec<String> = txids
+            .iter()
+            .filter(|txid| !self.transactions.contains_key(*txid))
+            .cloned()
+            .collect();
+        let txs = backend.get_transactions(&txids).await?;
+        for (txid, tx) in txids.into_iter().zip(txs) {
+            match tx {
+                Ok(tx) => {
+                    self.transactions.insert(txid, Some(tx));
+                }
+                Err(Error::TransactionNotFound) => {
+                    self.transactions.insert(txid, None);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+
+    /// Fetch the IDs of the transactions paying an address, and the
+    /// transactions themselves
+    pub async fn fetch_address(
+        &mut self,
+        backend: &dyn L1Backend,
+        address: &str,
+    ) -> Result<(), Error> {
+        if self.address_txids.contains_key(address) {
+            return Ok(());
+        }
+        let txids = backend.list_transactions(address).await?;
+        let () = self.fetch_transactions(backend, &txids).await?;
+        self.address_txids.insert(address.to_owned(), txids);
+        Ok(())
+    }
+
+    /// Description of the snapshot's backend, for logs
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Fetched transaction by ID
+    pub fn get_transaction(
+        &self,
+        txid: &str,
+    ) -> Result<TransactionInfo, Error> {
+        match self.transactions.get(txid) {
+            Some(Some(tx)) => Ok(tx.clone()),
+            Some(None) => Err(Error::TransactionNotFound),
+            None => Err(Error::NotInSnapshot),
+        }
+    }
+
+    /// IDs of the transactions paying a fetched address
+    pub fn list_transactions(
+        &self,
+        address: &str,
+    ) -> Result<Vec<String>, Error> {
+        self.address_txids
+            .get(address)
+            .cloned()
+            .ok_or(Error::NotInSnapshot)
+    }
+
+    /// Transactions paying watched scripts in a fetched block
+    pub fn get_block_transactions(
+        &self,
+        block_hash: &BlockHash,
+    ) -> Result<Vec<TransactionInfo>, Error> {
+        let Some(blocks) = &self.blocks else {
+            return Err(Error::BlocksNotSupported);
+        };
+        blocks.get(block_hash).cloned().ok_or(Error::NotInSnapshot)
+    }
+
+    /// Find fetched transactions paying between `min_sats` and `max_sats`
+    /// (inclusive) to a script, as by
+    /// [`L1Backend::find_transactions_by_script_and_amount_range`]. Senders
+    /// are only known if the transactions they spend were fetched.
+    pub fn find_transactions_by_script_and_amount_range(
+        &self,
+        address: &str,
+        script: &bitcoin::Script,
+        min_sats: u64,
+        max_sats: u64,
+    ) -> Result<Vec<(String, TransactionInfo, u64)>, Error> {
+        let mut matches = Vec::new();
+        for txid in self.list_transactions(address)? {
+            let tx = match self.get_transaction(&txid) {
+                Ok(tx) => tx,
+                Err(Error::TransactionNotFound) => continue,
+                Err(err) => {
+                    tracing::warn!(
+                        "Error getting transaction {}: {}",
+                        txid,
+                        err
+                    );
+                    continue;
+                }
+            };
+            if let Some(amount_sats) =
+                tx.amount_paid_to(script, min_sats, max_sats)
+            {
+                let sender = self
+                    .sender_address(&tx)
+                    .unwrap_or_else(|| "unknown".to_owned());
+                matches.push((sender, tx, amount_sats));
+            }
+        }
+        Ok(matches)
+    }
+
+    /// Find fetched transactions paying a specific amount to a script (see
+    /// [`Self::find_transactions_by_script_and_amount_range`])
+    pub fn find_transactions_by_script_and_amount(
+        &self,
+        address: &str,
+        script: &bitcoin::Script,
+        amount_sats: u64,
+    ) -> Result<Vec<(String, TransactionInfo)>, Error> {
+        let matches = self.find_transactions_by_script_and_amount_range(
+            address,
+            script,
+            amount_sats,
+            amount_sats,
+        )?;
+        Ok(matches
+            .into_iter()
+            .map(|(sender, tx, _amount_sats)| (sender, tx))
+            .collect())
+    }
+
+    /// Address spent by the first input of a fetched transaction, if the
+    /// spent transaction was fetched too
+    fn sender_address(&self, tx: &TransactionInfo) -> Option<String> {
+        let vin = tx.vin.first()?;
+        let (Some(input_txid), Some(input_vout)) = (&vin.txid, &vin.vout)
+        else {
+            return None;
+        };
+        let input_tx = self.get_transaction(input_txid).ok()?;
+        let input_vout_data = input_tx.vout.get(*input_vout as usize)?;
+        input_vout_data.script_pub_key.address.clone().or_else(|| {
+            input_vout_data
+                .script_pub_key
+                .addresses
+                .as_ref()
+                .and_then(|addrs| addrs.first().cloned())
+        })
+    }
+}
@@ -0,0 +1,276 @@
+//! TLS for L1 endpoints
+//!
+//! HTTPS nodes and servers (Bitcoin Core RPC, Esplora) and Electrum servers
+//! over `ssl://` are verified against the Mozilla root certificates by
+//! default. An endpoint may instead trust its own CA certificate, or pin the
+//! server certificate's SHA-256 fingerprint, for nodes with self-signed
+//! certificates.
+
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, LazyLock},
+};
+
+use bitcoin::hashes::{Hash as _, sha256};
+use parking_lot::Mutex;
+use rustls::{
+    client::danger::{
+        HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier,
+    },
+    crypto::CryptoProvider,
+    pki_types::{CertificateDer, ServerName, UnixTime, pem::PemObject as _},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::parent_chain_rpc::Error;
+
+/// HTTP clients for L1 endpoints, by TLS settings. Clients are shared, so
+/// that endpoints with the same TLS settings share a connection pool. CA
+/// certificate files are read when a client is first built.
+static HTTP_CLIENTS: LazyLock<Mutex<HashMap<TlsConfig, reqwest::Client>>> =
+    LazyLock::new(Default::default);
+
+/// TLS settings of an L1 endpoint in the L1 config file
+#[derive(
+    Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize,
+)]
+pub struct TlsConfig {
+    /// PEM file with the CA certificates to trust instead of the Mozilla
+    /// root certificates
+    #[serde(
+        default,
+        rename = "tls_ca_cert",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub ca_cert: Option<PathBuf>,
+    /// SHA-256 fingerprint of the server certificate, in hex (colons are
+    /// ignored). The pinned certificate is accepted whichever CA issued it.
+    #[serde(
+        default,
+        rename = "tls_cert_sha256",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub cert_sha256: Option<String>,
+}
+
+impl TlsConfig {
+    fn is_default(&self) -> bool {
+        self.ca_cert.is_none() && self.cert_sha256.is_none()
+    }
+
+    fn root_certificates(&self) -> Result<rustls::RootCertStore, Error> {
+        let Some(ca_cert) = &self.ca_cert else {
+            return Ok(rustls::RootCertStore {
+                roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+            });
+        };
+        let invalid_ca_cert = |err: &dyn std::fmt::Display| {
+            Error::InvalidTlsConfig(format!("{}: {err}", ca_cert.display()))
+        };
+        let mut roots = rustls::RootCertStore::empty();
+        for cert in CertificateDer::pem_file_iter(ca_cert)
+            .map_err(|err| invalid_ca_cert(&err))?
+        {
+            let cert = cert.map_err(|err| invalid_ca_cert(&err))?;
+            let () = roots.add(cert)?;
+        }
+        if roots.is_empty() {
+            return Err(invalid_ca_cert(&"no certificates"));
+        }
+        Ok(roots)
+    }
+
+    /// Config for TLS connections to the endpoint
+    pub(crate) fn client_config(&self) -> Result<rustls::ClientConfig, Error> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let builder =
+            rustls::ClientConfig::builder_with_provider(provider.clone())
+                .with_safe_default_protocol_versions()?;
+        let config = match &self.cert_sha256 {
+            Some(fingerprint) => builder
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(
+                    PinnedCertVerifier {
+                        sha256: parse_fingerprint(fingerprint)?,
+                        provider,
+                    },
+                ))
+                .with_no_client_auth(),
+            None => builder
+                .with_root_certificates(self.root_certificates()?)
+                .with_no_client_auth(),
+        };
+        Ok(config)
+    }
+
+    /// HTTP client for the endpoint, from the shared pool. Without custom
+    /// TLS settings, the client's default TLS is used. Timeouts are set per
+    /// request.
+    pub(crate) fn http_client(&self) -> Result<reqwest::Client, Error> {
+        let mut clients = HTTP_CLIENTS.lock();
+        if let Some(client) = clients.get(self) {
+            return Ok(client.clone());
+        }
+        let builder = reqwest::Client::builder();
+        let builder = if self.is_default() {
+            builder
+        } else {
+            builder.use_preconfigured_tls(self.client_config()?)
+        };
+        let client = builder.build()?;
+        clients.insert(self.clone(), client.clone());
+        Ok(client)
+    }
+}
+
+fn parse_fingerprint(fingerprint: &str) -> Result<[u8; 32], Error> {
+    let fingerprint_hex = fingerprint.replace(':', "");
+    let mut sha256 = [0; 32];
+    hex::decode_to_slice(fingerprint_hex.trim(), &mut sha256).map_err(
+        |err| {
+            Error::InvalidTlsConfig(format!(
+                "invalid certificate fingerprint `{fingerprint}`: {err}"
+            ))
+        },
+    )?;
+    Ok(sha256)
+}
+
+/// Accepts only the server certificate with the pinned fingerprint
+#[derive(Debug)]
+struct PinnedCertVerifier {
+    sha256: [u8; 32],
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer,
+        _intermediates: &[CertificateDer],
+        _server_name: &ServerName,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let sha256 = sha256::Hash::hash(end_entity.as_ref()).to_byte_array();
+        if sha256 == self.sha256 {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::InvalidCertificate(
+                rustls::CertificateError::ApplicationVerificationFailure,
+            ))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use bitcoin::hashes::{Hash as _, sha256};
+    use parking_lot::Mutex;
+    use rustls::{
+        client::danger::ServerCertVerifier as _,
+        pki_types::{ServerName, UnixTime},
+    };
+
+    use super::{PinnedCertVerifier, TlsConfig, parse_fingerprint};
+    use crate::parent_chain_rpc::Error;
+
+    #[test]
+    fn pinned_fingerprint_accepts_only_that_certificate() {
+        let pinned =
+            rcgen::generate_simple_self_signed(vec!["node.local".into()])
+                .unwrap();
+        let other =
+            rcgen::generate_simple_self_signed(vec!["node.local".into()])
+                .unwrap();
+        // Fingerprint as printed by `openssl x509 -fingerprint -sha256`
+        let fingerprint = sha256::Hash::hash(pinned.cert.der())
+            .to_byte_array()
+            .iter()
+            .map(|byte| format!("{byte:02X}"))
+            .collect::<Vec<_>>()
+            .join(":");
+        let verifier = PinnedCertVerifier {
+            sha256: parse_fingerprint(&fingerprint).unwrap(),
+            provider: Arc::new(rustls::crypto::ring::default_provider()),
+        };
+        let server_name = ServerName::try_from("node.local").unwrap();
+        let verify = |cert: &rustls::pki_types::CertificateDer| {
+            verifier.verify_server_cert(
+                cert,
+                &[],
+                &server_name,
+                &[],
+                UnixTime::now(),
+            )
+        };
+        assert!(verify(pinned.cert.der()).is_ok());
+        assert!(verify(other.cert.der()).is_err());
+        assert!(matches!(
+            parse_fingerprint("abcd"),
+            Err(Error::InvalidTlsConfig(_))
+        ));
+    }
+
+    #[test]
+    fn ca_cert_replaces_default_roots() {
+        let ca = rcgen::generate_simple_self_signed(vec!["node.local".into()])
+            .unwrap();
+        let path = std::env::temp_dir().join("coinshift_l1_tls_ca.pem");
+        std::fs::write(&path, ca.cert.pem()).unwrap();
+        let tls = TlsConfig {
+            ca_cert: Some(path.clone()),
+            cert_sha256: None,
+        };
+        let roots = tls.root_certificates();
+        let client = tls.http_client();
+        drop(std::fs::remove_file(&path)); // best-effort cleanup
+        assert_eq!(roots.unwrap().len(), 1);
+        assert!(client.is_ok());
+        let missing = TlsConfig {
+            ca_cert: Some("/nonexistent/ca.pem".into()),
+            cert_sha256: None,
+        };
+        assert!(matches!(
+            missing.client_config(),
+            Err(Error::InvalidTlsConfig(_))
+        ));
+    }
+}
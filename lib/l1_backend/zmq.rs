@@ -0,0 +1,410 @@
+//! ZMQ notifications from parent chain nodes
+//!
+//! Bitcoin Core compatible nodes can publish every transaction they accept
+//! (`zmqpubrawtx`) and the hash of every new block (`zmqpubhashblock`).
+//! Relayed transactions are matched against a [`SwapScriptIndex`] of pending
+//! swaps, so that fills are noticed as soon as they reach the L1 mempool, and
+//! block notifications trigger confirmation updates. Parent chains with ZMQ
+//! configured do not need to be polled.
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use bitcoin::{BlockHash, ScriptBuf, Transaction, hashes::Hash as _};
+use parking_lot::RwLock;
+use tokio::sync::mpsc;
+use zeromq::{Socket as _, SocketRecv as _, SubSocket, ZmqMessage};
+
+use crate::{
+    parent_chain_rpc::{Error, L1ConfigEntry},
+    types::{ParentChainType, Swap, SwapId, SwapState, l1_recipient_script},
+};
+
+/// Topic of raw transaction notifications
+pub const RAWTX_TOPIC: &str = "rawtx";
+
+/// Topic of block hash notifications
+pub const HASHBLOCK_TOPIC: &str = "hashblock";
+
+/// Delay before reconnecting to a ZMQ endpoint after a failure
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Notification from a parent chain node
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum L1Event {
+    /// A relayed transaction pays a pending swap's L1 recipient script and
+    /// amount. The transaction may not be confirmed yet.
+    Fill {
+        parent_chain: ParentChainType,
+        swap_id: SwapId,
+        txid: bitcoin::Txid,
+    },
+    /// A new block was connected
+    Block {
+        parent_chain: ParentChainType,
+        block_hash: BlockHash,
+    },
+}
+
+/// Pending swaps of a parent chain, by the L1 output script that fills them
+#[derive(Debug, Default)]
+pub struct SwapScriptIndex {
+    /// Swaps and their L1 amounts, by L1 recipient script
+    swaps: HashMap<ScriptBuf, Vec<(SwapId, bitcoin::Amount)>>,
+}
+
+impl SwapScriptIndex {
+    /// Index the swaps of `parent_chain` that a single L1 payment can fill
+    /// at sidechain height `height`: pending swaps with an L1 recipient that
+    /// do not allow partial fills. As when connecting 2WPD, open swaps are
+    /// only indexed while a taker holds a live reservation.
+    pub fn new(
+        parent_chain: ParentChainType,
+        swaps: &[Swap],
+        height: u32,
+    ) -> Self {
+        let mut index = Self::default();
+        for swap in swaps {
+            if swap.parent_chain != parent_chain
+                || swap.state != SwapState::Pending
+                || swap.allows_partial_fills()
+                || (swap.l2_recipient.is_none()
+                    && swap.live_reservation(height).is_none())
+            {
+                continue;
+            }
+            let (Some(l1_recipient), Some(l1_amount)) =
+                (&swap.l1_recipient_address, swap.l1_amount)
+            else {
+                continue;
+            };
+            let script = match &swap.l1_recipient_script {
+                Some(script) => script.clone(),
+                None => match l1_recipient_script(parent_chain, l1_recipient) {
+                    Ok(script) => script,
+                    Err(err) => {
+                        tracing::debug!(
+                            swap_id = %swap.id,
+                            error = %err,
+                            "Not indexing swap with invalid L1 recipient"
+                        );
+                        continue;
+                    }
+                },
+            };
+            index
+                .swaps
+                .entry(script)
+                .or_default()
+                .push((swap.id, l1_amount));
+        }
+        index
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.swaps.is_empty()
+    }
+
+    /// Swaps that `tx` pays the exact L1 amount of
+    pub fn fills(&self, tx: &Transaction) -> Vec<SwapId> {
+        let mut swap_ids = Vec::new();
+        for output in &tx.output {
+            let Some(swaps) = self.swaps.get(&output.script_pubkey) else {
+                continue;
+            };
+            for (swap_id, l1_amount) in swaps {
+                if output.value == *l1_amount && !swap_ids.contains(swap_id) {
+                    swap_ids.push(*swap_id);
+                }
+            }
+        }
+        swap_ids
+    }
+}
+
+/// ZMQ endpoints configured for a parent chain, with the topics to
+/// subscribe to on each. Endpoints publishing both topics are subscribed to
+/// once.
+pub fn subscriptions(
+    entry: &L1ConfigEntry,
+) -> Vec<(String, Vec<&'static str>)> {
+    let mut subscriptions: Vec<(String, Vec<&'static str>)> = Vec::new();
+    let topics = [
+        (&entry.zmq_rawtx, RAWTX_TOPIC),
+        (&entry.zmq_hashblock, HASHBLOCK_TOPIC),
+    ];
+    for (endpoint, topic) in topics {
+        let Some(endpoint) = endpoint else { continue };
+        match subscriptions.iter_mut().find(|(url, _)| url == endpoint) {
+            Some((_, topics)) => topics.push(topic),
+            None => subscriptions.push((endpoint.clone(), vec![topic])),
+        }
+    }
+    subscriptions
+}
+
+/// Subscriber to a parent chain node's ZMQ endpoint. Fills of swaps in the
+/// shared index and new blocks are sent as [`L1Event`]s.
+pub struct ZmqSubscriber {
+    parent_chain: ParentChainType,
+    endpoint: String,
+    topics: Vec<&'static str>,
+    index: Arc<RwLock<SwapScriptIndex>>,
+    events: mpsc::UnboundedSender<L1Event>,
+}
+
+impl ZmqSubscriber {
+    pub fn new(
+        parent_chain: ParentChainType,
+        endpoint: String,
+        topics: Vec<&'static str>,
+        index: Arc<RwLock<SwapScriptIndex>>,
+        events: mpsc::UnboundedSender<L1Event>,
+    ) -> Self {
+        Self {
+            parent_chain,
+            endpoint,
+            topics,
+            index,
+            events,
+        }
+    }
+
+    /// Connect to the endpoint and handle notifications. Returns once the
+    /// event receiver is dropped, or with an error if the connection fails.
+    pub async fn subscribe(&self) -> Result<(), Error> {
+        let mut socket = SubSocket::new();
+        socket.connect(&self.endpoint).await?;
+        for topic in &self.topics {
+            socket.subscribe(topic).await?;
+        }
+        tracing::info!(
+            parent_chain = ?self.parent_chain,
+            endpoint = %self.endpoint,
+            topics = ?self.topics,
+            "Subscribed to L1 ZMQ notifications"
+        );
+        loop {
+            let message = socket.recv().await?;
+            if self.handle_message(&message).is_err() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Subscribe, reconnecting after failures, until the event receiver is
+    /// dropped
+    pub async fn run(self) {
+        while !self.events.is_closed() {
+            if let Err(err) = self.subscribe().await {
+                tracing::warn!(
+                    parent_chain = ?self.parent_chain,
+                    endpoint = %self.endpoint,
+                    error = %err,
+                    "L1 ZMQ subscription failed, reconnecting in {}s",
+                    RECONNECT_DELAY.as_secs()
+                );
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        }
+    }
+
+    /// Handle a notification. Messages consist of the topic, the body and
+    /// a sequence number. Fails if the event receiver was dropped.
+    fn handle_message(
+        &self,
+        message: &ZmqMessage,
+    ) -> Result<(), mpsc::error::SendError<L1Event>> {
+        let (Some(topic), Some(body)) = (message.get(0), message.get(1)) else {
+            tracing::debug!(
+                endpoint = %self.endpoint,
+                "Ignoring ZMQ message without a body"
+            );
+            return Ok(());
+        };
+        match topic.as_ref() {
+            topic if topic == RAWTX_TOPIC.as_bytes() => {
+                let tx: Transaction =
+                    match bitcoin::consensus::deserialize(body) {
+                        Ok(tx) => tx,
+                        Err(err) => {
+                            tracing::warn!(
+                                endpoint = %self.endpoint,
+                                error = %err,
+                                "Invalid transaction in ZMQ notification"
+                            );
+                            return Ok(());
+                        }
+                    };
+                let swap_ids = self.index.read().fills(&tx);
+                let txid = tx.compute_txid();
+                for swap_id in swap_ids {
+                    tracing::info!(
+                        parent_chain = ?self.parent_chain,
+                        swap_id = %swap_id,
+                        l1_txid = %txid,
+                        "Detected L1 transaction for swap from ZMQ"
+                    );
+                    self.events.send(L1Event::Fill {
+                        parent_chain: self.parent_chain,
+                        swap_id,
+                        txid,
+                    })?;
+                }
+            }
+            topic if topic == HASHBLOCK_TOPIC.as_bytes() => {
+                // Block hashes are published in RPC byte order
+                let Ok(mut bytes) = <[u8; 32]>::try_from(body.as_ref()) else {
+                    tracing::warn!(
+                        endpoint = %self.endpoint,
+                        "Invalid block hash in ZMQ notification"
+                    );
+                    return Ok(());
+                };
+                bytes.reverse();
+                let block_hash = BlockHash::from_byte_array(bytes);
+                tracing::debug!(
+                    parent_chain = ?self.parent_chain,
+                    block_hash = %block_hash,
+                    "New L1 block from ZMQ"
+                );
+                self.events.send(L1Event::Block {
+                    parent_chain: self.parent_chain,
+                    block_hash,
+                })?;
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use bitcoin::{
+        Amount, OutPoint, Transaction, TxIn, TxOut, absolute::LockTime,
+        hashes::Hash as _, transaction::Version,
+    };
+    use parking_lot::RwLock;
+    use tokio::sync::mpsc;
+    use zeromq::{PubSocket, Socket as _, SocketSend as _, ZmqMessage};
+
+    use super::{
+        HASHBLOCK_TOPIC, L1Event, RAWTX_TOPIC, SwapScriptIndex, ZmqSubscriber,
+    };
+    use crate::{
+        l1_backend::mock::MockL1Backend,
+        types::{ParentChainType, SwapId, l1_recipient_script},
+    };
+
+    const ADDRESS: &str = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+
+    fn payment(amount_sats: u64) -> Transaction {
+        let script =
+            l1_recipient_script(ParentChainType::Regtest, ADDRESS).unwrap();
+        Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::new(bitcoin::Txid::all_zeros(), 0),
+                ..TxIn::default()
+            }],
+            output: vec![TxOut {
+                value: Amount::from_sat(amount_sats),
+                script_pubkey: script,
+            }],
+        }
+    }
+
+    fn zmq_message(topic: &str, body: Vec<u8>) -> ZmqMessage {
+        let mut message = ZmqMessage::from(topic.to_owned());
+        message.push_back(body.into());
+        message.push_back(0u32.to_le_bytes().to_vec().into());
+        message
+    }
+
+    #[test]
+    fn index_matches_script_and_amount() {
+        let mut index = SwapScriptIndex::default();
+        let swap_id = SwapId([7; 32]);
+        index.swaps.insert(
+            l1_recipient_script(ParentChainType::Regtest, ADDRESS).unwrap(),
+            vec![(swap_id, Amount::from_sat(5_000_000))],
+        );
+        assert_eq!(index.fills(&payment(5_000_000)), vec![swap_id]);
+        assert!(index.fills(&payment(4_999_999)).is_empty());
+    }
+
+    #[test]
+    fn notifies_fills_and_blocks() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let mut publisher = PubSocket::new();
+            let endpoint = publisher.bind("tcp://127.0.0.1:0").await.unwrap();
+
+            let swap_id = SwapId([7; 32]);
+            let mut index = SwapScriptIndex::default();
+            index.swaps.insert(
+                l1_recipient_script(ParentChainType::Regtest, ADDRESS).unwrap(),
+                vec![(swap_id, Amount::from_sat(5_000_000))],
+            );
+            let (events_tx, mut events_rx) = mpsc::unbounded_channel();
+            let subscriber = ZmqSubscriber::new(
+                ParentChainType::Regtest,
+                endpoint.to_string(),
+                vec![RAWTX_TOPIC, HASHBLOCK_TOPIC],
+                Arc::new(RwLock::new(index)),
+                events_tx,
+            );
+            let subscriber = tokio::spawn(subscriber.run());
+
+            let tx = payment(5_000_000);
+            let block_hash = MockL1Backend::new_regtest().mine_blocks(1);
+            let mut block_hash_bytes = block_hash.to_byte_array().to_vec();
+            block_hash_bytes.reverse();
+            let messages = [
+                zmq_message(
+                    RAWTX_TOPIC,
+                    bitcoin::consensus::serialize(&payment(1_000)),
+                ),
+                zmq_message(RAWTX_TOPIC, bitcoin::consensus::serialize(&tx)),
+                zmq_message(HASHBLOCK_TOPIC, block_hash_bytes),
+            ];
+            // Messages published before the subscription is set up are
+            // dropped, so publish until the events arrive
+            let mut events = Vec::new();
+            while events.len() < 2 {
+                for message in &messages {
+                    publisher.send(message.clone()).await.unwrap();
+                }
+                while let Ok(Some(event)) = tokio::time::timeout(
+                    Duration::from_millis(100),
+                    events_rx.recv(),
+                )
+                .await
+                {
+                    if !events.contains(&event) {
+                        events.push(event);
+                    }
+                }
+            }
+            assert_eq!(
+                events,
+                vec![
+                    L1Event::Fill {
+                        parent_chain: ParentChainType::Regtest,
+                        swap_id,
+                        txid: tx.compute_txid(),
+                    },
+                    L1Event::Block {
+                        parent_chain: ParentChainType::Regtest,
+                        block_hash,
+                    },
+                ]
+            );
+            drop(events_rx);
+            subscriber.abort();
+        });
+    }
+}
@@ -0,0 +1,344 @@
+//! L1 configuration
+//!
+//! The L1 config file (`l1_rpc_configs.json` in the data directory) holds
+//! the endpoints of each parent chain, in the format described at
+//! [`parent_chain_rpc::load_l1_config_entry_from_path`]. [`L1Config`] owns
+//! the file: the node, the GUI and the RPC server (and so the CLI) read and
+//! write it through the same handle, and edits made to the file by hand are
+//! picked up on the next read.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use parking_lot::RwLock;
+
+use crate::{
+    l1_backend::{self, L1Backend},
+    parent_chain_rpc::{self, Error, L1ConfigEntry, L1Endpoint},
+    types::ParentChainType,
+};
+
+/// Name of the L1 config file in the data directory
+pub const L1_CONFIG_FILE_NAME: &str = "l1_rpc_configs.json";
+
+/// Entries of the L1 config file, by parent chain
+pub type L1ConfigEntries = HashMap<ParentChainType, L1ConfigEntry>;
+
+/// Entries as last read from the file
+#[derive(Debug, Default)]
+struct Cached {
+    /// Modification time of the file when it was read, or `None` if there
+    /// was no file
+    modified: Option<SystemTime>,
+    entries: L1ConfigEntries,
+}
+
+/// Handle to the L1 config file. Clones share the entries read from the
+/// file, which is read again whenever its modification time changes.
+#[derive(Clone, Debug)]
+pub struct L1Config {
+    path: Arc<PathBuf>,
+    cached: Arc<RwLock<Cached>>,
+}
+
+impl L1Config {
+    /// L1 config file in a data directory
+    pub fn new(datadir: &Path) -> Self {
+        Self::from_path(datadir.join(L1_CONFIG_FILE_NAME))
+    }
+
+    pub fn from_path(path: PathBuf) -> Self {
+        Self {
+            path: Arc::new(path),
+            cached: Arc::default(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        std::fs::metadata(&*self.path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+
+    /// Read the file. A missing file has no entries.
+    fn read(&self) -> Result<L1ConfigEntries, Error> {
+        match std::fs::read_to_string(&*self.path) {
+            Ok(file_content) => Ok(serde_json::from_str(&file_content)?),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                Ok(HashMap::new())
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Entries of every parent chain. If the file changed since it was
+    /// last read, it is read again; if it cannot be read, the error is
+    /// logged and the previous entries are kept.
+    pub fn entries(&self) -> L1ConfigEntries {
+        let modified = self.modified();
+        {
+            let cached = self.cached.read();
+            if cached.modified == modified {
+                return cached.entries.clone();
+            }
+        }
+        let mut cached = self.cached.write();
+        if cached.modified != modified {
+            match self.read() {
+                Ok(entries) => {
+                    tracing::info!(
+                        path = %self.path.display(),
+                        parent_chains = ?entries.keys().collect::<Vec<_>>(),
+                        "Loaded L1 config"
+                    );
+                    cached.entries = entries;
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        path = %self.path.display(),
+                        error = %err,
+                        "Failed to read L1 config, keeping previous config"
+                    );
+                }
+            }
+            cached.modified = modified;
+        }
+        cached.entries.clone()
+    }
+
+    /// Entry of a parent chain, if configured
+    pub fn entry(
+        &self,
+        parent_chain: ParentChainType,
+    ) -> Option<L1ConfigEntry> {
+        self.entries().remove(&parent_chain)
+    }
+
+    /// Backend for a parent chain (see [`l1_backend::from_config`]).
+    /// Backends that cannot be set up are logged and skipped.
+    pub fn backend(
+        &self,
+        parent_chain: ParentChainType,
+    ) -> Option<Box<dyn L1Backend>> {
+        let entry = self.entry(parent_chain)?;
+        match l1_backend::from_config(parent_chain, &entry) {
+            Ok(backend) => Some(backend),
+            Err(err) => {
+                tracing::error!(
+                    ?parent_chain,
+                    error = %err,
+                    "Failed to set up L1 backend"
+                );
+                None
+            }
+        }
+    }
+
+    /// Update the entries and write them to the file, creating its parent
+    /// directory if needed. The file is replaced atomically. If the
+    /// current file cannot be parsed, it is overwritten.
+    fn update<F>(&self, f: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut L1ConfigEntries),
+    {
+        let mut cached = self.cached.write();
+        let mut entries = self.read().unwrap_or_else(|err| {
+            tracing::warn!(
+                path = %self.path.display(),
+                error = %err,
+                "Overwriting unreadable L1 config"
+            );
+            HashMap::new()
+        });
+        let () = f(&mut entries);
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(&entries)?;
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, json)?;
+        std::fs::rename(&tmp_path, &*self.path)?;
+        cached.modified = self.modified();
+        cached.entries = entries;
+        tracing::info!(path = %self.path.display(), "Saved L1 config");
+        Ok(())
+    }
+
+    /// Set the primary endpoint of each of the given parent chains. The
+    /// extra endpoints, quorum and ZMQ settings of their entries are kept,
+    /// as are the entries of other parent chains.
+    pub fn set_endpoints(
+        &self,
+        endpoints: &[(ParentChainType, L1Endpoint)],
+    ) -> Result<(), Error> {
+        self.update(|entries| {
+            for (parent_chain, endpoint) in endpoints {
+                entries.entry(*parent_chain).or_default().endpoint =
+                    endpoint.clone();
+            }
+        })
+    }
+
+    /// Set or, if `entry` is `None`, remove the entry of a parent chain
+    pub fn set_entry(
+        &self,
+        parent_chain: ParentChainType,
+        entry: Option<L1ConfigEntry>,
+    ) -> Result<(), Error> {
+        self.update(|entries| match entry {
+            Some(entry) => {
+                entries.insert(parent_chain, entry);
+            }
+            None => {
+                entries.remove(&parent_chain);
+            }
+        })
+    }
+
+    /// Check that every endpoint of an entry serves the entry's chain (see
+    /// [`parent_chain_rpc::verify_l1_endpoint`]), and that its quorum is
+    /// between 1 and the number of endpoints. Call before app start.
+    pub async fn validate(&self) -> Result<(), Error> {
+        for (parent_chain, entry) in self.entries() {
+            let endpoints = entry.endpoints().count();
+            let quorum = entry.quorum();
+            if quorum == 0 || quorum > endpoints {
+                return Err(Error::InvalidQuorum { quorum, endpoints });
+            }
+            for endpoint in entry.endpoints() {
+                let () = parent_chain_rpc::verify_l1_endpoint(
+                    parent_chain,
+                    endpoint,
+                )
+                .await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::L1Config;
+    use crate::{
+        parent_chain_rpc::{Error, L1Endpoint},
+        types::ParentChainType,
+    };
+
+    /// Config file in a fresh temp directory
+    fn temp_config(name: &str) -> L1Config {
+        let dir = std::env::temp_dir().join(name);
+        drop(std::fs::remove_dir_all(&dir)); // best-effort cleanup
+        L1Config::new(&dir)
+    }
+
+    #[test]
+    fn validate_invalid_quorum_fails() {
+        let config = temp_config("coinshift_l1_config_quorum");
+        let configs = serde_json::json!({
+            "Signet": {
+                "url": "http://localhost:38332",
+                "user": "user",
+                "password": "password",
+                "extra_endpoints": [
+                    { "backend": "esplora", "url": "http://localhost:3000" }
+                ],
+                "quorum": 3
+            }
+        });
+        std::fs::create_dir_all(config.path().parent().unwrap()).unwrap();
+        std::fs::write(config.path(), configs.to_string()).unwrap();
+        let result = futures::executor::block_on(config.validate());
+        drop(std::fs::remove_file(config.path())); // best-effort cleanup
+        assert!(matches!(
+            result,
+            Err(Error::InvalidQuorum {
+                quorum: 3,
+                endpoints: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn validate_empty_or_missing_ok() {
+        let config =
+            L1Config::from_path("/nonexistent/l1_rpc_configs.json".into());
+        assert!(config.entries().is_empty());
+        assert!(futures::executor::block_on(config.validate()).is_ok());
+    }
+
+    #[test]
+    fn set_endpoints_keeps_custom_endpoints() {
+        let config = temp_config("coinshift_l1_config_write");
+        let configs = serde_json::json!({
+            "BCH": { "backend": "electrum", "url": "ssl://bch.example.org:50002" },
+            "Signet": {
+                "url": "http://localhost:38332",
+                "extra_endpoints": [
+                    { "backend": "esplora", "url": "https://mempool.space/signet/api" }
+                ],
+                "quorum": 2
+            }
+        });
+        std::fs::create_dir_all(config.path().parent().unwrap()).unwrap();
+        std::fs::write(config.path(), configs.to_string()).unwrap();
+        let endpoint =
+            L1Endpoint::bitcoin_core("http://u:p@signet.example.org:38332")
+                .unwrap();
+        let written =
+            config.set_endpoints(&[(ParentChainType::Signet, endpoint)]);
+        // Read back through a separate handle
+        let reloaded = L1Config::from_path(config.path().to_owned());
+        let bch = reloaded.entry(ParentChainType::BCH);
+        let signet = reloaded.entry(ParentChainType::Signet);
+        drop(std::fs::remove_file(config.path())); // best-effort cleanup
+        written.unwrap();
+        assert_eq!(bch.unwrap().endpoint.url, "ssl://bch.example.org:50002");
+        let signet = signet.unwrap();
+        assert_eq!(signet.endpoint.url, "http://signet.example.org:38332/");
+        assert_eq!(signet.endpoint.user, "u");
+        assert_eq!(signet.quorum(), 2);
+    }
+
+    #[test]
+    fn reloads_file_edited_by_hand() {
+        let config = temp_config("coinshift_l1_config_reload");
+        let other = config.clone();
+        assert!(config.entries().is_empty());
+        let () = config
+            .set_entry(ParentChainType::BCH, Some(Default::default()))
+            .unwrap();
+        let saved = other.entries();
+        // Edits are detected by modification time
+        let edit = |json: serde_json::Value| {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            std::fs::write(config.path(), json.to_string()).unwrap();
+        };
+        let () = edit(serde_json::json!({ "Not a chain": { "url": "" } }));
+        let after_invalid_edit = other.entries();
+        let () = edit(serde_json::json!({
+            "Signet": { "url": "http://localhost:38332/" }
+        }));
+        let after_edit = other.entries();
+        let () = config.set_entry(ParentChainType::Signet, None).unwrap();
+        let after_remove = other.entries();
+        drop(std::fs::remove_file(config.path())); // best-effort cleanup
+        assert!(saved.contains_key(&ParentChainType::BCH));
+        // Unparseable files are ignored
+        assert!(after_invalid_edit.contains_key(&ParentChainType::BCH));
+        assert_eq!(
+            after_edit[&ParentChainType::Signet].endpoint.url,
+            "http://localhost:38332/"
+        );
+        assert!(!after_edit.contains_key(&ParentChainType::BCH));
+        assert!(after_remove.is_empty());
+    }
+}
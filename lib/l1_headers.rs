@@ -0,0 +1,1953 @@
+//! Header chains for swap parent chains
+//!
+//! Each [`ParentChainType`] has its own chain of proof-of-work validated
+//! headers, with cumulative work, stored in the [`crate::state::State`].
+//! Headers only enter a header chain through [`TxData::L1Headers`] relays
+//! in sidechain blocks, so every node has the same header chains, and swap
+//! confirmations counted against them are part of consensus. A parent chain
+//! RPC node can only point at transactions; it cannot fake depth without
+//! doing the work.
+//!
+//! Headers are checked as by the parent chain's nodes: proof of work,
+//! difficulty transitions, and timestamps after the median of the previous
+//! 11 headers and at most [`MAX_FUTURE_BLOCK_TIME`] ahead of the node's
+//! clock. Only chains with [verifiable headers] are supported: signet blocks,
+//! for instance, are signed rather than mined.
+//!
+//! A chain is anchored at the highest hard-coded checkpoint of its parent
+//! chain ([`anchor_checkpoint`]), or on chains that retarget every
+//! `interval` blocks, at the start of the checkpoint's retarget period
+//! ([`anchor_height`]). The first relay must start at the anchor and reach
+//! the checkpoint. Reorgs deeper than [`MAX_REORG_DEPTH`] are rejected. The
+//! relays connected by a sidechain block are undone when the block is
+//! disconnected.
+//!
+//! [verifiable headers]: crate::types::ChainParams::verifiable_headers
+//!
+//! [`TxData::L1Headers`]: crate::types::TxData::L1Headers
+
+use std::collections::HashMap;
+
+use bitcoin::{
+    self, CompactTarget, Target, block::Header as L1Header, hashes::Hash as _,
+};
+use heed::types::SerdeBincode;
+use serde::{Deserialize, Serialize};
+use sneed::{
+    DatabaseUnique, EnvError, RoTxn, RwTxn, db::error::Error as DbError,
+    rwtxn::Error as RwTxnError,
+};
+
+use crate::{
+    l1_backend::{L1Backend, snapshot::L1Snapshot},
+    parent_chain_rpc,
+    types::{AsertAnchor, ChainNetwork, DifficultyAdjustment, ParentChainType},
+};
+
+/// Maximum number of blocks that may be disconnected from a best header
+/// chain in a single reorg
+pub const MAX_REORG_DEPTH: u32 = 144;
+
+/// Maximum number of headers in a single relay
+pub const MAX_HEADERS_PER_SYNC: u32 = 2016;
+
+/// Maximum number of seconds that a header's timestamp may be ahead of the
+/// node's clock, as on the parent chains
+pub const MAX_FUTURE_BLOCK_TIME: u64 = 2 * 60 * 60;
+
+/// Number of headers, ending with a header's parent, whose median time the
+/// header's timestamp must exceed
+const MEDIAN_TIME_SPAN: usize = 11;
+
+/// Size of a consensus-encoded header
+const L1_HEADER_SIZE: usize = 80;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Db(#[from] DbError),
+    #[error("Database env error")]
+    DbEnv(#[from] EnvError),
+    #[error("Database write error")]
+    DbWrite(#[from] RwTxnError),
+    #[error(
+        "{parent_chain:?} header {block_hash} at height {height} conflicts with checkpoint {checkpoint}"
+    )]
+    CheckpointMismatch {
+        parent_chain: ParentChainType,
+        block_hash: bitcoin::BlockHash,
+        height: u32,
+        checkpoint: bitcoin::BlockHash,
+    },
+    #[error("{parent_chain:?} header {block_hash} has invalid difficulty bits")]
+    InvalidDifficulty {
+        parent_chain: ParentChainType,
+        block_hash: bitcoin::BlockHash,
+    },
+    #[error(
+        "{parent_chain:?} header {block_hash} does not satisfy its proof of work target"
+    )]
+    InsufficientWork {
+        parent_chain: ParentChainType,
+        block_hash: bitcoin::BlockHash,
+    },
+    #[error(
+        "{parent_chain:?} header {block_hash} has a timestamp {time} not after the median time past {median_time_past}"
+    )]
+    TimeTooOld {
+        parent_chain: ParentChainType,
+        block_hash: bitcoin::BlockHash,
+        time: u32,
+        median_time_past: u32,
+    },
+    #[error(
+        "{parent_chain:?} header {block_hash} has a timestamp {time} too far in the future"
+    )]
+    TimeTooNew {
+        parent_chain: ParentChainType,
+        block_hash: bitcoin::BlockHash,
+        time: u32,
+    },
+    #[error(
+        "{parent_chain:?} header at height {height} is below the header chain's anchor"
+    )]
+    UnknownAncestor {
+        parent_chain: ParentChainType,
+        height: u32,
+    },
+    #[error("{0:?} header chain already has an anchor")]
+    AlreadyAnchored(ParentChainType),
+    #[error("{0:?} header chain has no anchor")]
+    NotAnchored(ParentChainType),
+    #[error(
+        "{parent_chain:?} header {block_hash} extends unknown header {prev_block_hash}"
+    )]
+    UnknownParent {
+        parent_chain: ParentChainType,
+        block_hash: bitcoin::BlockHash,
+        prev_block_hash: bitcoin::BlockHash,
+    },
+    #[error(
+        "{parent_chain:?} reorg of depth {depth} exceeds the maximum of {MAX_REORG_DEPTH}"
+    )]
+    ReorgTooDeep {
+        parent_chain: ParentChainType,
+        depth: u32,
+    },
+    #[error(
+        "{parent_chain:?} node does not share a recent block with the local header chain"
+    )]
+    NoCommonAncestor { parent_chain: ParentChainType },
+    #[error("{0:?} header relay is empty")]
+    EmptyRelay(ParentChainType),
+    #[error(
+        "{parent_chain:?} header relay anchoring the header chain must reach checkpoint height {checkpoint_height}"
+    )]
+    AnchorRelayTooShort {
+        parent_chain: ParentChainType,
+        checkpoint_height: u32,
+    },
+    #[error(
+        "{parent_chain:?} header relay of {len} headers exceeds the maximum of {MAX_HEADERS_PER_SYNC}"
+    )]
+    RelayTooLong {
+        parent_chain: ParentChainType,
+        len: usize,
+    },
+    #[error(
+        "{parent_chain:?} header relay contains an invalid header encoding"
+    )]
+    InvalidHeaderEncoding { parent_chain: ParentChainType },
+    #[error(
+        "{parent_chain:?} header {block_hash} does not extend the previous header of the relay"
+    )]
+    UnlinkedRelay {
+        parent_chain: ParentChainType,
+        block_hash: bitcoin::BlockHash,
+    },
+    #[error(transparent)]
+    ParentChainRpc(#[from] parent_chain_rpc::Error),
+    #[error("header proof of work is not supported for {0:?}")]
+    UnsupportedParentChain(ParentChainType),
+}
+
+/// A stored L1 header
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct L1HeaderInfo {
+    pub header: L1Header,
+    pub height: u32,
+    /// Cumulative work from the anchor up to and including this header
+    pub total_work: bitcoin::Work,
+}
+
+/// Headers before a header's parent that the header's validity depends on
+#[derive(Clone, Copy, Debug, Default)]
+struct HeaderContext {
+    /// Median time of the parent and up to 10 of its predecessors
+    median_time_past: u32,
+    /// At retarget heights, time of the first header of the retarget period
+    /// that ends with the parent
+    period_start_time: Option<u32>,
+    /// On testnets that retarget, bits of the last header of the current
+    /// retarget period that does not use the minimum difficulty
+    last_regular_bits: Option<CompactTarget>,
+}
+
+/// Tip of a best L1 header chain
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, utoipa::ToSchema,
+)]
+pub struct L1Tip {
+    #[schema(value_type = crate::types::schema::BitcoinBlockHash)]
+    pub block_hash: bitcoin::BlockHash,
+    pub height: u32,
+}
+
+/// Changes made to a header chain by a relay, used to undo it
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct L1RelayUndo {
+    parent_chain: ParentChainType,
+    /// Best tip before the relay, or `None` if the relay anchored the chain
+    prev_tip: Option<L1Tip>,
+    /// Headers added by the relay
+    added: Vec<bitcoin::BlockHash>,
+}
+
+/// Bits of the header after `parent` under the ASERT difficulty algorithm
+/// (aserti3-2d), as computed by Bitcoin Cash nodes. The target is the anchor
+/// target scaled by `2^((time_diff - interval * (height_diff + 1)) /
+/// half_life)`, in fixed point arithmetic.
+fn asert_bits(
+    parent_chain: ParentChainType,
+    anchor: &AsertAnchor,
+    half_life: u32,
+    parent: &L1HeaderInfo,
+) -> CompactTarget {
+    let params = parent_chain.params();
+    let interval = params.target_block_interval.as_secs() as i64;
+    let time_diff = i64::from(parent.header.time) - anchor.parent_time;
+    let height_diff = i64::from(parent.height) - i64::from(anchor.height);
+    let exponent = ((time_diff - interval * (height_diff + 1)) * 65536)
+        / i64::from(half_life);
+    // 2^exponent is 2^shifts * factor / 65536, with the factor approximated
+    // by a cubic in the fractional part of the exponent
+    let shifts = exponent >> 16;
+    let frac = u64::from(exponent as u16);
+    let factor = 65536
+        + ((195_766_423_245_049 * frac
+            + 971_821_376 * frac * frac
+            + 5_127 * frac * frac * frac
+            + (1 << 47))
+            >> 48);
+    // The anchor target is `mantissa * 2^exp`
+    let mut mantissa = u64::from(anchor.bits & 0x007f_ffff);
+    let mut exp = 8 * (i64::from(anchor.bits >> 24) - 3);
+    if exp < 0 {
+        mantissa >>= -exp;
+        exp = 0;
+    }
+    // The next target is `mantissa * 2^shift`, rounded down
+    let mantissa = mantissa * factor;
+    let shift = exp + shifts - 16;
+    let mut target = [0u8; 32];
+    if shift < 0 {
+        let value = u32::try_from(-shift)
+            .ok()
+            .and_then(|shift| mantissa.checked_shr(shift))
+            .unwrap_or(0);
+        target[..8].copy_from_slice(&value.to_le_bytes());
+    } else if 64 - i64::from(mantissa.leading_zeros()) + shift > 256 {
+        return CompactTarget::from_consensus(params.pow_limit_bits);
+    } else {
+        let value = (mantissa << (shift % 8)).to_le_bytes();
+        for (byte, value_byte) in
+            target[(shift / 8) as usize..].iter_mut().zip(value)
+        {
+            *byte = value_byte;
+        }
+    }
+    if target == [0; 32] {
+        target[0] = 1;
+    }
+    let target = Target::from_le_bytes(target);
+    if target > params.pow_limit() {
+        CompactTarget::from_consensus(params.pow_limit_bits)
+    } else {
+        target.to_compact_lossy()
+    }
+}
+
+/// Bits of the header at a retarget height under the Bitcoin difficulty
+/// algorithm: the parent's target scaled by the time taken by the retarget
+/// period, bounded to a factor of four, as computed by Bitcoin Core.
+/// `timespan` is the time between the first header of the period and the
+/// parent.
+fn retarget_bits(
+    parent_chain: ParentChainType,
+    interval: u32,
+    parent_bits: CompactTarget,
+    timespan: i64,
+) -> CompactTarget {
+    let params = parent_chain.params();
+    let target_timespan =
+        u64::from(interval) * params.target_block_interval.as_secs();
+    let timespan = timespan.clamp(
+        (target_timespan / 4) as i64,
+        target_timespan.saturating_mul(4) as i64,
+    ) as u64;
+    // target * timespan / target_timespan, in 64-bit limbs with a fifth
+    // limb for the product
+    let target = Target::from_compact(parent_bits).to_le_bytes();
+    let mut limbs = [0u64; 5];
+    let mut carry = 0u128;
+    for (limb, bytes) in limbs.iter_mut().zip(target.chunks_exact(8)) {
+        let bytes = bytes.try_into().expect("chunks are 8 bytes");
+        let product = u128::from(u64::from_le_bytes(bytes))
+            * u128::from(timespan)
+            + carry;
+        *limb = product as u64;
+        carry = product >> 64;
+    }
+    limbs[4] = carry as u64;
+    let mut remainder = 0u128;
+    for limb in limbs.iter_mut().rev() {
+        let dividend = (remainder << 64) | u128::from(*limb);
+        *limb = (dividend / u128::from(target_timespan)) as u64;
+        remainder = dividend % u128::from(target_timespan);
+    }
+    if limbs[4] != 0 {
+        return CompactTarget::from_consensus(params.pow_limit_bits);
+    }
+    let mut target = [0u8; 32];
+    for (bytes, limb) in target.chunks_exact_mut(8).zip(limbs) {
+        bytes.copy_from_slice(&limb.to_le_bytes());
+    }
+    let target = Target::from_le_bytes(target);
+    if target > params.pow_limit() {
+        CompactTarget::from_consensus(params.pow_limit_bits)
+    } else {
+        target.to_compact_lossy()
+    }
+}
+
+/// Checkpoints of Bitcoin, from Bitcoin Core
+const BITCOIN_CHECKPOINTS: [(u32, &str); 13] = [
+    (
+        11_111,
+        "0000000069e244f73d78e8fd29ba2fd2ed618bd6fa2ee92559f542fdb26e7c1d",
+    ),
+    (
+        33_333,
+        "000000002dd5588a74784eaa7ab0507a18ad16a236e7b1ce69f00d7ddfb5d0a6",
+    ),
+    (
+        74_000,
+        "0000000000573993a3c9e41ce34471c079dcf5f52a0e824a81e7f953b8661a20",
+    ),
+    (
+        105_000,
+        "00000000000291ce28027faea320c8d2b054b2e0fe44a773f3eefb151d6bdc97",
+    ),
+    (
+        134_444,
+        "00000000000005b12ffd4cd315cd34ffd4a594f430ac814c91184a0d42d2b0fe",
+    ),
+    (
+        168_000,
+        "000000000000099e61ea72015e79632f216fe6cb33d7899acb35b75c8303b763",
+    ),
+    (
+        193_000,
+        "000000000000059f452a5f7340de6682a977387c17010ff6e6c3bd83ca8b1317",
+    ),
+    (
+        210_000,
+        "000000000000048b95347e83192f69cf0366076336c639f9b7228e9ba171342e",
+    ),
+    (
+        216_116,
+        "00000000000001b4f4b433e81ee46494af945cf96014816a4e2370f11b23df4e",
+    ),
+    (
+        225_430,
+        "00000000000001c108384350f74090433e7fcf79a606b8e797f065b130575932",
+    ),
+    (
+        250_000,
+        "000000000000003887df1f29024b06fc2200b55f8af8f35453d7be294df2d214",
+    ),
+    (
+        279_000,
+        "0000000000000001ae8c72a0b0c301f67e3afca10e819efa9041e458e9bd7e40",
+    ),
+    (
+        295_000,
+        "00000000000000004d9b4ef50f0f9d686fd69db2e03af35a100370c64632a983",
+    ),
+];
+
+/// Checkpoints of Bitcoin Cash after its fork from Bitcoin, from Bitcoin
+/// Cash Node
+const BITCOIN_CASH_CHECKPOINTS: [(u32, &str); 1] = [(
+    478_559,
+    "000000000000000000651ef99cb9fcbe0dadde1d424bd9f15ff20136191a5eec",
+)];
+
+/// Hard-coded `(height, block hash)` checkpoints for a parent chain, ordered
+/// by height, starting with the genesis block. Any header stored at one of
+/// these heights must have the listed hash. Bitcoin Cash shares the
+/// checkpoints of Bitcoin before its fork. Test networks and registry
+/// chains are only checkpointed at genesis.
+pub fn checkpoints(
+    parent_chain: ParentChainType,
+) -> Vec<(u32, bitcoin::BlockHash)> {
+    let checkpoints: &[&[(u32, &str)]] = match parent_chain {
+        ParentChainType::BTC => &[&BITCOIN_CHECKPOINTS],
+        ParentChainType::BCH => {
+            &[&BITCOIN_CHECKPOINTS, &BITCOIN_CASH_CHECKPOINTS]
+        }
+        _ => &[],
+    };
+    std::iter::once((0, parent_chain.genesis_block_hash()))
+        .chain(checkpoints.iter().copied().flatten().map(
+            |(height, block_hash)| {
+                (*height, block_hash.parse().expect("valid checkpoint hash"))
+            },
+        ))
+        .collect()
+}
+
+/// Checkpoint that a parent chain's header chain is anchored by: the
+/// highest of its [`checkpoints`]
+pub fn anchor_checkpoint(
+    parent_chain: ParentChainType,
+) -> (u32, bitcoin::BlockHash) {
+    checkpoints(parent_chain)
+        .into_iter()
+        .max_by_key(|(height, _)| *height)
+        .unwrap_or((0, parent_chain.genesis_block_hash()))
+}
+
+/// Height that a parent chain's header chain is anchored at: that of its
+/// [`anchor_checkpoint`], or on chains that retarget every `interval`
+/// blocks, the start of the checkpoint's retarget period, so that every
+/// retarget can be computed from stored headers. An anchor below the
+/// checkpoint is authenticated by the checkpoint, which the first relay must
+/// reach.
+pub fn anchor_height(parent_chain: ParentChainType) -> u32 {
+    let (height, _) = anchor_checkpoint(parent_chain);
+    match parent_chain.params().difficulty_adjustment {
+        DifficultyAdjustment::Retarget { interval }
+            if interval <= MAX_HEADERS_PER_SYNC =>
+        {
+            height - height.checked_rem(interval).unwrap_or(0)
+        }
+        _ => height,
+    }
+}
+
+/// The node's clock, in seconds since the Unix epoch
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/// Decode the consensus-encoded headers of a relay
+pub fn decode_relay(
+    parent_chain: ParentChainType,
+    headers: &[Vec<u8>],
+) -> Result<Vec<L1Header>, Error> {
+    headers
+        .iter()
+        .map(|header| {
+            if header.len() != L1_HEADER_SIZE {
+                return Err(Error::InvalidHeaderEncoding { parent_chain });
+            }
+            bitcoin::consensus::deserialize(header)
+                .map_err(|_| Error::InvalidHeaderEncoding { parent_chain })
+        })
+        .collect()
+}
+
+/// Consensus-encode headers for a relay
+pub fn encode_relay(headers: &[L1Header]) -> Vec<Vec<u8>> {
+    headers.iter().map(bitcoin::consensus::serialize).collect()
+}
+
+#[derive(Clone)]
+pub struct L1HeaderChain {
+    /// Best chain block hash at each height, for each parent chain
+    best_chain: DatabaseUnique<
+        SerdeBincode<(ParentChainType, u32)>,
+        SerdeBincode<bitcoin::BlockHash>,
+    >,
+    /// All known headers for each parent chain, including forks
+    headers: DatabaseUnique<
+        SerdeBincode<(ParentChainType, bitcoin::BlockHash)>,
+        SerdeBincode<L1HeaderInfo>,
+    >,
+    /// Anchor of each parent chain's header chain
+    anchors: DatabaseUnique<SerdeBincode<ParentChainType>, SerdeBincode<L1Tip>>,
+    /// Best tip of each parent chain's header chain
+    tips: DatabaseUnique<SerdeBincode<ParentChainType>, SerdeBincode<L1Tip>>,
+    /// Relays connected at each sidechain block height, in order, used to
+    /// undo them when the block is disconnected
+    relay_undo:
+        DatabaseUnique<SerdeBincode<u32>, SerdeBincode<Vec<L1RelayUndo>>>,
+}
+
+impl L1HeaderChain {
+    pub const NUM_DBS: u32 = 5;
+
+    pub fn new(env: &sneed::Env) -> Result<Self, Error> {
+        let mut rwtxn = env.write_txn().map_err(EnvError::from)?;
+        let best_chain =
+            DatabaseUnique::create(env, &mut rwtxn, "l1_header_best_chain")
+                .map_err(EnvError::from)?;
+        let headers = DatabaseUnique::create(env, &mut rwtxn, "l1_headers")
+            .map_err(EnvError::from)?;
+        let anchors =
+            DatabaseUnique::create(env, &mut rwtxn, "l1_header_anchors")
+                .map_err(EnvError::from)?;
+        let tips = DatabaseUnique::create(env, &mut rwtxn, "l1_header_tips")
+            .map_err(EnvError::from)?;
+        let relay_undo =
+            DatabaseUnique::create(env, &mut rwtxn, "l1_header_relay_undo")
+                .map_err(EnvError::from)?;
+        rwtxn.commit().map_err(RwTxnError::from)?;
+        Ok(Self {
+            best_chain,
+            headers,
+            anchors,
+            tips,
+            relay_undo,
+        })
+    }
+
+    pub fn try_get_anchor(
+        &self,
+        rotxn: &RoTxn,
+        parent_chain: ParentChainType,
+    ) -> Result<Option<L1Tip>, Error> {
+        let anchor = self
+            .anchors
+            .try_get(rotxn, &parent_chain)
+            .map_err(DbError::from)?;
+        Ok(anchor)
+    }
+
+    pub fn try_get_tip(
+        &self,
+        rotxn: &RoTxn,
+        parent_chain: ParentChainType,
+    ) -> Result<Option<L1Tip>, Error> {
+        let tip = self
+            .tips
+            .try_get(rotxn, &parent_chain)
+            .map_err(DbError::from)?;
+        Ok(tip)
+    }
+
+    pub fn try_get_header_info(
+        &self,
+        rotxn: &RoTxn,
+        parent_chain: ParentChainType,
+        block_hash: bitcoin::BlockHash,
+    ) -> Result<Option<L1HeaderInfo>, Error> {
+        let header_info = self
+            .headers
+            .try_get(rotxn, &(parent_chain, block_hash))
+            .map_err(DbError::from)?;
+        Ok(header_info)
+    }
+
+    /// Get the best chain block hash at the specified height
+    pub fn try_get_best_chain_hash(
+        &self,
+        rotxn: &RoTxn,
+        parent_chain: ParentChainType,
+        height: u32,
+    ) -> Result<Option<bitcoin::BlockHash>, Error> {
+        let block_hash = self
+            .best_chain
+            .try_get(rotxn, &(parent_chain, height))
+            .map_err(DbError::from)?;
+        Ok(block_hash)
+    }
+
+    /// Get the height of a block if it is in the best header chain
+    pub fn try_get_best_chain_height(
+        &self,
+        rotxn: &RoTxn,
+        parent_chain: ParentChainType,
+        block_hash: bitcoin::BlockHash,
+    ) -> Result<Option<u32>, Error> {
+        let Some(header_info) =
+            self.try_get_header_info(rotxn, parent_chain, block_hash)?
+        else {
+            return Ok(None);
+        };
+        let best_chain_hash = self.try_get_best_chain_hash(
+            rotxn,
+            parent_chain,
+            header_info.height,
+        )?;
+        Ok((best_chain_hash == Some(block_hash)).then_some(header_info.height))
+    }
+
+    /// Number of confirmations for a block, i.e. its depth in the best header
+    /// chain. Blocks that are unknown or not in the best chain have zero
+    /// confirmations.
+    pub fn confirmations(
+        &self,
+        rotxn: &RoTxn,
+        parent_chain: ParentChainType,
+        block_hash: bitcoin::BlockHash,
+    ) -> Result<u32, Error> {
+        let Some(tip) = self.try_get_tip(rotxn, parent_chain)? else {
+            return Ok(0);
+        };
+        let Some(height) =
+            self.try_get_best_chain_height(rotxn, parent_chain, block_hash)?
+        else {
+            return Ok(0);
+        };
+        Ok(tip.height - height + 1)
+    }
+
+    /// Count the confirmations of a parent chain transaction, fetched in
+    /// `snapshot`, against the best header chain. Transactions that are not
+    /// in a block have zero confirmations.
+    pub fn tx_confirmations(
+        &self,
+        rotxn: &RoTxn,
+        parent_chain: ParentChainType,
+        snapshot: &L1Snapshot,
+        txid: &str,
+    ) -> Result<u32, Error> {
+        let tx_info = snapshot.get_transaction(txid)?;
+        let Some(block_hash) = tx_info.blockhash else {
+            return Ok(0);
+        };
+        let block_hash = block_hash
+            .parse()
+            .map_err(|_| parent_chain_rpc::Error::InvalidResponse)?;
+        self.confirmations(rotxn, parent_chain, block_hash)
+    }
+
+    fn check_checkpoint(
+        parent_chain: ParentChainType,
+        block_hash: bitcoin::BlockHash,
+        height: u32,
+    ) -> Result<(), Error> {
+        if let Some((_, checkpoint)) = checkpoints(parent_chain)
+            .into_iter()
+            .find(|(checkpoint_height, _)| *checkpoint_height == height)
+            && checkpoint != block_hash
+        {
+            return Err(Error::CheckpointMismatch {
+                parent_chain,
+                block_hash,
+                height,
+                checkpoint,
+            });
+        }
+        Ok(())
+    }
+
+    /// Check that a header meets its own target, and that the target is
+    /// within the chain's proof of work limit
+    fn check_pow(
+        parent_chain: ParentChainType,
+        header: &L1Header,
+    ) -> Result<bitcoin::BlockHash, Error> {
+        let params = parent_chain.params();
+        if !params.verifiable_headers() {
+            return Err(Error::UnsupportedParentChain(parent_chain));
+        }
+        let block_hash = header.block_hash();
+        let target = header.target();
+        if target > params.pow_limit() {
+            return Err(Error::InvalidDifficulty {
+                parent_chain,
+                block_hash,
+            });
+        }
+        header
+            .validate_pow(target)
+            .map_err(|_| Error::InsufficientWork {
+                parent_chain,
+                block_hash,
+            })
+    }
+
+    /// Check that a header's timestamp is after the median time past, and
+    /// at most [`MAX_FUTURE_BLOCK_TIME`] ahead of `now`
+    fn check_timestamp(
+        parent_chain: ParentChainType,
+        context: &HeaderContext,
+        header: &L1Header,
+        now: u64,
+    ) -> Result<(), Error> {
+        if header.time <= context.median_time_past {
+            return Err(Error::TimeTooOld {
+                parent_chain,
+                block_hash: header.block_hash(),
+                time: header.time,
+                median_time_past: context.median_time_past,
+            });
+        }
+        if u64::from(header.time) > now + MAX_FUTURE_BLOCK_TIME {
+            return Err(Error::TimeTooNew {
+                parent_chain,
+                block_hash: header.block_hash(),
+                time: header.time,
+            });
+        }
+        Ok(())
+    }
+
+    /// Check the difficulty transition from a parent header.
+    ///
+    /// Chains that retarget must use the bits computed by
+    /// [`retarget_bits`] at retarget heights, and keep the bits unchanged
+    /// in between. ASERT chains (Bitcoin Cash) must use the bits computed by
+    /// [`asert_bits`], and other chains that adjust difficulty every block
+    /// may ease the target by at most a factor of four per block. Testnet
+    /// headers more than two block intervals after their parent may use the
+    /// proof of work limit, except at retarget heights; on chains that
+    /// retarget, the next header returns to the bits of the last header
+    /// that did not. Such headers must not be ahead of `now` at all, so
+    /// that they cannot be mined faster than one per two block intervals.
+    fn check_difficulty_transition(
+        parent_chain: ParentChainType,
+        parent: &L1HeaderInfo,
+        context: &HeaderContext,
+        header: &L1Header,
+        now: u64,
+    ) -> Result<(), Error> {
+        let params = parent_chain.params();
+        let height = parent.height + 1;
+        let min_difficulty = params.network == ChainNetwork::Testnet
+            && u64::from(header.time)
+                > u64::from(parent.header.time)
+                    + 2 * params.target_block_interval.as_secs()
+            && u64::from(header.time) <= now;
+        let min_difficulty_bits =
+            CompactTarget::from_consensus(params.pow_limit_bits);
+        let valid = match params.difficulty_adjustment {
+            DifficultyAdjustment::Fixed => header.bits == parent.header.bits,
+            DifficultyAdjustment::Retarget { interval }
+                if height % interval == 0 =>
+            {
+                let period_start_time = context.period_start_time.ok_or(
+                    Error::UnknownAncestor {
+                        parent_chain,
+                        height: height - interval,
+                    },
+                )?;
+                let timespan = i64::from(parent.header.time)
+                    - i64::from(period_start_time);
+                header.bits
+                    == retarget_bits(
+                        parent_chain,
+                        interval,
+                        parent.header.bits,
+                        timespan,
+                    )
+            }
+            _ if min_difficulty && header.bits == min_difficulty_bits => true,
+            DifficultyAdjustment::Retarget { .. } => {
+                header.bits
+                    == context.last_regular_bits.unwrap_or(parent.header.bits)
+            }
+            DifficultyAdjustment::Asert { anchor, half_life }
+                if parent.height >= anchor.height =>
+            {
+                header.bits
+                    == asert_bits(parent_chain, &anchor, half_life, parent)
+            }
+            DifficultyAdjustment::Asert { .. }
+            | DifficultyAdjustment::PerBlock => {
+                let prev_work = parent.header.work();
+                let work = header.work();
+                prev_work <= work + work + work + work
+            }
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(Error::InvalidDifficulty {
+                parent_chain,
+                block_hash: header.block_hash(),
+            })
+        }
+    }
+
+    /// Ancestor of a known header, or of a header earlier in the relay
+    /// being validated, at `height`
+    fn relay_ancestor(
+        &self,
+        rotxn: &RoTxn,
+        parent_chain: ParentChainType,
+        relayed: &HashMap<bitcoin::BlockHash, L1HeaderInfo>,
+        header_info: &L1HeaderInfo,
+        height: u32,
+    ) -> Result<L1HeaderInfo, Error> {
+        let mut ancestor = header_info.clone();
+        while ancestor.height > height {
+            ancestor = self
+                .relay_header_info(
+                    rotxn,
+                    parent_chain,
+                    relayed,
+                    ancestor.header.prev_blockhash,
+                )?
+                .ok_or(Error::UnknownAncestor {
+                    parent_chain,
+                    height,
+                })?;
+        }
+        Ok(ancestor)
+    }
+
+    /// Context of a header extending `parent`, which is known or earlier in
+    /// the relay being validated. Near the anchor, the median time past is
+    /// taken over the headers from the anchor.
+    fn header_context(
+        &self,
+        rotxn: &RoTxn,
+        parent_chain: ParentChainType,
+        relayed: &HashMap<bitcoin::BlockHash, L1HeaderInfo>,
+        parent: &L1HeaderInfo,
+    ) -> Result<HeaderContext, Error> {
+        let params = parent_chain.params();
+        let mut times = vec![parent.header.time];
+        let mut prev_blockhash = parent.header.prev_blockhash;
+        while times.len() < MEDIAN_TIME_SPAN
+            && let Some(ancestor) = self.relay_header_info(
+                rotxn,
+                parent_chain,
+                relayed,
+                prev_blockhash,
+            )?
+        {
+            times.push(ancestor.header.time);
+            prev_blockhash = ancestor.header.prev_blockhash;
+        }
+        times.sort_unstable();
+        let mut context = HeaderContext {
+            median_time_past: times[times.len() / 2],
+            ..HeaderContext::default()
+        };
+        let DifficultyAdjustment::Retarget { interval } =
+            params.difficulty_adjustment
+        else {
+            return Ok(context);
+        };
+        let height = parent.height + 1;
+        if height % interval == 0 {
+            let period_start = self.relay_ancestor(
+                rotxn,
+                parent_chain,
+                relayed,
+                parent,
+                height - interval,
+            )?;
+            context.period_start_time = Some(period_start.header.time);
+        } else if params.network == ChainNetwork::Testnet {
+            let min_difficulty_bits =
+                CompactTarget::from_consensus(params.pow_limit_bits);
+            let mut last_regular = parent.clone();
+            while last_regular.height % interval != 0
+                && last_regular.header.bits == min_difficulty_bits
+                && let Some(prev) = self.relay_header_info(
+                    rotxn,
+                    parent_chain,
+                    relayed,
+                    last_regular.header.prev_blockhash,
+                )?
+            {
+                last_regular = prev;
+            }
+            context.last_regular_bits = Some(last_regular.header.bits);
+        }
+        Ok(context)
+    }
+
+    /// Check a header extending `parent` (see [`Self::header_context`])
+    /// against its proof of work, timestamp, difficulty and checkpoints.
+    /// Returns its header info.
+    fn check_header(
+        &self,
+        rotxn: &RoTxn,
+        parent_chain: ParentChainType,
+        relayed: &HashMap<bitcoin::BlockHash, L1HeaderInfo>,
+        parent: &L1HeaderInfo,
+        header: &L1Header,
+        now: u64,
+    ) -> Result<L1HeaderInfo, Error> {
+        let block_hash = Self::check_pow(parent_chain, header)?;
+        let context =
+            self.header_context(rotxn, parent_chain, relayed, parent)?;
+        let () = Self::check_timestamp(parent_chain, &context, header, now)?;
+        let () = Self::check_difficulty_transition(
+            parent_chain,
+            parent,
+            &context,
+            header,
+            now,
+        )?;
+        let height = parent.height + 1;
+        let () = Self::check_checkpoint(parent_chain, block_hash, height)?;
+        Ok(L1HeaderInfo {
+            header: *header,
+            height,
+            total_work: parent.total_work + header.work(),
+        })
+    }
+
+    /// Header info for the anchor of an empty header chain, at the parent
+    /// chain's [`anchor_height`]. An anchor at the [`anchor_checkpoint`]
+    /// must be the checkpoint.
+    fn anchor_info(
+        parent_chain: ParentChainType,
+        header: &L1Header,
+    ) -> Result<L1HeaderInfo, Error> {
+        let block_hash = Self::check_pow(parent_chain, header)?;
+        let height = anchor_height(parent_chain);
+        let () = Self::check_checkpoint(parent_chain, block_hash, height)?;
+        Ok(L1HeaderInfo {
+            header: *header,
+            height,
+            total_work: header.work(),
+        })
+    }
+
+    /// Anchor an empty header chain at its anchor checkpoint
+    fn anchor(
+        &self,
+        rwtxn: &mut RwTxn,
+        parent_chain: ParentChainType,
+        header: &L1Header,
+    ) -> Result<L1Tip, Error> {
+        if self.try_get_anchor(rwtxn, parent_chain)?.is_some() {
+            return Err(Error::AlreadyAnchored(parent_chain));
+        }
+        let header_info = Self::anchor_info(parent_chain, header)?;
+        let block_hash = header.block_hash();
+        let height = header_info.height;
+        let tip = L1Tip { block_hash, height };
+        self.headers
+            .put(rwtxn, &(parent_chain, block_hash), &header_info)
+            .map_err(DbError::from)?;
+        self.best_chain
+            .put(rwtxn, &(parent_chain, height), &block_hash)
+            .map_err(DbError::from)?;
+        self.anchors
+            .put(rwtxn, &parent_chain, &tip)
+            .map_err(DbError::from)?;
+        self.tips
+            .put(rwtxn, &parent_chain, &tip)
+            .map_err(DbError::from)?;
+        tracing::info!(
+            ?parent_chain,
+            %block_hash,
+            %height,
+            "Anchored L1 header chain"
+        );
+        Ok(tip)
+    }
+
+    /// Store a header that extends a known header. If the header has more
+    /// cumulative work than the current tip, the best chain is reorganized
+    /// to end at the new header.
+    /// Returns the new tip if the best chain changed.
+    fn connect_header(
+        &self,
+        rwtxn: &mut RwTxn,
+        parent_chain: ParentChainType,
+        header: &L1Header,
+    ) -> Result<Option<L1Tip>, Error> {
+        let block_hash = Self::check_pow(parent_chain, header)?;
+        if self
+            .try_get_header_info(rwtxn, parent_chain, block_hash)?
+            .is_some()
+        {
+            return Ok(None);
+        }
+        let Some(tip) = self.try_get_tip(rwtxn, parent_chain)? else {
+            return Err(Error::NotAnchored(parent_chain));
+        };
+        let parent = self
+            .try_get_header_info(rwtxn, parent_chain, header.prev_blockhash)?
+            .ok_or(Error::UnknownParent {
+                parent_chain,
+                block_hash,
+                prev_block_hash: header.prev_blockhash,
+            })?;
+        let header_info = self.check_header(
+            rwtxn,
+            parent_chain,
+            &HashMap::new(),
+            &parent,
+            header,
+            now(),
+        )?;
+        let height = header_info.height;
+        let tip_info = self
+            .try_get_header_info(rwtxn, parent_chain, tip.block_hash)?
+            .ok_or(Error::NotAnchored(parent_chain))?;
+        if header_info.total_work <= tip_info.total_work {
+            self.headers
+                .put(rwtxn, &(parent_chain, block_hash), &header_info)
+                .map_err(DbError::from)?;
+            return Ok(None);
+        }
+        // Find the fork point, collecting the new best chain above it
+        let mut new_best_chain = vec![(height, block_hash)];
+        let mut ancestor = parent;
+        while self.try_get_best_chain_hash(
+            rwtxn,
+            parent_chain,
+            ancestor.height,
+        )? != Some(ancestor.header.block_hash())
+        {
+            new_best_chain
+                .push((ancestor.height, ancestor.header.block_hash()));
+            ancestor = self
+                .try_get_header_info(
+                    rwtxn,
+                    parent_chain,
+                    ancestor.header.prev_blockhash,
+                )?
+                .ok_or(Error::NotAnchored(parent_chain))?;
+        }
+        let fork_height = ancestor.height;
+        let depth = tip.height - fork_height;
+        if depth > MAX_REORG_DEPTH {
+            return Err(Error::ReorgTooDeep {
+                parent_chain,
+                depth,
+            });
+        }
+        if depth > 0 {
+            tracing::info!(
+                ?parent_chain,
+                old_tip = %tip.block_hash,
+                new_tip = %block_hash,
+                %fork_height,
+                %depth,
+                "Reorganizing L1 header chain"
+            );
+        }
+        self.headers
+            .put(rwtxn, &(parent_chain, block_hash), &header_info)
+            .map_err(DbError::from)?;
+        for stale_height in (fork_height + 1)..=tip.height {
+            self.best_chain
+                .delete(rwtxn, &(parent_chain, stale_height))
+                .map_err(DbError::from)?;
+        }
+        for (best_height, best_hash) in new_best_chain {
+            self.best_chain
+                .put(rwtxn, &(parent_chain, best_height), &best_hash)
+                .map_err(DbError::from)?;
+        }
+        let new_tip = L1Tip { block_hash, height };
+        self.tips
+            .put(rwtxn, &parent_chain, &new_tip)
+            .map_err(DbError::from)?;
+        Ok(Some(new_tip))
+    }
+
+    /// Header info of a known header, or of a header earlier in the relay
+    /// being validated
+    fn relay_header_info(
+        &self,
+        rotxn: &RoTxn,
+        parent_chain: ParentChainType,
+        relayed: &HashMap<bitcoin::BlockHash, L1HeaderInfo>,
+        block_hash: bitcoin::BlockHash,
+    ) -> Result<Option<L1HeaderInfo>, Error> {
+        match relayed.get(&block_hash) {
+            Some(header_info) => Ok(Some(header_info.clone())),
+            None => self.try_get_header_info(rotxn, parent_chain, block_hash),
+        }
+    }
+
+    /// Check a relay of headers against the header chain, without
+    /// connecting it. The headers must form a chain of at most
+    /// [`MAX_HEADERS_PER_SYNC`] headers. If the header chain is empty, the
+    /// relay must start at the [`anchor_height`] and reach the
+    /// [`anchor_checkpoint`]; otherwise the first header must extend a known
+    /// header. Every header that is not yet known
+    /// is checked as by [`Self::connect_relay`], including the reorg depth
+    /// limit. Returns the number of headers that are not yet known.
+    pub fn validate_relay(
+        &self,
+        rotxn: &RoTxn,
+        parent_chain: ParentChainType,
+        headers: &[L1Header],
+    ) -> Result<usize, Error> {
+        if headers.is_empty() {
+            return Err(Error::EmptyRelay(parent_chain));
+        }
+        if headers.len() > MAX_HEADERS_PER_SYNC as usize {
+            return Err(Error::RelayTooLong {
+                parent_chain,
+                len: headers.len(),
+            });
+        }
+        for pair in headers.windows(2) {
+            if pair[1].prev_blockhash != pair[0].block_hash() {
+                return Err(Error::UnlinkedRelay {
+                    parent_chain,
+                    block_hash: pair[1].block_hash(),
+                });
+            }
+        }
+        // Headers of the relay that are not yet known
+        let mut relayed = HashMap::<bitcoin::BlockHash, L1HeaderInfo>::new();
+        let (mut tip, mut tip_work, headers) = match self
+            .try_get_tip(rotxn, parent_chain)?
+        {
+            Some(tip) => {
+                let tip_info = self
+                    .try_get_header_info(rotxn, parent_chain, tip.block_hash)?
+                    .ok_or(Error::NotAnchored(parent_chain))?;
+                (tip, tip_info.total_work, headers)
+            }
+            None => {
+                let (checkpoint_height, _) = anchor_checkpoint(parent_chain);
+                let anchor_height = anchor_height(parent_chain);
+                if headers.len() as u32 <= checkpoint_height - anchor_height {
+                    return Err(Error::AnchorRelayTooShort {
+                        parent_chain,
+                        checkpoint_height,
+                    });
+                }
+                let (anchor, rest) =
+                    headers.split_first().expect("relay is not empty");
+                let anchor_info = Self::anchor_info(parent_chain, anchor)?;
+                let tip = L1Tip {
+                    block_hash: anchor.block_hash(),
+                    height: anchor_info.height,
+                };
+                let tip_work = anchor_info.total_work;
+                relayed.insert(tip.block_hash, anchor_info);
+                (tip, tip_work, rest)
+            }
+        };
+        let now = now();
+        for header in headers {
+            let block_hash = Self::check_pow(parent_chain, header)?;
+            if self
+                .relay_header_info(rotxn, parent_chain, &relayed, block_hash)?
+                .is_some()
+            {
+                continue;
+            }
+            let parent = self
+                .relay_header_info(
+                    rotxn,
+                    parent_chain,
+                    &relayed,
+                    header.prev_blockhash,
+                )?
+                .ok_or(Error::UnknownParent {
+                    parent_chain,
+                    block_hash,
+                    prev_block_hash: header.prev_blockhash,
+                })?;
+            let header_info = self.check_header(
+                rotxn,
+                parent_chain,
+                &relayed,
+                &parent,
+                header,
+                now,
+            )?;
+            let height = header_info.height;
+            if header_info.total_work > tip_work {
+                // The relay is a chain, so once one of its headers becomes
+                // the tip, the rest extend it. Until then, the best chain is
+                // the stored one.
+                if header.prev_blockhash != tip.block_hash {
+                    let mut ancestor = parent;
+                    while self.try_get_best_chain_hash(
+                        rotxn,
+                        parent_chain,
+                        ancestor.height,
+                    )? != Some(ancestor.header.block_hash())
+                    {
+                        ancestor = self
+                            .relay_header_info(
+                                rotxn,
+                                parent_chain,
+                                &relayed,
+                                ancestor.header.prev_blockhash,
+                            )?
+                            .ok_or(Error::NotAnchored(parent_chain))?;
+                    }
+                    let depth = tip.height - ancestor.height;
+                    if depth > MAX_REORG_DEPTH {
+                        return Err(Error::ReorgTooDeep {
+                            parent_chain,
+                            depth,
+                        });
+                    }
+                }
+                tip = L1Tip { block_hash, height };
+                tip_work = header_info.total_work;
+            }
+            relayed.insert(block_hash, header_info);
+        }
+        Ok(relayed.len())
+    }
+
+    /// Connect a relay of headers (see [`Self::validate_relay`]), recording
+    /// undo data under the sidechain block `height`. Headers that are
+    /// already known are skipped. Returns the number of headers added.
+    pub fn connect_relay(
+        &self,
+        rwtxn: &mut RwTxn,
+        parent_chain: ParentChainType,
+        headers: &[L1Header],
+        height: u32,
+    ) -> Result<usize, Error> {
+        let _: usize = self.validate_relay(rwtxn, parent_chain, headers)?;
+        let prev_tip = self.try_get_tip(rwtxn, parent_chain)?;
+        let mut added = Vec::new();
+        let mut headers = headers.iter();
+        if prev_tip.is_none()
+            && let Some(anchor) = headers.next()
+        {
+            let anchor = self.anchor(rwtxn, parent_chain, anchor)?;
+            added.push(anchor.block_hash);
+        }
+        for header in headers {
+            let block_hash = header.block_hash();
+            if self
+                .try_get_header_info(rwtxn, parent_chain, block_hash)?
+                .is_some()
+            {
+                continue;
+            }
+            let _: Option<L1Tip> =
+                self.connect_header(rwtxn, parent_chain, header)?;
+            added.push(block_hash);
+        }
+        if added.is_empty() {
+            return Ok(0);
+        }
+        let added_count = added.len();
+        let mut undo = self
+            .relay_undo
+            .try_get(rwtxn, &height)
+            .map_err(DbError::from)?
+            .unwrap_or_default();
+        undo.push(L1RelayUndo {
+            parent_chain,
+            prev_tip,
+            added,
+        });
+        self.relay_undo
+            .put(rwtxn, &height, &undo)
+            .map_err(DbError::from)?;
+        Ok(added_count)
+    }
+
+    /// Undo the relays connected at the sidechain block `height`, last to
+    /// first
+    pub fn disconnect_relays(
+        &self,
+        rwtxn: &mut RwTxn,
+        height: u32,
+    ) -> Result<(), Error> {
+        let Some(undo) = self
+            .relay_undo
+            .try_get(rwtxn, &height)
+            .map_err(DbError::from)?
+        else {
+            return Ok(());
+        };
+        self.relay_undo
+            .delete(rwtxn, &height)
+            .map_err(DbError::from)?;
+        for relay_undo in undo.iter().rev() {
+            let () = self.revert_relay(rwtxn, relay_undo)?;
+        }
+        Ok(())
+    }
+
+    fn revert_relay(
+        &self,
+        rwtxn: &mut RwTxn,
+        undo: &L1RelayUndo,
+    ) -> Result<(), Error> {
+        let parent_chain = undo.parent_chain;
+        let tip = self
+            .try_get_tip(rwtxn, parent_chain)?
+            .ok_or(Error::NotAnchored(parent_chain))?;
+        match undo.prev_tip {
+            None => {
+                let anchor = self
+                    .try_get_anchor(rwtxn, parent_chain)?
+                    .ok_or(Error::NotAnchored(parent_chain))?;
+                for height in anchor.height..=tip.height {
+                    self.best_chain
+                        .delete(rwtxn, &(parent_chain, height))
+                        .map_err(DbError::from)?;
+                }
+                self.anchors
+                    .delete(rwtxn, &parent_chain)
+                    .map_err(DbError::from)?;
+                self.tips
+                    .delete(rwtxn, &parent_chain)
+                    .map_err(DbError::from)?;
+            }
+            Some(prev_tip) => {
+                for height in (prev_tip.height + 1)..=tip.height {
+                    self.best_chain
+                        .delete(rwtxn, &(parent_chain, height))
+                        .map_err(DbError::from)?;
+                }
+                // Restore the previous best chain down to the fork point
+                let mut header_info = self
+                    .try_get_header_info(
+                        rwtxn,
+                        parent_chain,
+                        prev_tip.block_hash,
+                    )?
+                    .ok_or(Error::NotAnchored(parent_chain))?;
+                loop {
+                    let block_hash = header_info.header.block_hash();
+                    if self.try_get_best_chain_hash(
+                        rwtxn,
+                        parent_chain,
+                        header_info.height,
+                    )? == Some(block_hash)
+                    {
+                        break;
+                    }
+                    self.best_chain
+                        .put(
+                            rwtxn,
+                            &(parent_chain, header_info.height),
+                            &block_hash,
+                        )
+                        .map_err(DbError::from)?;
+                    header_info = self
+                        .try_get_header_info(
+                            rwtxn,
+                            parent_chain,
+                            header_info.header.prev_blockhash,
+                        )?
+                        .ok_or(Error::NotAnchored(parent_chain))?;
+                }
+                self.tips
+                    .put(rwtxn, &parent_chain, &prev_tip)
+                    .map_err(DbError::from)?;
+            }
+        }
+        for block_hash in &undo.added {
+            self.headers
+                .delete(rwtxn, &(parent_chain, *block_hash))
+                .map_err(DbError::from)?;
+        }
+        Ok(())
+    }
+
+    /// The end of the best header chain that a relay is fetched against
+    /// (see [`RelayBase::fetch_relay`]), read so that no transaction is held
+    /// while the backend is queried
+    pub fn relay_base(
+        &self,
+        rotxn: &RoTxn,
+        parent_chain: ParentChainType,
+    ) -> Result<RelayBase, Error> {
+        let Some(tip) = self.try_get_tip(rotxn, parent_chain)? else {
+            return Ok(RelayBase {
+                parent_chain,
+                tip: None,
+                best_chain: HashMap::new(),
+            });
+        };
+        let anchor = self
+            .try_get_anchor(rotxn, parent_chain)?
+            .ok_or(Error::NotAnchored(parent_chain))?;
+        let min_height = anchor
+            .height
+            .max(tip.height.saturating_sub(MAX_REORG_DEPTH));
+        let mut best_chain = HashMap::new();
+        for height in min_height..=tip.height {
+            if let Some(block_hash) =
+                self.try_get_best_chain_hash(rotxn, parent_chain, height)?
+            {
+                best_chain.insert(height, block_hash);
+            }
+        }
+        Ok(RelayBase {
+            parent_chain,
+            tip: Some((tip, anchor.height)),
+            best_chain,
+        })
+    }
+}
+
+/// The end of a best header chain, down to [`MAX_REORG_DEPTH`] blocks below
+/// the tip, read by [`L1HeaderChain::relay_base`]
+#[derive(Clone, Debug)]
+pub struct RelayBase {
+    parent_chain: ParentChainType,
+    /// Tip and anchor height, or `None` if the header chain is empty
+    tip: Option<(L1Tip, u32)>,
+    /// Best chain block hashes by height
+    best_chain: HashMap<u32, bitcoin::BlockHash>,
+}
+
+impl RelayBase {
+    /// Fetch the headers that `backend` has beyond the best header chain, to
+    /// be relayed in a [`crate::types::TxData::L1Headers`] transaction. An
+    /// empty header chain is relayed from its [`anchor_height`]. At most
+    /// [`MAX_HEADERS_PER_SYNC`] headers are fetched; none are returned if
+    /// the header chain is up to date with the backend.
+    pub async fn fetch_relay(
+        &self,
+        backend: &dyn L1Backend,
+    ) -> Result<Vec<L1Header>, Error> {
+        let parent_chain = self.parent_chain;
+        let backend_height = backend.get_block_height().await?;
+        let start_height = match self.tip {
+            None => anchor_height(parent_chain),
+            // A backend this far behind has nothing to relay
+            Some((tip, _))
+                if tip.height.saturating_sub(backend_height)
+                    > MAX_REORG_DEPTH =>
+            {
+                return Ok(Vec::new());
+            }
+            Some((tip, anchor_height)) => {
+                // Walk back to the highest block that the backend agrees on
+                let mut height = tip.height.min(backend_height);
+                loop {
+                    let local_hash = self.best_chain.get(&height).copied();
+                    let backend_hash = backend.get_block_hash(height).await?;
+                    if local_hash == Some(backend_hash) {
+                        break height + 1;
+                    }
+                    if height == anchor_height
+                        || tip.height - height >= MAX_REORG_DEPTH
+                    {
+                        return Err(Error::NoCommonAncestor { parent_chain });
+                    }
+                    height -= 1;
+                }
+            }
+        };
+        if start_height > backend_height {
+            return Ok(Vec::new());
+        }
+        let end_height =
+            backend_height.min(start_height + MAX_HEADERS_PER_SYNC - 1);
+        let headers = backend
+            .get_best_chain_headers(start_height..=end_height)
+            .await?;
+        if headers.len() != (end_height - start_height + 1) as usize {
+            return Err(parent_chain_rpc::Error::InvalidResponse.into());
+        }
+        Ok(headers.into_iter().map(|(_, header)| header).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{
+        TxMerkleNode,
+        block::{Header, Version},
+        hashes::Hash as _,
+        pow::CompactTarget,
+    };
+
+    use super::{
+        Error, HeaderContext, L1HeaderChain, L1HeaderInfo, L1Tip,
+        MAX_FUTURE_BLOCK_TIME, anchor_checkpoint, anchor_height,
+    };
+    use crate::{
+        chain_registry::{BCH_TESTNET4_ID, builtin_network},
+        types::ParentChainType,
+    };
+
+    /// Node clock of the tests, after the times of the test headers
+    const NOW: u64 = 1_800_000_000;
+
+    fn mine_header(prev_blockhash: bitcoin::BlockHash, bits: u32) -> Header {
+        mine_header_at(prev_blockhash, bits, 1_700_000_000)
+    }
+
+    fn mine_header_at(
+        prev_blockhash: bitcoin::BlockHash,
+        bits: u32,
+        time: u32,
+    ) -> Header {
+        let mut header = Header {
+            version: Version::TWO,
+            prev_blockhash,
+            merkle_root: TxMerkleNode::all_zeros(),
+            time,
+            bits: CompactTarget::from_consensus(bits),
+            nonce: 0,
+        };
+        while header.validate_pow(header.target()).is_err() {
+            header.nonce += 1;
+        }
+        header
+    }
+
+    /// Regtest headers mined on top of `prev`, with distinct times so that
+    /// forks of the same length have different hashes
+    fn mine_chain(prev: &Header, count: u32, time: u32) -> Vec<Header> {
+        let mut headers: Vec<Header> = Vec::new();
+        for i in 0..count {
+            let prev_blockhash =
+                headers.last().map_or(prev.block_hash(), Header::block_hash);
+            headers.push(mine_header_at(prev_blockhash, 0x207fffff, time + i));
+        }
+        headers
+    }
+
+    fn setup(name: &str) -> (std::path::PathBuf, sneed::Env, L1HeaderChain) {
+        let path = std::env::temp_dir().join(format!(
+            "coinshift_l1_headers_test_{name}_{}",
+            std::process::id()
+        ));
+        drop(std::fs::remove_dir_all(&path)); // best-effort cleanup
+        std::fs::create_dir_all(&path).unwrap();
+        let mut env_open_opts = heed::EnvOpenOptions::new();
+        env_open_opts
+            .map_size(64 * 1024 * 1024)
+            .max_dbs(L1HeaderChain::NUM_DBS);
+        let env = unsafe { sneed::Env::open(&env_open_opts, &path) }.unwrap();
+        let l1_headers = L1HeaderChain::new(&env).unwrap();
+        (path, env, l1_headers)
+    }
+
+    #[test]
+    fn check_pow_accepts_mined_header() {
+        let header = mine_header(bitcoin::BlockHash::all_zeros(), 0x207fffff);
+        let block_hash =
+            L1HeaderChain::check_pow(ParentChainType::Regtest, &header)
+                .unwrap();
+        assert_eq!(block_hash, header.block_hash());
+    }
+
+    #[test]
+    fn check_pow_rejects_ltc() {
+        let header = mine_header(bitcoin::BlockHash::all_zeros(), 0x207fffff);
+        assert!(matches!(
+            L1HeaderChain::check_pow(ParentChainType::LTC, &header),
+            Err(Error::UnsupportedParentChain(ParentChainType::LTC))
+        ));
+    }
+
+    #[test]
+    fn check_pow_rejects_signet() {
+        // Signet blocks are signed, so meeting the target proves nothing
+        let header = mine_header(bitcoin::BlockHash::all_zeros(), 0x1e0377ae);
+        assert!(matches!(
+            L1HeaderChain::check_pow(ParentChainType::Signet, &header),
+            Err(Error::UnsupportedParentChain(ParentChainType::Signet))
+        ));
+    }
+
+    #[test]
+    fn bitcoin_is_anchored_at_a_retarget_period() {
+        let (height, _) = anchor_checkpoint(ParentChainType::BTC);
+        assert_eq!(height, 295_000);
+        assert_eq!(anchor_height(ParentChainType::BTC), 294_336);
+        assert_eq!(anchor_height(ParentChainType::Regtest), 0);
+        // Bitcoin Cash no longer retargets, so it is anchored at its own
+        // checkpoint after the fork
+        assert_eq!(anchor_height(ParentChainType::BCH), 478_559);
+    }
+
+    #[test]
+    fn check_checkpoint_genesis() {
+        let genesis =
+            bitcoin::constants::genesis_block(bitcoin::Network::Regtest);
+        assert!(
+            L1HeaderChain::check_checkpoint(
+                ParentChainType::Regtest,
+                genesis.block_hash(),
+                0,
+            )
+            .is_ok()
+        );
+        let header = mine_header(bitcoin::BlockHash::all_zeros(), 0x207fffff);
+        assert!(matches!(
+            L1HeaderChain::check_checkpoint(
+                ParentChainType::Regtest,
+                header.block_hash(),
+                0,
+            ),
+            Err(Error::CheckpointMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn difficulty_must_not_change_without_retargeting() {
+        let parent = mine_header(bitcoin::BlockHash::all_zeros(), 0x207fffff);
+        let parent = L1HeaderInfo {
+            header: parent,
+            height: 1,
+            total_work: parent.work(),
+        };
+        let same_bits = mine_header(parent.header.block_hash(), 0x207fffff);
+        assert!(
+            L1HeaderChain::check_difficulty_transition(
+                ParentChainType::Regtest,
+                &parent,
+                &HeaderContext::default(),
+                &same_bits,
+                NOW,
+            )
+            .is_ok()
+        );
+        let other_bits = mine_header(parent.header.block_hash(), 0x2070ffff);
+        assert!(matches!(
+            L1HeaderChain::check_difficulty_transition(
+                ParentChainType::Regtest,
+                &parent,
+                &HeaderContext::default(),
+                &other_bits,
+                NOW,
+            ),
+            Err(Error::InvalidDifficulty { .. })
+        ));
+    }
+
+    #[test]
+    fn timestamps_must_follow_median_time_past() {
+        let chain = ParentChainType::Regtest;
+        let context = HeaderContext {
+            median_time_past: 1_700_000_000,
+            ..HeaderContext::default()
+        };
+        let now = 1_700_000_600;
+        let header = |time| {
+            mine_header_at(bitcoin::BlockHash::all_zeros(), 0x207fffff, time)
+        };
+        assert!(matches!(
+            L1HeaderChain::check_timestamp(
+                chain,
+                &context,
+                &header(1_700_000_000),
+                now,
+            ),
+            Err(Error::TimeTooOld { .. })
+        ));
+        assert!(
+            L1HeaderChain::check_timestamp(
+                chain,
+                &context,
+                &header(1_700_000_001),
+                now,
+            )
+            .is_ok()
+        );
+        let max_time = now + MAX_FUTURE_BLOCK_TIME;
+        assert!(
+            L1HeaderChain::check_timestamp(
+                chain,
+                &context,
+                &header(max_time as u32),
+                now,
+            )
+            .is_ok()
+        );
+        assert!(matches!(
+            L1HeaderChain::check_timestamp(
+                chain,
+                &context,
+                &header(max_time as u32 + 1),
+                now,
+            ),
+            Err(Error::TimeTooNew { .. })
+        ));
+    }
+
+    #[test]
+    fn bitcoin_difficulty_must_follow_retarget() {
+        let chain = ParentChainType::BTC;
+        // Unmined headers; only the bits are checked
+        let header = |time: u32, bits: u32| Header {
+            version: Version::TWO,
+            prev_blockhash: bitcoin::BlockHash::all_zeros(),
+            merkle_root: TxMerkleNode::all_zeros(),
+            time,
+            bits: CompactTarget::from_consensus(bits),
+            nonce: 0,
+        };
+        // Retargets from Bitcoin Core's tests
+        for (period_start_time, height, time, bits, next_bits) in [
+            (1_261_130_161, 32_255, 1_262_152_739, 0x1d00ffff, 0x1d00d86a),
+            // Limited to a quarter of the target
+            (1_279_008_237, 68_543, 1_279_297_671, 0x1c05a3f4, 0x1c0168fd),
+        ] {
+            let parent_header = header(time, bits);
+            let parent = L1HeaderInfo {
+                header: parent_header,
+                height,
+                total_work: parent_header.work(),
+            };
+            let context = HeaderContext {
+                period_start_time: Some(period_start_time),
+                ..HeaderContext::default()
+            };
+            let check = |bits| {
+                L1HeaderChain::check_difficulty_transition(
+                    chain,
+                    &parent,
+                    &context,
+                    &header(time + 600, bits),
+                    NOW,
+                )
+            };
+            assert!(check(next_bits).is_ok());
+            assert!(matches!(
+                check(bits),
+                Err(Error::InvalidDifficulty { .. })
+            ));
+            // Between retargets, the bits must not change
+            let parent = L1HeaderInfo {
+                height: height + 1,
+                ..parent
+            };
+            assert!(
+                L1HeaderChain::check_difficulty_transition(
+                    chain,
+                    &parent,
+                    &HeaderContext::default(),
+                    &header(time + 600, bits),
+                    NOW,
+                )
+                .is_ok()
+            );
+            assert!(matches!(
+                L1HeaderChain::check_difficulty_transition(
+                    chain,
+                    &parent,
+                    &HeaderContext::default(),
+                    &header(time + 600, next_bits),
+                    NOW,
+                ),
+                Err(Error::InvalidDifficulty { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn bch_difficulty_must_follow_asert() {
+        let chain = builtin_network(BCH_TESTNET4_ID);
+        // Unmined headers; only the bits are checked
+        let header = |time: u32, bits: u32| Header {
+            version: Version::TWO,
+            prev_blockhash: bitcoin::BlockHash::all_zeros(),
+            merkle_root: TxMerkleNode::all_zeros(),
+            time,
+            bits: CompactTarget::from_consensus(bits),
+            nonce: 0,
+        };
+        // An hour ahead of schedule at height 20000, so the target halves
+        let time = 1_605_451_779 + 600 * (20_000 - 16_844 + 1) - 3_600;
+        let parent_header = header(time, 0x1d00ffff);
+        let parent = L1HeaderInfo {
+            header: parent_header,
+            height: 20_000,
+            total_work: parent_header.work(),
+        };
+        let context = HeaderContext::default();
+        let check = |header: &Header| {
+            L1HeaderChain::check_difficulty_transition(
+                chain, &parent, &context, header, NOW,
+            )
+        };
+        assert!(check(&header(time + 600, 0x1c7fff80)).is_ok());
+        assert!(matches!(
+            check(&header(time + 600, 0x1d00ffff)),
+            Err(Error::InvalidDifficulty { .. })
+        ));
+        assert!(matches!(
+            check(&header(time + 600, 0x1c3fffc0)),
+            Err(Error::InvalidDifficulty { .. })
+        ));
+        // Testnet minimum difficulty blocks, after 20 minutes
+        assert!(check(&header(time + 1_201, 0x1d00ffff)).is_ok());
+        assert!(matches!(
+            check(&header(time + 1_200, 0x1d00ffff)),
+            Err(Error::InvalidDifficulty { .. })
+        ));
+        // ...that have passed by the node's clock
+        assert!(matches!(
+            L1HeaderChain::check_difficulty_transition(
+                chain,
+                &parent,
+                &context,
+                &header(time + 1_201, 0x1d00ffff),
+                u64::from(time) + 1_200,
+            ),
+            Err(Error::InvalidDifficulty { .. })
+        ));
+        // 1.5 hours ahead of schedule
+        let parent = L1HeaderInfo {
+            header: header(time - 1_800, 0x1c7fff80),
+            ..parent
+        };
+        assert!(
+            L1HeaderChain::check_difficulty_transition(
+                chain,
+                &parent,
+                &context,
+                &header(time, 0x1c5a8025),
+                NOW,
+            )
+            .is_ok()
+        );
+        // Before the ASERT anchor, the target may ease by at most a factor
+        // of four per block
+        let parent = L1HeaderInfo {
+            header: header(time - 1_800, 0x1c1fffe0),
+            height: 100,
+            ..parent
+        };
+        for (bits, valid) in
+            [(0x1c3fffc0, true), (0x1c07fff8, true), (0x1d00ffff, false)]
+        {
+            let valid_transition = L1HeaderChain::check_difficulty_transition(
+                chain,
+                &parent,
+                &context,
+                &header(time - 1_200, bits),
+                NOW,
+            )
+            .is_ok();
+            assert_eq!(valid_transition, valid);
+        }
+    }
+
+    #[test]
+    fn relay_must_start_at_anchor_checkpoint() {
+        let (path, env, l1_headers) = setup("anchor");
+        let chain = ParentChainType::Regtest;
+        // A self-mined header cannot anchor the header chain
+        let forged = mine_header(bitcoin::BlockHash::all_zeros(), 0x207fffff);
+        let rotxn = env.read_txn().unwrap();
+        assert!(matches!(
+            l1_headers.validate_relay(&rotxn, chain, &[forged]),
+            Err(Error::CheckpointMismatch { .. })
+        ));
+        let genesis =
+            bitcoin::constants::genesis_block(bitcoin::Network::Regtest).header;
+        let headers =
+            [&[genesis][..], &mine_chain(&genesis, 2, genesis.time + 1)]
+                .concat();
+        assert_eq!(
+            l1_headers.validate_relay(&rotxn, chain, &headers).unwrap(),
+            3
+        );
+        // Relayed headers must form a chain
+        let unlinked = [genesis, headers[2]];
+        assert!(matches!(
+            l1_headers.validate_relay(&rotxn, chain, &unlinked),
+            Err(Error::UnlinkedRelay { .. })
+        ));
+        drop(rotxn);
+        drop(std::fs::remove_dir_all(&path)); // best-effort cleanup
+    }
+
+    #[test]
+    fn disconnecting_relays_restores_the_header_chain() {
+        let (path, env, l1_headers) = setup("disconnect");
+        let chain = ParentChainType::Regtest;
+        let genesis =
+            bitcoin::constants::genesis_block(bitcoin::Network::Regtest).header;
+        let main_chain = mine_chain(&genesis, 3, genesis.time + 1);
+        let fork = mine_chain(&main_chain[0], 3, genesis.time + 1_000);
+
+        let mut rwtxn = env.write_txn().unwrap();
+        let relay = [&[genesis][..], &main_chain].concat();
+        assert_eq!(
+            l1_headers
+                .connect_relay(&mut rwtxn, chain, &relay, 1)
+                .unwrap(),
+            4
+        );
+        let main_tip = L1Tip {
+            block_hash: main_chain[2].block_hash(),
+            height: 3,
+        };
+        assert_eq!(
+            l1_headers.try_get_tip(&rwtxn, chain).unwrap(),
+            Some(main_tip)
+        );
+        // A longer fork reorgs the best chain
+        let fork_relay = [&main_chain[..1], &fork].concat();
+        assert_eq!(
+            l1_headers
+                .connect_relay(&mut rwtxn, chain, &fork_relay, 2)
+                .unwrap(),
+            3
+        );
+        assert_eq!(
+            l1_headers.try_get_tip(&rwtxn, chain).unwrap(),
+            Some(L1Tip {
+                block_hash: fork[2].block_hash(),
+                height: 4,
+            })
+        );
+        assert_eq!(
+            l1_headers
+                .confirmations(&rwtxn, chain, main_chain[2].block_hash())
+                .unwrap(),
+            0
+        );
+
+        l1_headers.disconnect_relays(&mut rwtxn, 2).unwrap();
+        assert_eq!(
+            l1_headers.try_get_tip(&rwtxn, chain).unwrap(),
+            Some(main_tip)
+        );
+        assert_eq!(
+            l1_headers
+                .confirmations(&rwtxn, chain, main_chain[1].block_hash())
+                .unwrap(),
+            2
+        );
+        assert_eq!(
+            l1_headers
+                .try_get_best_chain_hash(&rwtxn, chain, 4)
+                .unwrap(),
+            None
+        );
+        assert!(
+            l1_headers
+                .try_get_header_info(&rwtxn, chain, fork[0].block_hash())
+                .unwrap()
+                .is_none()
+        );
+
+        l1_headers.disconnect_relays(&mut rwtxn, 1).unwrap();
+        assert_eq!(l1_headers.try_get_tip(&rwtxn, chain).unwrap(), None);
+        assert_eq!(l1_headers.try_get_anchor(&rwtxn, chain).unwrap(), None);
+        assert_eq!(
+            l1_headers
+                .try_get_best_chain_hash(&rwtxn, chain, 0)
+                .unwrap(),
+            None
+        );
+        drop(rwtxn);
+        drop(std::fs::remove_dir_all(&path)); // best-effort cleanup
+    }
+}
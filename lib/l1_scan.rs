@@ -0,0 +1,678 @@
+//! Incremental scans of swap parent chains for swap payments
+//!
+//! Rather than looking up the address history of every pending swap on each
+//! 2WPD connect, the node keeps a scan cursor (the last L1 block scanned) for
+//! each [`ParentChainType`], and caches the L1 transactions paying watched
+//! swap scripts. Both are node-local and stored in the node's database, so
+//! scans resume from the cursor after a restart. Blocks are only scanned up
+//! to the tip of the consensus [`L1HeaderChain`].
+//!
+//! A script's address history is only looked up the first time it is
+//! watched. After that, only blocks above the cursor on the best header
+//! chain are fetched. Backends that cannot serve blocks (Electrum servers)
+//! have the history of every watched script looked up when new blocks
+//! arrive instead.
+
+use std::collections::{HashMap, HashSet};
+
+use bitcoin::{BlockHash, ScriptBuf, TxOut, Txid};
+use heed::types::SerdeBincode;
+use serde::{Deserialize, Serialize};
+use sneed::{
+    DatabaseUnique, EnvError, RoTxn, RwTxn, db::error::Error as DbError,
+    rwtxn::Error as RwTxnError,
+};
+
+use crate::{
+    l1_backend::{L1Backend, snapshot::L1Snapshot},
+    l1_headers::{self, L1HeaderChain, L1Tip},
+    parent_chain_rpc::{self, ScriptPubKey, TransactionInfo, Vout},
+    types::ParentChainType,
+};
+
+/// Maximum number of blocks fetched from a parent chain backend per scan
+pub const MAX_BLOCKS_PER_SCAN: u32 = 144;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Db(#[from] DbError),
+    #[error("Database env error")]
+    DbEnv(#[from] EnvError),
+    #[error("Database write error")]
+    DbWrite(#[from] RwTxnError),
+    #[error(transparent)]
+    L1Headers(#[from] l1_headers::Error),
+    #[error(transparent)]
+    ParentChainRpc(#[from] parent_chain_rpc::Error),
+}
+
+/// A cached L1 transaction
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct L1ScanTx {
+    /// Block containing the transaction, if it was confirmed when cached
+    pub block_hash: Option<BlockHash>,
+    pub outputs: Vec<TxOut>,
+}
+
+impl L1ScanTx {
+    fn to_transaction_info(&self, txid: &Txid) -> TransactionInfo {
+        TransactionInfo {
+            txid: txid.to_string(),
+            confirmations: 0,
+            blockhash: self.block_hash.map(|block_hash| block_hash.to_string()),
+            blockheight: None,
+            vout: self
+                .outputs
+                .iter()
+                .map(|output| Vout {
+                    value: output.value,
+                    script_pub_key: ScriptPubKey {
+                        hex: output.script_pubkey.clone(),
+                        address: None,
+                        addresses: None,
+                    },
+                })
+                .collect(),
+            vin: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct L1ScanCache {
+    /// Last block scanned on each parent chain's best header chain
+    cursors: DatabaseUnique<SerdeBincode<ParentChainType>, SerdeBincode<L1Tip>>,
+    /// IDs of cached transactions paying each watched script. A script is
+    /// present once its address history has been looked up.
+    script_txids: DatabaseUnique<
+        SerdeBincode<(ParentChainType, ScriptBuf)>,
+        SerdeBincode<Vec<Txid>>,
+    >,
+    /// Cached transactions paying watched scripts
+    transactions: DatabaseUnique<
+        SerdeBincode<(ParentChainType, Txid)>,
+        SerdeBincode<L1ScanTx>,
+    >,
+}
+
+impl L1ScanCache {
+    pub const NUM_DBS: u32 = 3;
+
+    pub fn new(env: &sneed::Env) -> Result<Self, Error> {
+        let mut rwtxn = env.write_txn().map_err(EnvError::from)?;
+        let cursors =
+            DatabaseUnique::create(env, &mut rwtxn, "l1_scan_cursors")
+                .map_err(EnvError::from)?;
+        let script_txids =
+            DatabaseUnique::create(env, &mut rwtxn, "l1_scan_script_txids")
+                .map_err(EnvError::from)?;
+        let transactions =
+            DatabaseUnique::create(env, &mut rwtxn, "l1_scan_transactions")
+                .map_err(EnvError::from)?;
+        rwtxn.commit().map_err(RwTxnError::from)?;
+        Ok(Self {
+            cursors,
+            script_txids,
+            transactions,
+        })
+    }
+
+    pub fn try_get_cursor(
+        &self,
+        rotxn: &RoTxn,
+        parent_chain: ParentChainType,
+    ) -> Result<Option<L1Tip>, Error> {
+        let cursor = self
+            .cursors
+            .try_get(rotxn, &parent_chain)
+            .map_err(DbError::from)?;
+        Ok(cursor)
+    }
+
+    /// Cached transactions paying a script
+    pub fn script_transactions(
+        &self,
+        rotxn: &RoTxn,
+        parent_chain: ParentChainType,
+        script: &ScriptBuf,
+    ) -> Result<Vec<TransactionInfo>, Error> {
+        let txids = self
+            .script_txids
+            .try_get(rotxn, &(parent_chain, script.clone()))
+            .map_err(DbError::from)?
+            .unwrap_or_default();
+        let mut txs = Vec::with_capacity(txids.len());
+        for txid in txids {
+            if let Some(tx) = self
+                .transactions
+                .try_get(rotxn, &(parent_chain, txid))
+                .map_err(DbError::from)?
+            {
+                txs.push(tx.to_transaction_info(&txid));
+            }
+        }
+        Ok(txs)
+    }
+
+    /// Cache a transaction, and index it under each of `scripts` that it
+    /// pays
+    fn put_transaction(
+        &self,
+        rwtxn: &mut RwTxn,
+        parent_chain: ParentChainType,
+        tx: &TransactionInfo,
+        scripts: &HashSet<&ScriptBuf>,
+    ) -> Result<(), Error> {
+        let txid: Txid = tx
+            .txid
+            .parse()
+            .map_err(|_| parent_chain_rpc::Error::InvalidResponse)?;
+        let block_hash = match tx.blockhash.as_deref() {
+            Some(block_hash) => Some(
+                block_hash
+                    .parse()
+                    .map_err(|_| parent_chain_rpc::Error::InvalidResponse)?,
+            ),
+            None => None,
+        };
+        let scan_tx = L1ScanTx {
+            block_hash,
+            outputs: tx
+                .vout
+                .iter()
+                .map(|vout| TxOut {
+                    value: vout.value,
+                    script_pubkey: vout.script_pub_key.hex.clone(),
+                })
+                .collect(),
+        };
+        self.transactions
+            .put(rwtxn, &(parent_chain, txid), &scan_tx)
+            .map_err(DbError::from)?;
+        for output in &scan_tx.outputs {
+            if !scripts.contains(&output.script_pubkey) {
+                continue;
+            }
+            let key = (parent_chain, output.script_pubkey.clone());
+            let mut txids = self
+                .script_txids
+                .try_get(rwtxn, &key)
+                .map_err(DbError::from)?
+                .unwrap_or_default();
+            if !txids.contains(&txid) {
+                txids.push(txid);
+                self.script_txids
+                    .put(rwtxn, &key, &txids)
+                    .map_err(DbError::from)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Look up the address history of a script, and cache the transactions
+    /// paying it
+    fn scan_history(
+        &self,
+        rwtxn: &mut RwTxn,
+        parent_chain: ParentChainType,
+        snapshot: &L1Snapshot,
+        script: &ScriptBuf,
+        address: &str,
+    ) -> Result<(), Error> {
+        let key = (parent_chain, script.clone());
+        if self
+            .script_txids
+            .try_get(rwtxn, &key)
+            .map_err(DbError::from)?
+            .is_none()
+        {
+            self.script_txids
+                .put(rwtxn, &key, &Vec::new())
+                .map_err(DbError::from)?;
+        }
+        let scripts = HashSet::from([script]);
+        for txid in snapshot.list_transactions(address)? {
+            let tx = match snapshot.get_transaction(&txid) {
+                Ok(tx) => tx,
+                Err(parent_chain_rpc::Error::TransactionNotFound) => continue,
+                Err(err) => return Err(err.into()),
+            };
+            if tx.amount_paid_to(script, 0, u64::MAX).is_some() {
+                self.put_transaction(rwtxn, parent_chain, &tx, &scripts)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Walk back from a cursor to the best header chain, e.g. after an L1
+    /// reorg. Returns `None` if the cursor is not in the header chain.
+    fn best_chain_cursor(
+        rotxn: &RoTxn,
+        l1_headers: &L1HeaderChain,
+        parent_chain: ParentChainType,
+        mut cursor: L1Tip,
+    ) -> Result<Option<L1Tip>, Error> {
+        loop {
+            if l1_headers
+                .try_get_best_chain_height(
+                    rotxn,
+                    parent_chain,
+                    cursor.block_hash,
+                )?
+                .is_some()
+            {
+                return Ok(Some(cursor));
+            }
+            let Some(header_info) = l1_headers.try_get_header_info(
+                rotxn,
+                parent_chain,
+                cursor.block_hash,
+            )?
+            else {
+                return Ok(None);
+            };
+            let Some(height) = cursor.height.checked_sub(1) else {
+                return Ok(None);
+            };
+            cursor = L1Tip {
+                block_hash: header_info.header.prev_blockhash,
+                height,
+            };
+        }
+    }
+
+    /// Cache the transactions paying `watched` scripts (with their
+    /// addresses), up to the tip of the consensus header chain, from the L1
+    /// data fetched for the scan (see [`Self::plan`]).
+    ///
+    /// Scripts watched for the first time have their address history looked
+    /// up. Blocks above the scan cursor are then fetched, up to
+    /// [`MAX_BLOCKS_PER_SCAN`] per scan. If the parent chain has no cursor
+    /// yet, the history of every watched script is looked up instead, and
+    /// the cursor is set to the header chain tip. Returns the new cursor.
+    pub fn scan(
+        &self,
+        rwtxn: &mut RwTxn,
+        l1_headers: &L1HeaderChain,
+        parent_chain: ParentChainType,
+        snapshot: &L1Snapshot,
+        watched: &[(ScriptBuf, String)],
+    ) -> Result<L1Tip, Error> {
+        let tip = l1_headers
+            .try_get_tip(rwtxn, parent_chain)?
+            .ok_or(l1_headers::Error::NotAnchored(parent_chain))?;
+        let cursor = match self.try_get_cursor(rwtxn, parent_chain)? {
+            Some(cursor) => Self::best_chain_cursor(
+                rwtxn,
+                l1_headers,
+                parent_chain,
+                cursor,
+            )?,
+            None => None,
+        };
+        let Some(mut cursor) = cursor else {
+            for (script, address) in watched {
+                self.scan_history(
+                    rwtxn,
+                    parent_chain,
+                    snapshot,
+                    script,
+                    address,
+                )?;
+            }
+            self.cursors
+                .put(rwtxn, &parent_chain, &tip)
+                .map_err(DbError::from)?;
+            return Ok(tip);
+        };
+        for (script, address) in watched {
+            if self
+                .script_txids
+                .try_get(rwtxn, &(parent_chain, script.clone()))
+                .map_err(DbError::from)?
+                .is_none()
+            {
+                self.scan_history(
+                    rwtxn,
+                    parent_chain,
+                    snapshot,
+                    script,
+                    address,
+                )?;
+            }
+        }
+        let scripts: HashSet<&ScriptBuf> =
+            watched.iter().map(|(script, _)| script).collect();
+        let scan_to = tip.height.min(cursor.height + MAX_BLOCKS_PER_SCAN);
+        for height in (cursor.height + 1)..=scan_to {
+            let block_hash = l1_headers
+                .try_get_best_chain_hash(rwtxn, parent_chain, height)?
+                .ok_or(l1_headers::Error::NotAnchored(parent_chain))?;
+            let txs = match snapshot.get_block_transactions(&block_hash) {
+                Ok(txs) => txs,
+                Err(parent_chain_rpc::Error::BlocksNotSupported) => {
+                    for (script, address) in watched {
+                        self.scan_history(
+                            rwtxn,
+                            parent_chain,
+                            snapshot,
+                            script,
+                            address,
+                        )?;
+                    }
+                    cursor = tip;
+                    self.cursors
+                        .put(rwtxn, &parent_chain, &cursor)
+                        .map_err(DbError::from)?;
+                    break;
+                }
+                Err(err) => return Err(err.into()),
+            };
+            for tx in txs {
+                if tx
+                    .vout
+                    .iter()
+                    .any(|vout| scripts.contains(&vout.script_pub_key.hex))
+                {
+                    let tx = TransactionInfo {
+                        blockhash: Some(block_hash.to_string()),
+                        ..tx
+                    };
+                    self.put_transaction(rwtxn, parent_chain, &tx, &scripts)?;
+                }
+            }
+            cursor = L1Tip { block_hash, height };
+            self.cursors
+                .put(rwtxn, &parent_chain, &cursor)
+                .map_err(DbError::from)?;
+        }
+        Ok(cursor)
+    }
+
+    /// Plan the fetch of the L1 data that [`Self::scan`] and swap lookups
+    /// need, so that they can be applied from an [`L1Snapshot`] in a later
+    /// write transaction without network I/O.
+    ///
+    /// The snapshot will hold the blocks above the scan cursor, up to the
+    /// tip of the consensus header chain, and the address histories of the
+    /// `watched` scripts that the scan will look up.
+    pub fn plan(
+        &self,
+        rotxn: &RoTxn,
+        l1_headers: &L1HeaderChain,
+        parent_chain: ParentChainType,
+        watched: &[(ScriptBuf, String)],
+    ) -> Result<L1ScanPlan, Error> {
+        let tip_height = l1_headers
+            .try_get_tip(rotxn, parent_chain)?
+            .ok_or(l1_headers::Error::NotAnchored(parent_chain))?
+            .height;
+        let cursor = match self.try_get_cursor(rotxn, parent_chain)? {
+            Some(cursor) => Self::best_chain_cursor(
+                rotxn,
+                l1_headers,
+                parent_chain,
+                cursor,
+            )?,
+            None => None,
+        };
+        let Some(cursor) = cursor else {
+            return Ok(L1ScanPlan {
+                watched: watched.to_vec(),
+                addresses: watched
+                    .iter()
+                    .map(|(_script, address)| address.clone())
+                    .collect(),
+                blocks: Vec::new(),
+            });
+        };
+        let mut addresses = Vec::new();
+        for (script, address) in watched {
+            if self
+                .script_txids
+                .try_get(rotxn, &(parent_chain, script.clone()))
+                .map_err(DbError::from)?
+                .is_none()
+            {
+                addresses.push(address.clone());
+            }
+        }
+        let scan_to = tip_height.min(cursor.height + MAX_BLOCKS_PER_SCAN);
+        let mut blocks = Vec::new();
+        for height in (cursor.height + 1)..=scan_to {
+            let block_hash = l1_headers
+                .try_get_best_chain_hash(rotxn, parent_chain, height)?
+                .ok_or(l1_headers::Error::NotAnchored(parent_chain))?;
+            blocks.push(block_hash);
+        }
+        Ok(L1ScanPlan {
+            watched: watched.to_vec(),
+            addresses,
+            blocks,
+        })
+    }
+
+    /// Payments to `watched` scripts among the cached transactions. Scripts
+    /// that are not watched are looked up in `snapshot`.
+    pub fn scanned_payments<'a>(
+        &self,
+        rotxn: &RoTxn,
+        parent_chain: ParentChainType,
+        snapshot: &'a L1Snapshot,
+        watched: &[(ScriptBuf, String)],
+    ) -> Result<ScannedPayments<'a>, Error> {
+        let mut payments = HashMap::with_capacity(watched.len());
+        for (script, _address) in watched {
+            let txs = self.script_transactions(rotxn, parent_chain, script)?;
+            payments.insert(script.clone(), txs);
+        }
+        Ok(ScannedPayments { snapshot, payments })
+    }
+}
+
+/// L1 data to fetch for a scan, planned by [`L1ScanCache::plan`]
+#[derive(Clone, Debug)]
+pub struct L1ScanPlan {
+    /// Scripts (with their addresses) to scan for
+    watched: Vec<(ScriptBuf, String)>,
+    /// Addresses whose history is looked up
+    addresses: Vec<String>,
+    /// Best chain blocks above the scan cursor
+    blocks: Vec<BlockHash>,
+}
+
+impl L1ScanPlan {
+    /// Fetch the planned L1 data from `backend`. If the backend cannot serve
+    /// blocks, the history of every watched script is fetched instead.
+    pub async fn fetch(
+        &self,
+        backend: &dyn L1Backend,
+    ) -> Result<L1Snapshot, Error> {
+        let mut snapshot = L1Snapshot::new(backend, self.watched.clone());
+        for address in &self.addresses {
+            let () = snapshot.fetch_address(backend, address).await?;
+        }
+        let scripts: HashSet<&ScriptBuf> =
+            self.watched.iter().map(|(script, _)| script).collect();
+        for block_hash in &self.blocks {
+            match snapshot.fetch_block(backend, *block_hash, &scripts).await {
+                Ok(()) => (),
+                Err(parent_chain_rpc::Error::BlocksNotSupported) => {
+                    for (_script, address) in &self.watched {
+                        let () =
+                            snapshot.fetch_address(backend, address).await?;
+                    }
+                    break;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(snapshot)
+    }
+}
+
+/// Payments to watched scripts, served from an [`L1ScanCache`]. Cached
+/// transactions do not record their sender.
+pub struct ScannedPayments<'a> {
+    snapshot: &'a L1Snapshot,
+    /// Cached transactions paying each watched script
+    payments: HashMap<ScriptBuf, Vec<TransactionInfo>>,
+}
+
+impl<'a> ScannedPayments<'a> {
+    /// Payments found in `snapshot` alone, e.g. if the scan failed
+    pub fn unscanned(snapshot: &'a L1Snapshot) -> Self {
+        Self {
+            snapshot,
+            payments: HashMap::new(),
+        }
+    }
+
+    /// Name of the backend that the payments were fetched from
+    pub fn name(&self) -> &str {
+        self.snapshot.name()
+    }
+
+    /// Find transactions paying between `min_sats` and `max_sats`
+    /// (inclusive) to a script (see
+    /// [`L1Snapshot::find_transactions_by_script_and_amount_range`])
+    pub fn find_transactions_by_script_and_amount_range(
+        &self,
+        address: &str,
+        script: &bitcoin::Script,
+        min_sats: u64,
+        max_sats: u64,
+    ) -> Result<Vec<(String, TransactionInfo, u64)>, parent_chain_rpc::Error>
+    {
+        let Some(txs) = self.payments.get(script) else {
+            return self.snapshot.find_transactions_by_script_and_amount_range(
+                address, script, min_sats, max_sats,
+            );
+        };
+        Ok(txs
+            .iter()
+            .filter_map(|tx| {
+                let amount_sats =
+                    tx.amount_paid_to(script, min_sats, max_sats)?;
+                Some(("unknown".to_owned(), tx.clone(), amount_sats))
+            })
+            .collect())
+    }
+
+    /// Find transactions paying a specific amount to a script
+    pub fn find_transactions_by_script_and_amount(
+        &self,
+        address: &str,
+        script: &bitcoin::Script,
+        amount_sats: u64,
+    ) -> Result<Vec<(String, TransactionInfo)>, parent_chain_rpc::Error> {
+        let matches = self.find_transactions_by_script_and_amount_range(
+            address,
+            script,
+            amount_sats,
+            amount_sats,
+        )?;
+        Ok(matches
+            .into_iter()
+            .map(|(sender, tx, _amount_sats)| (sender, tx))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::Amount;
+    use futures::executor::block_on;
+
+    use super::L1ScanCache;
+    use crate::{
+        l1_backend::mock::MockL1Backend,
+        l1_headers::L1HeaderChain,
+        types::{ParentChainType, l1_recipient_script},
+    };
+
+    const ADDRESS: &str = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+
+    #[test]
+    fn scan_resumes_from_cursor() {
+        let path = std::env::temp_dir()
+            .join(format!("coinshift_l1_scan_test_{}", std::process::id()));
+        drop(std::fs::remove_dir_all(&path)); // best-effort cleanup
+        std::fs::create_dir_all(&path).unwrap();
+        let mut env_open_opts = heed::EnvOpenOptions::new();
+        env_open_opts
+            .map_size(64 * 1024 * 1024)
+            .max_dbs(L1HeaderChain::NUM_DBS + L1ScanCache::NUM_DBS);
+        let env = unsafe { sneed::Env::open(&env_open_opts, &path) }.unwrap();
+        let l1_headers = L1HeaderChain::new(&env).unwrap();
+        let l1_scan = L1ScanCache::new(&env).unwrap();
+        let chain = ParentChainType::Regtest;
+
+        let backend = MockL1Backend::new_regtest();
+        // Relay the mock's headers, as a sidechain block at `height` would
+        let relay_headers = |rwtxn: &mut sneed::RwTxn, height| {
+            let relay_base = l1_headers.relay_base(rwtxn, chain).unwrap();
+            let headers = block_on(relay_base.fetch_relay(&backend)).unwrap();
+            let _: usize = l1_headers
+                .connect_relay(rwtxn, chain, &headers, height)
+                .unwrap();
+        };
+        let script = l1_recipient_script(chain, ADDRESS).unwrap();
+        let watched = vec![(script.clone(), ADDRESS.to_owned())];
+        let _tip = backend.mine_blocks(2);
+        // Found from the address history, before it is mined
+        let first_txid =
+            backend.send_to_script(ADDRESS, script.clone(), Amount::ONE_BTC);
+
+        let mut rwtxn = env.write_txn().unwrap();
+        relay_headers(&mut rwtxn, 1);
+        let plan = l1_scan.plan(&rwtxn, &l1_headers, chain, &watched).unwrap();
+        let snapshot = block_on(plan.fetch(&backend)).unwrap();
+        let cursor = l1_scan
+            .scan(&mut rwtxn, &l1_headers, chain, &snapshot, &watched)
+            .unwrap();
+        assert_eq!(cursor.height, 2);
+        let txs = l1_scan.script_transactions(&rwtxn, chain, &script).unwrap();
+        assert_eq!(txs.len(), 1);
+        assert_eq!(txs[0].txid, first_txid);
+        assert_eq!(txs[0].blockhash, None);
+        rwtxn.commit().unwrap();
+
+        // Found by scanning new blocks. The mock does not list this payment
+        // in the history of the address.
+        let block_hash = backend.mine_blocks(1);
+        let second_txid =
+            backend.send_to_script("other", script.clone(), Amount::ONE_SAT);
+        let tip = backend.mine_blocks(1);
+
+        // Scans resume from the stored cursor
+        let l1_scan = L1ScanCache::new(&env).unwrap();
+        let mut rwtxn = env.write_txn().unwrap();
+        relay_headers(&mut rwtxn, 2);
+        let plan = l1_scan.plan(&rwtxn, &l1_headers, chain, &watched).unwrap();
+        let snapshot = block_on(plan.fetch(&backend)).unwrap();
+        let cursor = l1_scan
+            .scan(&mut rwtxn, &l1_headers, chain, &snapshot, &watched)
+            .unwrap();
+        assert_eq!(cursor.block_hash, tip);
+        let scanned = l1_scan
+            .scanned_payments(&rwtxn, chain, &snapshot, &watched)
+            .unwrap();
+        let matches = scanned
+            .find_transactions_by_script_and_amount_range(
+                ADDRESS,
+                &script,
+                1,
+                Amount::ONE_BTC.to_sat(),
+            )
+            .unwrap();
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].1.txid, first_txid);
+        assert_eq!(matches[0].1.blockhash, Some(block_hash.to_string()));
+        assert_eq!(matches[1].1.txid, second_txid);
+        assert_eq!(matches[1].1.blockhash, Some(tip.to_string()));
+        drop(rwtxn);
+        drop(std::fs::remove_dir_all(&path)); // best-effort cleanup
+    }
+}
@@ -3,6 +3,11 @@
 
 pub mod archive;
 pub mod authorization;
+pub mod chain_registry;
+pub mod l1_backend;
+pub mod l1_config;
+pub mod l1_headers;
+pub mod l1_scan;
 pub mod mempool;
 pub mod miner;
 pub mod net;
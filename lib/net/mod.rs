@@ -168,7 +168,7 @@ const SIGNET_SEED_NODE_ADDRS: &[SocketAddr] = {
 const fn seed_node_addrs(network: Network) -> &'static [SocketAddr] {
     match network {
         Network::Signet => SIGNET_SEED_NODE_ADDRS,
-        Network::Regtest => &[],
+        Network::Regtest | Network::Testnet4 | Network::Mainnet => &[],
     }
 }
 
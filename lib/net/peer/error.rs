@@ -75,9 +75,14 @@ pub(in crate::net::peer) mod connection {
         BadMagic(crate::net::peer::message::MagicBytes),
         #[error("bincode error")]
         Bincode(#[from] bincode::Error),
+        #[error(
+            "peer has a different chain registry (hash {})",
+            hex::encode(.0)
+        )]
+        ChainRegistryMismatch(crate::types::Hash),
         #[error("connection error")]
         Connection(#[from] quinn::ConnectionError),
-        #[error("failed to read magic bytes")]
+        #[error("failed to read network prefix")]
         ReadMagic(#[source] quinn::ReadExactError),
         #[error("read to end error")]
         ReadToEnd(#[from] quinn::ReadToEndError),
@@ -6,9 +6,11 @@ use borsh::BorshSerialize;
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    chain_registry,
     net::peer::{PeerState, PeerStateId},
     types::{
-        AuthorizedTransaction, BlockHash, Body, Header, Network, Tip, Txid,
+        AuthorizedTransaction, BlockHash, Body, Hash, Header, Network, Tip,
+        Txid,
     },
 };
 
@@ -18,19 +20,38 @@ pub type MagicBytes = [u8; MAGIC_BYTES_LEN];
 
 pub const fn magic_bytes(network: Network) -> MagicBytes {
     // First 4 bytes are the US-TTY (LSB Right) Baudot–Murray code for "SHIFT".
-    // Rightmost bits of the 4th byte is the network identifier.
+    // The bits of the 4th byte left of T are the network identifier.
     let b0 = 0b0010_0001; // S(10100) - lower 5 bits: 10100, upper 3 bits from H: 001
     let b1 = 0b1000_1001; // H(00101) upper 2 bits + I(00110) full + F(01101) lower 1 bit
     let b2 = 0b0000_1011; // F(01101) upper 4 bits + T(10000) lower 4 bits
     let mut b3 = 0b0000_0001; // T(10000) upper 1 bit
 
+    // Signet keeps the magic bytes used before other networks were added
     match network {
-        Network::Regtest => (),
-        Network::Signet => b3 |= 0b0000_0001,
+        Network::Signet => (),
+        Network::Regtest => b3 |= 0b0000_0010,
+        Network::Testnet4 => b3 |= 0b0000_0100,
+        Network::Mainnet => b3 |= 0b0000_0110,
     }
     [b0, b1, b2, b3]
 }
 
+pub const NETWORK_PREFIX_LEN: usize =
+    MAGIC_BYTES_LEN + std::mem::size_of::<Hash>();
+
+pub type NetworkPrefix = [u8; NETWORK_PREFIX_LEN];
+
+/// Prefix of every message: the magic bytes of the network, followed by the
+/// hash of the chain registry. Swap validation depends on the registry, so
+/// nodes that registered different parent chains are on different networks.
+pub fn network_prefix(network: Network) -> NetworkPrefix {
+    let mut prefix = [0u8; NETWORK_PREFIX_LEN];
+    prefix[..MAGIC_BYTES_LEN].copy_from_slice(&magic_bytes(network));
+    prefix[MAGIC_BYTES_LEN..]
+        .copy_from_slice(&chain_registry::registry().hash());
+    prefix
+}
+
 #[derive(BorshSerialize, Clone, Debug, Deserialize, Serialize)]
 pub struct Heartbeat(pub PeerState);
 
@@ -17,6 +17,7 @@ use tokio::{spawn, task::JoinHandle, time::Duration};
 
 use crate::{
     archive::Archive,
+    chain_registry,
     state::State,
     types::{AuthorizedTransaction, Hash, Network, Tip, Version, hash, schema},
 };
@@ -176,21 +177,38 @@ impl Connection {
         })
     }
 
+    /// Read the network prefix of a message, refusing messages from other
+    /// networks and from peers with a different chain registry
+    async fn receive_network_prefix(
+        network: Network,
+        recv: &mut RecvStream,
+    ) -> Result<(), error::connection::Receive> {
+        let mut prefix = [0u8; message::NETWORK_PREFIX_LEN];
+        recv.read_exact(&mut prefix)
+            .await
+            .map_err(error::connection::Receive::ReadMagic)?;
+        let (magic_bytes, registry_hash) =
+            prefix.split_at(message::MAGIC_BYTES_LEN);
+        if magic_bytes != message::magic_bytes(network) {
+            return Err(error::connection::Receive::BadMagic(
+                magic_bytes.try_into().expect("magic bytes length"),
+            ));
+        }
+        if registry_hash != chain_registry::registry().hash() {
+            return Err(error::connection::Receive::ChainRegistryMismatch(
+                registry_hash.try_into().expect("hash length"),
+            ));
+        }
+        Ok(())
+    }
+
     async fn receive_request(
         &self,
     ) -> Result<(RequestMessage, SendStream), error::connection::ReceiveRequest>
     {
         let (tx, mut rx) = self.inner.accept_bi().await?;
         tracing::trace!(recv_id = %rx.id(), "Receiving request");
-        let mut magic_bytes = [0u8; message::MAGIC_BYTES_LEN];
-        rx.read_exact(&mut magic_bytes)
-            .await
-            .map_err(error::connection::Receive::ReadMagic)?;
-        if magic_bytes != message::magic_bytes(self.network) {
-            return Err(
-                error::connection::Receive::BadMagic(magic_bytes).into()
-            );
-        }
+        let () = Self::receive_network_prefix(self.network, &mut rx).await?;
         let msg_bytes = rx.read_to_end(Connection::READ_REQUEST_LIMIT).await?;
         let msg: RequestMessage = bincode::deserialize(&msg_bytes)?;
         tracing::trace!(
@@ -212,7 +230,7 @@ impl Connection {
             "Sending heartbeat"
         );
         let message = RequestMessageRef::from(heartbeat);
-        let mut message_buf = message::magic_bytes(self.network).to_vec();
+        let mut message_buf = message::network_prefix(self.network).to_vec();
         bincode::serialize_into::<&mut Vec<_>, _>(&mut message_buf, &message)?;
         send.write_all(&message_buf).await.map_err(|err| {
             error::connection::Send::Write {
@@ -230,15 +248,7 @@ impl Connection {
         read_response_limit: NonZeroUsize,
     ) -> ResponseResult {
         tracing::trace!(recv_id = %recv.id(), "Receiving response");
-        let mut magic_bytes = [0u8; message::MAGIC_BYTES_LEN];
-        recv.read_exact(&mut magic_bytes)
-            .await
-            .map_err(error::connection::Receive::ReadMagic)?;
-        if magic_bytes != message::magic_bytes(network) {
-            return Err(
-                error::connection::Receive::BadMagic(magic_bytes).into()
-            );
-        }
+        let () = Self::receive_network_prefix(network, &mut recv).await?;
         let response_bytes =
             recv.read_to_end(read_response_limit.get()).await?;
         let response: ResponseMessage = bincode::deserialize(&response_bytes)?;
@@ -262,7 +272,7 @@ impl Connection {
             "Sending request"
         );
         let message = RequestMessageRef::from(request);
-        let mut message_buf = message::magic_bytes(self.network).to_vec();
+        let mut message_buf = message::network_prefix(self.network).to_vec();
         bincode::serialize_into::<&mut Vec<_>, _>(&mut message_buf, &message)?;
         send.write_all(&message_buf).await.map_err(|err| {
             error::connection::Send::Write {
@@ -278,7 +288,7 @@ impl Connection {
     }
 
     // Send a pre-serialized response, where the response does not include
-    // magic bytes
+    // the network prefix
     async fn send_serialized_response(
         network: Network,
         mut response_tx: SendStream,
@@ -290,7 +300,7 @@ impl Connection {
         );
         async {
             response_tx
-                .write_all(&message::magic_bytes(network))
+                .write_all(&message::network_prefix(network))
                 .await?;
             response_tx.write_all(serialized_response).await
         }
@@ -316,7 +326,7 @@ impl Connection {
             send_id = %response_tx.id(),
             "Sending response"
         );
-        let mut message_buf = message::magic_bytes(network).to_vec();
+        let mut message_buf = message::network_prefix(network).to_vec();
         bincode::serialize_into::<&mut Vec<_>, _>(&mut message_buf, &response)?;
         response_tx.write_all(&message_buf).await.map_err(|err| {
             {
@@ -14,6 +14,9 @@ use tonic::transport::Channel;
 
 use crate::{
     archive::{self, Archive},
+    l1_config::L1Config,
+    l1_headers::{self, L1HeaderChain, L1Tip},
+    l1_scan::{self, L1ScanCache},
     mempool::{self, MemPool},
     net::{self, Net, Peer},
     state::{self, State},
@@ -21,7 +24,8 @@ use crate::{
         Accumulator, Address, AmountOverflowError, AmountUnderflowError,
         Authorized, AuthorizedTransaction, BlockHash, BmmResult, Body,
         FilledTransaction, GetValue, Header, Network, OutPoint, OutPointKey,
-        Output, SpentOutput, SwapId, Tip, Transaction, Txid, WithdrawalBundle,
+        Output, ParentChainType, SpentOutput, SwapId, SwapState, Tip,
+        Transaction, TxData, Txid, WithdrawalBundle,
         proto::{self, mainchain},
     },
     util::Watchable,
@@ -55,6 +59,10 @@ pub enum Error {
     DbWrite(#[from] RwTxnError),
     #[error("I/O error")]
     Io(#[from] std::io::Error),
+    #[error("L1 header chain error")]
+    L1Headers(#[from] l1_headers::Error),
+    #[error("L1 scan error")]
+    L1Scan(#[from] l1_scan::Error),
     #[error("error requesting mainchain ancestors")]
     MainchainAncestors(#[source] mainchain_task::ResponseError),
     #[error("mempool error")]
@@ -107,7 +115,8 @@ pub struct NodeConfig<MainchainTransport = Channel> {
         Option<mainchain::WalletClient<MainchainTransport>>,
     pub network: Network,
     pub wallet: Option<Arc<crate::wallet::Wallet>>,
-    pub l1_rpc_config_path: Option<std::path::PathBuf>,
+    /// L1 config, usually [`L1Config::new`] in `datadir`
+    pub l1_config: L1Config,
 }
 
 #[derive(Clone)]
@@ -119,6 +128,10 @@ pub struct Node<MainchainTransport = Channel> {
     /// Swap IDs we created that are still pending (mempool). Only creator can cancel those.
     created_pending_swap_ids: Arc<StdMutex<HashSet<SwapId>>>,
     env: sneed::Env,
+    /// L1 txids (in RPC byte order) seen paying pending swaps, from ZMQ
+    /// notifications. Applied to the swap once confirmed.
+    l1_fills: Arc<StdMutex<HashMap<SwapId, String>>>,
+    l1_config: L1Config,
     mainchain_task: MainchainTaskHandle,
     mempool: MemPool,
     net: Net,
@@ -157,6 +170,7 @@ where
                 .max_dbs(
                     State::NUM_DBS
                         + Archive::NUM_DBS
+                        + L1ScanCache::NUM_DBS
                         + MemPool::NUM_DBS
                         + Net::NUM_DBS,
                 );
@@ -194,11 +208,12 @@ where
         let state = State::new(&env)?;
         tracing::debug!("Node::new: State created");
         tracing::debug!("Node::new: Creating Archive");
-        let archive = Archive::new(&env).map_err(|e| {
+        let archive = Archive::new(&env, config.network).map_err(|e| {
             tracing::error!(error = %e, "Node::new: Failed to create Archive");
             e
         })?;
         tracing::info!("Node::new: Archive created successfully");
+        let l1_scan = L1ScanCache::new(&env)?;
         tracing::debug!("Node::new: Creating MemPool");
         let mempool = MemPool::new(&env)?;
         tracing::debug!("Node::new: MemPool created");
@@ -225,6 +240,7 @@ where
             runtime,
             env.clone(),
             archive.clone(),
+            l1_scan,
             mainchain_task.clone(),
             mainchain_task_response_rx,
             mempool.clone(),
@@ -232,7 +248,7 @@ where
             peer_info_rx,
             state.clone(),
             wallet_clone,
-            config.l1_rpc_config_path,
+            config.l1_config.clone(),
         );
         tracing::info!("Node::new: NetTaskHandle created");
         let cusf_mainchain_wallet = config
@@ -290,6 +306,8 @@ where
             cusf_mainchain_wallet,
             created_pending_swap_ids: Arc::new(StdMutex::new(HashSet::new())),
             env,
+            l1_fills: Arc::new(StdMutex::new(HashMap::new())),
+            l1_config: config.l1_config,
             mainchain_task,
             mempool,
             net,
@@ -321,6 +339,36 @@ where
         }
     }
 
+    /// Record an L1 transaction (txid in RPC byte order) seen paying a
+    /// pending swap, e.g. from the parent chain node's ZMQ notifications
+    pub fn notify_l1_fill(&self, swap_id: SwapId, l1_txid: String) {
+        if let Ok(mut fills) = self.l1_fills.lock() {
+            fills.insert(swap_id, l1_txid);
+        }
+    }
+
+    /// L1 transactions seen paying pending swaps, that have not been
+    /// applied yet
+    pub fn l1_fills(&self) -> Vec<(SwapId, String)> {
+        self.l1_fills
+            .lock()
+            .map(|fills| {
+                fills
+                    .iter()
+                    .map(|(swap_id, l1_txid)| (*swap_id, l1_txid.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Forget a seen L1 fill (e.g. once applied, or the swap is no longer
+    /// pending)
+    pub fn remove_l1_fill(&self, swap_id: &SwapId) {
+        if let Ok(mut fills) = self.l1_fills.lock() {
+            fills.remove(swap_id);
+        }
+    }
+
     pub fn env(&self) -> &Env {
         &self.env
     }
@@ -329,6 +377,124 @@ where
         &self.archive
     }
 
+    pub fn l1_config(&self) -> &L1Config {
+        &self.l1_config
+    }
+
+    pub fn l1_headers(&self) -> &L1HeaderChain {
+        self.state.l1_headers()
+    }
+
+    /// Get the tip of the best header chain for a parent chain
+    pub fn try_get_l1_header_tip(
+        &self,
+        parent_chain: ParentChainType,
+    ) -> Result<Option<L1Tip>, Error> {
+        let rotxn = self.env.read_txn().map_err(EnvError::from)?;
+        Ok(self.l1_headers().try_get_tip(&rotxn, parent_chain)?)
+    }
+
+    /// Submit a relay of parent chain headers to the mempool, to be
+    /// committed to the consensus header chain by the next block
+    pub fn submit_l1_headers(
+        &self,
+        parent_chain: ParentChainType,
+        headers: &[bitcoin::block::Header],
+    ) -> Result<Txid, Error> {
+        let transaction = Transaction {
+            inputs: Vec::new(),
+            proof: Default::default(),
+            outputs: Vec::new(),
+            data: TxData::L1Headers {
+                parent_chain,
+                headers: l1_headers::encode_relay(headers),
+            },
+        };
+        let txid = transaction.txid();
+        let () = self.submit_transaction(AuthorizedTransaction {
+            transaction,
+            authorizations: Vec::new(),
+        })?;
+        Ok(txid)
+    }
+
+    /// Relay the headers that the L1 config's backends have beyond the
+    /// consensus header chains, for each parent chain with active swaps.
+    /// Parent chains whose backend fails are logged and skipped.
+    pub async fn relay_l1_headers(&self) -> Result<(), Error> {
+        // Read the header chains without holding a transaction across
+        // backend queries
+        let relay_bases = {
+            let rotxn = self.env.read_txn().map_err(EnvError::from)?;
+            let parent_chains: HashSet<ParentChainType> = self
+                .state
+                .load_all_swaps(&rotxn)?
+                .into_iter()
+                .filter(|swap| {
+                    !matches!(
+                        swap.state,
+                        SwapState::Completed | SwapState::Cancelled
+                    )
+                })
+                .map(|swap| swap.parent_chain)
+                .collect();
+            let mut relay_bases = Vec::new();
+            for parent_chain in parent_chains {
+                match self.l1_headers().relay_base(&rotxn, parent_chain) {
+                    Ok(relay_base) => {
+                        relay_bases.push((parent_chain, relay_base))
+                    }
+                    Err(err) => {
+                        tracing::warn!(
+                            ?parent_chain,
+                            error = %err,
+                            "Failed to read L1 header chain to relay against"
+                        );
+                    }
+                }
+            }
+            relay_bases
+        };
+        let mut relays = Vec::new();
+        for (parent_chain, relay_base) in relay_bases {
+            let Some(backend) = self.l1_config.backend(parent_chain) else {
+                continue;
+            };
+            match relay_base.fetch_relay(backend.as_ref()).await {
+                Ok(headers) if headers.is_empty() => (),
+                Ok(headers) => relays.push((parent_chain, headers)),
+                Err(err) => {
+                    tracing::warn!(
+                        ?parent_chain,
+                        backend = %backend.name(),
+                        error = %err,
+                        "Failed to fetch L1 headers to relay"
+                    );
+                }
+            }
+        }
+        for (parent_chain, headers) in relays {
+            match self.submit_l1_headers(parent_chain, &headers) {
+                Ok(txid) => {
+                    tracing::debug!(
+                        ?parent_chain,
+                        %txid,
+                        headers = %headers.len(),
+                        "Submitted L1 header relay"
+                    );
+                }
+                Err(err) => {
+                    tracing::debug!(
+                        ?parent_chain,
+                        error = %err,
+                        "L1 header relay was not accepted"
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Borrow the CUSF mainchain client, and execute the provided future.
     /// The CUSF mainchain client will be locked while the future is running.
     pub async fn with_cusf_mainchain<F, Output>(&self, f: F) -> Output
@@ -605,7 +771,16 @@ where
         let mut fee = bitcoin::Amount::ZERO;
         let mut returned_transactions = vec![];
         let mut spent_utxos = HashSet::new();
+        let mut relayed_chains = HashSet::new();
         for transaction in transactions {
+            // A block may relay headers for each parent chain at most once.
+            // Later relays stay in the mempool for the next block.
+            if let TxData::L1Headers { parent_chain, .. } =
+                transaction.transaction.data
+                && relayed_chains.contains(&parent_chain)
+            {
+                continue;
+            }
             let inputs: HashSet<_> =
                 transaction.transaction.inputs.iter().copied().collect();
             if !spent_utxos.is_disjoint(&inputs) {
@@ -623,6 +798,11 @@ where
                     .delete(&mut rwtxn, transaction.transaction.txid())?;
                 continue;
             }
+            if let TxData::L1Headers { parent_chain, .. } =
+                transaction.transaction.data
+            {
+                relayed_chains.insert(parent_chain);
+            }
             let filled_transaction = self
                 .state
                 .fill_authorized_transaction(&rwtxn, transaction)?;
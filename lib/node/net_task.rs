@@ -4,7 +4,6 @@ use std::{
     cmp::Ordering,
     collections::{HashMap, HashSet},
     net::SocketAddr,
-    path::PathBuf,
     sync::Arc,
     time::Duration,
 };
@@ -27,13 +26,15 @@ use tokio_stream::StreamNotifyClose;
 use super::mainchain_task::{self, MainchainTaskHandle};
 use crate::{
     archive::{self, Archive},
+    l1_backend::snapshot::L1Snapshot,
+    l1_config::L1Config,
+    l1_scan::L1ScanCache,
     mempool::{self, MemPool},
     net::{
         self, Net, PeerConnectionError, PeerConnectionInfo,
         PeerConnectionMailboxError, PeerConnectionMessage, PeerInfoRx,
         PeerRequest, PeerResponse, PeerStateId, peer_message,
     },
-    parent_chain_rpc::RpcConfig,
     state::{self, State},
     types::{
         BmmResult, Body, Header, MerkleRoot, ParentChainType, Tip,
@@ -89,12 +90,13 @@ impl From<net::Error> for Error {
 fn connect_tip_(
     rwtxn: &mut RwTxn<'_>,
     archive: &Archive,
+    l1_scan: &L1ScanCache,
     mempool: &MemPool,
     state: &State,
     header: &Header,
     body: &Body,
     two_way_peg_data: &mainchain::TwoWayPegData,
-    rpc_config_getter: Option<&dyn Fn(ParentChainType) -> Option<RpcConfig>>,
+    l1_snapshots: &HashMap<ParentChainType, L1Snapshot>,
     wallet: Option<&crate::wallet::Wallet>,
 ) -> Result<(), Error> {
     let block_hash = header.hash();
@@ -119,7 +121,8 @@ fn connect_tip_(
     let () = state.connect_two_way_peg_data(
         rwtxn,
         two_way_peg_data,
-        rpc_config_getter,
+        l1_scan,
+        l1_snapshots,
         wallet,
     )?;
     let accumulator = state.get_accumulator(rwtxn)?;
@@ -268,26 +271,51 @@ fn disconnect_tip_(
     Ok(())
 }
 
-/// Re-org to the specified tip, if it is better than the current tip.
-/// The new tip block and all ancestor blocks must exist in the node's archive.
-/// A result of `Ok(true)` indicates a successful re-org.
-/// A result of `Ok(false)` indicates that no re-org was attempted.
-fn reorg_to_tip(
+/// Fetch the L1 data for connecting 2WPD from the backends in the L1 config
+/// file, before taking the write lock
+async fn prefetch_l1(
     env: &sneed::Env,
+    l1_scan: &L1ScanCache,
+    state: &State,
+    l1_config: &L1Config,
+) -> Result<HashMap<ParentChainType, L1Snapshot>, Error> {
+    let plans = {
+        let rotxn = env.read_txn().map_err(EnvError::from)?;
+        state.plan_l1_scans(&rotxn, l1_scan)?
+    };
+    let l1_snapshots =
+        state::fetch_l1(&plans, |parent_chain| l1_config.backend(parent_chain))
+            .await;
+    Ok(l1_snapshots)
+}
+
+/// A re-org that has disconnected the old tip, with blocks left to connect
+struct Reorg {
+    /// Tip before the re-org
+    tip: Option<Tip>,
+    new_tip: Tip,
+    /// Blocks left to connect, the new tip first
+    blocks: Vec<(Header, Body)>,
+    /// Mainchain blocks left to connect 2WPD for, the newest first
+    two_way_peg_data_batch: Vec<(bitcoin::BlockHash, mainchain::BlockInfo)>,
+}
+
+/// Start a re-org to the specified tip, if it is better than the current
+/// tip, by disconnecting the current tip until the common ancestor is
+/// reached. A result of `Ok(None)` indicates that no re-org was attempted.
+fn begin_reorg(
+    rwtxn: &mut RwTxn,
     archive: &Archive,
     mempool: &MemPool,
     state: &State,
     new_tip: Tip,
-    rpc_config_path: Option<&PathBuf>,
-    wallet: Option<&crate::wallet::Wallet>,
-) -> Result<bool, Error> {
-    let mut rwtxn = env.write_txn().map_err(EnvError::from)?;
-    let tip_height = state.try_get_height(&rwtxn)?;
+) -> Result<Option<Reorg>, Error> {
+    let tip_height = state.try_get_height(rwtxn)?;
     let tip = state
-        .try_get_tip(&rwtxn)?
+        .try_get_tip(rwtxn)?
         .map(|tip_hash| {
             let bmm_verification =
-                archive.get_best_main_verification(&rwtxn, tip_hash)?;
+                archive.get_best_main_verification(rwtxn, tip_hash)?;
             Ok::<_, Error>(Tip {
                 block_hash: tip_hash,
                 main_block_hash: bmm_verification,
@@ -296,18 +324,18 @@ fn reorg_to_tip(
         .transpose()?;
     if let Some(tip) = tip {
         // check that new tip is better than current tip
-        if archive.better_tip(&rwtxn, tip, new_tip)? != Some(new_tip) {
+        if archive.better_tip(rwtxn, tip, new_tip)? != Some(new_tip) {
             tracing::debug!(
                 ?tip,
                 ?new_tip,
                 "New tip is not better than current tip"
             );
-            return Ok(false);
+            return Ok(None);
         }
     }
     let common_ancestor = if let Some(tip) = tip {
         archive.last_common_ancestor(
-            &rwtxn,
+            rwtxn,
             tip.block_hash,
             new_tip.block_hash,
         )?
@@ -316,19 +344,19 @@ fn reorg_to_tip(
     };
     // Check that all necessary bodies exist before disconnecting tip
     let blocks_to_apply: NonEmpty<(Header, Body)> = {
-        let header = archive.get_header(&rwtxn, new_tip.block_hash)?;
-        let body = archive.get_body(&rwtxn, new_tip.block_hash)?;
+        let header = archive.get_header(rwtxn, new_tip.block_hash)?;
+        let body = archive.get_body(rwtxn, new_tip.block_hash)?;
         let ancestors = if let Some(prev_side_hash) = header.prev_side_hash {
             archive
-                .ancestors(&rwtxn, prev_side_hash)
+                .ancestors(rwtxn, prev_side_hash)
                 .take_while(|block_hash| {
                     Ok(common_ancestor.is_none_or(|common_ancestor| {
                         *block_hash != common_ancestor
                     }))
                 })
                 .map(|block_hash| {
-                    let header = archive.get_header(&rwtxn, block_hash)?;
-                    let body = archive.get_body(&rwtxn, block_hash)?;
+                    let header = archive.get_header(rwtxn, block_hash)?;
+                    let body = archive.get_body(rwtxn, block_hash)?;
                     Ok((header, body))
                 })
                 .collect()?
@@ -344,7 +372,7 @@ fn reorg_to_tip(
     if let Some(tip_height) = tip_height {
         let common_ancestor_height =
             if let Some(common_ancestor) = common_ancestor {
-                Some(archive.get_height(&rwtxn, common_ancestor)?)
+                Some(archive.get_height(rwtxn, common_ancestor)?)
             } else {
                 None
             };
@@ -362,29 +390,29 @@ fn reorg_to_tip(
                 tip_height + 1
             };
         for _ in 0..disconnects {
-            let () = disconnect_tip_(&mut rwtxn, archive, mempool, state)?;
+            let () = disconnect_tip_(rwtxn, archive, mempool, state)?;
         }
     }
     {
-        let tip_hash = state.try_get_tip(&rwtxn)?;
+        let tip_hash = state.try_get_tip(rwtxn)?;
         assert_eq!(tip_hash, common_ancestor);
     }
-    let mut two_way_peg_data_batch: Vec<_> = {
+    let two_way_peg_data_batch: Vec<_> = {
         let common_ancestor_header =
             if let Some(common_ancestor) = common_ancestor {
-                Some(archive.get_header(&rwtxn, common_ancestor)?)
+                Some(archive.get_header(rwtxn, common_ancestor)?)
             } else {
                 None
             };
         let common_ancestor_prev_main_hash =
             common_ancestor_header.map(|header| header.prev_main_hash);
         archive
-            .main_ancestors(&rwtxn, blocks_to_apply.head.0.prev_main_hash)
+            .main_ancestors(rwtxn, blocks_to_apply.head.0.prev_main_hash)
             .take_while(|ancestor| {
                 Ok(Some(ancestor) != common_ancestor_prev_main_hash.as_ref())
             })
             .filter_map(|ancestor| {
-                match archive.try_get_main_block_info(&rwtxn, &ancestor) {
+                match archive.try_get_main_block_info(rwtxn, &ancestor) {
                     Ok(Some(block_info)) => Ok(Some((ancestor, block_info))),
                     Ok(None) => {
                         // Mainchain block info is missing - this can happen on startup
@@ -401,12 +429,32 @@ fn reorg_to_tip(
             })
             .collect()?
     };
-    // Apply blocks until new tip is reached
-    for (header, body) in blocks_to_apply.into_iter().rev() {
+    Ok(Some(Reorg {
+        tip,
+        new_tip,
+        blocks: blocks_to_apply.into(),
+        two_way_peg_data_batch,
+    }))
+}
+
+/// Apply the blocks of a re-org until a tip better than the old tip is
+/// reached, or until the new tip is reached, and commit.
+#[allow(clippy::too_many_arguments)]
+fn connect_reorg_blocks(
+    mut rwtxn: RwTxn,
+    archive: &Archive,
+    l1_scan: &L1ScanCache,
+    mempool: &MemPool,
+    state: &State,
+    reorg: &mut Reorg,
+    l1_snapshots: &HashMap<ParentChainType, L1Snapshot>,
+    wallet: Option<&crate::wallet::Wallet>,
+) -> Result<(), Error> {
+    while let Some((header, body)) = reorg.blocks.pop() {
         let two_way_peg_data = {
             let mut two_way_peg_data = mainchain::TwoWayPegData::default();
             'fill_2wpd: while let Some((block_hash, block_info)) =
-                two_way_peg_data_batch.pop()
+                reorg.two_way_peg_data_batch.pop()
             {
                 two_way_peg_data.block_info.replace(block_hash, block_info);
                 if block_hash == header.prev_main_hash {
@@ -415,26 +463,16 @@ fn reorg_to_tip(
             }
             two_way_peg_data
         };
-        let rpc_config_getter: Option<
-            Box<dyn Fn(ParentChainType) -> Option<RpcConfig>>,
-        > = rpc_config_path.map(|path| {
-            let p = path.clone();
-            Box::new(move |chain: ParentChainType| {
-                crate::parent_chain_rpc::load_rpc_config_from_path(&p, chain)
-            }) as Box<dyn Fn(ParentChainType) -> Option<RpcConfig>>
-        });
-        let rpc_config_getter: Option<
-            &dyn Fn(ParentChainType) -> Option<RpcConfig>,
-        > = rpc_config_getter.as_ref().map(|b| b.as_ref());
         let () = connect_tip_(
             &mut rwtxn,
             archive,
+            l1_scan,
             mempool,
             state,
             &header,
             &body,
             &two_way_peg_data,
-            rpc_config_getter,
+            l1_snapshots,
             wallet,
         )?;
         let new_tip_hash = state.try_get_tip(&rwtxn)?.unwrap();
@@ -444,19 +482,79 @@ fn reorg_to_tip(
             block_hash: new_tip_hash,
             main_block_hash: bmm_verification,
         };
-        if let Some(tip) = tip
+        if let Some(tip) = reorg.tip
             && archive.better_tip(&rwtxn, tip, new_tip)? != Some(new_tip)
         {
             continue;
         }
         rwtxn.commit().map_err(RwTxnError::from)?;
         tracing::info!("synced to tip: {}", new_tip.block_hash);
-        rwtxn = env.write_txn().map_err(EnvError::from)?;
+        return Ok(());
     }
     let tip = state.try_get_tip(&rwtxn)?;
-    assert_eq!(tip, Some(new_tip.block_hash));
+    assert_eq!(tip, Some(reorg.new_tip.block_hash));
     rwtxn.commit().map_err(RwTxnError::from)?;
-    tracing::info!("synced to tip: {}", new_tip.block_hash);
+    tracing::info!("synced to tip: {}", reorg.new_tip.block_hash);
+    Ok(())
+}
+
+/// Re-org to the specified tip, if it is better than the current tip.
+/// The new tip block and all ancestor blocks must exist in the node's archive.
+/// A result of `Ok(true)` indicates a successful re-org.
+/// A result of `Ok(false)` indicates that no re-org was attempted.
+///
+/// L1 data for connecting 2WPD is fetched before each write transaction, so
+/// that no transaction is held while L1 backends are queried.
+#[allow(clippy::too_many_arguments)]
+async fn reorg_to_tip(
+    env: &sneed::Env,
+    archive: &Archive,
+    l1_scan: &L1ScanCache,
+    mempool: &MemPool,
+    state: &State,
+    new_tip: Tip,
+    l1_config: &L1Config,
+    wallet: Option<&crate::wallet::Wallet>,
+) -> Result<bool, Error> {
+    let l1_snapshots = prefetch_l1(env, l1_scan, state, l1_config).await?;
+    let reorg = task::block_in_place(|| {
+        let mut rwtxn = env.write_txn().map_err(EnvError::from)?;
+        let Some(mut reorg) =
+            begin_reorg(&mut rwtxn, archive, mempool, state, new_tip)?
+        else {
+            return Ok::<_, Error>(None);
+        };
+        let () = connect_reorg_blocks(
+            rwtxn,
+            archive,
+            l1_scan,
+            mempool,
+            state,
+            &mut reorg,
+            &l1_snapshots,
+            wallet,
+        )?;
+        Ok(Some(reorg))
+    })?;
+    let Some(mut reorg) = reorg else {
+        return Ok(false);
+    };
+    while !reorg.blocks.is_empty() {
+        let l1_snapshots = prefetch_l1(env, l1_scan, state, l1_config).await?;
+        let () = task::block_in_place(|| {
+            let rwtxn = env.write_txn().map_err(EnvError::from)?;
+            connect_reorg_blocks(
+                rwtxn,
+                archive,
+                l1_scan,
+                mempool,
+                state,
+                &mut reorg,
+                &l1_snapshots,
+                wallet,
+            )
+        })?;
+    }
     Ok(true)
 }
 
@@ -464,14 +562,16 @@ fn reorg_to_tip(
 struct NetTaskContext {
     env: sneed::Env,
     archive: Archive,
+    /// Scan cursors and cached transactions of swap parent chains
+    l1_scan: L1ScanCache,
     mainchain_task: MainchainTaskHandle,
     mempool: MemPool,
     net: Net,
     state: State,
     wallet: Option<Arc<crate::wallet::Wallet>>,
-    /// Path to L1 RPC config JSON (e.g. l1_rpc_configs.json). When set, Coinshift
-    /// will query the swap target chain on each block connect to update swap state.
-    rpc_config_path: Option<PathBuf>,
+    /// L1 config, used to query swap target chains on each block connect
+    /// to update swap state
+    l1_config: L1Config,
 }
 
 /// Message indicating a tip that is ready to reorg to, with the address of the
@@ -1094,17 +1194,17 @@ impl NetTask {
                     }
                     let mut guard = OneshotGuard::new(resp_tx);
 
-                    let reorg_result = task::block_in_place(|| {
-                        reorg_to_tip(
-                            &self.ctxt.env,
-                            &self.ctxt.archive,
-                            &self.ctxt.mempool,
-                            &self.ctxt.state,
-                            new_tip,
-                            self.ctxt.rpc_config_path.as_ref(),
-                            self.ctxt.wallet.as_deref(),
-                        )
-                    });
+                    let reorg_result = reorg_to_tip(
+                        &self.ctxt.env,
+                        &self.ctxt.archive,
+                        &self.ctxt.l1_scan,
+                        &self.ctxt.mempool,
+                        &self.ctxt.state,
+                        new_tip,
+                        &self.ctxt.l1_config,
+                        self.ctxt.wallet.as_deref(),
+                    )
+                    .await;
                     let reorg_applied = match reorg_result {
                         Ok(applied) => applied,
                         Err(err) => {
@@ -1320,6 +1420,7 @@ impl NetTaskHandle {
         runtime: &tokio::runtime::Runtime,
         env: sneed::Env,
         archive: Archive,
+        l1_scan: L1ScanCache,
         mainchain_task: MainchainTaskHandle,
         mainchain_task_response_rx: UnboundedReceiver<mainchain_task::Response>,
         mempool: MemPool,
@@ -1327,17 +1428,18 @@ impl NetTaskHandle {
         peer_info_rx: PeerInfoRx,
         state: State,
         wallet: Option<Arc<crate::wallet::Wallet>>,
-        rpc_config_path: Option<PathBuf>,
+        l1_config: L1Config,
     ) -> Self {
         let ctxt = NetTaskContext {
             env,
             archive,
+            l1_scan,
             mainchain_task,
             mempool,
             net,
             state,
             wallet,
-            rpc_config_path,
+            l1_config,
         };
         let (
             forward_mainchain_task_request_tx,
@@ -1,11 +1,16 @@
 //! Connect and disconnect blocks
 
+use std::collections::HashSet;
+
 use rustreexo::accumulator::node_hash::BitcoinNodeHash;
 use sneed::{RoTxn, RwTxn, db::error::Error as DbError};
 
 use crate::{
     authorization::Authorization,
-    state::{Error, PrevalidatedBlock, State, error},
+    state::{
+        Error, PrevalidatedBlock, State, error, htlc,
+        swap::{self, SwapUndo, SwapUndoSource},
+    },
     types::{
         AccumulatorDiff, AmountOverflowError, Body, FilledTransaction,
         GetAddress as _, GetValue as _, Header, InPoint, MerkleRoot, OutPoint,
@@ -14,6 +19,68 @@ use crate::{
     },
 };
 
+/// Validate the L1 header relays of a block. A block may carry at most one
+/// relay per parent chain, so each relay is checked against the header chains
+/// as they were before the block.
+fn validate_l1_header_relays(
+    state: &State,
+    rotxn: &RoTxn,
+    filled_transactions: &[FilledTransaction],
+) -> Result<(), Error> {
+    let mut relayed_chains = HashSet::new();
+    for filled in filled_transactions {
+        if !matches!(filled.transaction.data, TxData::L1Headers { .. }) {
+            continue;
+        }
+        let (parent_chain, _headers) =
+            state.validate_l1_headers(rotxn, &filled.transaction)?;
+        if !relayed_chains.insert(parent_chain) {
+            return Err(Error::InvalidTransaction(format!(
+                "block relays {parent_chain:?} headers more than once"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Validate the swap creations of a block, and that no other transaction
+/// spends an output locked to a swap. Swap ids must be unique within the
+/// block as well as across the chain.
+fn validate_swap_creates(
+    state: &State,
+    rotxn: &RoTxn,
+    filled_transactions: &[FilledTransaction],
+) -> Result<(), Error> {
+    let mut created_swaps = HashSet::new();
+    for filled in filled_transactions {
+        match &filled.transaction.data {
+            TxData::SwapCreate { swap_id, .. } => {
+                if !created_swaps.insert(*swap_id) {
+                    return Err(Error::InvalidTransaction(format!(
+                        "block creates swap {} more than once",
+                        SwapId(*swap_id)
+                    )));
+                }
+                let () = swap::validate_swap_create(
+                    state,
+                    rotxn,
+                    &filled.transaction,
+                    filled,
+                )?;
+            }
+            TxData::Regular | TxData::HtlcRedeem { .. } => {
+                let () = swap::validate_no_locked_outputs(
+                    state,
+                    rotxn,
+                    &filled.transaction,
+                )?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
 /// Prevalidate a block: compute and verify all read-only checks and
 /// prepare data needed for fast connection.
 pub fn prevalidate(
@@ -98,6 +165,7 @@ pub fn prevalidate(
         total_fees = total_fees
             .checked_add(state.validate_filled_transaction(&filled_tx)?)
             .ok_or(AmountOverflowError)?;
+        let () = htlc::validate_htlc_spends(&filled_tx, next_height)?;
         filled_transactions.push(filled_tx);
     }
     let computed_merkle_root = Body::compute_merkle_root(
@@ -118,6 +186,8 @@ pub fn prevalidate(
             return Err(Error::UtxoDoubleSpent);
         }
     }
+    let () = validate_l1_header_relays(state, rotxn, &filled_transactions)?;
+    let () = validate_swap_creates(state, rotxn, &filled_transactions)?;
     let mut coinbase_value = bitcoin::Amount::ZERO;
     let mut accumulator_diff = AccumulatorDiff::with_capacity(
         body.coinbase.len() + accumulator_diff_txs.len(),
@@ -146,32 +216,33 @@ pub fn prevalidate(
     if coinbase_value > total_fees {
         return Err(Error::NotEnoughFees);
     }
-    // For SwapClaim transactions, SwapPending inputs are owned by the swap
-    // creator but spent by the claimer (who may be a different wallet).
-    // Skip the address-matching check for those specific inputs — swap
-    // validation already ensures legitimacy.
-    let mut auth_offset = 0usize;
-    let mut swap_claim_pending = std::collections::HashSet::<usize>::new();
+    // Outputs locked to a swap are owned by its creator but spent by the
+    // claimer (who may be a different wallet). Skip the address-matching
+    // check for those inputs only — swap validation already ensures
+    // legitimacy.
+    let mut released_inputs = Vec::new();
     for filled_tx in &filled_transactions {
-        if matches!(filled_tx.transaction.data, TxData::SwapClaim { .. }) {
-            for (i, utxo) in filled_tx.spent_utxos.iter().enumerate() {
-                if utxo.content.is_swap_pending() {
-                    swap_claim_pending.insert(auth_offset + i);
-                }
-            }
-        }
-        auth_offset += filled_tx.spent_utxos.len();
+        released_inputs.extend(swap::swap_claim_released_inputs(
+            state,
+            rotxn,
+            &filled_tx.transaction,
+        )?);
     }
     let spent_utxos = filled_transactions
         .iter()
         .flat_map(|t| t.spent_utxos.iter());
-    for (idx, (authorization, spent_utxo)) in
-        body.authorizations.iter().zip(spent_utxos).enumerate()
+    for ((authorization, spent_utxo), released) in body
+        .authorizations
+        .iter()
+        .zip(spent_utxos)
+        .zip(released_inputs)
     {
-        if swap_claim_pending.contains(&idx) {
+        if released {
             continue;
         }
-        if authorization.get_address() != spent_utxo.address {
+        if authorization.get_address()
+            != htlc::spend_authorizer(spent_utxo, next_height)
+        {
             return Err(Error::WrongPubKeyForAddress);
         }
     }
@@ -231,6 +302,7 @@ pub fn connect_prevalidated(
             .map_err(DbError::from)?;
     }
 
+    let mut swap_undo = SwapUndo::default();
     for filled in &pre.filled_transactions {
         let txid = filled.transaction.txid();
         for (vin, (outpoint, _)) in filled.transaction.inputs.iter().enumerate()
@@ -280,6 +352,8 @@ pub fn connect_prevalidated(
                 l2_amount,
                 l1_recipient_address,
                 l1_amount,
+                min_fill_l1_amount,
+                expires_in_blocks,
             } => {
                 let swap_id = SwapId(*swap_id);
                 let current_height = pre.next_height;
@@ -287,38 +361,31 @@ pub fn connect_prevalidated(
                 // Reconstruct L1 txid
                 let l1_txid = SwapTxId::from_bytes(l1_txid_bytes);
 
-                // Check if swap already exists (might be from mempool or previous block)
-                // If it exists but is corrupted, delete it first to avoid issues
-                match state.get_swap(rwtxn, &swap_id) {
-                    Ok(Some(ref existing)) => {
-                        tracing::warn!(
-                            swap_id = %swap_id,
-                            existing_state = ?existing.state,
-                            "Swap already exists in database, will overwrite during block connection"
-                        );
-                    }
-                    Ok(None) => {
-                        // Swap doesn't exist, that's fine
-                    }
-                    Err(_) => {
-                        // Swap exists but is corrupted - delete it first
-                        tracing::warn!(
-                            swap_id = %swap_id,
-                            "Existing swap is corrupted, deleting before saving new one"
-                        );
-                        // Try to delete the corrupted swap
-                        drop(state.swaps.delete(rwtxn, &swap_id));
-                    }
-                }
+                // Validate against the state as of the preceding
+                // transactions, so that an existing swap is never overwritten
+                let () = swap::validate_swap_create(
+                    state,
+                    rwtxn,
+                    &filled.transaction,
+                    filled,
+                )?;
+                swap_undo.record_swap(state, rwtxn, swap_id)?;
 
                 // L2 creator = first input's address (only they may cancel/delete)
                 let l2_creator_address =
                     filled.spent_utxos.first().map(|o| o.address);
 
+                // An L1 → L2 offer has no L1 recipient until it is accepted
+                let direction = if l1_recipient_address.is_some() {
+                    crate::types::SwapDirection::L2ToL1
+                } else {
+                    crate::types::SwapDirection::L1ToL2
+                };
+
                 // Reconstruct swap object
-                let swap = Swap::new(
+                let mut swap = Swap::new(
                     swap_id,
-                    crate::types::SwapDirection::L2ToL1,
+                    direction,
                     *parent_chain,
                     l1_txid,
                     Some(*required_confirmations),
@@ -329,17 +396,13 @@ pub fn connect_prevalidated(
                     current_height,
                     Some(
                         current_height
-                            + parent_chain.default_swap_expiration_blocks(),
+                            + parent_chain
+                                .swap_expiration_blocks(*expires_in_blocks),
                     ),
                     l2_creator_address,
                 );
-
-                // Verify swap ID matches
-                if swap.id.0 != swap_id.0 {
-                    return Err(Error::InvalidTransaction(
-                        "Swap ID mismatch in SwapCreate".to_owned(),
-                    ));
-                }
+                swap.min_fill_l1_amount =
+                    min_fill_l1_amount.map(bitcoin::Amount::from_sat);
 
                 tracing::debug!(
                     swap_id = %swap_id,
@@ -365,6 +428,8 @@ pub fn connect_prevalidated(
                                 txid,
                                 vout: vout as u32,
                             };
+                            swap_undo
+                                .record_locked_output(state, rwtxn, outpoint)?;
                             state.lock_output_to_swap(
                                 rwtxn, &outpoint, &swap_id,
                             )?;
@@ -383,11 +448,7 @@ pub fn connect_prevalidated(
                     "Swap saved during block connection"
                 );
             }
-            TxData::SwapClaim {
-                swap_id,
-                l2_claimer_address,
-                ..
-            } => {
+            TxData::SwapClaim { swap_id, .. } => {
                 let swap_id = SwapId(*swap_id);
 
                 // Get swap
@@ -395,46 +456,225 @@ pub fn connect_prevalidated(
                     .get_swap(rwtxn, &swap_id)?
                     .ok_or_else(|| Error::SwapNotFound { swap_id })?;
 
-                // If this node hasn't yet observed the L1 fill (swap
-                // state set by local, non-deterministic L1 monitoring),
-                // trust the block and advance the swap to ReadyToClaim.
-                // The miner who produced the block already validated the
-                // L1 side before including this SwapClaim.
-                if !matches!(swap.state, SwapState::ReadyToClaim) {
-                    tracing::warn!(
-                        %swap_id,
-                        state = ?swap.state,
-                        "SwapClaim in block but local swap not ReadyToClaim; \
-                         advancing state to match block"
-                    );
-                    swap.state = SwapState::ReadyToClaim;
+                // A claim for a partially fillable swap records the fills it
+                // proves, releases their share, and re-locks the rest
+                if swap.allows_partial_fills() {
+                    let new_fills = swap::validate_partial_swap_claim(
+                        state, rwtxn, &swap, filled,
+                    )?;
+                    swap_undo.record_swap_fills(state, rwtxn, &swap)?;
+                    let mut fills = state.get_swap_fills(rwtxn, &swap_id)?;
+                    fills.extend(new_fills);
+                    state.put_swap_fills(
+                        rwtxn,
+                        &swap_id,
+                        swap.parent_chain,
+                        &fills,
+                    )?;
+                    for (outpoint, _) in &filled.transaction.inputs {
+                        if state.is_output_locked_to_swap(rwtxn, outpoint)?
+                            == Some(swap_id)
+                        {
+                            swap_undo.record_locked_output(
+                                state, rwtxn, *outpoint,
+                            )?;
+                            state.unlock_output_from_swap(rwtxn, outpoint)?;
+                        }
+                    }
+                    for (vout, output) in
+                        filled.transaction.outputs.iter().enumerate()
+                    {
+                        if output.content.is_swap_pending() {
+                            let outpoint = OutPoint::Regular {
+                                txid,
+                                vout: vout as u32,
+                            };
+                            swap_undo
+                                .record_locked_output(state, rwtxn, outpoint)?;
+                            state.lock_output_to_swap(
+                                rwtxn, &outpoint, &swap_id,
+                            )?;
+                        }
+                    }
+                    swap.state = swap.state_from_fills(&fills);
+                    swap_undo.record_swap(state, rwtxn, swap_id)?;
                     state.save_swap(rwtxn, &swap)?;
+                    continue;
                 }
 
-                // For open swaps, verify claimer address is provided
-                if swap.l2_recipient.is_none() && l2_claimer_address.is_none() {
-                    return Err(Error::InvalidTransaction(
-                        "Open swap claim requires l2_claimer_address"
-                            .to_string(),
-                    ));
-                }
+                // Every claim carries an SPV proof of the L1 fill, checked
+                // against the consensus header chain, so every node reaches
+                // the same result regardless of what its own L1 backend saw.
+                // An earlier transaction in this block may have changed the
+                // swap, so re-check against the current state.
+                let fill = swap::validate_full_swap_claim(
+                    state, rwtxn, &swap, filled,
+                )?;
+                tracing::debug!(
+                    %swap_id,
+                    l1_txid = %fill.txid,
+                    l1_block_hash = %fill.block_hash,
+                    "SwapClaim L1 fill proven by inclusion proof"
+                );
+                swap.update_l1_txid(SwapTxId::from_bitcoin_txid(&fill.txid));
+                swap.set_l1_txid_validation_block(
+                    header.hash(),
+                    pre.next_height,
+                );
 
                 // Unlock outputs
                 for (outpoint, _) in &filled.transaction.inputs {
                     if state.is_output_locked_to_swap(rwtxn, outpoint)?
                         == Some(swap_id)
                     {
+                        swap_undo
+                            .record_locked_output(state, rwtxn, *outpoint)?;
                         state.unlock_output_from_swap(rwtxn, outpoint)?;
                     }
                 }
 
                 // Mark swap as completed
                 swap.mark_completed();
+                swap_undo.record_swap(state, rwtxn, swap_id)?;
+                state.save_swap(rwtxn, &swap)?;
+            }
+            TxData::SwapAccept {
+                swap_id,
+                l1_recipient_address,
+            } => {
+                let swap_id = SwapId(*swap_id);
+
+                // An earlier transaction in this block may have accepted
+                // the same offer, so re-check against the current state
+                swap::validate_swap_accept(state, rwtxn, &filled.transaction)?;
+                let mut swap = state
+                    .get_swap(rwtxn, &swap_id)?
+                    .ok_or_else(|| Error::SwapNotFound { swap_id })?;
+
+                // Lock the accepting party's SwapPending outputs
+                for (vout, output) in
+                    filled.transaction.outputs.iter().enumerate()
+                {
+                    if output.content.is_swap_pending() {
+                        let outpoint = OutPoint::Regular {
+                            txid,
+                            vout: vout as u32,
+                        };
+                        swap_undo
+                            .record_locked_output(state, rwtxn, outpoint)?;
+                        state
+                            .lock_output_to_swap(rwtxn, &outpoint, &swap_id)?;
+                    }
+                }
+
+                swap.accept(l1_recipient_address.clone());
+                swap_undo.record_swap(state, rwtxn, swap_id)?;
                 state.save_swap(rwtxn, &swap)?;
             }
-            TxData::Regular => {}
+            TxData::SwapCancel { swap_id } => {
+                let swap_id = SwapId(*swap_id);
+
+                // An earlier transaction in this block may have changed the
+                // swap, so re-check against the current state
+                swap::validate_swap_cancel(state, rwtxn, filled)?;
+                let mut swap = state
+                    .get_swap(rwtxn, &swap_id)?
+                    .ok_or_else(|| Error::SwapNotFound { swap_id })?;
+
+                let unlocked_count =
+                    swap_undo.unlock_swap_outputs(state, rwtxn, swap_id)?;
+                tracing::debug!(
+                    %swap_id,
+                    unlocked_outputs = %unlocked_count,
+                    "Swap cancelled by its creator"
+                );
+
+                swap.state = SwapState::Cancelled;
+                swap_undo.record_swap(state, rwtxn, swap_id)?;
+                state.save_swap(rwtxn, &swap)?;
+            }
+            TxData::SwapReserve {
+                swap_id,
+                l2_claimer_address,
+                reserve_blocks,
+            } => {
+                let swap_id = SwapId(*swap_id);
+
+                // An earlier transaction in this block may have reserved
+                // the same swap, so re-check against the current state
+                swap::validate_swap_reserve(state, rwtxn, &filled.transaction)?;
+                let mut swap = state
+                    .get_swap(rwtxn, &swap_id)?
+                    .ok_or_else(|| Error::SwapNotFound { swap_id })?;
+
+                // A previous reservation has lapsed: its bond is forfeited
+                let _: u32 = swap::lapse_swap_reservation(
+                    state,
+                    rwtxn,
+                    &mut swap,
+                    &mut swap_undo,
+                )?;
+
+                // Lock the bond
+                let mut bond_outpoints = Vec::new();
+                let mut bond = bitcoin::Amount::ZERO;
+                for (vout, output) in
+                    filled.transaction.outputs.iter().enumerate()
+                {
+                    if output.content.is_swap_pending() {
+                        let outpoint = OutPoint::Regular {
+                            txid,
+                            vout: vout as u32,
+                        };
+                        swap_undo
+                            .record_locked_output(state, rwtxn, outpoint)?;
+                        state
+                            .lock_output_to_swap(rwtxn, &outpoint, &swap_id)?;
+                        bond_outpoints.push(outpoint);
+                        bond = bond
+                            .checked_add(output.get_value())
+                            .ok_or(AmountOverflowError)?;
+                    }
+                }
+
+                swap.reservation = Some(crate::types::SwapReservation {
+                    l2_claimer_address: *l2_claimer_address,
+                    expires_at_height: pre.next_height + reserve_blocks,
+                    bond_outpoints,
+                    bond,
+                });
+                swap.set_l2_claimer_address(*l2_claimer_address);
+                swap_undo.record_swap(state, rwtxn, swap_id)?;
+                state.save_swap(rwtxn, &swap)?;
+            }
+            TxData::L1Headers {
+                parent_chain,
+                headers,
+            } => {
+                let headers =
+                    crate::l1_headers::decode_relay(*parent_chain, headers)?;
+                let _: usize = state.l1_headers.connect_relay(
+                    rwtxn,
+                    *parent_chain,
+                    &headers,
+                    pre.next_height,
+                )?;
+            }
+            TxData::Regular | TxData::HtlcRedeem { .. } => {
+                let () = swap::validate_no_locked_outputs(
+                    state,
+                    rwtxn,
+                    &filled.transaction,
+                )?;
+            }
         }
     }
+    state.put_swap_undo(
+        rwtxn,
+        pre.next_height,
+        SwapUndoSource::Block,
+        &swap_undo,
+    )?;
 
     // Update tip/height
     let block_hash = header.hash();
@@ -553,6 +793,7 @@ pub fn validate(
         total_fees = total_fees
             .checked_add(state.validate_filled_transaction(filled_transaction)?)
             .ok_or(AmountOverflowError)?;
+        let () = htlc::validate_htlc_spends(filled_transaction, height)?;
         // verify utreexo proof
         if !accumulator
             .verify(&filled_transaction.transaction.proof, &spent_utxo_hashes)?
@@ -568,32 +809,35 @@ pub fn validate(
             return Err(Error::UtxoDoubleSpent);
         }
     }
+    let () = validate_l1_header_relays(state, rotxn, &filled_transactions)?;
+    let () = validate_swap_creates(state, rotxn, &filled_transactions)?;
     if coinbase_value > total_fees {
         return Err(Error::NotEnoughFees);
     }
-    // Same SwapClaim + SwapPending skip as in prevalidate.
-    let mut auth_offset = 0usize;
-    let mut swap_claim_pending = std::collections::HashSet::<usize>::new();
+    // Same skip for outputs locked to a claimed swap as in prevalidate.
+    let mut released_inputs = Vec::new();
     for filled_tx in &filled_transactions {
-        if matches!(filled_tx.transaction.data, TxData::SwapClaim { .. }) {
-            for (i, utxo) in filled_tx.spent_utxos.iter().enumerate() {
-                if utxo.content.is_swap_pending() {
-                    swap_claim_pending.insert(auth_offset + i);
-                }
-            }
-        }
-        auth_offset += filled_tx.spent_utxos.len();
+        released_inputs.extend(swap::swap_claim_released_inputs(
+            state,
+            rotxn,
+            &filled_tx.transaction,
+        )?);
     }
     let spent_utxos = filled_transactions
         .iter()
         .flat_map(|t| t.spent_utxos.iter());
-    for (idx, (authorization, spent_utxo)) in
-        body.authorizations.iter().zip(spent_utxos).enumerate()
+    for ((authorization, spent_utxo), released) in body
+        .authorizations
+        .iter()
+        .zip(spent_utxos)
+        .zip(released_inputs)
     {
-        if swap_claim_pending.contains(&idx) {
+        if released {
             continue;
         }
-        if authorization.get_address() != spent_utxo.address {
+        if authorization.get_address()
+            != htlc::spend_authorizer(spent_utxo, height)
+        {
             return Err(Error::WrongPubKeyForAddress);
         }
     }
@@ -690,6 +934,23 @@ pub fn connect(
         };
         filled_txs.push(filled_tx);
     }
+    let height = state.try_get_height(rwtxn)?.map_or(0, |height| height + 1);
+    for transaction in &body.transactions {
+        if let TxData::L1Headers {
+            parent_chain,
+            headers,
+        } = &transaction.data
+        {
+            let headers =
+                crate::l1_headers::decode_relay(*parent_chain, headers)?;
+            let _: usize = state.l1_headers.connect_relay(
+                rwtxn,
+                *parent_chain,
+                &headers,
+                height,
+            )?;
+        }
+    }
     let merkle_root = Body::compute_merkle_root(
         body.coinbase.as_slice(),
         filled_txs.as_slice(),
@@ -702,7 +963,6 @@ pub fn connect(
         return Err(err);
     }
     let block_hash = header.hash();
-    let height = state.try_get_height(rwtxn)?.map_or(0, |height| height + 1);
     state.tip.put(rwtxn, &(), &block_hash)?;
     state.height.put(rwtxn, &(), &height)?;
     let () = accumulator.apply_diff(accumulator_diff)?;
@@ -728,6 +988,19 @@ pub fn disconnect_tip(
         };
         return Err(Error::InvalidHeader(err));
     }
+    let height = state
+        .try_get_height(rwtxn)?
+        .expect("Height should not be None");
+    // Revert swap mutations exactly, from undo data. Blocks connected before
+    // swap undo data was recorded fall back to reverting each swap
+    // transaction individually.
+    let swap_undo =
+        state.take_swap_undo(rwtxn, height, SwapUndoSource::Block)?;
+    let revert_swap_txs = swap_undo.is_none();
+    if let Some(swap_undo) = swap_undo {
+        let () = swap_undo.revert(state, rwtxn)?;
+    }
+    let () = state.l1_headers.disconnect_relays(rwtxn, height)?;
     let mut accumulator = state
         .utreexo_accumulator
         .try_get(rwtxn, &())
@@ -740,57 +1013,97 @@ pub fn disconnect_tip(
         let txid = tx.txid();
 
         // Rollback swap transactions
-        match &tx.data {
-            TxData::SwapCreate { swap_id, .. } => {
-                let swap_id = SwapId(*swap_id);
+        if revert_swap_txs {
+            match &tx.data {
+                TxData::SwapCreate { swap_id, .. } => {
+                    let swap_id = SwapId(*swap_id);
 
-                // Unlock outputs for L2 → L1 swaps
-                // Only unlock SwapPending outputs that were locked
-                for (vout, output) in tx.outputs.iter().enumerate().rev() {
-                    if matches!(
-                        output.content,
-                        crate::types::OutputContent::SwapPending { .. }
-                    ) {
-                        let outpoint = OutPoint::Regular {
-                            txid,
-                            vout: vout as u32,
-                        };
-                        if state.is_output_locked_to_swap(rwtxn, &outpoint)?
-                            == Some(swap_id)
-                        {
-                            state.unlock_output_from_swap(rwtxn, &outpoint)?;
+                    // Unlock outputs for L2 → L1 swaps
+                    // Only unlock SwapPending outputs that were locked
+                    for (vout, output) in tx.outputs.iter().enumerate().rev() {
+                        if matches!(
+                            output.content,
+                            crate::types::OutputContent::SwapPending { .. }
+                        ) {
+                            let outpoint = OutPoint::Regular {
+                                txid,
+                                vout: vout as u32,
+                            };
+                            if state
+                                .is_output_locked_to_swap(rwtxn, &outpoint)?
+                                == Some(swap_id)
+                            {
+                                state.unlock_output_from_swap(
+                                    rwtxn, &outpoint,
+                                )?;
+                            }
                         }
                     }
+
+                    // Delete swap (rollback: no creator check)
+                    state.delete_swap_unchecked(rwtxn, &swap_id)?;
                 }
+                TxData::SwapClaim { swap_id, .. } => {
+                    let swap_id = SwapId(*swap_id);
 
-                // Delete swap (rollback: no creator check)
-                state.delete_swap_unchecked(rwtxn, &swap_id)?;
-            }
-            TxData::SwapClaim { swap_id, .. } => {
-                let swap_id = SwapId(*swap_id);
+                    // Get swap
+                    let mut swap = state
+                        .get_swap(rwtxn, &swap_id)?
+                        .ok_or_else(|| Error::SwapNotFound { swap_id })?;
 
-                // Get swap
-                let mut swap = state
-                    .get_swap(rwtxn, &swap_id)?
-                    .ok_or_else(|| Error::SwapNotFound { swap_id })?;
+                    // Re-lock outputs
+                    for (outpoint, _) in tx.inputs.iter().rev() {
+                        if state
+                            .is_output_locked_to_swap(rwtxn, outpoint)?
+                            .is_none()
+                        {
+                            state.lock_output_to_swap(
+                                rwtxn, outpoint, &swap_id,
+                            )?;
+                        }
+                    }
 
-                // Re-lock outputs
-                for (outpoint, _) in tx.inputs.iter().rev() {
-                    if state
-                        .is_output_locked_to_swap(rwtxn, outpoint)?
-                        .is_none()
-                    {
-                        state.lock_output_to_swap(rwtxn, outpoint, &swap_id)?;
+                    // Revert swap state
+                    if matches!(swap.state, SwapState::Completed) {
+                        swap.state = SwapState::ReadyToClaim;
+                        state.save_swap(rwtxn, &swap)?;
                     }
                 }
+                TxData::SwapAccept { swap_id, .. } => {
+                    let swap_id = SwapId(*swap_id);
 
-                // Revert swap state
-                if matches!(swap.state, SwapState::Completed) {
-                    swap.state = SwapState::ReadyToClaim;
-                    state.save_swap(rwtxn, &swap)?;
+                    // Unlock the accepting party's outputs
+                    for (vout, output) in tx.outputs.iter().enumerate().rev() {
+                        if output.content.is_swap_pending() {
+                            let outpoint = OutPoint::Regular {
+                                txid,
+                                vout: vout as u32,
+                            };
+                            if state
+                                .is_output_locked_to_swap(rwtxn, &outpoint)?
+                                == Some(swap_id)
+                            {
+                                state.unlock_output_from_swap(
+                                    rwtxn, &outpoint,
+                                )?;
+                            }
+                        }
+                    }
+
+                    // Return the offer to awaiting acceptance
+                    if let Some(mut swap) = state.get_swap(rwtxn, &swap_id)? {
+                        swap.l1_recipient_address = None;
+                        state.save_swap(rwtxn, &swap)?;
+                    }
                 }
+                // Blocks with swap cancellations or reservations always have
+                // undo data
+                TxData::SwapCancel { .. } | TxData::SwapReserve { .. } => {}
+                // Relays are reverted from their own undo data
+                TxData::Regular
+                | TxData::HtlcRedeem { .. }
+                | TxData::L1Headers { .. } => {}
             }
-            TxData::Regular => {}
         }
 
         // delete UTXOs, last-to-first
@@ -853,9 +1166,6 @@ pub fn disconnect_tip(
                 Err(Error::NoUtxo { outpoint })
             }
         })?;
-    let height = state
-        .try_get_height(rwtxn)?
-        .expect("Height should not be None");
     match (header.prev_side_hash, height) {
         (None, 0) => {
             state.tip.delete(rwtxn, &()).map_err(DbError::from)?;
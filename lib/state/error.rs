@@ -4,8 +4,8 @@ use transitive::Transitive;
 
 use crate::types::{
     AmountOverflowError, AmountUnderflowError, BlockHash,
-    ComputeMerkleRootError, M6id, MerkleRoot, OutPoint, SwapId, Txid,
-    UtreexoError, WithdrawalBundleError,
+    ComputeMerkleRootError, L1ProofError, M6id, MerkleRoot, OutPoint,
+    ParentChainType, SwapId, Txid, UtreexoError, WithdrawalBundleError,
 };
 
 #[derive(Debug, Error)]
@@ -106,6 +106,13 @@ pub enum Error {
     SwapNotCreator,
     #[error("Invalid transaction: {0}")]
     InvalidTransaction(String),
+    #[error("preimage does not match the payment hash of HTLC {outpoint}")]
+    HtlcPreimageMismatch { outpoint: OutPoint },
+    #[error("HTLC {outpoint} cannot be refunded before height {refund_height}")]
+    HtlcRefundTooEarly {
+        outpoint: OutPoint,
+        refund_height: u32,
+    },
     #[error(
         "L1 txid already used by another swap: {existing_swap_id} (requested for {swap_id})"
     )]
@@ -113,6 +120,27 @@ pub enum Error {
         swap_id: SwapId,
         existing_swap_id: SwapId,
     },
+    #[error(
+        "L1 block {block_hash} is not in the {parent_chain:?} best header chain"
+    )]
+    L1BlockNotInBestChain {
+        parent_chain: ParentChainType,
+        block_hash: bitcoin::BlockHash,
+    },
+    #[error(
+        "L1 fill of swap {swap_id} has {confirmations} confirmations, {required} required"
+    )]
+    InsufficientL1Confirmations {
+        swap_id: SwapId,
+        confirmations: u32,
+        required: u32,
+    },
+    #[error("L1 header chain error")]
+    L1Headers(#[from] crate::l1_headers::Error),
+    #[error("L1 scan error")]
+    L1Scan(#[from] crate::l1_scan::Error),
+    #[error(transparent)]
+    L1Proof(#[from] L1ProofError),
     #[error(transparent)]
     ParentChainRpc(#[from] crate::parent_chain_rpc::Error),
 }
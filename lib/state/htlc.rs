@@ -0,0 +1,78 @@
+//! Hashed timelock contract (HTLC) spending rules
+
+use bitcoin::hashes::{Hash as _, sha256};
+
+use crate::{
+    state::Error,
+    types::{Address, FilledTransaction, Output, OutputContent, TxData},
+};
+
+/// Address that must authorize spending an output in a block at `height`.
+/// An HTLC is spent by its own address before its refund height, and by its
+/// refund address from then on.
+pub fn spend_authorizer(output: &Output, height: u32) -> Address {
+    match &output.content {
+        OutputContent::Htlc {
+            refund_height,
+            refund_address,
+            ..
+        } if height >= *refund_height => *refund_address,
+        _ => output.address,
+    }
+}
+
+/// Validate the HTLC inputs of a transaction in a block at `height`.
+/// Before its refund height, an HTLC can only be spent by an `HtlcRedeem`
+/// transaction revealing the preimage of its payment hash.
+pub fn validate_htlc_spends(
+    filled_transaction: &FilledTransaction,
+    height: u32,
+) -> Result<(), Error> {
+    let transaction = &filled_transaction.transaction;
+    let preimage_hash = match &transaction.data {
+        TxData::HtlcRedeem { preimage } => {
+            Some(sha256::Hash::hash(preimage).to_byte_array())
+        }
+        _ => None,
+    };
+    let mut redeemed = 0;
+    for ((outpoint, _), spent_utxo) in transaction
+        .inputs
+        .iter()
+        .zip(filled_transaction.spent_utxos.iter())
+    {
+        let OutputContent::Htlc {
+            payment_hash,
+            refund_height,
+            ..
+        } = &spent_utxo.content
+        else {
+            continue;
+        };
+        if height >= *refund_height {
+            continue;
+        }
+        match preimage_hash {
+            Some(preimage_hash) if preimage_hash == *payment_hash => {
+                redeemed += 1;
+            }
+            Some(_) => {
+                return Err(Error::HtlcPreimageMismatch {
+                    outpoint: *outpoint,
+                });
+            }
+            None => {
+                return Err(Error::HtlcRefundTooEarly {
+                    outpoint: *outpoint,
+                    refund_height: *refund_height,
+                });
+            }
+        }
+    }
+    if preimage_hash.is_some() && redeemed == 0 {
+        return Err(Error::InvalidTransaction(
+            "HtlcRedeem transaction does not redeem any HTLC".to_string(),
+        ));
+    }
+    Ok(())
+}
@@ -15,26 +15,35 @@ use sneed::{
 
 use crate::{
     authorization::Authorization,
+    l1_backend::snapshot::L1Snapshot,
+    l1_headers::{self, L1HeaderChain},
     types::{
         Accumulator, Address, AmountOverflowError, AmountUnderflowError,
         Authorized, AuthorizedTransaction, BlockHash, Body, FilledTransaction,
         GetAddress, GetValue, Header, InPoint, M6id, MerkleRoot, OutPoint,
         OutPointKey, Output, ParentChainType, PointedOutput, SpentOutput, Swap,
-        SwapId, SwapState, SwapTxId, Transaction, TxData, VERSION, Verify,
-        Version, WithdrawalBundle, WithdrawalBundleStatus,
-        proto::mainchain::TwoWayPegData,
+        SwapFill, SwapId, SwapOffer, SwapReservation, SwapState, SwapTxId,
+        Transaction, TxData, VERSION, Verify, Version, WithdrawalBundle,
+        WithdrawalBundleStatus, proto::mainchain::TwoWayPegData,
     },
     util::Watchable,
 };
 
 mod block;
 mod error;
+mod htlc;
 mod rollback;
 mod swap;
 mod two_way_peg_data;
 
 pub use error::Error;
 use rollback::RollBack;
+use swap::{SwapUndo, SwapUndoSource};
+pub use swap::{
+    fetch_swap_fill_proofs, prove_swap_fills, proven_swap_fills,
+    verify_l1_fill_depth, verify_swap_claim_proof,
+};
+pub use two_way_peg_data::fetch_l1;
 
 pub const WITHDRAWAL_BUNDLE_FAILURE_GAP: u32 = 4;
 
@@ -134,13 +143,25 @@ pub struct State {
         DatabaseUnique<SerdeBincode<Address>, SerdeBincode<Vec<SwapId>>>,
     /// Tracks which outputs are locked to which swap
     pub locked_swap_outputs: DatabaseUnique<OutPointKey, SerdeBincode<SwapId>>,
+    /// L1 payments recorded for partially fillable swaps
+    swap_fills:
+        DatabaseUnique<SerdeBincode<SwapId>, SerdeBincode<Vec<SwapFill>>>,
+    /// Swap mutations made at each block height, used to revert them on
+    /// disconnect
+    swap_undo: DatabaseUnique<
+        SerdeBincode<(u32, SwapUndoSource)>,
+        SerdeBincode<SwapUndo>,
+    >,
+    /// Consensus header chains for swap parent chains
+    l1_headers: L1HeaderChain,
     _version: DatabaseUnique<UnitKey, SerdeBincode<Version>>,
 }
 
 impl State {
-    pub const NUM_DBS: u32 = 15;
+    pub const NUM_DBS: u32 = 17 + L1HeaderChain::NUM_DBS;
 
     pub fn new(env: &sneed::Env) -> Result<Self, Error> {
+        let l1_headers = L1HeaderChain::new(env)?;
         let mut rwtxn = env.write_txn().map_err(EnvError::from)?;
         let tip = DatabaseUnique::create(env, &mut rwtxn, "tip")
             .map_err(EnvError::from)?;
@@ -188,6 +209,10 @@ impl State {
         let locked_swap_outputs =
             DatabaseUnique::create(env, &mut rwtxn, "locked_swap_outputs")
                 .map_err(EnvError::from)?;
+        let swap_fills = DatabaseUnique::create(env, &mut rwtxn, "swap_fills")
+            .map_err(EnvError::from)?;
+        let swap_undo = DatabaseUnique::create(env, &mut rwtxn, "swap_undo")
+            .map_err(EnvError::from)?;
         let version = DatabaseUnique::create(env, &mut rwtxn, "state_version")
             .map_err(EnvError::from)?;
         if version
@@ -215,10 +240,18 @@ impl State {
             swaps_by_l1_txid,
             swaps_by_recipient,
             locked_swap_outputs,
+            swap_fills,
+            swap_undo,
+            l1_headers,
             _version: version,
         })
     }
 
+    /// Consensus header chains for swap parent chains
+    pub fn l1_headers(&self) -> &L1HeaderChain {
+        &self.l1_headers
+    }
+
     pub fn try_get_tip(
         &self,
         rotxn: &RoTxn,
@@ -387,6 +420,41 @@ impl State {
             .ok_or_else(|| AmountUnderflowError.into())
     }
 
+    /// Validate an L1 header relay transaction against the consensus header
+    /// chains, returning the relay's parent chain and decoded headers.
+    /// Relays must have no inputs or outputs, and must add at least one
+    /// header.
+    pub fn validate_l1_headers(
+        &self,
+        rotxn: &RoTxn,
+        transaction: &Transaction,
+    ) -> Result<(ParentChainType, Vec<bitcoin::block::Header>), Error> {
+        let TxData::L1Headers {
+            parent_chain,
+            headers,
+        } = &transaction.data
+        else {
+            return Err(Error::InvalidTransaction(
+                "not an L1 header relay".to_string(),
+            ));
+        };
+        if !transaction.inputs.is_empty() || !transaction.outputs.is_empty() {
+            return Err(Error::InvalidTransaction(
+                "L1 header relays must not have inputs or outputs".to_string(),
+            ));
+        }
+        let headers = l1_headers::decode_relay(*parent_chain, headers)?;
+        let new_headers =
+            self.l1_headers
+                .validate_relay(rotxn, *parent_chain, &headers)?;
+        if new_headers == 0 {
+            return Err(Error::InvalidTransaction(format!(
+                "{parent_chain:?} header relay does not add any headers"
+            )));
+        }
+        Ok((*parent_chain, headers))
+    }
+
     pub fn validate_transaction(
         &self,
         rotxn: &RoTxn,
@@ -413,7 +481,24 @@ impl State {
                     &filled_transaction,
                 )?;
             }
-            TxData::Regular => {
+            TxData::SwapAccept { .. } => {
+                swap::validate_swap_accept(
+                    self,
+                    rotxn,
+                    &transaction.transaction,
+                )?;
+            }
+            TxData::SwapCancel { .. } => {
+                swap::validate_swap_cancel(self, rotxn, &filled_transaction)?;
+            }
+            TxData::SwapReserve { .. } => {
+                swap::validate_swap_reserve(
+                    self,
+                    rotxn,
+                    &transaction.transaction,
+                )?;
+            }
+            TxData::Regular | TxData::HtlcRedeem { .. } => {
                 // Validate that regular transactions don't spend locked outputs
                 swap::validate_no_locked_outputs(
                     self,
@@ -421,25 +506,36 @@ impl State {
                     &transaction.transaction,
                 )?;
             }
+            TxData::L1Headers { .. } => {
+                let _: (ParentChainType, Vec<bitcoin::block::Header>) =
+                    self.validate_l1_headers(rotxn, &transaction.transaction)?;
+            }
         }
 
-        let is_swap_claim =
-            matches!(transaction.transaction.data, TxData::SwapClaim { .. });
-        for (authorization, spent_utxo) in transaction
+        // HTLC spending rules are checked against the next block's height
+        let next_height = self.try_get_height(rotxn)?.map_or(0, |h| h + 1);
+        let () = htlc::validate_htlc_spends(&filled_transaction, next_height)?;
+
+        // Outputs locked to a swap are owned by its creator but spent by
+        // the claimer. validate_swap_claim already checked the claim, so
+        // their address-matching check is skipped.
+        let released_inputs = swap::swap_claim_released_inputs(
+            self,
+            rotxn,
+            &transaction.transaction,
+        )?;
+        for ((authorization, spent_utxo), released) in transaction
             .authorizations
             .iter()
             .zip(filled_transaction.spent_utxos.iter())
+            .zip(released_inputs)
         {
-            // For SwapClaim transactions, SwapPending inputs are owned by
-            // the swap creator but spent by the claimer.  The swap
-            // validation (validate_swap_claim) already ensures the claim
-            // is legitimate (ReadyToClaim, correct recipient, locked
-            // output), so we skip the address-matching check for those
-            // inputs.
-            if is_swap_claim && spent_utxo.content.is_swap_pending() {
+            if released {
                 continue;
             }
-            if authorization.get_address() != spent_utxo.address {
+            if authorization.get_address()
+                != htlc::spend_authorizer(spent_utxo, next_height)
+            {
                 return Err(Error::WrongPubKeyForAddress);
             }
         }
@@ -803,75 +899,6 @@ impl State {
         Ok(())
     }
 
-    /// Cancel a swap (unlock outputs and mark as cancelled)
-    /// Only allowed for Pending swaps (before L1 transaction is detected).
-    /// Only the swap creator may cancel; pass `creator` from wallet (None = deny for old records).
-    pub fn cancel_swap(
-        &self,
-        rwtxn: &mut RwTxn,
-        swap_id: &SwapId,
-        creator: Option<&Address>,
-    ) -> Result<(), Error> {
-        // Get swap - use get_swap which handles deserialization errors gracefully
-        let mut swap = self
-            .get_swap(rwtxn, swap_id)?
-            .ok_or(Error::SwapNotFound { swap_id: *swap_id })?;
-
-        // Only the creator may cancel
-        match &swap.l2_creator_address {
-            Some(swap_creator) => {
-                let allowed =
-                    creator.map(|c| c == swap_creator).unwrap_or(false);
-                if !allowed {
-                    return Err(Error::SwapNotCreator);
-                }
-            }
-            None => {
-                // Old record without creator; deny to avoid allowing cross-user cancel
-                return Err(Error::SwapNotCreator);
-            }
-        }
-
-        // Only allow cancellation for Pending swaps
-        if !matches!(swap.state, SwapState::Pending) {
-            return Err(Error::InvalidTransaction(format!(
-                "Swap {} cannot be cancelled (state: {:?}). Only Pending swaps can be cancelled.",
-                swap_id, swap.state
-            )));
-        }
-
-        // Find and unlock all outputs locked to this swap
-        let mut unlocked_count = 0;
-        let locked_outputs: Vec<(OutPointKey, SwapId)> = self
-            .locked_swap_outputs
-            .iter(rwtxn)
-            .map_err(DbError::from)?
-            .map(|(key, swap_id)| Ok((key, swap_id)))
-            .collect()?;
-
-        for (outpoint_key, locked_swap_id) in locked_outputs {
-            if locked_swap_id == *swap_id {
-                let outpoint: OutPoint = outpoint_key.into();
-                self.unlock_output_from_swap(rwtxn, &outpoint)?;
-                unlocked_count += 1;
-            }
-        }
-
-        tracing::info!(
-            swap_id = %swap_id,
-            unlocked_outputs = %unlocked_count,
-            "Cancelling swap and unlocking outputs"
-        );
-
-        // Mark swap as cancelled
-        swap.state = SwapState::Cancelled;
-
-        // Save updated swap
-        self.save_swap(rwtxn, &swap)?;
-
-        Ok(())
-    }
-
     /// Only the swap creator may delete; pass `creator` from wallet (None = deny for old records).
     pub fn delete_swap(
         &self,
@@ -917,22 +944,10 @@ impl State {
                 .map_err(DbError::from)?;
 
             // Update swaps_by_recipient index (only for pre-specified swaps)
-            if let Some(recipient) = swap.l2_recipient
-                && let Some(mut recipient_swaps) = self
-                    .swaps_by_recipient
-                    .try_get(rwtxn, &recipient)
-                    .map_err(DbError::from)?
-            {
-                recipient_swaps.retain(|id| *id != swap.id);
-                if recipient_swaps.is_empty() {
-                    self.swaps_by_recipient
-                        .delete(rwtxn, &recipient)
-                        .map_err(DbError::from)?;
-                } else {
-                    self.swaps_by_recipient
-                        .put(rwtxn, &recipient, &recipient_swaps)
-                        .map_err(DbError::from)?;
-                }
+            if let Some(recipient) = swap.l2_recipient {
+                self.remove_from_swaps_by_recipient(
+                    rwtxn, &recipient, &swap.id,
+                )?;
             }
         } else {
             // Swap not found or corrupted - log warning but still try to delete
@@ -951,6 +966,111 @@ impl State {
         Ok(())
     }
 
+    fn remove_from_swaps_by_recipient(
+        &self,
+        rwtxn: &mut RwTxn,
+        recipient: &Address,
+        swap_id: &SwapId,
+    ) -> Result<(), Error> {
+        let Some(mut recipient_swaps) = self
+            .swaps_by_recipient
+            .try_get(rwtxn, recipient)
+            .map_err(DbError::from)?
+        else {
+            return Ok(());
+        };
+        recipient_swaps.retain(|id| id != swap_id);
+        if recipient_swaps.is_empty() {
+            self.swaps_by_recipient
+                .delete(rwtxn, recipient)
+                .map_err(DbError::from)?;
+        } else {
+            self.swaps_by_recipient
+                .put(rwtxn, recipient, &recipient_swaps)
+                .map_err(DbError::from)?;
+        }
+        Ok(())
+    }
+
+    /// Restore a swap to a previous value, or remove it if `swap` is `None`.
+    /// Unlike [`Self::delete_swap_unchecked`], swaps in any state may be
+    /// removed. For internal use only (block and 2WPD rollback).
+    fn restore_swap(
+        &self,
+        rwtxn: &mut RwTxn,
+        swap_id: &SwapId,
+        swap: Option<&Swap>,
+    ) -> Result<(), Error> {
+        // Remove index entries for the current value that the previous value
+        // does not have
+        if let Some(current) = self.get_swap(rwtxn, swap_id)? {
+            let l1_txid_key = (current.parent_chain, current.l1_txid.clone());
+            if swap.is_none_or(|swap| {
+                (swap.parent_chain, &swap.l1_txid)
+                    != (current.parent_chain, &current.l1_txid)
+            }) && self
+                .swaps_by_l1_txid
+                .try_get(rwtxn, &l1_txid_key)
+                .map_err(DbError::from)?
+                == Some(*swap_id)
+            {
+                self.swaps_by_l1_txid
+                    .delete(rwtxn, &l1_txid_key)
+                    .map_err(DbError::from)?;
+            }
+            if let Some(recipient) = current.l2_recipient
+                && swap.is_none_or(|swap| swap.l2_recipient != Some(recipient))
+            {
+                self.remove_from_swaps_by_recipient(
+                    rwtxn, &recipient, swap_id,
+                )?;
+            }
+        }
+        match swap {
+            Some(swap) => self.save_swap(rwtxn, swap),
+            None => {
+                self.swaps.delete(rwtxn, swap_id).map_err(DbError::from)?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Store the swap mutations made at a block height, if any
+    fn put_swap_undo(
+        &self,
+        rwtxn: &mut RwTxn,
+        height: u32,
+        source: SwapUndoSource,
+        swap_undo: &SwapUndo,
+    ) -> Result<(), Error> {
+        if swap_undo.is_empty() {
+            return Ok(());
+        }
+        self.swap_undo
+            .put(rwtxn, &(height, source), swap_undo)
+            .map_err(DbError::from)?;
+        Ok(())
+    }
+
+    /// Remove and return the swap mutations made at a block height
+    fn take_swap_undo(
+        &self,
+        rwtxn: &mut RwTxn,
+        height: u32,
+        source: SwapUndoSource,
+    ) -> Result<Option<SwapUndo>, Error> {
+        let swap_undo = self
+            .swap_undo
+            .try_get(rwtxn, &(height, source))
+            .map_err(DbError::from)?;
+        if swap_undo.is_some() {
+            self.swap_undo
+                .delete(rwtxn, &(height, source))
+                .map_err(DbError::from)?;
+        }
+        Ok(swap_undo)
+    }
+
     /// Unlock all outputs locked to a specific swap
     /// This is useful when a swap is corrupted and can't be read normally
     pub fn unlock_all_outputs_for_swap(
@@ -1132,6 +1252,62 @@ impl State {
         }
     }
 
+    /// L1 payments recorded for a partially fillable swap, in the order they
+    /// were recorded
+    pub fn get_swap_fills(
+        &self,
+        rotxn: &RoTxn,
+        swap_id: &SwapId,
+    ) -> Result<Vec<SwapFill>, Error> {
+        let fills = self
+            .swap_fills
+            .try_get(rotxn, swap_id)
+            .map_err(DbError::from)?
+            .unwrap_or_default();
+        Ok(fills)
+    }
+
+    /// Replace the fills recorded for a swap, keeping the `swaps_by_l1_txid`
+    /// index in sync so that each fill's L1 tx cannot be reused by another
+    /// swap.
+    fn put_swap_fills(
+        &self,
+        rwtxn: &mut RwTxn,
+        swap_id: &SwapId,
+        parent_chain: ParentChainType,
+        fills: &[SwapFill],
+    ) -> Result<(), Error> {
+        for fill in self.get_swap_fills(rwtxn, swap_id)? {
+            let l1_txid_key = (parent_chain, fill.l1_txid);
+            if !fills.iter().any(|new| new.l1_txid == l1_txid_key.1)
+                && self
+                    .swaps_by_l1_txid
+                    .try_get(rwtxn, &l1_txid_key)
+                    .map_err(DbError::from)?
+                    == Some(*swap_id)
+            {
+                self.swaps_by_l1_txid
+                    .delete(rwtxn, &l1_txid_key)
+                    .map_err(DbError::from)?;
+            }
+        }
+        for fill in fills {
+            self.swaps_by_l1_txid
+                .put(rwtxn, &(parent_chain, fill.l1_txid.clone()), swap_id)
+                .map_err(DbError::from)?;
+        }
+        if fills.is_empty() {
+            self.swap_fills
+                .delete(rwtxn, swap_id)
+                .map_err(DbError::from)?;
+        } else {
+            self.swap_fills
+                .put(rwtxn, swap_id, &fills.to_vec())
+                .map_err(DbError::from)?;
+        }
+        Ok(())
+    }
+
     pub fn get_swaps_by_recipient(
         &self,
         rotxn: &RoTxn,
@@ -1153,6 +1329,30 @@ impl State {
         }
     }
 
+    /// Open L2 → L1 swaps of `parent_chain` that a taker can fill in the
+    /// next block, best price first (see [`SwapOffer::cmp_best_first`])
+    pub fn get_swap_offers(
+        &self,
+        rotxn: &RoTxn,
+        parent_chain: ParentChainType,
+    ) -> Result<Vec<SwapOffer>, Error> {
+        let next_height = self.try_get_height(rotxn)?.map_or(0, |h| h + 1);
+        let mut offers = Vec::new();
+        for swap in self.load_all_swaps(rotxn)? {
+            if swap.parent_chain != parent_chain {
+                continue;
+            }
+            let fills = if swap.allows_partial_fills() {
+                self.get_swap_fills(rotxn, &swap.id)?
+            } else {
+                Vec::new()
+            };
+            offers.extend(SwapOffer::new(&swap, &fills, next_height));
+        }
+        offers.sort_by(SwapOffer::cmp_best_first);
+        Ok(offers)
+    }
+
     pub fn load_all_swaps(&self, rotxn: &RoTxn) -> Result<Vec<Swap>, Error> {
         let mut swaps = Vec::new();
         let mut iter = self.swaps.iter(rotxn)?;
@@ -1572,6 +1772,35 @@ impl State {
             )));
         }
 
+        // An L1 → L2 offer has no L1 recipient to fill until it is accepted
+        if swap.is_awaiting_acceptance() {
+            return Err(Error::InvalidTransaction(format!(
+                "Swap {} is an L1 → L2 offer that has not been accepted",
+                swap_id
+            )));
+        }
+
+        // Partially fillable swaps record each L1 payment as a fill
+        if swap.allows_partial_fills() {
+            return Err(Error::InvalidTransaction(format!(
+                "Swap {} allows partial fills; its fills are proven by claims",
+                swap_id
+            )));
+        }
+
+        // A reserved open swap can only be filled by the reserving taker
+        if let Some(reservation) = swap.live_reservation(block_height)
+            && l2_claimer_address
+                .is_some_and(|addr| addr != reservation.l2_claimer_address)
+        {
+            return Err(Error::InvalidTransaction(format!(
+                "Swap {} is reserved for L2 address {} until height {}",
+                swap_id,
+                reservation.l2_claimer_address,
+                reservation.expires_at_height
+            )));
+        }
+
         // Only accept confirmed L1 transactions (consistent with query_and_update_swap)
         if confirmations == 0 {
             return Err(Error::InvalidTransaction(format!(
@@ -1661,7 +1890,11 @@ impl State {
             .ok_or_else(|| Error::SwapNotFound { swap_id: *swap_id })?;
 
         let (current, required) = match swap.state {
-            SwapState::WaitingConfirmations(c, r) => (c, r),
+            SwapState::WaitingConfirmations(c, r)
+                if !swap.allows_partial_fills() =>
+            {
+                (c, r)
+            }
             _ => {
                 return Err(Error::InvalidTransaction(format!(
                     "Swap {} is not in WaitingConfirmations (state: {:?}). Only confirmation count can be updated for waiting swaps.",
@@ -1804,6 +2037,8 @@ impl State {
                         l2_amount,
                         l1_recipient_address,
                         l1_amount,
+                        min_fill_l1_amount,
+                        expires_in_blocks,
                     } => {
                         let swap_id = SwapId(*swap_id);
 
@@ -1814,10 +2049,16 @@ impl State {
                         let l2_creator_address =
                             filled.spent_utxos.first().map(|o| o.address);
 
+                        let direction = if l1_recipient_address.is_some() {
+                            crate::types::SwapDirection::L2ToL1
+                        } else {
+                            crate::types::SwapDirection::L1ToL2
+                        };
+
                         // Reconstruct swap object
-                        let swap = Swap::new(
+                        let mut swap = Swap::new(
                             swap_id,
-                            crate::types::SwapDirection::L2ToL1,
+                            direction,
                             *parent_chain,
                             l1_txid,
                             Some(*required_confirmations),
@@ -1826,9 +2067,23 @@ impl State {
                             l1_recipient_address.clone(),
                             l1_amount.map(bitcoin::Amount::from_sat),
                             height,
-                            None, // TODO: Add expiration support
+                            Some(
+                                height
+                                    + parent_chain.swap_expiration_blocks(
+                                        *expires_in_blocks,
+                                    ),
+                            ),
                             l2_creator_address,
                         );
+                        swap.min_fill_l1_amount =
+                            min_fill_l1_amount.map(bitcoin::Amount::from_sat);
+                        // The fill ledger is rebuilt from the claims
+                        self.put_swap_fills(
+                            rwtxn,
+                            &swap_id,
+                            *parent_chain,
+                            &[],
+                        )?;
 
                         // Lock outputs for L2 → L1 swaps
                         // Only lock SwapPending outputs (never change outputs)
@@ -1890,6 +2145,55 @@ impl State {
                                 }
                             }
 
+                            // A partial claim records the fills it proves,
+                            // and re-locks the rest of the swap's outputs.
+                            // The claim was validated when its block was
+                            // connected, so the depth of its fills is not
+                            // checked again.
+                            if swap.allows_partial_fills() {
+                                let mut fills =
+                                    self.get_swap_fills(rwtxn, &swap_id)?;
+                                match swap::claimed_swap_fills(
+                                    self,
+                                    rwtxn,
+                                    &swap,
+                                    transaction,
+                                ) {
+                                    Ok(new_fills) => fills.extend(new_fills),
+                                    Err(err) => tracing::warn!(
+                                        swap_id = %swap_id,
+                                        block_height = height,
+                                        error = %err,
+                                        "Failed to reconstruct fills of swap claim"
+                                    ),
+                                }
+                                self.put_swap_fills(
+                                    rwtxn,
+                                    &swap_id,
+                                    swap.parent_chain,
+                                    &fills,
+                                )?;
+                                for (vout, output) in filled
+                                    .transaction
+                                    .outputs
+                                    .iter()
+                                    .enumerate()
+                                {
+                                    if output.content.is_swap_pending() {
+                                        let outpoint = OutPoint::Regular {
+                                            txid,
+                                            vout: vout as u32,
+                                        };
+                                        self.lock_output_to_swap(
+                                            rwtxn, &outpoint, &swap_id,
+                                        )?;
+                                    }
+                                }
+                                swap.state = swap.state_from_fills(&fills);
+                                self.save_swap(rwtxn, &swap)?;
+                                continue;
+                            }
+
                             // Mark swap as completed
                             swap.mark_completed();
                             self.save_swap(rwtxn, &swap)?;
@@ -1901,7 +2205,120 @@ impl State {
                             );
                         }
                     }
-                    TxData::Regular => {}
+                    TxData::SwapAccept {
+                        swap_id,
+                        l1_recipient_address,
+                    } => {
+                        let swap_id = SwapId(*swap_id);
+
+                        if let Some(mut swap) =
+                            self.get_swap(rwtxn, &swap_id)?
+                        {
+                            // Lock the accepting party's SwapPending outputs
+                            for (vout, output) in
+                                filled.transaction.outputs.iter().enumerate()
+                            {
+                                if output.content.is_swap_pending() {
+                                    let outpoint = OutPoint::Regular {
+                                        txid,
+                                        vout: vout as u32,
+                                    };
+                                    if self
+                                        .is_output_locked_to_swap(
+                                            rwtxn, &outpoint,
+                                        )?
+                                        .is_none()
+                                    {
+                                        self.lock_output_to_swap(
+                                            rwtxn, &outpoint, &swap_id,
+                                        )?;
+                                    }
+                                }
+                            }
+
+                            swap.accept(l1_recipient_address.clone());
+                            self.save_swap(rwtxn, &swap)?;
+                        } else {
+                            tracing::warn!(
+                                swap_id = %swap_id,
+                                block_height = height,
+                                "SwapAccept found but swap not found in database"
+                            );
+                        }
+                    }
+                    TxData::SwapCancel { swap_id } => {
+                        let swap_id = SwapId(*swap_id);
+
+                        if let Some(mut swap) =
+                            self.get_swap(rwtxn, &swap_id)?
+                        {
+                            self.unlock_all_outputs_for_swap(rwtxn, &swap_id)?;
+                            swap.state = SwapState::Cancelled;
+                            self.save_swap(rwtxn, &swap)?;
+                        } else {
+                            tracing::warn!(
+                                swap_id = %swap_id,
+                                block_height = height,
+                                "SwapCancel found but swap not found in database"
+                            );
+                        }
+                    }
+                    TxData::SwapReserve {
+                        swap_id,
+                        l2_claimer_address,
+                        reserve_blocks,
+                    } => {
+                        let swap_id = SwapId(*swap_id);
+
+                        if let Some(mut swap) =
+                            self.get_swap(rwtxn, &swap_id)?
+                        {
+                            // Unlock the bond of a lapsed reservation
+                            if let Some(reservation) = swap.reservation.take() {
+                                for outpoint in &reservation.bond_outpoints {
+                                    self.unlock_output_from_swap(
+                                        rwtxn, outpoint,
+                                    )?;
+                                }
+                            }
+                            let mut bond_outpoints = Vec::new();
+                            let mut bond = bitcoin::Amount::ZERO;
+                            for (vout, output) in
+                                filled.transaction.outputs.iter().enumerate()
+                            {
+                                if output.content.is_swap_pending() {
+                                    let outpoint = OutPoint::Regular {
+                                        txid,
+                                        vout: vout as u32,
+                                    };
+                                    self.lock_output_to_swap(
+                                        rwtxn, &outpoint, &swap_id,
+                                    )?;
+                                    bond_outpoints.push(outpoint);
+                                    bond = bond
+                                        .checked_add(output.get_value())
+                                        .ok_or(AmountOverflowError)?;
+                                }
+                            }
+                            swap.reservation = Some(SwapReservation {
+                                l2_claimer_address: *l2_claimer_address,
+                                expires_at_height: height + reserve_blocks,
+                                bond_outpoints,
+                                bond,
+                            });
+                            swap.set_l2_claimer_address(*l2_claimer_address);
+                            self.save_swap(rwtxn, &swap)?;
+                        } else {
+                            tracing::warn!(
+                                swap_id = %swap_id,
+                                block_height = height,
+                                "SwapReserve found but swap not found in database"
+                            );
+                        }
+                    }
+                    TxData::Regular
+                    | TxData::HtlcRedeem { .. }
+                    | TxData::L1Headers { .. } => {}
                 }
             }
 
@@ -1970,22 +2387,31 @@ impl State {
         &self,
         rwtxn: &mut RwTxn,
         two_way_peg_data: &TwoWayPegData,
-        rpc_config_getter: Option<
-            &dyn Fn(
-                ParentChainType,
-            ) -> Option<crate::parent_chain_rpc::RpcConfig>,
-        >,
+        l1_scan: &crate::l1_scan::L1ScanCache,
+        l1_snapshots: &HashMap<ParentChainType, L1Snapshot>,
         wallet: Option<&crate::wallet::Wallet>,
     ) -> Result<(), Error> {
         two_way_peg_data::connect(
             self,
             rwtxn,
             two_way_peg_data,
-            rpc_config_getter,
+            l1_scan,
+            l1_snapshots,
             wallet,
         )
     }
 
+    /// Plan the L1 data to fetch (see [`fetch_l1`]) for
+    /// [`Self::connect_two_way_peg_data`], before taking the write lock
+    pub fn plan_l1_scans(
+        &self,
+        rotxn: &RoTxn,
+        l1_scan: &crate::l1_scan::L1ScanCache,
+    ) -> Result<HashMap<ParentChainType, crate::l1_scan::L1ScanPlan>, Error>
+    {
+        two_way_peg_data::plan_l1_scans(self, rotxn, l1_scan)
+    }
+
     pub fn disconnect_two_way_peg_data(
         &self,
         rwtxn: &mut RwTxn,
@@ -1,14 +1,169 @@
 //! Swap validation and processing
 
-use sneed::RoTxn;
+use fallible_iterator::FallibleIterator;
+use serde::{Deserialize, Serialize};
+use sneed::{RoTxn, RwTxn, db::error::Error as DbError};
 
 use crate::{
+    l1_backend::L1Backend,
     state::{Error, State},
     types::{
-        FilledTransaction, SwapId, SwapState, SwapTxId, Transaction, TxData,
+        Address, FilledTransaction, GetValue as _, L1InclusionProof,
+        MAX_PROOFS_PER_CLAIM, OutPoint, OutPointKey, OutputContent,
+        ParentChainType, Swap, SwapDirection, SwapFill, SwapId, SwapState,
+        SwapTxId, Transaction, TxData, VerifiedL1Fill, l1_recipient_script,
     },
 };
 
+/// Where swap mutations at a block height were made
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum SwapUndoSource {
+    /// Swap transactions in the block body
+    Block,
+    /// Swap processing while connecting two-way peg data
+    TwoWayPegData,
+}
+
+/// Swap mutations made while connecting a block or its two-way peg data.
+/// Records the value of each swap and output lock before it was first
+/// modified, so that the mutations can be reverted exactly on disconnect.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct SwapUndo {
+    /// Swaps prior to modification. `None` if the swap did not exist.
+    swaps: Vec<(SwapId, Option<Swap>)>,
+    /// Output locks prior to modification. `None` if the output was not
+    /// locked.
+    locked_outputs: Vec<(OutPoint, Option<SwapId>)>,
+    /// Fills of partially fillable swaps prior to modification
+    fills: Vec<(SwapId, ParentChainType, Vec<SwapFill>)>,
+}
+
+impl SwapUndo {
+    pub fn is_empty(&self) -> bool {
+        self.swaps.is_empty()
+            && self.locked_outputs.is_empty()
+            && self.fills.is_empty()
+    }
+
+    /// Record a swap before it is modified
+    pub fn record_swap(
+        &mut self,
+        state: &State,
+        rotxn: &RoTxn,
+        swap_id: SwapId,
+    ) -> Result<(), Error> {
+        if self.swaps.iter().any(|(id, _)| *id == swap_id) {
+            return Ok(());
+        }
+        let swap = state.get_swap(rotxn, &swap_id)?;
+        self.swaps.push((swap_id, swap));
+        Ok(())
+    }
+
+    /// Record an output lock before it is modified
+    pub fn record_locked_output(
+        &mut self,
+        state: &State,
+        rotxn: &RoTxn,
+        outpoint: OutPoint,
+    ) -> Result<(), Error> {
+        if self.locked_outputs.iter().any(|(op, _)| *op == outpoint) {
+            return Ok(());
+        }
+        let swap_id = state.is_output_locked_to_swap(rotxn, &outpoint)?;
+        self.locked_outputs.push((outpoint, swap_id));
+        Ok(())
+    }
+
+    /// Record the fills of a swap before they are modified
+    pub fn record_swap_fills(
+        &mut self,
+        state: &State,
+        rotxn: &RoTxn,
+        swap: &Swap,
+    ) -> Result<(), Error> {
+        if self.fills.iter().any(|(id, _, _)| *id == swap.id) {
+            return Ok(());
+        }
+        let fills = state.get_swap_fills(rotxn, &swap.id)?;
+        self.fills.push((swap.id, swap.parent_chain, fills));
+        Ok(())
+    }
+
+    /// Unlock all outputs locked to a swap, recording them for undo.
+    /// Returns the number of unlocked outputs.
+    pub fn unlock_swap_outputs(
+        &mut self,
+        state: &State,
+        rwtxn: &mut RwTxn,
+        swap_id: SwapId,
+    ) -> Result<u32, Error> {
+        let mut unlocked_count = 0u32;
+        let locked_outputs: Vec<(OutPointKey, SwapId)> = state
+            .locked_swap_outputs
+            .iter(rwtxn)
+            .map_err(DbError::from)?
+            .map(|(key, sid)| Ok((key, sid)))
+            .collect()?;
+        for (outpoint_key, locked_swap_id) in locked_outputs {
+            if locked_swap_id == swap_id {
+                let outpoint: OutPoint = outpoint_key.into();
+                self.record_locked_output(state, rwtxn, outpoint)?;
+                state.unlock_output_from_swap(rwtxn, &outpoint)?;
+                unlocked_count += 1;
+            }
+        }
+        Ok(unlocked_count)
+    }
+
+    /// Restore all recorded swaps, fills and output locks, last-to-first
+    pub fn revert(self, state: &State, rwtxn: &mut RwTxn) -> Result<(), Error> {
+        for (outpoint, swap_id) in self.locked_outputs.into_iter().rev() {
+            match swap_id {
+                Some(swap_id) => {
+                    state.lock_output_to_swap(rwtxn, &outpoint, &swap_id)?
+                }
+                None => state.unlock_output_from_swap(rwtxn, &outpoint)?,
+            }
+        }
+        for (swap_id, parent_chain, fills) in self.fills.into_iter().rev() {
+            state.put_swap_fills(rwtxn, &swap_id, parent_chain, &fills)?;
+        }
+        for (swap_id, swap) in self.swaps.into_iter().rev() {
+            state.restore_swap(rwtxn, &swap_id, swap.as_ref())?;
+        }
+        Ok(())
+    }
+}
+
+/// Clear the lapsed reservation of a swap, forfeiting the taker's bond to the
+/// swap creator by unlocking the bond outputs. Records the swap and the bond
+/// locks for undo; the caller saves the swap.
+/// Returns the number of unlocked outputs.
+pub fn lapse_swap_reservation(
+    state: &State,
+    rwtxn: &mut RwTxn,
+    swap: &mut Swap,
+    swap_undo: &mut SwapUndo,
+) -> Result<u32, Error> {
+    let Some(reservation) = swap.reservation.take() else {
+        return Ok(0);
+    };
+    swap_undo.record_swap(state, rwtxn, swap.id)?;
+    let mut unlocked_count = 0u32;
+    for outpoint in reservation.bond_outpoints {
+        if state.is_output_locked_to_swap(rwtxn, &outpoint)? == Some(swap.id) {
+            swap_undo.record_locked_output(state, rwtxn, outpoint)?;
+            state.unlock_output_from_swap(rwtxn, &outpoint)?;
+            unlocked_count += 1;
+        }
+    }
+    if swap.l2_claimer_address == Some(reservation.l2_claimer_address) {
+        swap.l2_claimer_address = None;
+    }
+    Ok(unlocked_count)
+}
+
 /// Validate a SwapCreate transaction
 pub fn validate_swap_create(
     state: &State,
@@ -18,13 +173,15 @@ pub fn validate_swap_create(
 ) -> Result<(), Error> {
     let TxData::SwapCreate {
         swap_id,
-        parent_chain: _,
+        parent_chain,
         l1_txid_bytes: _,
-        required_confirmations: _,
+        required_confirmations,
         l2_recipient,
         l2_amount,
         l1_recipient_address,
         l1_amount,
+        min_fill_l1_amount,
+        expires_in_blocks,
     } = &transaction.data
     else {
         return Err(Error::InvalidTransaction(
@@ -33,29 +190,46 @@ pub fn validate_swap_create(
     };
 
     // 1. Verify swap ID matches computed ID
-    let computed_swap_id = if let (Some(l1_addr), Some(l1_amt)) =
-        (l1_recipient_address.as_ref(), l1_amount)
-    {
+    // We need the sender's address - get it from the first input
+    let l2_sender_address = filled_transaction
+        .spent_utxos
+        .first()
+        .ok_or_else(|| {
+            Error::InvalidTransaction("SwapCreate must have inputs".to_string())
+        })?
+        .address;
+    let computed_swap_id = match (l1_recipient_address.as_ref(), l1_amount) {
         // L2 → L1 swap
-        // We need the sender's address - get it from the first input
-        let first_input =
-            filled_transaction.spent_utxos.first().ok_or_else(|| {
-                Error::InvalidTransaction(
-                    "SwapCreate must have inputs".to_string(),
-                )
-            })?;
-        let l2_sender_address = first_input.address;
-        SwapId::from_l2_to_l1(
+        (Some(l1_addr), Some(l1_amt)) => SwapId::from_l2_to_l1(
             l1_addr,
             bitcoin::Amount::from_sat(*l1_amt),
             &l2_sender_address,
             l2_recipient.as_ref(), // Now optional
-        )
-    } else {
-        return Err(Error::InvalidTransaction(
-            "L2 → L1 swap requires l1_recipient_address and l1_amount"
-                .to_string(),
-        ));
+        ),
+        // L1 → L2 swap offer
+        (None, Some(l1_amt)) => {
+            let l2_recipient = l2_recipient.as_ref().ok_or_else(|| {
+                Error::InvalidTransaction(
+                    "L1 → L2 swap requires l2_recipient".to_string(),
+                )
+            })?;
+            if *l1_amt == 0 {
+                return Err(Error::InvalidTransaction(
+                    "L1 amount must be greater than zero".to_string(),
+                ));
+            }
+            SwapId::from_l1_to_l2(
+                bitcoin::Amount::from_sat(*l1_amt),
+                bitcoin::Amount::from_sat(*l2_amount),
+                &l2_sender_address,
+                l2_recipient,
+            )
+        }
+        (_, None) => {
+            return Err(Error::InvalidTransaction(
+                "SwapCreate requires l1_amount".to_string(),
+            ));
+        }
     };
 
     if computed_swap_id.0 != *swap_id {
@@ -74,6 +248,27 @@ pub fn validate_swap_create(
         )));
     }
 
+    // 2a. The parent chain must be in the chain registry, and its headers
+    // must be verifiable, so that payments on it can be proven to claim the
+    // swap
+    let Some(descriptor) = parent_chain.descriptor() else {
+        return Err(Error::InvalidTransaction(format!(
+            "Unknown parent chain {parent_chain}"
+        )));
+    };
+    if !descriptor.params.verifiable_headers() {
+        return Err(Error::InvalidTransaction(format!(
+            "Payments on parent chain {parent_chain} cannot be proven"
+        )));
+    }
+
+    // 2b. The L1 recipient must be an address on the parent chain, so that
+    // L1 payments can be matched on its output script
+    if let Some(l1_addr) = l1_recipient_address {
+        let _script: bitcoin::ScriptBuf =
+            l1_recipient_script(*parent_chain, l1_addr)?;
+    }
+
     // 3. Verify l2_amount > 0
     if *l2_amount == 0 {
         return Err(Error::InvalidTransaction(
@@ -81,6 +276,36 @@ pub fn validate_swap_create(
         ));
     }
 
+    // 3a. Partial fills are only supported for L2 → L1 swaps, and the
+    // minimum fill must lie within the L1 amount
+    if let Some(min_fill) = min_fill_l1_amount {
+        if l1_recipient_address.is_none() {
+            return Err(Error::InvalidTransaction(
+                "Partial fills are only supported for L2 → L1 swaps"
+                    .to_string(),
+            ));
+        }
+        if *min_fill == 0 || Some(*min_fill) > *l1_amount {
+            return Err(Error::InvalidTransaction(format!(
+                "Minimum fill must be between 1 and the L1 amount, got {min_fill}"
+            )));
+        }
+    }
+
+    // 3b. A creator-chosen expiry must lie within the parent chain's limits
+    if let Some(expires_in) = expires_in_blocks {
+        validate_swap_expiration(*parent_chain, *expires_in)?;
+    }
+
+    // 3c. The swap must require enough confirmations for its L1 amount
+    if let Some(l1_amount) = l1_amount {
+        validate_swap_confirmations(
+            *parent_chain,
+            bitcoin::Amount::from_sat(*l1_amount),
+            *required_confirmations,
+        )?;
+    }
+
     // 4. Verify transaction has outputs
     if transaction.outputs.is_empty() {
         return Err(Error::InvalidTransaction(
@@ -165,6 +390,333 @@ pub fn validate_swap_create(
         }
     }
 
+    // 6. For L1 → L2 offers, the creator locks nothing: the L2 coins are
+    // locked by whoever accepts the offer
+    if l1_recipient_address.is_none() {
+        validate_no_locked_outputs(state, rotxn, transaction)?;
+        if transaction
+            .outputs
+            .iter()
+            .any(|output| output.content.is_swap_pending())
+        {
+            return Err(Error::InvalidTransaction(
+                "L1 → L2 swap offer must not create SwapPending outputs"
+                    .to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Validate a creator-chosen swap expiration (in L2 blocks) against the
+/// parent chain's limits
+pub fn validate_swap_expiration(
+    parent_chain: ParentChainType,
+    expires_in_blocks: u32,
+) -> Result<(), Error> {
+    let min = parent_chain.min_swap_expiration_blocks();
+    let max = parent_chain.max_swap_expiration_blocks();
+    if !(min..=max).contains(&expires_in_blocks) {
+        return Err(Error::InvalidTransaction(format!(
+            "Swap expiration must be between {min} and {max} blocks for \
+             {parent_chain:?}, got {expires_in_blocks}"
+        )));
+    }
+    Ok(())
+}
+
+/// Validate the confirmations required by a swap against the minimum for
+/// its L1 amount on the parent chain (see
+/// [`ParentChainType::min_confirmations`])
+pub fn validate_swap_confirmations(
+    parent_chain: ParentChainType,
+    l1_amount: bitcoin::Amount,
+    required_confirmations: u32,
+) -> Result<(), Error> {
+    let min = parent_chain.min_confirmations(l1_amount);
+    if required_confirmations < min {
+        return Err(Error::InvalidTransaction(format!(
+            "Swaps of {} sats on {parent_chain:?} must require at least \
+             {min} confirmations, got {required_confirmations}",
+            l1_amount.to_sat()
+        )));
+    }
+    Ok(())
+}
+
+/// Validate a SwapAccept transaction
+pub fn validate_swap_accept(
+    state: &State,
+    rotxn: &RoTxn,
+    transaction: &Transaction,
+) -> Result<(), Error> {
+    let TxData::SwapAccept {
+        swap_id,
+        l1_recipient_address,
+    } = &transaction.data
+    else {
+        return Err(Error::InvalidTransaction(
+            "Expected SwapAccept transaction".to_string(),
+        ));
+    };
+
+    let swap_id = SwapId(*swap_id);
+
+    // 1. Verify swap exists and is an L1 → L2 offer awaiting acceptance
+    let swap = state
+        .get_swap(rotxn, &swap_id)?
+        .ok_or_else(|| Error::SwapNotFound { swap_id })?;
+    if swap.direction != SwapDirection::L1ToL2 {
+        return Err(Error::InvalidTransaction(format!(
+            "Swap {} is not an L1 → L2 swap",
+            swap_id
+        )));
+    }
+    if !swap.is_awaiting_acceptance() {
+        return Err(Error::InvalidTransaction(format!(
+            "Swap {} is not awaiting acceptance (state: {:?})",
+            swap_id, swap.state
+        )));
+    }
+
+    // 2. Verify swap has not expired
+    let next_height = state.try_get_height(rotxn)?.map_or(0, |h| h + 1);
+    if let Some(expires_at) = swap.expires_at_height
+        && next_height >= expires_at
+    {
+        return Err(Error::InvalidTransaction(format!(
+            "Swap {} expired at height {}",
+            swap_id, expires_at
+        )));
+    }
+
+    // 3. Verify L1 recipient address is set, and is an address on the
+    // parent chain
+    if l1_recipient_address.trim().is_empty() {
+        return Err(Error::InvalidTransaction(
+            "SwapAccept requires l1_recipient_address".to_string(),
+        ));
+    }
+    let _script: bitcoin::ScriptBuf =
+        l1_recipient_script(swap.parent_chain, l1_recipient_address)?;
+
+    // 4. Verify inputs aren't locked
+    validate_no_locked_outputs(state, rotxn, transaction)?;
+
+    // 5. Verify SwapPending outputs lock exactly l2_amount to this swap
+    let mut locked_value = bitcoin::Amount::ZERO;
+    for output in &transaction.outputs {
+        let OutputContent::SwapPending {
+            value,
+            swap_id: output_swap_id,
+        } = &output.content
+        else {
+            continue;
+        };
+        if *output_swap_id != swap_id.0 {
+            return Err(Error::InvalidTransaction(format!(
+                "SwapPending output for swap {} in SwapAccept for swap {}",
+                hex::encode(output_swap_id),
+                swap_id
+            )));
+        }
+        locked_value = locked_value.checked_add(*value).ok_or_else(|| {
+            Error::InvalidTransaction("Output value overflow".to_string())
+        })?;
+    }
+    if locked_value != swap.l2_amount {
+        return Err(Error::InvalidTransaction(format!(
+            "SwapAccept must lock {} to swap {}, locks {}",
+            swap.l2_amount, swap_id, locked_value
+        )));
+    }
+
+    Ok(())
+}
+
+/// Validate a SwapCancel transaction
+pub fn validate_swap_cancel(
+    state: &State,
+    rotxn: &RoTxn,
+    filled_transaction: &FilledTransaction,
+) -> Result<(), Error> {
+    let transaction = &filled_transaction.transaction;
+    let TxData::SwapCancel { swap_id } = &transaction.data else {
+        return Err(Error::InvalidTransaction(
+            "Expected SwapCancel transaction".to_string(),
+        ));
+    };
+
+    let swap_id = SwapId(*swap_id);
+
+    // 1. Verify swap exists and can still be cancelled. Only on-chain
+    // records count: L1 payments seen by this node but not yet claimed on L2
+    // do not prevent a cancellation.
+    let swap = state
+        .get_swap(rotxn, &swap_id)?
+        .ok_or_else(|| Error::SwapNotFound { swap_id })?;
+    if swap.is_closed() {
+        return Err(Error::InvalidTransaction(format!(
+            "Swap {} cannot be cancelled (state: {:?})",
+            swap_id, swap.state
+        )));
+    }
+    if !state.get_swap_fills(rotxn, &swap_id)?.is_empty() {
+        return Err(Error::InvalidTransaction(format!(
+            "Swap {} has claimed fills and cannot be cancelled",
+            swap_id
+        )));
+    }
+
+    // 2. Verify the swap creator signed the transaction. Authorizations are
+    // checked against the spent outputs' addresses, so spending an output
+    // owned by the creator as the first input proves the creator signed.
+    let Some(creator) = swap.l2_creator_address else {
+        return Err(Error::SwapNotCreator);
+    };
+    let signer = filled_transaction
+        .spent_utxos
+        .first()
+        .map(|output| output.address);
+    if signer != Some(creator) {
+        return Err(Error::SwapNotCreator);
+    }
+
+    // 3. A reserved swap cannot be cancelled, which would forfeit the
+    // taker's bond to the creator
+    let next_height = state.try_get_height(rotxn)?.map_or(0, |h| h + 1);
+    if swap.live_reservation(next_height).is_some() {
+        return Err(Error::InvalidTransaction(format!(
+            "Swap {} is reserved by a taker and cannot be cancelled",
+            swap_id
+        )));
+    }
+
+    // 4. Verify inputs aren't locked
+    validate_no_locked_outputs(state, rotxn, transaction)?;
+
+    // 5. A cancellation must not lock anything
+    if transaction
+        .outputs
+        .iter()
+        .any(|output| output.content.is_swap_pending())
+    {
+        return Err(Error::InvalidTransaction(
+            "SwapCancel must not create SwapPending outputs".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Validate a SwapReserve transaction
+pub fn validate_swap_reserve(
+    state: &State,
+    rotxn: &RoTxn,
+    transaction: &Transaction,
+) -> Result<(), Error> {
+    let TxData::SwapReserve {
+        swap_id,
+        l2_claimer_address: _,
+        reserve_blocks,
+    } = &transaction.data
+    else {
+        return Err(Error::InvalidTransaction(
+            "Expected SwapReserve transaction".to_string(),
+        ));
+    };
+
+    let swap_id = SwapId(*swap_id);
+
+    // 1. Verify swap exists and is an open L2 → L1 swap that has not been
+    // filled. Partially fillable swaps have a claimer per fill instead.
+    let swap = state
+        .get_swap(rotxn, &swap_id)?
+        .ok_or_else(|| Error::SwapNotFound { swap_id })?;
+    if swap.direction != SwapDirection::L2ToL1
+        || swap.l2_recipient.is_some()
+        || swap.allows_partial_fills()
+    {
+        return Err(Error::InvalidTransaction(format!(
+            "Swap {} is not an open swap that can be reserved",
+            swap_id
+        )));
+    }
+    // Only a claim fills the swap on L2: L1 payments seen by this node do not
+    // prevent a reservation
+    if swap.is_closed() {
+        return Err(Error::InvalidTransaction(format!(
+            "Swap {} has already been claimed or cancelled (state: {:?})",
+            swap_id, swap.state
+        )));
+    }
+
+    // 2. Verify the swap is not reserved, and the reservation ends before
+    // the swap expires
+    let next_height = state.try_get_height(rotxn)?.map_or(0, |h| h + 1);
+    if let Some(reservation) = swap.live_reservation(next_height) {
+        return Err(Error::InvalidTransaction(format!(
+            "Swap {} is reserved until height {}",
+            swap_id, reservation.expires_at_height
+        )));
+    }
+    let max_reserve_blocks = swap.parent_chain.max_swap_reservation_blocks();
+    if *reserve_blocks == 0 || *reserve_blocks > max_reserve_blocks {
+        return Err(Error::InvalidTransaction(format!(
+            "Reservation must be between 1 and {} blocks, got {}",
+            max_reserve_blocks, reserve_blocks
+        )));
+    }
+    if let Some(expires_at) = swap.expires_at_height
+        && next_height.saturating_add(*reserve_blocks) > expires_at
+    {
+        return Err(Error::InvalidTransaction(format!(
+            "Reservation of swap {} must end by its expiry at height {}",
+            swap_id, expires_at
+        )));
+    }
+
+    // 3. Verify inputs aren't locked
+    validate_no_locked_outputs(state, rotxn, transaction)?;
+
+    // 4. Verify the bond is locked to this swap, in outputs owned by the
+    // swap creator
+    let Some(creator) = swap.l2_creator_address else {
+        return Err(Error::InvalidTransaction(format!(
+            "Swap {} has no creator to receive a reservation bond",
+            swap_id
+        )));
+    };
+    let mut bond = bitcoin::Amount::ZERO;
+    for output in &transaction.outputs {
+        let OutputContent::SwapPending {
+            value,
+            swap_id: output_swap_id,
+        } = &output.content
+        else {
+            continue;
+        };
+        if *output_swap_id != swap_id.0 || output.address != creator {
+            return Err(Error::InvalidTransaction(format!(
+                "SwapReserve bond outputs must be locked to swap {} and \
+                 owned by its creator",
+                swap_id
+            )));
+        }
+        bond = bond.checked_add(*value).ok_or_else(|| {
+            Error::InvalidTransaction("Output value overflow".to_string())
+        })?;
+    }
+    if bond < swap.reservation_bond() {
+        return Err(Error::InvalidTransaction(format!(
+            "SwapReserve must lock a bond of at least {} to swap {}, locks {}",
+            swap.reservation_bond(),
+            swap_id,
+            bond
+        )));
+    }
+
     Ok(())
 }
 
@@ -173,7 +725,7 @@ pub fn validate_swap_claim(
     state: &State,
     rotxn: &RoTxn,
     transaction: &Transaction,
-    _filled_transaction: &FilledTransaction,
+    filled_transaction: &FilledTransaction,
 ) -> Result<(), Error> {
     let TxData::SwapClaim { swap_id, .. } = &transaction.data else {
         return Err(Error::InvalidTransaction(
@@ -183,37 +735,80 @@ pub fn validate_swap_claim(
 
     let swap_id = SwapId(*swap_id);
 
-    // 1. Verify swap exists
+    // Verify swap exists
     let swap = state
         .get_swap(rotxn, &swap_id)?
         .ok_or_else(|| Error::SwapNotFound { swap_id })?;
 
-    // 2. Verify swap is in ReadyToClaim state
-    if !matches!(swap.state, SwapState::ReadyToClaim) {
+    // Partially fillable swaps release the share of each proven fill
+    if swap.allows_partial_fills() {
+        let _: Vec<SwapFill> = validate_partial_swap_claim(
+            state,
+            rotxn,
+            &swap,
+            filled_transaction,
+        )?;
+    } else {
+        let _: VerifiedL1Fill =
+            validate_full_swap_claim(state, rotxn, &swap, filled_transaction)?;
+    }
+    Ok(())
+}
+
+/// Validate a SwapClaim for a swap that is filled by a single L1 payment.
+///
+/// The claim must carry an SPV proof of the L1 fill (see
+/// [`verify_swap_claim_proof`]), declared for the claim's recipient (see
+/// [`Swap::fill_claimer`]). The swap state that this node records from
+/// its own L1 backend is not part of consensus, so it never authorizes a
+/// claim.
+///
+/// Returns the proven L1 fill.
+pub fn validate_full_swap_claim(
+    state: &State,
+    rotxn: &RoTxn,
+    swap: &Swap,
+    filled_transaction: &FilledTransaction,
+) -> Result<VerifiedL1Fill, Error> {
+    let transaction = &filled_transaction.transaction;
+    let TxData::SwapClaim {
+        l2_claimer_address,
+        proof_data,
+        ..
+    } = &transaction.data
+    else {
+        return Err(Error::InvalidTransaction(
+            "Expected SwapClaim transaction".to_string(),
+        ));
+    };
+    let swap_id = swap.id;
+
+    // 1. Verify the swap can still be claimed
+    if !matches!(
+        swap.state,
+        SwapState::Pending
+            | SwapState::WaitingConfirmations(..)
+            | SwapState::ReadyToClaim
+    ) {
         return Err(Error::InvalidTransaction(format!(
-            "Swap {} is not ready to claim (state: {:?})",
+            "Swap {} is not claimable (state: {:?})",
             swap_id, swap.state
         )));
     }
 
-    // 2.5. For open swaps, verify L1 transaction exists (someone filled it)
-    if swap.l2_recipient.is_none() {
-        // Open swap - verify L1 transaction was detected
-        let zero_hash32 = [0u8; 32];
-        let has_l1_tx = !matches!(swap.l1_txid, SwapTxId::Hash32(h) if h == zero_hash32)
-            && !matches!(swap.l1_txid, SwapTxId::Hash(ref v) if v.is_empty() || v.iter().all(|&b| b == 0));
-
-        if !has_l1_tx {
-            return Err(Error::InvalidTransaction(
-                "Open swap cannot be claimed until L1 transaction is detected"
-                    .to_string(),
-            ));
-        }
-    }
+    // 2. Verify the SPV proof of the L1 fill
+    let fill =
+        verify_swap_claim_proof(state, rotxn, swap, proof_data.as_deref())?;
 
-    // 3. Verify at least one input is locked to this swap
+    // 3. Verify at least one input is locked to this swap. SwapPending
+    // inputs must all be locked to it: unlocked ones, such as a forfeited
+    // reservation bond, belong to their owner.
     let mut found_locked_input = false;
-    for (outpoint, _) in &transaction.inputs {
+    for ((outpoint, _), spent_utxo) in transaction
+        .inputs
+        .iter()
+        .zip(filled_transaction.spent_utxos.iter())
+    {
         if let Some(locked_swap_id) =
             state.is_output_locked_to_swap(rotxn, outpoint)?
         {
@@ -224,6 +819,11 @@ pub fn validate_swap_claim(
                 )));
             }
             found_locked_input = true;
+        } else if spent_utxo.content.is_swap_pending() {
+            return Err(Error::InvalidTransaction(format!(
+                "SwapPending input {} is not locked to swap {}",
+                outpoint, swap_id
+            )));
         }
     }
 
@@ -234,29 +834,23 @@ pub fn validate_swap_claim(
         ));
     }
 
-    // 4. Verify output goes to correct recipient
-    let TxData::SwapClaim {
-        l2_claimer_address, ..
-    } = &transaction.data
-    else {
-        unreachable!()
-    };
-
+    // 4. Verify the L2 amount goes to the correct recipient
     let expected_recipient = if let Some(recipient) = swap.l2_recipient {
         // Pre-specified swap: must go to specified recipient
         recipient
     } else {
         // Open swap
-        if let Some(stored_l2) = swap.l2_claimer_address {
-            // Claim only valid for the L2 address the filler declared when providing L1 tx details
-            if l2_claimer_address.as_ref() != Some(&stored_l2) {
+        if let Some(reservation) = &swap.reservation {
+            // A reserved swap can only be claimed by the reserving taker
+            if l2_claimer_address.as_ref()
+                != Some(&reservation.l2_claimer_address)
+            {
                 return Err(Error::InvalidTransaction(
-                    "Open swap claim must use the L2 address declared when the L1 transaction was submitted".to_string(),
+                    "Open swap claim must use the L2 address that reserved the swap".to_string(),
                 ));
             }
-            stored_l2
+            reservation.l2_claimer_address
         } else if let Some(claimer_addr) = l2_claimer_address {
-            // No stored L2 (e.g. auto-detected L1 tx): accept claimer address from tx
             *claimer_addr
         } else {
             return Err(Error::InvalidTransaction(
@@ -265,21 +859,513 @@ pub fn validate_swap_claim(
         }
     };
 
-    let recipient_receives = transaction
+    // 5. Verify the L1 fill was declared for the recipient, so that a
+    // visible L1 payment cannot be claimed by anyone but its payer
+    if swap.fill_claimer(fill.l2_claimer_address) != Some(expected_recipient) {
+        return Err(Error::InvalidTransaction(format!(
+            "Swap {}: L1 fill {} is not declared for {}",
+            swap_id, fill.txid, expected_recipient
+        )));
+    }
+
+    validate_claim_payment(transaction, &expected_recipient, swap.l2_amount)?;
+
+    Ok(fill)
+}
+
+/// Validate a SwapClaim for a partially fillable swap.
+///
+/// The claim carries an SPV proof of each L1 fill that it releases the L2
+/// share of (see [`proven_swap_fills`]), and every fill must have the
+/// swap's required confirmations (see [`verify_l1_fill_depth`]). The
+/// recipient of the shares is the `l2_claimer_address` of the claim, or the
+/// swap's `l2_recipient` if none is given. The rest of the spent swap
+/// outputs must be re-locked to the swap in `SwapPending` outputs.
+///
+/// Returns the proven fills, to be appended to the swap's fill ledger.
+pub fn validate_partial_swap_claim(
+    state: &State,
+    rotxn: &RoTxn,
+    swap: &Swap,
+    filled_transaction: &FilledTransaction,
+) -> Result<Vec<SwapFill>, Error> {
+    let transaction = &filled_transaction.transaction;
+    let TxData::SwapClaim {
+        l2_claimer_address,
+        proof_data,
+        ..
+    } = &transaction.data
+    else {
+        return Err(Error::InvalidTransaction(
+            "Expected SwapClaim transaction".to_string(),
+        ));
+    };
+
+    // 1. Verify the swap can still be claimed
+    if matches!(swap.state, SwapState::Completed | SwapState::Cancelled) {
+        return Err(Error::InvalidTransaction(format!(
+            "Swap {} is not claimable (state: {:?})",
+            swap.id, swap.state
+        )));
+    }
+
+    // 2. Determine the recipient of the released share
+    let recipient =
+        l2_claimer_address.or(swap.l2_recipient).ok_or_else(|| {
+            Error::InvalidTransaction(
+                "Open swap claim requires l2_claimer_address".to_string(),
+            )
+        })?;
+
+    // 3. Verify the SPV proofs of the fills released by this claim
+    let Some(proof_data) = proof_data else {
+        return Err(Error::InvalidTransaction(format!(
+            "SwapClaim for swap {} requires L1 inclusion proofs of its fills",
+            swap.id
+        )));
+    };
+    let proofs = L1InclusionProof::decode_many(proof_data)?;
+    if proofs.is_empty() {
+        return Err(Error::InvalidTransaction(format!(
+            "SwapClaim for swap {} must prove at least one fill",
+            swap.id
+        )));
+    }
+    let fills = proven_swap_fills(state, rotxn, swap, &recipient, &proofs)?;
+    for (_, fill) in &fills {
+        let _: u32 = verify_l1_fill_depth(state, rotxn, swap, fill)?;
+    }
+    let release: bitcoin::Amount =
+        fills.iter().map(|(fill, _)| fill.l2_amount).sum();
+
+    // 4. Sum the spent outputs locked to this swap
+    let mut locked_value = bitcoin::Amount::ZERO;
+    for ((outpoint, _), spent_utxo) in transaction
+        .inputs
+        .iter()
+        .zip(&filled_transaction.spent_utxos)
+    {
+        match state.is_output_locked_to_swap(rotxn, outpoint)? {
+            Some(locked_swap_id) if locked_swap_id != swap.id => {
+                return Err(Error::InvalidTransaction(format!(
+                    "Input {} is locked to different swap {}",
+                    outpoint, locked_swap_id
+                )));
+            }
+            Some(_) => {
+                locked_value = locked_value
+                    .checked_add(spent_utxo.get_value())
+                    .ok_or_else(|| {
+                        Error::InvalidTransaction(
+                            "Input value overflow".to_string(),
+                        )
+                    })?;
+            }
+            None if spent_utxo.content.is_swap_pending() => {
+                return Err(Error::InvalidTransaction(format!(
+                    "SwapPending input {} is not locked to swap {}",
+                    outpoint, swap.id
+                )));
+            }
+            None => (),
+        }
+    }
+    if locked_value == bitcoin::Amount::ZERO {
+        return Err(Error::InvalidTransaction(
+            "SwapClaim must spend at least one output locked to the swap"
+                .to_string(),
+        ));
+    }
+    let remainder = locked_value.checked_sub(release).ok_or_else(|| {
+        Error::InvalidTransaction(format!(
+            "SwapClaim releases {}, but only spends {} locked to swap {}",
+            release, locked_value, swap.id
+        ))
+    })?;
+
+    // 5. Verify the remainder is re-locked to the swap
+    let mut relocked_value = bitcoin::Amount::ZERO;
+    for output in &transaction.outputs {
+        let OutputContent::SwapPending { value, swap_id } = &output.content
+        else {
+            continue;
+        };
+        if *swap_id != swap.id.0 {
+            return Err(Error::InvalidTransaction(format!(
+                "SwapPending output for swap {} in SwapClaim for swap {}",
+                hex::encode(swap_id),
+                swap.id
+            )));
+        }
+        relocked_value =
+            relocked_value.checked_add(*value).ok_or_else(|| {
+                Error::InvalidTransaction("Output value overflow".to_string())
+            })?;
+    }
+    if relocked_value != remainder {
+        return Err(Error::InvalidTransaction(format!(
+            "SwapClaim must re-lock {} to swap {}, re-locks {}",
+            remainder, swap.id, relocked_value
+        )));
+    }
+
+    // 6. Verify the released share goes to the recipient
+    validate_claim_payment(transaction, &recipient, release)?;
+
+    Ok(fills.into_iter().map(|(fill, _)| fill).collect())
+}
+
+/// Verify that the outputs of a SwapClaim pay at least `amount` to
+/// `recipient`. Outputs re-locked to the swap do not count.
+fn validate_claim_payment(
+    transaction: &Transaction,
+    recipient: &Address,
+    amount: bitcoin::Amount,
+) -> Result<(), Error> {
+    let paid = transaction
         .outputs
         .iter()
-        .any(|output| output.address == expected_recipient);
+        .filter(|output| {
+            output.address == *recipient && !output.content.is_swap_pending()
+        })
+        .try_fold(bitcoin::Amount::ZERO, |acc, output| {
+            acc.checked_add(output.get_value())
+        })
+        .ok_or_else(|| {
+            Error::InvalidTransaction("Output value overflow".to_string())
+        })?;
+    if paid < amount {
+        return Err(Error::InvalidTransaction(format!(
+            "SwapClaim must pay at least {} to {}, pays {}",
+            amount, recipient, paid
+        )));
+    }
+    Ok(())
+}
 
-    if !recipient_receives {
+/// Verify the SPV proofs of fills of a partially fillable swap, in order,
+/// following the fills already in its ledger. Each proof must show a
+/// payment within the swap's next fill range (see
+/// [`Swap::next_fill_range`]) to the swap's L1 recipient, in an L1
+/// transaction that has not paid this or any other swap, and the fill must
+/// be declared for `recipient` (see [`Swap::fill_claimer`]).
+///
+/// This does not check the depth of the fills (see
+/// [`verify_l1_fill_depth`]). Returns each fill with its L2 share.
+pub fn proven_swap_fills(
+    state: &State,
+    rotxn: &RoTxn,
+    swap: &Swap,
+    recipient: &Address,
+    proofs: &[L1InclusionProof],
+) -> Result<Vec<(SwapFill, VerifiedL1Fill)>, Error> {
+    let Some(l1_recipient_address) = swap.l1_recipient_address.as_ref() else {
         return Err(Error::InvalidTransaction(format!(
-            "SwapClaim must have at least one output to {}",
-            expected_recipient
+            "Swap {} has no L1 recipient to verify a proof against",
+            swap.id
         )));
+    };
+    let recipient_script =
+        l1_recipient_script(swap.parent_chain, l1_recipient_address)?;
+    let ledger = state.get_swap_fills(rotxn, &swap.id)?;
+    let mut filled: bitcoin::Amount =
+        ledger.iter().map(|fill| fill.l1_amount).sum();
+    let mut fills: Vec<(SwapFill, VerifiedL1Fill)> =
+        Vec::with_capacity(proofs.len());
+    for proof in proofs {
+        let Some((min_fill, max_fill)) = swap.next_fill_range(filled) else {
+            return Err(Error::InvalidTransaction(format!(
+                "Swap {} is already fully filled",
+                swap.id
+            )));
+        };
+        let fill = proof.verify_payment(
+            swap.parent_chain,
+            &recipient_script,
+            min_fill,
+            max_fill,
+        )?;
+        let l1_txid = SwapTxId::from_bitcoin_txid(&fill.txid);
+        if ledger
+            .iter()
+            .chain(fills.iter().map(|(fill, _)| fill))
+            .any(|fill| fill.l1_txid == l1_txid)
+        {
+            return Err(Error::InvalidTransaction(format!(
+                "Swap {}: L1 tx {} is already recorded as a fill",
+                swap.id, fill.txid
+            )));
+        }
+        validate_l1_txid_unused(state, rotxn, swap, &l1_txid)?;
+        if swap.fill_claimer(fill.l2_claimer_address) != Some(*recipient) {
+            return Err(Error::InvalidTransaction(format!(
+                "Swap {}: L1 fill {} is not declared for {}",
+                swap.id, fill.txid, recipient
+            )));
+        }
+        let l2_amount = swap.l2_share_of_fill(filled, fill.amount);
+        filled += fill.amount;
+        fills.push((
+            SwapFill {
+                l1_txid,
+                l1_amount: fill.amount,
+                l2_amount,
+                l2_claimer_address: *recipient,
+            },
+            fill,
+        ));
     }
+    Ok(fills)
+}
 
+/// Fills proven by a SwapClaim for a partially fillable swap (see
+/// [`proven_swap_fills`]), without checking their depth. Used to rebuild the
+/// fill ledger from claims that were validated when they were connected.
+pub fn claimed_swap_fills(
+    state: &State,
+    rotxn: &RoTxn,
+    swap: &Swap,
+    transaction: &Transaction,
+) -> Result<Vec<SwapFill>, Error> {
+    let TxData::SwapClaim {
+        l2_claimer_address,
+        proof_data: Some(proof_data),
+        ..
+    } = &transaction.data
+    else {
+        return Err(Error::InvalidTransaction(
+            "Expected SwapClaim transaction with L1 inclusion proofs"
+                .to_string(),
+        ));
+    };
+    let recipient =
+        l2_claimer_address.or(swap.l2_recipient).ok_or_else(|| {
+            Error::InvalidTransaction(
+                "Open swap claim requires l2_claimer_address".to_string(),
+            )
+        })?;
+    let proofs = L1InclusionProof::decode_many(proof_data)?;
+    let fills = proven_swap_fills(state, rotxn, swap, &recipient, &proofs)?;
+    Ok(fills.into_iter().map(|(fill, _)| fill).collect())
+}
+
+/// Fetch inclusion proofs of the confirmed L1 payments to a partially
+/// fillable swap's L1 recipient from `backend`, oldest first, as candidates
+/// for [`prove_swap_fills`].
+pub async fn fetch_swap_fill_proofs(
+    backend: &dyn L1Backend,
+    swap: &Swap,
+) -> Result<Vec<L1InclusionProof>, Error> {
+    let (Some(l1_recipient_address), Some(l1_amount)) =
+        (swap.l1_recipient_address.as_deref(), swap.l1_amount)
+    else {
+        return Ok(Vec::new());
+    };
+    let recipient_script =
+        l1_recipient_script(swap.parent_chain, l1_recipient_address)?;
+    let mut candidates = backend
+        .find_transactions_by_script_and_amount_range(
+            l1_recipient_address,
+            &recipient_script,
+            1,
+            l1_amount.to_sat(),
+        )
+        .await?;
+    // Older payments fill the swap first
+    candidates.sort_by_key(|(_, tx_info, _)| {
+        std::cmp::Reverse(tx_info.confirmations)
+    });
+    let mut proofs = Vec::new();
+    for (_, tx_info, _) in candidates {
+        if tx_info.blockhash.is_none() {
+            continue;
+        }
+        proofs.push(backend.get_inclusion_proof(&tx_info.txid).await?);
+    }
+    Ok(proofs)
+}
+
+/// Find the L1 fills of a partially fillable swap that `recipient` can
+/// claim, among the `candidates` fetched by [`fetch_swap_fill_proofs`]. A
+/// payment is only kept if it is a valid fill following the fills before
+/// it, with enough confirmations in the consensus header chain, so that the
+/// proofs can be used in a SwapClaim as they are.
+///
+/// Returns the proofs and the fills that they prove.
+pub fn prove_swap_fills(
+    state: &State,
+    rotxn: &RoTxn,
+    swap: &Swap,
+    recipient: &Address,
+    candidates: Vec<L1InclusionProof>,
+) -> Result<(Vec<L1InclusionProof>, Vec<SwapFill>), Error> {
+    let mut proofs = Vec::new();
+    let mut fills = Vec::new();
+    for proof in candidates {
+        if proofs.len() == MAX_PROOFS_PER_CLAIM {
+            break;
+        }
+        let l1_txid =
+            proof.decode_transaction().ok().map(|tx| tx.compute_txid());
+        proofs.push(proof);
+        let proven = proven_swap_fills(state, rotxn, swap, recipient, &proofs)
+            .and_then(|mut proven| {
+                let (fill, l1_fill) = proven.pop().expect("one fill per proof");
+                let _: u32 =
+                    verify_l1_fill_depth(state, rotxn, swap, &l1_fill)?;
+                Ok(fill)
+            });
+        match proven {
+            Ok(fill) => fills.push(fill),
+            Err(err) => {
+                tracing::debug!(
+                    swap_id = %swap.id,
+                    l1_txid = ?l1_txid,
+                    error = %err,
+                    "L1 payment is not a claimable fill"
+                );
+                let _: Option<L1InclusionProof> = proofs.pop();
+            }
+        }
+    }
+    Ok((proofs, fills))
+}
+
+/// Verify the SPV proof that a SwapClaim must carry.
+///
+/// The proof must show a payment of exactly the swap's L1 amount to the
+/// swap's L1 recipient (see [`verify_l1_fill_depth`] for where the payment
+/// must be), in an L1 transaction that has not been claimed by a different
+/// swap.
+pub fn verify_swap_claim_proof(
+    state: &State,
+    rotxn: &RoTxn,
+    swap: &Swap,
+    proof_data: Option<&[u8]>,
+) -> Result<VerifiedL1Fill, Error> {
+    let Some(proof_data) = proof_data else {
+        return Err(Error::InvalidTransaction(format!(
+            "SwapClaim for swap {} requires an L1 inclusion proof",
+            swap.id
+        )));
+    };
+    let (Some(l1_recipient_address), Some(l1_amount)) =
+        (swap.l1_recipient_address.as_ref(), swap.l1_amount)
+    else {
+        return Err(Error::InvalidTransaction(format!(
+            "Swap {} has no L1 recipient to verify a proof against",
+            swap.id
+        )));
+    };
+    let proof = L1InclusionProof::decode(proof_data)?;
+    let recipient_script =
+        l1_recipient_script(swap.parent_chain, l1_recipient_address)?;
+    let fill = proof.verify(swap.parent_chain, &recipient_script, l1_amount)?;
+    let _: u32 = verify_l1_fill_depth(state, rotxn, swap, &fill)?;
+    validate_l1_txid_unused(
+        state,
+        rotxn,
+        swap,
+        &SwapTxId::from_bitcoin_txid(&fill.txid),
+    )?;
+    Ok(fill)
+}
+
+/// Check that an L1 transaction has not paid a swap other than `swap`. Only
+/// claims are consensus, so the L1 tx counts as used once a claim has
+/// completed another swap with it, or recorded it as a fill of another
+/// partially fillable swap.
+fn validate_l1_txid_unused(
+    state: &State,
+    rotxn: &RoTxn,
+    swap: &Swap,
+    l1_txid: &SwapTxId,
+) -> Result<(), Error> {
+    let Some(existing) =
+        state.get_swap_by_l1_txid(rotxn, &swap.parent_chain, l1_txid)?
+    else {
+        return Ok(());
+    };
+    if existing.id == swap.id {
+        return Ok(());
+    }
+    let used = if existing.allows_partial_fills() {
+        state
+            .get_swap_fills(rotxn, &existing.id)?
+            .iter()
+            .any(|fill| fill.l1_txid == *l1_txid)
+    } else {
+        matches!(existing.state, SwapState::Completed)
+    };
+    if used {
+        return Err(Error::L1TxidAlreadyUsed {
+            swap_id: swap.id,
+            existing_swap_id: existing.id,
+        });
+    }
     Ok(())
 }
 
+/// Check that a proven L1 fill is in the best chain of the consensus header
+/// chain (see [`State::l1_headers`]), with at least the swap's required
+/// confirmations, and no more than the parent chain's maximum L1
+/// transaction age. Returns the number of confirmations.
+pub fn verify_l1_fill_depth(
+    state: &State,
+    rotxn: &RoTxn,
+    swap: &Swap,
+    fill: &VerifiedL1Fill,
+) -> Result<u32, Error> {
+    let confirmations = state.l1_headers().confirmations(
+        rotxn,
+        swap.parent_chain,
+        fill.block_hash,
+    )?;
+    if confirmations == 0 {
+        return Err(Error::L1BlockNotInBestChain {
+            parent_chain: swap.parent_chain,
+            block_hash: fill.block_hash,
+        });
+    }
+    if confirmations < swap.required_confirmations {
+        return Err(Error::InsufficientL1Confirmations {
+            swap_id: swap.id,
+            confirmations,
+            required: swap.required_confirmations,
+        });
+    }
+    let max_age = swap.parent_chain.max_l1_tx_age_blocks();
+    if confirmations > max_age {
+        return Err(Error::InvalidTransaction(format!(
+            "Swap {}: L1 tx is too old ({} confirmations exceeds max age of {} blocks for {:?})",
+            swap.id, confirmations, max_age, swap.parent_chain
+        )));
+    }
+    Ok(confirmations)
+}
+
+/// Which inputs of a transaction a SwapClaim releases without the signature
+/// of their owner: those locked to the claimed swap. Every other input,
+/// including an unlocked SwapPending output, must be signed by its owner.
+pub fn swap_claim_released_inputs(
+    state: &State,
+    rotxn: &RoTxn,
+    transaction: &Transaction,
+) -> Result<Vec<bool>, Error> {
+    let TxData::SwapClaim { swap_id, .. } = &transaction.data else {
+        return Ok(vec![false; transaction.inputs.len()]);
+    };
+    let swap_id = SwapId(*swap_id);
+    transaction
+        .inputs
+        .iter()
+        .map(|(outpoint, _)| {
+            Ok(state.is_output_locked_to_swap(rotxn, outpoint)?
+                == Some(swap_id))
+        })
+        .collect()
+}
+
 /// Validate that non-SwapClaim transactions don't spend locked outputs
 pub fn validate_no_locked_outputs(
     state: &State,
@@ -305,3 +1391,632 @@ pub fn validate_no_locked_outputs(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::Amount;
+    use futures::executor::block_on;
+
+    use super::{
+        fetch_swap_fill_proofs, prove_swap_fills, proven_swap_fills,
+        swap_claim_released_inputs, validate_claim_payment,
+        validate_full_swap_claim, validate_partial_swap_claim,
+        validate_swap_cancel, validate_swap_create, validate_swap_reserve,
+        verify_swap_claim_proof,
+    };
+    use crate::{
+        l1_backend::{L1Backend as _, mock::MockL1Backend},
+        state::{Error, State},
+        types::{
+            Address, FilledTransaction, L1InclusionProof, OutPoint, Output,
+            OutputContent, ParentChainType, Swap, SwapDirection, SwapFill,
+            SwapId, SwapState, SwapTxId, Transaction, TxData, Txid,
+            l2_claimer_declaration,
+        },
+    };
+
+    const L1_RECIPIENT: &str = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+
+    /// Open an empty state in a fresh environment
+    fn setup(name: &str) -> (sneed::Env, State) {
+        let path = std::env::temp_dir()
+            .join(format!("coinshift_swap_test_{name}_{}", std::process::id()));
+        drop(std::fs::remove_dir_all(&path)); // best-effort cleanup
+        std::fs::create_dir_all(&path).unwrap();
+        let mut env_open_opts = heed::EnvOpenOptions::new();
+        env_open_opts
+            .map_size(64 * 1024 * 1024)
+            .max_dbs(State::NUM_DBS);
+        let env = unsafe { sneed::Env::open(&env_open_opts, &path) }.unwrap();
+        let state = State::new(&env).unwrap();
+        (env, state)
+    }
+
+    /// Open L2 → L1 swap, filled by paying its L1 amount on `backend`.
+    /// Returns the swap and the txid of the fill.
+    fn filled_swap(backend: &MockL1Backend) -> (Swap, String) {
+        let swap = Swap::new(
+            SwapId([1u8; 32]),
+            SwapDirection::L2ToL1,
+            ParentChainType::Regtest,
+            SwapTxId::Hash32([0u8; 32]),
+            Some(3),
+            None,
+            Amount::from_sat(1_000_000),
+            Some(L1_RECIPIENT.to_owned()),
+            Some(Amount::from_sat(500_000)),
+            0,
+            None,
+            Some(Address([3u8; 20])),
+        );
+        let l1_txid = backend.send_to_script(
+            L1_RECIPIENT,
+            swap.l1_recipient_script.clone().unwrap(),
+            swap.l1_amount.unwrap(),
+        );
+        (swap, l1_txid)
+    }
+
+    /// Open, partially fillable L2 → L1 swap of 1_000_000 L2 for 500_000
+    /// L1, in fills of at least 100_000
+    fn partial_swap() -> Swap {
+        let mut swap = Swap::new(
+            SwapId([2u8; 32]),
+            SwapDirection::L2ToL1,
+            ParentChainType::Regtest,
+            SwapTxId::Hash32([0u8; 32]),
+            Some(3),
+            None,
+            Amount::from_sat(1_000_000),
+            Some(L1_RECIPIENT.to_owned()),
+            Some(Amount::from_sat(500_000)),
+            0,
+            None,
+            Some(Address([3u8; 20])),
+        );
+        swap.min_fill_l1_amount = Some(Amount::from_sat(100_000));
+        swap
+    }
+
+    /// Pay `amount` towards `swap` on `backend`, declaring `l2_claimer` if
+    /// given. Returns the txid.
+    fn send_fill(
+        backend: &MockL1Backend,
+        swap: &Swap,
+        amount: Amount,
+        l2_claimer: Option<Address>,
+    ) -> String {
+        let mut outputs = vec![bitcoin::TxOut {
+            value: amount,
+            script_pubkey: swap.l1_recipient_script.clone().unwrap(),
+        }];
+        outputs.extend(l2_claimer.map(|l2_claimer| bitcoin::TxOut {
+            value: Amount::ZERO,
+            script_pubkey: l2_claimer_declaration(&l2_claimer),
+        }));
+        backend.send_outputs(L1_RECIPIENT, outputs)
+    }
+
+    /// Relay the headers of `backend` into the consensus header chain, as a
+    /// sidechain block would
+    fn relay_headers(
+        state: &State,
+        rwtxn: &mut sneed::RwTxn,
+        backend: &MockL1Backend,
+    ) {
+        let relay_base = state
+            .l1_headers()
+            .relay_base(rwtxn, ParentChainType::Regtest)
+            .unwrap();
+        let headers = block_on(relay_base.fetch_relay(backend)).unwrap();
+        let _: usize = state
+            .l1_headers()
+            .connect_relay(rwtxn, ParentChainType::Regtest, &headers, 1)
+            .unwrap();
+    }
+
+    #[test]
+    fn claim_proof_needs_required_confirmations() {
+        let (env, state) = setup("confirmations");
+        let backend = MockL1Backend::new_regtest();
+        let (swap, l1_txid) = filled_swap(&backend);
+        let _tip = backend.mine_blocks(1);
+        let proof = block_on(backend.get_inclusion_proof(&l1_txid))
+            .unwrap()
+            .encode();
+        let mut rwtxn = env.write_txn().unwrap();
+        relay_headers(&state, &mut rwtxn, &backend);
+        assert!(matches!(
+            verify_swap_claim_proof(&state, &rwtxn, &swap, Some(&proof)),
+            Err(Error::InsufficientL1Confirmations {
+                confirmations: 1,
+                required: 3,
+                ..
+            })
+        ));
+
+        let _tip = backend.mine_blocks(2);
+        relay_headers(&state, &mut rwtxn, &backend);
+        let fill = verify_swap_claim_proof(&state, &rwtxn, &swap, Some(&proof))
+            .unwrap();
+        assert_eq!(fill.txid.to_string(), l1_txid);
+    }
+
+    #[test]
+    fn claim_proof_must_be_in_consensus_header_chain() {
+        let (env, state) = setup("forged_header");
+        let backend = MockL1Backend::new_regtest();
+        let _tip = backend.mine_blocks(10);
+        let mut rwtxn = env.write_txn().unwrap();
+        relay_headers(&state, &mut rwtxn, &backend);
+
+        // The claimer mines their own block with valid proof of work, which
+        // the consensus header chain does not contain
+        let forged = MockL1Backend::new_regtest();
+        let (swap, l1_txid) = filled_swap(&forged);
+        let _tip = forged.mine_blocks(3);
+        let proof = block_on(forged.get_inclusion_proof(&l1_txid))
+            .unwrap()
+            .encode();
+        assert!(matches!(
+            verify_swap_claim_proof(&state, &rwtxn, &swap, Some(&proof)),
+            Err(Error::L1BlockNotInBestChain { .. })
+        ));
+        assert!(verify_swap_claim_proof(&state, &rwtxn, &swap, None).is_err());
+    }
+
+    #[test]
+    fn partial_fills_are_bound_to_their_declared_claimer() {
+        let (env, state) = setup("declared_claimer");
+        let backend = MockL1Backend::new_regtest();
+        let swap = partial_swap();
+        let alice = Address([1u8; 20]);
+        let bob = Address([2u8; 20]);
+        let alice_txid =
+            send_fill(&backend, &swap, Amount::from_sat(200_000), Some(alice));
+        let undeclared_txid =
+            send_fill(&backend, &swap, Amount::from_sat(100_000), None);
+        let _tip = backend.mine_blocks(3);
+        let alice_proof =
+            block_on(backend.get_inclusion_proof(&alice_txid)).unwrap();
+        let undeclared_proof =
+            block_on(backend.get_inclusion_proof(&undeclared_txid)).unwrap();
+        let mut rwtxn = env.write_txn().unwrap();
+        relay_headers(&state, &mut rwtxn, &backend);
+
+        // Alice's fill cannot be claimed by anyone else
+        assert!(
+            proven_swap_fills(
+                &state,
+                &rwtxn,
+                &swap,
+                &bob,
+                std::slice::from_ref(&alice_proof)
+            )
+            .is_err()
+        );
+        // An undeclared fill of an open swap cannot be claimed at all
+        assert!(
+            proven_swap_fills(
+                &state,
+                &rwtxn,
+                &swap,
+                &alice,
+                std::slice::from_ref(&undeclared_proof)
+            )
+            .is_err()
+        );
+        // A fill cannot be claimed twice
+        assert!(
+            proven_swap_fills(
+                &state,
+                &rwtxn,
+                &swap,
+                &alice,
+                &[alice_proof.clone(), alice_proof.clone()]
+            )
+            .is_err()
+        );
+        let fills = proven_swap_fills(
+            &state,
+            &rwtxn,
+            &swap,
+            &alice,
+            std::slice::from_ref(&alice_proof),
+        )
+        .unwrap();
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].0.l1_amount, Amount::from_sat(200_000));
+        assert_eq!(fills[0].0.l2_amount, Amount::from_sat(400_000));
+        assert_eq!(fills[0].0.l2_claimer_address, alice);
+
+        // Only the declared fill is proven for alice
+        let candidates =
+            block_on(fetch_swap_fill_proofs(&backend, &swap)).unwrap();
+        let (proofs, fills) =
+            prove_swap_fills(&state, &rwtxn, &swap, &alice, candidates)
+                .unwrap();
+        assert_eq!(proofs, vec![alice_proof]);
+        assert_eq!(fills.len(), 1);
+    }
+
+    #[test]
+    fn open_swap_fill_cannot_be_claimed_by_third_party() {
+        let (env, state) = setup("third_party_claim");
+        let backend = MockL1Backend::new_regtest();
+        let (swap, undeclared_txid) = filled_swap(&backend);
+        let alice = Address([1u8; 20]);
+        let bob = Address([2u8; 20]);
+        let alice_txid =
+            send_fill(&backend, &swap, swap.l1_amount.unwrap(), Some(alice));
+        let _tip = backend.mine_blocks(3);
+        let mut rwtxn = env.write_txn().unwrap();
+        relay_headers(&state, &mut rwtxn, &backend);
+        let locked = OutPoint::Regular {
+            txid: Txid([1u8; 32]),
+            vout: 0,
+        };
+        state
+            .lock_output_to_swap(&mut rwtxn, &locked, &swap.id)
+            .unwrap();
+        let claim = |claimer: Address, l1_txid: &str| {
+            let proof = block_on(backend.get_inclusion_proof(l1_txid)).unwrap();
+            FilledTransaction {
+                transaction: Transaction {
+                    inputs: vec![(locked, [0u8; 32])],
+                    outputs: vec![Output {
+                        address: claimer,
+                        content: OutputContent::Value(swap.l2_amount),
+                    }],
+                    data: TxData::SwapClaim {
+                        swap_id: swap.id.0,
+                        l2_claimer_address: Some(claimer),
+                        proof_data: Some(proof.encode()),
+                    },
+                    ..Transaction::default()
+                },
+                spent_utxos: vec![Output {
+                    address: Address([3u8; 20]),
+                    content: OutputContent::SwapPending {
+                        value: swap.l2_amount,
+                        swap_id: swap.id.0,
+                    },
+                }],
+            }
+        };
+
+        // Bob cannot claim with Alice's fill
+        assert!(
+            validate_full_swap_claim(
+                &state,
+                &rwtxn,
+                &swap,
+                &claim(bob, &alice_txid)
+            )
+            .is_err()
+        );
+        // An undeclared fill of an open swap cannot be claimed at all
+        assert!(
+            validate_full_swap_claim(
+                &state,
+                &rwtxn,
+                &swap,
+                &claim(bob, &undeclared_txid)
+            )
+            .is_err()
+        );
+        let fill = validate_full_swap_claim(
+            &state,
+            &rwtxn,
+            &swap,
+            &claim(alice, &alice_txid),
+        )
+        .unwrap();
+        assert_eq!(fill.l2_claimer_address, Some(alice));
+    }
+
+    #[test]
+    fn claim_must_pay_recipient_in_full() {
+        let alice = Address([1u8; 20]);
+        let mut transaction = Transaction {
+            outputs: vec![
+                Output {
+                    address: alice,
+                    content: OutputContent::Value(Amount::from_sat(600_000)),
+                },
+                // Outputs re-locked to the swap do not count
+                Output {
+                    address: alice,
+                    content: OutputContent::SwapPending {
+                        value: Amount::from_sat(400_000),
+                        swap_id: [2u8; 32],
+                    },
+                },
+            ],
+            ..Transaction::default()
+        };
+        assert!(
+            validate_claim_payment(
+                &transaction,
+                &alice,
+                Amount::from_sat(1_000_000)
+            )
+            .is_err()
+        );
+        transaction.outputs.push(Output {
+            address: alice,
+            content: OutputContent::Value(Amount::from_sat(400_000)),
+        });
+        validate_claim_payment(
+            &transaction,
+            &alice,
+            Amount::from_sat(1_000_000),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn swaps_cannot_be_created_on_chains_without_proofs() {
+        let (env, state) = setup("unprovable_chain");
+        let creator = Address([1u8; 20]);
+        let l2_recipient = Address([2u8; 20]);
+        let l1_amount = Amount::from_sat(500_000);
+        let l2_amount = Amount::from_sat(1_000_000);
+        let swap_id = SwapId::from_l1_to_l2(
+            l1_amount,
+            l2_amount,
+            &creator,
+            &l2_recipient,
+        );
+        let swap_create = |parent_chain: ParentChainType| FilledTransaction {
+            transaction: Transaction {
+                inputs: vec![(
+                    OutPoint::Regular {
+                        txid: Txid([1u8; 32]),
+                        vout: 0,
+                    },
+                    [0u8; 32],
+                )],
+                outputs: vec![Output {
+                    address: creator,
+                    content: OutputContent::Value(Amount::from_sat(10_000)),
+                }],
+                data: TxData::SwapCreate {
+                    swap_id: swap_id.0,
+                    parent_chain,
+                    l1_txid_bytes: vec![0u8; 32],
+                    required_confirmations: 6,
+                    l2_recipient: Some(l2_recipient),
+                    l2_amount: l2_amount.to_sat(),
+                    l1_recipient_address: None,
+                    l1_amount: Some(l1_amount.to_sat()),
+                    min_fill_l1_amount: None,
+                    expires_in_blocks: None,
+                },
+                ..Transaction::default()
+            },
+            spent_utxos: vec![Output {
+                address: creator,
+                content: OutputContent::Value(Amount::from_sat(10_000)),
+            }],
+        };
+        let rotxn = env.read_txn().unwrap();
+        let validate = |parent_chain| {
+            let filled = swap_create(parent_chain);
+            validate_swap_create(&state, &rotxn, &filled.transaction, &filled)
+        };
+        assert!(validate(ParentChainType::Regtest).is_ok());
+        // Scrypt proof of work and signet blocks cannot be verified
+        for parent_chain in [ParentChainType::LTC, ParentChainType::Signet] {
+            assert!(matches!(
+                validate(parent_chain),
+                Err(Error::InvalidTransaction(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn claim_cannot_spend_unlocked_swap_pending_inputs() {
+        let (env, state) = setup("unlocked_pending");
+        let backend = MockL1Backend::new_regtest();
+        let swap = partial_swap();
+        let alice = Address([1u8; 20]);
+        let bob = Address([2u8; 20]);
+        let alice_txid =
+            send_fill(&backend, &swap, Amount::from_sat(200_000), Some(alice));
+        let _tip = backend.mine_blocks(3);
+        let alice_proof =
+            block_on(backend.get_inclusion_proof(&alice_txid)).unwrap();
+        let mut rwtxn = env.write_txn().unwrap();
+        relay_headers(&state, &mut rwtxn, &backend);
+
+        let locked = OutPoint::Regular {
+            txid: Txid([1u8; 32]),
+            vout: 0,
+        };
+        state
+            .lock_output_to_swap(&mut rwtxn, &locked, &swap.id)
+            .unwrap();
+        let mut filled_transaction = FilledTransaction {
+            transaction: Transaction {
+                inputs: vec![(locked, [0u8; 32])],
+                outputs: vec![
+                    Output {
+                        address: alice,
+                        content: OutputContent::Value(Amount::from_sat(
+                            400_000,
+                        )),
+                    },
+                    Output {
+                        address: Address([3u8; 20]),
+                        content: OutputContent::SwapPending {
+                            value: Amount::from_sat(600_000),
+                            swap_id: swap.id.0,
+                        },
+                    },
+                ],
+                data: TxData::SwapClaim {
+                    swap_id: swap.id.0,
+                    l2_claimer_address: Some(alice),
+                    proof_data: Some(L1InclusionProof::encode_many(&[
+                        alice_proof,
+                    ])),
+                },
+                ..Transaction::default()
+            },
+            spent_utxos: vec![Output {
+                address: Address([3u8; 20]),
+                content: OutputContent::SwapPending {
+                    value: Amount::from_sat(1_000_000),
+                    swap_id: swap.id.0,
+                },
+            }],
+        };
+        let fills = validate_partial_swap_claim(
+            &state,
+            &rwtxn,
+            &swap,
+            &filled_transaction,
+        )
+        .unwrap();
+        assert_eq!(fills.len(), 1);
+
+        // Bob's SwapPending output names the swap, but is not locked to it:
+        // the claim may neither spend it nor skip his signature
+        let unlocked = OutPoint::Regular {
+            txid: Txid([1u8; 32]),
+            vout: 1,
+        };
+        filled_transaction
+            .transaction
+            .inputs
+            .push((unlocked, [0u8; 32]));
+        filled_transaction.spent_utxos.push(Output {
+            address: bob,
+            content: OutputContent::SwapPending {
+                value: Amount::from_sat(50_000),
+                swap_id: swap.id.0,
+            },
+        });
+        assert!(
+            validate_partial_swap_claim(
+                &state,
+                &rwtxn,
+                &swap,
+                &filled_transaction
+            )
+            .is_err()
+        );
+        assert_eq!(
+            swap_claim_released_inputs(
+                &state,
+                &rwtxn,
+                &filled_transaction.transaction
+            )
+            .unwrap(),
+            vec![true, false]
+        );
+    }
+
+    #[test]
+    fn cancel_depends_only_on_consensus_records() {
+        let (env, state) = setup("cancel");
+        let backend = MockL1Backend::new_regtest();
+        let (mut swap, l1_txid) = filled_swap(&backend);
+        let creator = Address([3u8; 20]);
+        let cancel = |signer: Address| FilledTransaction {
+            transaction: Transaction {
+                inputs: vec![(
+                    OutPoint::Regular {
+                        txid: Txid([4u8; 32]),
+                        vout: 0,
+                    },
+                    [0u8; 32],
+                )],
+                data: TxData::SwapCancel { swap_id: swap.id.0 },
+                ..Transaction::default()
+            },
+            spent_utxos: vec![Output {
+                address: signer,
+                content: OutputContent::Value(Amount::from_sat(1_000)),
+            }],
+        };
+        let mut rwtxn = env.write_txn().unwrap();
+
+        // An L1 payment seen by this node does not prevent a cancellation
+        swap.state = SwapState::ReadyToClaim;
+        swap.update_l1_txid(SwapTxId::from_hex(&l1_txid).unwrap());
+        state.save_swap(&mut rwtxn, &swap).unwrap();
+        validate_swap_cancel(&state, &rwtxn, &cancel(creator)).unwrap();
+        assert!(matches!(
+            validate_swap_cancel(&state, &rwtxn, &cancel(Address([1u8; 20]))),
+            Err(Error::SwapNotCreator)
+        ));
+
+        // A claimed fill does
+        state
+            .put_swap_fills(
+                &mut rwtxn,
+                &swap.id,
+                swap.parent_chain,
+                &[SwapFill {
+                    l1_txid: swap.l1_txid.clone(),
+                    l1_amount: swap.l1_amount.unwrap(),
+                    l2_amount: swap.l2_amount,
+                    l2_claimer_address: Address([1u8; 20]),
+                }],
+            )
+            .unwrap();
+        assert!(
+            validate_swap_cancel(&state, &rwtxn, &cancel(creator)).is_err()
+        );
+
+        swap.state = SwapState::Completed;
+        state.save_swap(&mut rwtxn, &swap).unwrap();
+        assert!(
+            validate_swap_cancel(&state, &rwtxn, &cancel(creator)).is_err()
+        );
+    }
+
+    #[test]
+    fn reserve_depends_only_on_consensus_records() {
+        let (env, state) = setup("reserve");
+        let backend = MockL1Backend::new_regtest();
+        let (mut swap, l1_txid) = filled_swap(&backend);
+        let reserve = Transaction {
+            inputs: vec![(
+                OutPoint::Regular {
+                    txid: Txid([4u8; 32]),
+                    vout: 0,
+                },
+                [0u8; 32],
+            )],
+            outputs: vec![Output {
+                address: Address([3u8; 20]),
+                content: OutputContent::SwapPending {
+                    value: swap.reservation_bond(),
+                    swap_id: swap.id.0,
+                },
+            }],
+            data: TxData::SwapReserve {
+                swap_id: swap.id.0,
+                l2_claimer_address: Address([1u8; 20]),
+                reserve_blocks: 1,
+            },
+            ..Transaction::default()
+        };
+        let mut rwtxn = env.write_txn().unwrap();
+
+        // An L1 payment seen by this node does not prevent a reservation
+        swap.state = SwapState::ReadyToClaim;
+        swap.update_l1_txid(SwapTxId::from_hex(&l1_txid).unwrap());
+        swap.set_l2_claimer_address(Address([2u8; 20]));
+        state.save_swap(&mut rwtxn, &swap).unwrap();
+        validate_swap_reserve(&state, &rwtxn, &reserve).unwrap();
+
+        // A claim does
+        swap.state = SwapState::Completed;
+        state.save_swap(&mut rwtxn, &swap).unwrap();
+        assert!(validate_swap_reserve(&state, &rwtxn, &reserve).is_err());
+    }
+}
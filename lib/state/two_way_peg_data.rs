@@ -5,18 +5,20 @@ use std::collections::{BTreeMap, HashMap};
 use fallible_iterator::FallibleIterator;
 use sneed::{RoTxn, RwTxn, db::error::Error as DbError};
 
-use crate::parent_chain_rpc::{ParentChainRpcClient, RpcConfig};
 use crate::{
+    l1_backend::{L1Backend, snapshot::L1Snapshot},
+    l1_scan::{L1ScanCache, L1ScanPlan, ScannedPayments},
     state::{
         Error, State, WITHDRAWAL_BUNDLE_FAILURE_GAP, WithdrawalBundleInfo,
         rollback::RollBack,
+        swap::{self, SwapUndo, SwapUndoSource},
     },
     types::{
         AccumulatorDiff, AggregatedWithdrawal, AmountOverflowError, BlockHash,
         GetValue, InPoint, M6id, OutPoint, OutPointKey, Output, OutputContent,
         ParentChainType, PointedOutput, PointedOutputRef, SpentOutput, Swap,
         SwapId, SwapState, SwapTxId, WithdrawalBundle, WithdrawalBundleEvent,
-        WithdrawalBundleStatus, hash,
+        WithdrawalBundleStatus, hash, l1_recipient_script,
         proto::mainchain::{BlockEvent, TwoWayPegData},
     },
     wallet::Wallet,
@@ -529,6 +531,18 @@ fn connect_event(
     Ok(())
 }
 
+/// Output script that L1 payments to a swap must pay. Swaps stored
+/// without a script derive it from their L1 recipient address.
+fn swap_recipient_script(
+    swap: &Swap,
+    l1_recipient: &str,
+) -> Result<bitcoin::ScriptBuf, Error> {
+    match &swap.l1_recipient_script {
+        Some(script) => Ok(script.clone()),
+        None => Ok(l1_recipient_script(swap.parent_chain, l1_recipient)?),
+    }
+}
+
 /// Process coinshift transactions - update swap states based on L1 transactions
 /// This should be called when connecting 2WPD to check for L1 transactions
 /// that match pending swaps.
@@ -539,13 +553,14 @@ fn connect_event(
 /// Flow:
 /// 1. Get all pending swaps
 /// 2. For each swap, query swap.parent_chain (e.g., Signet) for transactions
-/// 3. Match transactions by: l1_recipient_address and l1_amount
+/// 3. Match transactions by: l1_recipient_script and l1_amount
 /// 4. Update swap state based on found transactions and confirmations
 ///
-/// **BMM / header chain / merkle proof:** None of these are used for swap L1
-/// verification in this repo. L1 presence and confirmations are taken from the
-/// configured parent chain RPC only (no BMM reports, no per–parent-chain header
-/// chain for swaps, no merkle proof of L1 tx in block).
+/// **Header chain:** L1 presence is discovered via the configured parent
+/// chain RPC, but confirmations are counted against the consensus
+/// per-parent-chain header chain ([`State::l1_headers`]), which only advances
+/// through header relays in sidechain blocks. A transaction in a block that
+/// is not on the best header chain has zero confirmations.
 ///
 /// Query L1 blockchain for matching transactions and update swap.
 ///
@@ -559,46 +574,73 @@ fn connect_event(
 fn query_and_update_swap(
     state: &State,
     rwtxn: &mut RwTxn,
-    rpc_config: &RpcConfig,
+    payments: &ScannedPayments,
     swap: &mut Swap,
     l1_recipient: &str,
     l1_amount: bitcoin::Amount,
     block_hash: BlockHash,
     block_height: u32,
 ) -> Result<bool, Error> {
-    let client = ParentChainRpcClient::new(rpc_config.clone());
+    // An open swap only accepts a new fill while a taker holds a live
+    // reservation, so that the L1 payment can only come from that taker
+    if swap.l2_recipient.is_none()
+        && !swap.has_l1_txid()
+        && swap.live_reservation(block_height).is_none()
+    {
+        tracing::debug!(
+            swap_id = %swap.id,
+            "Open swap is not reserved; not accepting L1 fills"
+        );
+        return Ok(false);
+    }
+
     let amount_sats = l1_amount.to_sat();
+    let recipient_script = swap_recipient_script(swap, l1_recipient)?;
 
-    // Find transactions matching address and amount
-    let matches = client
-        .find_transactions_by_address_and_amount(l1_recipient, amount_sats)?;
+    // Find transactions matching script and amount
+    let matches = payments.find_transactions_by_script_and_amount(
+        l1_recipient,
+        &recipient_script,
+        amount_sats,
+    )?;
 
     if matches.is_empty() {
         return Ok(false);
     }
 
-    // Only accept transactions that are confirmed and included in a block,
-    // and not older than the chain's max L1 tx age
+    // Only accept transactions that are included in a block on the best
+    // header chain, and not older than the chain's max L1 tx age
     let max_age = swap.parent_chain.max_l1_tx_age_blocks();
-    let matches: Vec<_> = matches
-        .into_iter()
-        .filter(|(_, tx_info)| {
-            if tx_info.confirmations == 0 || tx_info.blockheight.is_none() {
-                return false;
-            }
-            if tx_info.confirmations > max_age {
-                tracing::info!(
-                    swap_id = %swap.id,
-                    l1_txid = %tx_info.txid,
-                    confirmations = %tx_info.confirmations,
-                    max_age = %max_age,
-                    "Rejecting L1 tx: too old (confirmations exceed max_l1_tx_age_blocks)"
-                );
-                return false;
-            }
-            true
-        })
-        .collect();
+    let mut confirmed_matches = Vec::with_capacity(matches.len());
+    for (sender_address, tx_info) in matches {
+        let Some(l1_block_hash) = tx_info
+            .blockhash
+            .as_deref()
+            .and_then(|block_hash| block_hash.parse().ok())
+        else {
+            continue;
+        };
+        let confirmations = state.l1_headers().confirmations(
+            rwtxn,
+            swap.parent_chain,
+            l1_block_hash,
+        )?;
+        if confirmations == 0 {
+            continue;
+        }
+        if confirmations > max_age {
+            tracing::info!(
+                swap_id = %swap.id,
+                l1_txid = %tx_info.txid,
+                confirmations = %confirmations,
+                max_age = %max_age,
+                "Rejecting L1 tx: too old (confirmations exceed max_l1_tx_age_blocks)"
+            );
+            continue;
+        }
+        confirmed_matches.push((sender_address, tx_info, confirmations));
+    }
+    let matches = confirmed_matches;
     if matches.is_empty() {
         tracing::debug!(
             swap_id = %swap.id,
@@ -609,7 +651,8 @@ fn query_and_update_swap(
 
     // Use the first valid match (most recent transaction)
     // In a production system, you might want to handle multiple matches differently
-    let (sender_address, tx_info) = &matches[0];
+    let (sender_address, tx_info, confirmations) = &matches[0];
+    let confirmations = *confirmations;
 
     // Convert txid string from parent chain RPC (RPC byte order) to SwapTxId (canonical storage)
     let l1_txid = SwapTxId::from_hex_rpc(&tx_info.txid)
@@ -639,7 +682,7 @@ fn query_and_update_swap(
         tracing::info!(
             swap_id = %swap.id,
             l1_txid = %tx_info.txid,
-            confirmations = %tx_info.confirmations,
+            confirmations = %confirmations,
             sender = %sender_address,
             is_open_swap = %swap.l2_recipient.is_none(),
             "Detected new L1 transaction for swap"
@@ -654,11 +697,11 @@ fn query_and_update_swap(
         swap.set_l1_txid_validation_block(block_hash, block_height);
 
         // Update state based on confirmations
-        if tx_info.confirmations >= swap.required_confirmations {
+        if confirmations >= swap.required_confirmations {
             swap.state = SwapState::ReadyToClaim;
         } else {
             swap.state = SwapState::WaitingConfirmations(
-                tx_info.confirmations,
+                confirmations,
                 swap.required_confirmations,
             );
         }
@@ -671,19 +714,19 @@ fn query_and_update_swap(
             _ => 0,
         };
 
-        if tx_info.confirmations > current_confirmations {
+        if confirmations > current_confirmations {
             tracing::debug!(
                 swap_id = %swap.id,
                 old_confirmations = %current_confirmations,
-                new_confirmations = %tx_info.confirmations,
+                new_confirmations = %confirmations,
                 "Updating swap confirmations"
             );
 
-            if tx_info.confirmations >= swap.required_confirmations {
+            if confirmations >= swap.required_confirmations {
                 swap.state = SwapState::ReadyToClaim;
             } else {
                 swap.state = SwapState::WaitingConfirmations(
-                    tx_info.confirmations,
+                    confirmations,
                     swap.required_confirmations,
                 );
             }
@@ -695,12 +738,203 @@ fn query_and_update_swap(
     }
 }
 
+/// Process a partially fillable swap while connecting 2WPD.
+///
+/// Fills are recorded by the claims that prove them, so there is nothing to
+/// look up on L1. Once expired, the swap is cancelled and the rest of its
+/// outputs unlocked.
+///
+/// Returns `true` if the swap was cancelled.
+fn process_partial_swap(
+    state: &State,
+    rwtxn: &mut RwTxn,
+    block_height: u32,
+    swap: &Swap,
+    swap_undo: &mut SwapUndo,
+) -> Result<bool, Error> {
+    let expired = swap
+        .expires_at_height
+        .is_some_and(|expires_at| block_height >= expires_at);
+    if !expired {
+        return Ok(false);
+    }
+    let fills = state.get_swap_fills(rwtxn, &swap.id)?;
+    let unlocked_count =
+        swap_undo.unlock_swap_outputs(state, rwtxn, swap.id)?;
+    tracing::info!(
+        swap_id = %swap.id,
+        block_height = %block_height,
+        fills = %fills.len(),
+        unlocked_outputs = %unlocked_count,
+        "Partially fillable swap expired, unlocking remaining outputs and marking as cancelled"
+    );
+    let mut swap = swap.clone();
+    swap.state = SwapState::Cancelled;
+    swap_undo.record_swap(state, rwtxn, swap.id)?;
+    state.save_swap(rwtxn, &swap)?;
+    Ok(true)
+}
+
+/// Recipient scripts (with their addresses) of active swaps, by parent
+/// chain
+fn watched_scripts(
+    swaps: &[Swap],
+) -> HashMap<ParentChainType, Vec<(bitcoin::ScriptBuf, String)>> {
+    let mut watched = HashMap::<ParentChainType, Vec<_>>::new();
+    for swap in swaps {
+        // Fills of partially fillable swaps are proven by their claims
+        let active = !swap.allows_partial_fills()
+            && matches!(
+                swap.state,
+                SwapState::Pending | SwapState::WaitingConfirmations(..)
+            );
+        let Some(l1_recipient) = swap.l1_recipient_address.as_deref() else {
+            continue;
+        };
+        if !active {
+            continue;
+        }
+        let Ok(script) = swap_recipient_script(swap, l1_recipient) else {
+            continue;
+        };
+        let scripts: &mut Vec<(bitcoin::ScriptBuf, String)> =
+            watched.entry(swap.parent_chain).or_default();
+        if !scripts
+            .iter()
+            .any(|(watched_script, _)| *watched_script == script)
+        {
+            scripts.push((script, l1_recipient.to_owned()));
+        }
+    }
+    watched
+}
+
+/// Plan the L1 data to fetch for connecting 2WPD, for each parent chain with
+/// active swaps (see [`L1ScanCache::plan`]). Parent chains that cannot be
+/// planned are logged and skipped.
+pub fn plan_l1_scans(
+    state: &State,
+    rotxn: &RoTxn,
+    l1_scan: &L1ScanCache,
+) -> Result<HashMap<ParentChainType, L1ScanPlan>, Error> {
+    let swaps = state.load_all_swaps(rotxn)?;
+    let mut plans = HashMap::new();
+    for (parent_chain, scripts) in watched_scripts(&swaps) {
+        match l1_scan.plan(rotxn, state.l1_headers(), parent_chain, &scripts) {
+            Ok(plan) => {
+                plans.insert(parent_chain, plan);
+            }
+            Err(err) => {
+                tracing::warn!(
+                    ?parent_chain,
+                    error = %err,
+                    "Failed to plan L1 scan; skipping L1 lookups for swaps on this parent chain"
+                );
+            }
+        }
+    }
+    Ok(plans)
+}
+
+/// Fetch the L1 data planned by [`plan_l1_scans`] from the backend of each
+/// parent chain. This is done before taking the write lock, so that
+/// connecting 2WPD does no network I/O. Parent chains whose backend fails
+/// are logged and skipped.
+pub async fn fetch_l1(
+    plans: &HashMap<ParentChainType, L1ScanPlan>,
+    get_l1_backend: impl Fn(ParentChainType) -> Option<Box<dyn L1Backend>>,
+) -> HashMap<ParentChainType, L1Snapshot> {
+    let mut snapshots = HashMap::new();
+    for (parent_chain, plan) in plans {
+        let Some(backend) = get_l1_backend(*parent_chain) else {
+            continue;
+        };
+        match plan.fetch(backend.as_ref()).await {
+            Ok(snapshot) => {
+                snapshots.insert(*parent_chain, snapshot);
+            }
+            Err(err) => {
+                tracing::warn!(
+                    ?parent_chain,
+                    backend = %backend.name(),
+                    error = %err,
+                    "Failed to fetch L1 data; skipping L1 lookups for swaps on this parent chain"
+                );
+            }
+        }
+    }
+    snapshots
+}
+
+/// Scan the parent chains of active swaps for payments to their L1
+/// recipients (see [`L1ScanCache::scan`]), from the L1 data fetched by
+/// [`fetch_l1`]. Returns the payments for each parent chain with fetched
+/// data. If the scan succeeded, swap payments are found among the cached
+/// transactions; otherwise swaps are looked up individually in the fetched
+/// data.
+///
+/// Swaps whose scripts were not watched when the data was fetched (e.g.
+/// swaps created in the block being connected) are scanned at a later
+/// connect.
+fn scan_parent_chains<'a>(
+    state: &State,
+    rwtxn: &mut RwTxn,
+    l1_scan: &L1ScanCache,
+    l1_snapshots: &'a HashMap<ParentChainType, L1Snapshot>,
+    swaps: &[Swap],
+) -> Result<HashMap<ParentChainType, ScannedPayments<'a>>, Error> {
+    let mut scanned = HashMap::new();
+    for (parent_chain, mut scripts) in watched_scripts(swaps) {
+        let Some(snapshot) = l1_snapshots.get(&parent_chain) else {
+            continue;
+        };
+        scripts.retain(|(script, _)| snapshot.watches(script));
+        let payments = match l1_scan.scan(
+            rwtxn,
+            state.l1_headers(),
+            parent_chain,
+            snapshot,
+            &scripts,
+        ) {
+            Ok(cursor) => {
+                tracing::debug!(
+                    ?parent_chain,
+                    l1_block_hash = %cursor.block_hash,
+                    l1_height = %cursor.height,
+                    watched_scripts = %scripts.len(),
+                    "Scanned parent chain for swap payments"
+                );
+                l1_scan.scanned_payments(
+                    rwtxn,
+                    parent_chain,
+                    snapshot,
+                    &scripts,
+                )?
+            }
+            Err(err) => {
+                tracing::warn!(
+                    ?parent_chain,
+                    backend = %snapshot.name(),
+                    error = %err,
+                    "Failed to scan parent chain; looking up swaps individually"
+                );
+                ScannedPayments::unscanned(snapshot)
+            }
+        };
+        scanned.insert(parent_chain, payments);
+    }
+    Ok(scanned)
+}
+
+#[allow(clippy::too_many_arguments)]
 fn process_coinshift_transactions(
     state: &State,
     rwtxn: &mut RwTxn,
     block_height: u32,
     block_hash: BlockHash,
-    rpc_config_getter: Option<&dyn Fn(ParentChainType) -> Option<RpcConfig>>,
+    l1_scan: &L1ScanCache,
+    l1_snapshots: &HashMap<ParentChainType, L1Snapshot>,
+    swap_undo: &mut SwapUndo,
 ) -> Result<(), Error> {
     tracing::debug!(%block_height, "Starting to scan enforcer for coinshift transactions");
 
@@ -713,16 +947,38 @@ fn process_coinshift_transactions(
         "Loaded swaps from state, scanning enforcer for matching transactions"
     );
 
+    // Scan each swap parent chain once, rather than per swap
+    let l1_payments =
+        scan_parent_chains(state, rwtxn, l1_scan, l1_snapshots, &swaps)?;
+
     let mut pending_swaps_count = 0;
     let mut expired_swaps_count = 0;
     let mut scanned_swaps_count = 0;
 
     for mut swap in swaps {
-        // Only process L2 → L1 swaps that are pending or waiting for confirmations
-        if !matches!(
-            swap.state,
-            SwapState::Pending | SwapState::WaitingConfirmations(..)
-        ) {
+        // Partially fillable swaps keep accepting fills until they are
+        // completed or cancelled
+        if swap.allows_partial_fills() {
+            if swap.is_closed() {
+                continue;
+            }
+            pending_swaps_count += 1;
+            if process_partial_swap(
+                state,
+                rwtxn,
+                block_height,
+                &swap,
+                swap_undo,
+            )? {
+                expired_swaps_count += 1;
+            }
+            continue;
+        }
+
+        // Expiry and reservations follow the L2 height alone, whatever L1
+        // payments this node has seen, so every swap that has not been
+        // claimed or cancelled is processed
+        if swap.is_closed() {
             continue;
         }
 
@@ -748,20 +1004,8 @@ fn process_coinshift_transactions(
             && block_height >= expires_at
         {
             // Unlock all outputs locked to this swap so the creator can spend them again
-            let mut unlocked_count = 0u32;
-            let locked_outputs: Vec<(OutPointKey, SwapId)> = state
-                .locked_swap_outputs
-                .iter(rwtxn)
-                .map_err(DbError::from)?
-                .map(|(key, sid)| Ok((key, sid)))
-                .collect()?;
-            for (outpoint_key, locked_swap_id) in locked_outputs {
-                if locked_swap_id == swap.id {
-                    let outpoint: OutPoint = outpoint_key.into();
-                    state.unlock_output_from_swap(rwtxn, &outpoint)?;
-                    unlocked_count += 1;
-                }
-            }
+            let unlocked_count =
+                swap_undo.unlock_swap_outputs(state, rwtxn, swap.id)?;
 
             tracing::info!(
                 swap_id = %swap.id,
@@ -771,11 +1015,38 @@ fn process_coinshift_transactions(
                 "Swap expired, unlocking outputs and marking as cancelled"
             );
             swap.state = SwapState::Cancelled;
+            swap_undo.record_swap(state, rwtxn, swap.id)?;
             state.save_swap(rwtxn, &swap)?;
             expired_swaps_count += 1;
             continue;
         }
 
+        // A reservation that lapses before the swap is claimed forfeits the
+        // taker's bond to the swap creator, and reopens the swap
+        if swap.reservation.as_ref().is_some_and(|reservation| {
+            block_height >= reservation.expires_at_height
+        }) {
+            let unlocked_count = swap::lapse_swap_reservation(
+                state, rwtxn, &mut swap, swap_undo,
+            )?;
+            tracing::info!(
+                swap_id = %swap.id,
+                block_height = %block_height,
+                unlocked_outputs = %unlocked_count,
+                "Swap reservation lapsed, forfeiting bond to the swap creator"
+            );
+            state.save_swap(rwtxn, &swap)?;
+        }
+
+        // Only swaps whose L1 payment has not yet reached its confirmations
+        // are looked up on L1
+        if !matches!(
+            swap.state,
+            SwapState::Pending | SwapState::WaitingConfirmations(..)
+        ) {
+            continue;
+        }
+
         // For L2 → L1 swaps, we need to check if the L1 transaction exists
         // on the SWAP TARGET CHAIN (swap.parent_chain), NOT the sidechain's mainchain.
         //
@@ -808,30 +1079,30 @@ fn process_coinshift_transactions(
         // the swap target chain (swap.parent_chain). If no RPC is configured here,
         // we skip L1 lookup and the swap stays Pending until RPC is set or the user
         // manually updates via update_swap_l1_txid.
-        // Query L1 blockchain for matching transactions if RPC config is available.
-        // URL is chosen by swap.parent_chain: we look up that chain in l1_rpc_configs.json.
-        // If no RPC config exists for this chain, we skip L1 lookup and the swap stays
-        // Pending until config is set or the user updates via update_swap_l1_txid.
+        // Query L1 blockchain for matching transactions if a backend is available.
+        // The backend is chosen by swap.parent_chain: we look up that chain in
+        // l1_rpc_configs.json. If no backend exists for this chain, we skip L1 lookup
+        // and the swap stays Pending until config is set or the user updates via
+        // update_swap_l1_txid.
         let l1_recipient_clone = swap.l1_recipient_address.clone();
         let l1_amount_clone = swap.l1_amount;
         let parent_chain_clone = swap.parent_chain;
         if let (Some(l1_recipient), Some(l1_amount)) =
             (l1_recipient_clone.as_deref(), l1_amount_clone)
-            && let Some(get_rpc_config) = rpc_config_getter
-            && let Some(rpc_config) = get_rpc_config(parent_chain_clone)
+            && let Some(payments) = l1_payments.get(&parent_chain_clone)
         {
             tracing::info!(
                 swap_id = %swap.id,
                 parent_chain = ?parent_chain_clone,
                 l1_recipient = %l1_recipient,
                 l1_amount_sats = %l1_amount.to_sat(),
-                url = %rpc_config.url,
+                backend = %payments.name(),
                 "Querying L1 for swap"
             );
             match query_and_update_swap(
                 state,
                 rwtxn,
-                &rpc_config,
+                payments,
                 &mut swap,
                 l1_recipient,
                 l1_amount,
@@ -846,6 +1117,7 @@ fn process_coinshift_transactions(
                             state = ?swap.state,
                             "Updated swap with L1 transaction"
                         );
+                        swap_undo.record_swap(state, rwtxn, swap.id)?;
                         state.save_swap(rwtxn, &swap)?;
                     }
                 }
@@ -854,7 +1126,7 @@ fn process_coinshift_transactions(
                         swap_id = %swap.id,
                         parent_chain = ?parent_chain_clone,
                         l1_recipient = %l1_recipient,
-                        url = %rpc_config.url,
+                        backend = %payments.name(),
                         error = %e,
                         "Failed to query L1 for swap; swap will stay pending until RPC succeeds or l1_txid is set manually"
                     );
@@ -887,7 +1159,8 @@ pub fn connect(
     state: &State,
     rwtxn: &mut RwTxn,
     two_way_peg_data: &TwoWayPegData,
-    rpc_config_getter: Option<&dyn Fn(ParentChainType) -> Option<RpcConfig>>,
+    l1_scan: &L1ScanCache,
+    l1_snapshots: &HashMap<ParentChainType, L1Snapshot>,
     wallet: Option<&Wallet>,
 ) -> Result<(), Error> {
     let block_height = state.try_get_height(rwtxn)?.ok_or(Error::NoTip)?;
@@ -918,12 +1191,21 @@ pub fn connect(
 
     // Process coinshift transactions after processing deposits/withdrawals
     let block_hash = state.try_get_tip(rwtxn)?.ok_or(Error::NoTip)?;
+    let mut swap_undo = SwapUndo::default();
     process_coinshift_transactions(
         state,
         rwtxn,
         block_height,
         block_hash,
-        rpc_config_getter,
+        l1_scan,
+        l1_snapshots,
+        &mut swap_undo,
+    )?;
+    state.put_swap_undo(
+        rwtxn,
+        block_height,
+        SwapUndoSource::TwoWayPegData,
+        &swap_undo,
     )?;
     // Handle deposits.
     if let Some(latest_deposit_block_hash) = latest_deposit_block_hash {
@@ -1287,6 +1569,14 @@ pub fn disconnect(
     let mut accumulator_diff = AccumulatorDiff::default();
     let mut latest_deposit_block_hash = None;
     let mut latest_withdrawal_bundle_event_block_hash = None;
+    // Revert swap expirations and L1 fill updates
+    if let Some(swap_undo) = state.take_swap_undo(
+        rwtxn,
+        block_height,
+        SwapUndoSource::TwoWayPegData,
+    )? {
+        let () = swap_undo.revert(state, rwtxn)?;
+    }
     // Restore pending withdrawal bundle
     for (event_block_hash, event_block_info) in
         two_way_peg_data.block_info.iter().rev()
@@ -1377,3 +1667,115 @@ pub fn disconnect(
         .map_err(DbError::from)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::Amount;
+    use futures::executor::block_on;
+
+    use super::query_and_update_swap;
+    use crate::{
+        l1_backend::{mock::MockL1Backend, snapshot::L1Snapshot},
+        l1_scan::ScannedPayments,
+        state::State,
+        types::{
+            Address, BlockHash, ParentChainType, Swap, SwapDirection, SwapId,
+            SwapState, SwapTxId,
+        },
+    };
+
+    const L1_RECIPIENT: &str = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+
+    /// Open an empty state in a fresh environment
+    fn setup(name: &str) -> (sneed::Env, State) {
+        let path = std::env::temp_dir()
+            .join(format!("coinshift_2wpd_test_{name}_{}", std::process::id()));
+        drop(std::fs::remove_dir_all(&path)); // best-effort cleanup
+        std::fs::create_dir_all(&path).unwrap();
+        let mut env_open_opts = heed::EnvOpenOptions::new();
+        env_open_opts
+            .map_size(64 * 1024 * 1024)
+            .max_dbs(State::NUM_DBS);
+        let env = unsafe { sneed::Env::open(&env_open_opts, &path) }.unwrap();
+        let state = State::new(&env).unwrap();
+        (env, state)
+    }
+
+    #[test]
+    fn swap_tracks_mock_l1_payment_confirmations() {
+        let (env, state) = setup("confirmations");
+        let backend = MockL1Backend::new_regtest();
+        let mut swap = Swap::new(
+            SwapId([1u8; 32]),
+            SwapDirection::L2ToL1,
+            ParentChainType::Regtest,
+            SwapTxId::Hash32([0u8; 32]),
+            None,
+            Some(Address([2u8; 20])),
+            Amount::from_sat(1_000_000),
+            Some(L1_RECIPIENT.to_owned()),
+            Some(Amount::from_sat(500_000)),
+            0,
+            None,
+            Some(Address([3u8; 20])),
+        );
+        let l1_amount = swap.l1_amount.unwrap();
+        let l1_txid = backend.send_to_script(
+            L1_RECIPIENT,
+            swap.l1_recipient_script.clone().unwrap(),
+            l1_amount,
+        );
+        let block_hash = BlockHash([0u8; 32]);
+        let mut rwtxn = env.write_txn().unwrap();
+        let mut relay_and_query = |swap: &mut Swap| {
+            // Relay the mock's headers, as a sidechain block would
+            let relay_base = state
+                .l1_headers()
+                .relay_base(&rwtxn, ParentChainType::Regtest)
+                .unwrap();
+            let headers = block_on(relay_base.fetch_relay(&backend)).unwrap();
+            if !headers.is_empty() {
+                let _: usize = state
+                    .l1_headers()
+                    .connect_relay(
+                        &mut rwtxn,
+                        ParentChainType::Regtest,
+                        &headers,
+                        1,
+                    )
+                    .unwrap();
+            }
+            // Fetch the recipient's history, as the 2WPD prefetch would
+            let mut snapshot = L1Snapshot::new(&backend, Vec::new());
+            let () = block_on(snapshot.fetch_address(&backend, L1_RECIPIENT))
+                .unwrap();
+            query_and_update_swap(
+                &state,
+                &mut rwtxn,
+                &ScannedPayments::unscanned(&snapshot),
+                swap,
+                L1_RECIPIENT,
+                l1_amount,
+                block_hash,
+                1,
+            )
+            .unwrap()
+        };
+
+        // Payments that are not in a block are ignored
+        assert!(!relay_and_query(&mut swap));
+        assert_eq!(swap.state, SwapState::Pending);
+
+        let _tip = backend.mine_blocks(1);
+        assert!(relay_and_query(&mut swap));
+        assert_eq!(swap.l1_txid, SwapTxId::from_hex_rpc(&l1_txid).unwrap());
+        assert_eq!(
+            swap.state,
+            SwapState::WaitingConfirmations(1, swap.required_confirmations)
+        );
+
+        let _tip = backend.mine_blocks(swap.required_confirmations - 1);
+        assert!(relay_and_query(&mut swap));
+        assert_eq!(swap.state, SwapState::ReadyToClaim);
+    }
+}
@@ -0,0 +1,225 @@
+//! Bitcoin Cash CashAddr decoding
+//!
+//! Bitcoin Cash addresses are written either in CashAddr format
+//! (`bitcoincash:qp...`) or in the legacy base58 format shared with
+//! Bitcoin, which is decoded with the other base58 formats in
+//! [`chain_params`](super::chain_params). Both encode the same P2PKH/P2SH
+//! output scripts, so swaps match BCH payments on the decoded script rather
+//! than on the address string.
+
+use bitcoin::{PubkeyHash, ScriptBuf, ScriptHash, hashes::Hash as _};
+use thiserror::Error;
+
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+/// Number of base32 characters in a CashAddr checksum
+const CHECKSUM_LEN: usize = 8;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("CashAddr prefix `{found}` is not `{expected}`")]
+    WrongPrefix { expected: String, found: String },
+    #[error("mixed case CashAddr")]
+    MixedCase,
+    #[error("invalid CashAddr character `{0}`")]
+    InvalidChar(char),
+    #[error("invalid CashAddr checksum")]
+    InvalidChecksum,
+    #[error("invalid CashAddr padding")]
+    InvalidPadding,
+    #[error("unsupported CashAddr version byte {0:#04x}")]
+    UnsupportedVersion(u8),
+    #[error("invalid address payload length {0}")]
+    InvalidLength(usize),
+}
+
+/// CashAddr checksum (BCH code over GF(2^5), as specified by the CashAddr
+/// spec)
+fn polymod(values: impl IntoIterator<Item = u8>) -> u64 {
+    const GENERATORS: [u64; 5] = [
+        0x98f2bc8e61,
+        0x79b76d99e2,
+        0xf33e5fb3c4,
+        0xae2eabe2a8,
+        0x1e4f43e470,
+    ];
+    let mut checksum: u64 = 1;
+    for value in values {
+        let top = checksum >> 35;
+        checksum = ((checksum & 0x07ffffffff) << 5) ^ u64::from(value);
+        for (i, generator) in GENERATORS.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+    checksum ^ 1
+}
+
+/// Regroup 5-bit values into bytes, rejecting non-zero padding
+fn from_base32(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut acc: u32 = 0;
+    let mut bits = 0;
+    let mut res = Vec::with_capacity(data.len() * 5 / 8);
+    for value in data {
+        acc = ((acc << 5) | u32::from(*value)) & 0x1fff;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            res.push((acc >> bits) as u8);
+        }
+    }
+    if bits >= 5 || (acc & ((1 << bits) - 1)) != 0 {
+        return Err(Error::InvalidPadding);
+    }
+    Ok(res)
+}
+
+/// Decode the payload (version byte followed by hash) of a CashAddr with a
+/// known prefix
+fn decode_with_prefix(prefix: &str, payload: &str) -> Result<Vec<u8>, Error> {
+    let data = payload
+        .chars()
+        .map(|c| {
+            CHARSET
+                .iter()
+                .position(|charset_c| char::from(*charset_c) == c)
+                .map(|value| value as u8)
+                .ok_or(Error::InvalidChar(c))
+        })
+        .collect::<Result<Vec<u8>, _>>()?;
+    if data.len() <= CHECKSUM_LEN {
+        return Err(Error::InvalidLength(data.len()));
+    }
+    let checksum_input = prefix
+        .bytes()
+        .map(|b| b & 0x1f)
+        .chain(std::iter::once(0))
+        .chain(data.iter().copied());
+    if polymod(checksum_input) != 0 {
+        return Err(Error::InvalidChecksum);
+    }
+    from_base32(&data[..data.len() - CHECKSUM_LEN])
+}
+
+/// Decode a CashAddr for the network with the given prefix into the
+/// output script it pays. The prefix may be omitted from the address.
+pub fn script_pubkey(address: &str, prefix: &str) -> Result<ScriptBuf, Error> {
+    if address.chars().any(|c| c.is_ascii_lowercase())
+        && address.chars().any(|c| c.is_ascii_uppercase())
+    {
+        return Err(Error::MixedCase);
+    }
+    let address = address.to_ascii_lowercase();
+    let payload = match address.split_once(':') {
+        Some((found, payload)) => {
+            if found != prefix {
+                return Err(Error::WrongPrefix {
+                    expected: prefix.to_owned(),
+                    found: found.to_owned(),
+                });
+            }
+            payload
+        }
+        // Without a prefix, the checksum only matches the right network
+        None => &address,
+    };
+    let payload = decode_with_prefix(prefix, payload)?;
+    let (version, hash) = payload
+        .split_first()
+        .ok_or(Error::InvalidLength(payload.len()))?;
+    let hash: [u8; 20] = hash
+        .try_into()
+        .map_err(|_| Error::InvalidLength(hash.len()))?;
+    // Type bits 3-6, size bits 0-2. Only 160-bit hashes are supported.
+    match version {
+        0x00 => Ok(ScriptBuf::new_p2pkh(&PubkeyHash::from_byte_array(hash))),
+        0x08 => Ok(ScriptBuf::new_p2sh(&ScriptHash::from_byte_array(hash))),
+        _ => Err(Error::UnsupportedVersion(*version)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, script_pubkey};
+
+    /// Examples from the CashAddr spec
+    const CASHADDR_P2PKH: &str =
+        "bitcoincash:qpm2qsznhks23z7629mms6s4cwef74vcwvy22gdx6a";
+    const TESTNET_CASHADDR_P2PKH: &str =
+        "bchtest:qpm2qsznhks23z7629mms6s4cwef74vcwvqcw003ap";
+    const CASHADDR_P2SH: &str =
+        "bitcoincash:ppm2qsznhks23z7629mms6s4cwef74vcwvn0h829pq";
+
+    #[test]
+    fn cashaddrs_of_other_networks_are_rejected() {
+        let script = script_pubkey(CASHADDR_P2PKH, "bitcoincash").unwrap();
+        assert!(script.is_p2pkh());
+        // The same hash on testnet only decodes for the testnet prefix
+        assert!(
+            script_pubkey(TESTNET_CASHADDR_P2PKH, "bchtest")
+                .unwrap()
+                .is_p2pkh()
+        );
+        assert!(matches!(
+            script_pubkey(TESTNET_CASHADDR_P2PKH, "bitcoincash"),
+            Err(Error::WrongPrefix { .. })
+        ));
+        let unprefixed = TESTNET_CASHADDR_P2PKH.split_once(':').unwrap().1;
+        assert!(matches!(
+            script_pubkey(unprefixed, "bitcoincash"),
+            Err(Error::InvalidChecksum)
+        ));
+        // Prefix is optional, and upper case is allowed
+        let unprefixed = CASHADDR_P2PKH.split_once(':').unwrap().1;
+        assert_eq!(script_pubkey(unprefixed, "bitcoincash").unwrap(), script);
+        assert_eq!(
+            script_pubkey(&CASHADDR_P2PKH.to_ascii_uppercase(), "bitcoincash")
+                .unwrap(),
+            script
+        );
+    }
+
+    #[test]
+    fn cashaddr_p2sh() {
+        assert!(
+            script_pubkey(CASHADDR_P2SH, "bitcoincash")
+                .unwrap()
+                .is_p2sh()
+        );
+    }
+
+    #[test]
+    fn invalid_cashaddrs_are_rejected() {
+        let mut corrupted = CASHADDR_P2PKH.to_owned();
+        corrupted.pop();
+        corrupted.push('q');
+        assert!(matches!(
+            script_pubkey(&corrupted, "bitcoincash"),
+            Err(Error::InvalidChecksum)
+        ));
+        let wrong_prefix =
+            TESTNET_CASHADDR_P2PKH.replace("bchtest", "bitcoincash");
+        assert!(matches!(
+            script_pubkey(&wrong_prefix, "bitcoincash"),
+            Err(Error::InvalidChecksum)
+        ));
+        assert!(matches!(
+            script_pubkey(CASHADDR_P2PKH, "bchtest"),
+            Err(Error::WrongPrefix { .. })
+        ));
+        // Unprefixed addresses of another network fail the checksum
+        let unprefixed = CASHADDR_P2PKH.split_once(':').unwrap().1;
+        assert!(matches!(
+            script_pubkey(unprefixed, "bchtest"),
+            Err(Error::InvalidChecksum)
+        ));
+        assert!(matches!(
+            script_pubkey(
+                "bitcoincash:Qpm2qsznhks23z7629mms6s4cwef74vcwvy22gdx6a",
+                "bitcoincash"
+            ),
+            Err(Error::MixedCase)
+        ));
+    }
+}
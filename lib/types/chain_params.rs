@@ -0,0 +1,586 @@
+//! Consensus and address parameters of the parent chains
+//!
+//! Each [`ParentChainType`](super::ParentChainType) is a specific network of
+//! a specific coin. [`ChainParams`] holds what the sidechain needs to know
+//! about it: the genesis block that L1 endpoints must serve, how its headers
+//! are verified, and how its addresses decode into output scripts. The
+//! parameters of the built-in chains are defined here; those of other
+//! chains are loaded with the [chain registry](crate::chain_registry).
+
+use std::time::Duration;
+
+use bitcoin::{
+    CompactTarget, PubkeyHash, ScriptBuf, ScriptHash, Target, WitnessProgram,
+    WitnessVersion, bech32, hashes::Hash as _,
+};
+use serde::{Deserialize, Serialize};
+use serde_with::{DurationSeconds, serde_as};
+use thiserror::Error;
+
+use super::cashaddr;
+
+/// Network variant of a parent chain
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChainNetwork {
+    Mainnet,
+    Testnet,
+    Signet,
+    Regtest,
+}
+
+/// Proof of work hash function
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowAlgorithm {
+    /// Double SHA-256 of the header, as in Bitcoin
+    Sha256d,
+    /// Scrypt of the header, as in Litecoin. Headers are not verified for
+    /// these chains.
+    Scrypt,
+}
+
+/// Reference block of the ASERT difficulty algorithm
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AsertAnchor {
+    pub height: u32,
+    /// Difficulty bits of the anchor block
+    pub bits: u32,
+    /// Timestamp of the anchor block's parent
+    pub parent_time: i64,
+}
+
+/// How the difficulty target changes between headers.
+///
+/// On testnets, a header whose timestamp is more than two target block
+/// intervals after its parent's, and not ahead of the node's clock, may use
+/// the proof of work limit instead, unless difficulty is
+/// [`DifficultyAdjustment::Fixed`] or the header is at a retarget height.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DifficultyAdjustment {
+    /// The target changes every `interval` blocks, scaled by the time taken
+    /// by the previous `interval` blocks (bounded to a factor of four), as
+    /// in Bitcoin
+    Retarget { interval: u32 },
+    /// The target of every block after the anchor is set by the Bitcoin Cash
+    /// ASERT algorithm (aserti3-2d), with a half life in seconds. Headers up
+    /// to the anchor are held to the [`DifficultyAdjustment::PerBlock`]
+    /// bound.
+    Asert { anchor: AsertAnchor, half_life: u32 },
+    /// The target may change on every block, by an algorithm that is not
+    /// verified. The target may ease by at most a factor of four from the
+    /// parent's.
+    PerBlock,
+    /// The target never changes
+    Fixed,
+}
+
+#[derive(Debug, Error)]
+pub enum AddressError {
+    #[error(transparent)]
+    CashAddr(#[from] cashaddr::Error),
+    #[error("invalid base58 address: {0}")]
+    Base58(#[from] bitcoin::base58::Error),
+    #[error("invalid segwit address: {0}")]
+    Segwit(String),
+    #[error("invalid witness program: {0}")]
+    WitnessProgram(String),
+    #[error("segwit address with prefix `{0}` is for another network")]
+    WrongSegwitHrp(String),
+    #[error("base58 address with version byte {0:#04x} is for another network")]
+    WrongVersion(u8),
+    #[error("invalid address payload length {0}")]
+    InvalidLength(usize),
+}
+
+/// Address encodings of a parent chain
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct AddressParams {
+    /// Human-readable part of bech32/bech32m segwit addresses, if the chain
+    /// has segwit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segwit_hrp: Option<String>,
+    /// CashAddr prefix, if the chain uses CashAddr
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cashaddr_prefix: Option<String>,
+    /// Base58 version bytes of P2PKH addresses
+    #[serde(default)]
+    pub p2pkh_versions: Vec<u8>,
+    /// Base58 version bytes of P2SH addresses. Litecoin accepts both its
+    /// own and the Bitcoin version byte.
+    #[serde(default)]
+    pub p2sh_versions: Vec<u8>,
+}
+
+impl AddressParams {
+    /// Whether `address` starts with `hrp` and the bech32 separator
+    fn has_segwit_hrp(address: &str, hrp: &str) -> bool {
+        address.get(..=hrp.len()).is_some_and(|prefix| {
+            prefix.eq_ignore_ascii_case(&format!("{hrp}1"))
+        })
+    }
+
+    /// Decode a base58 address into the output script it pays
+    fn decode_base58(&self, address: &str) -> Result<ScriptBuf, AddressError> {
+        let payload = bitcoin::base58::decode_check(address)?;
+        let (version, hash) = payload
+            .split_first()
+            .ok_or(AddressError::InvalidLength(payload.len()))?;
+        let hash: [u8; 20] = hash
+            .try_into()
+            .map_err(|_| AddressError::InvalidLength(hash.len()))?;
+        if self.p2pkh_versions.contains(version) {
+            Ok(ScriptBuf::new_p2pkh(&PubkeyHash::from_byte_array(hash)))
+        } else if self.p2sh_versions.contains(version) {
+            Ok(ScriptBuf::new_p2sh(&ScriptHash::from_byte_array(hash)))
+        } else {
+            Err(AddressError::WrongVersion(*version))
+        }
+    }
+
+    /// Decode an address of this chain, in any of its encodings, into the
+    /// output script it pays
+    pub fn script_pubkey(
+        &self,
+        address: &str,
+    ) -> Result<ScriptBuf, AddressError> {
+        match bech32::segwit::decode(address) {
+            Ok((hrp, version, program)) => {
+                let hrp = hrp.to_lowercase();
+                if self.segwit_hrp.as_deref() != Some(hrp.as_str()) {
+                    return Err(AddressError::WrongSegwitHrp(hrp));
+                }
+                let version =
+                    WitnessVersion::try_from(version).map_err(|err| {
+                        AddressError::WitnessProgram(err.to_string())
+                    })?;
+                let program =
+                    WitnessProgram::new(version, &program).map_err(|err| {
+                        AddressError::WitnessProgram(err.to_string())
+                    })?;
+                return Ok(ScriptBuf::new_witness_program(&program));
+            }
+            Err(err)
+                if self
+                    .segwit_hrp
+                    .as_deref()
+                    .is_some_and(|hrp| Self::has_segwit_hrp(address, hrp)) =>
+            {
+                return Err(AddressError::Segwit(err.to_string()));
+            }
+            Err(_) => (),
+        }
+        match (self.decode_base58(address), self.cashaddr_prefix.as_deref()) {
+            (Err(AddressError::Base58(_)), Some(prefix)) => {
+                Ok(cashaddr::script_pubkey(address, prefix)?)
+            }
+            (res, _) => res,
+        }
+    }
+}
+
+/// Parameters of a parent chain network
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ChainParams {
+    pub network: ChainNetwork,
+    pub genesis_block_hash: bitcoin::BlockHash,
+    /// Target block interval, in seconds
+    #[serde_as(as = "DurationSeconds<u64>")]
+    pub target_block_interval: Duration,
+    pub pow_algorithm: PowAlgorithm,
+    /// Easiest target allowed, in compact encoding
+    pub pow_limit_bits: u32,
+    pub difficulty_adjustment: DifficultyAdjustment,
+    pub address: AddressParams,
+    pub default_rpc_port: u16,
+}
+
+/// Parse a genesis block hash of a built-in chain
+fn genesis(hash: &str) -> bitcoin::BlockHash {
+    hash.parse().expect("valid block hash")
+}
+
+/// Address params of a built-in chain
+fn address_params(
+    segwit_hrp: Option<&str>,
+    cashaddr_prefix: Option<&str>,
+    p2pkh_versions: &[u8],
+    p2sh_versions: &[u8],
+) -> AddressParams {
+    AddressParams {
+        segwit_hrp: segwit_hrp.map(str::to_owned),
+        cashaddr_prefix: cashaddr_prefix.map(str::to_owned),
+        p2pkh_versions: p2pkh_versions.to_vec(),
+        p2sh_versions: p2sh_versions.to_vec(),
+    }
+}
+
+impl ChainParams {
+    /// Easiest target allowed
+    pub fn pow_limit(&self) -> Target {
+        Target::from_compact(CompactTarget::from_consensus(self.pow_limit_bits))
+    }
+
+    /// Whether headers of this chain can be verified on their own, so that
+    /// payments on it can be proven to the sidechain. Scrypt proof of work
+    /// is not verified, and signet blocks are valid by a signature of the
+    /// signet challenge in their coinbase rather than by proof of work,
+    /// which anyone can produce at the signet's easy target.
+    pub fn verifiable_headers(&self) -> bool {
+        self.pow_algorithm == PowAlgorithm::Sha256d
+            && self.network != ChainNetwork::Signet
+    }
+
+    /// Bitcoin mainnet
+    pub fn bitcoin() -> Self {
+        Self {
+            network: ChainNetwork::Mainnet,
+            genesis_block_hash: genesis(
+                "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f",
+            ),
+            target_block_interval: Duration::from_secs(600),
+            pow_algorithm: PowAlgorithm::Sha256d,
+            pow_limit_bits: 0x1d00ffff,
+            difficulty_adjustment: DifficultyAdjustment::Retarget {
+                interval: 2016,
+            },
+            address: address_params(Some("bc"), None, &[0x00], &[0x05]),
+            default_rpc_port: 8332,
+        }
+    }
+
+    /// Bitcoin signet (the default signet)
+    pub fn bitcoin_signet() -> Self {
+        Self {
+            network: ChainNetwork::Signet,
+            genesis_block_hash: genesis(
+                "00000008819873e925422c1ff0f99f7cc9bbb232af63a077a480a3633bee1ef6",
+            ),
+            target_block_interval: Duration::from_secs(600),
+            pow_algorithm: PowAlgorithm::Sha256d,
+            pow_limit_bits: 0x1e0377ae,
+            difficulty_adjustment: DifficultyAdjustment::Retarget {
+                interval: 2016,
+            },
+            address: address_params(Some("tb"), None, &[0x6f], &[0xc4]),
+            default_rpc_port: 38332,
+        }
+    }
+
+    /// Bitcoin regtest
+    pub fn bitcoin_regtest() -> Self {
+        Self {
+            network: ChainNetwork::Regtest,
+            genesis_block_hash: genesis(
+                "0f9188f13cb7b2c71f2a335e3a4fc328bf5beb436012afca590b1a11466e2206",
+            ),
+            target_block_interval: Duration::from_secs(600),
+            pow_algorithm: PowAlgorithm::Sha256d,
+            pow_limit_bits: 0x207fffff,
+            difficulty_adjustment: DifficultyAdjustment::Fixed,
+            address: address_params(Some("bcrt"), None, &[0x6f], &[0xc4]),
+            default_rpc_port: 18443,
+        }
+    }
+
+    /// Bitcoin Cash mainnet. Addresses may be in CashAddr or legacy base58
+    /// format.
+    pub fn bitcoin_cash() -> Self {
+        Self {
+            network: ChainNetwork::Mainnet,
+            genesis_block_hash: genesis(
+                "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f",
+            ),
+            target_block_interval: Duration::from_secs(600),
+            pow_algorithm: PowAlgorithm::Sha256d,
+            pow_limit_bits: 0x1d00ffff,
+            difficulty_adjustment: DifficultyAdjustment::Asert {
+                anchor: AsertAnchor {
+                    height: 661647,
+                    bits: 0x1804dafe,
+                    parent_time: 1605447844,
+                },
+                half_life: 2 * 24 * 60 * 60,
+            },
+            address: address_params(
+                None,
+                Some("bitcoincash"),
+                &[0x00],
+                &[0x05],
+            ),
+            default_rpc_port: 8332,
+        }
+    }
+
+    /// Bitcoin Cash testnet4. Addresses may be in CashAddr or legacy base58
+    /// format.
+    pub fn bitcoin_cash_testnet4() -> Self {
+        Self {
+            network: ChainNetwork::Testnet,
+            genesis_block_hash: genesis(
+                "000000001dd410c49a788668ce26751718cc797474d3152a5fc073dd44fd9f7b",
+            ),
+            target_block_interval: Duration::from_secs(600),
+            pow_algorithm: PowAlgorithm::Sha256d,
+            pow_limit_bits: 0x1d00ffff,
+            difficulty_adjustment: DifficultyAdjustment::Asert {
+                anchor: AsertAnchor {
+                    height: 16844,
+                    bits: 0x1d00ffff,
+                    parent_time: 1605451779,
+                },
+                half_life: 60 * 60,
+            },
+            address: address_params(None, Some("bchtest"), &[0x6f], &[0xc4]),
+            default_rpc_port: 28332,
+        }
+    }
+
+    /// Bitcoin Cash regtest, which shares its genesis block with Bitcoin
+    /// regtest
+    pub fn bitcoin_cash_regtest() -> Self {
+        Self {
+            network: ChainNetwork::Regtest,
+            genesis_block_hash: genesis(
+                "0f9188f13cb7b2c71f2a335e3a4fc328bf5beb436012afca590b1a11466e2206",
+            ),
+            target_block_interval: Duration::from_secs(600),
+            pow_algorithm: PowAlgorithm::Sha256d,
+            pow_limit_bits: 0x207fffff,
+            difficulty_adjustment: DifficultyAdjustment::Fixed,
+            address: address_params(None, Some("bchreg"), &[0x6f], &[0xc4]),
+            default_rpc_port: 18443,
+        }
+    }
+
+    /// Litecoin mainnet
+    pub fn litecoin() -> Self {
+        Self {
+            network: ChainNetwork::Mainnet,
+            genesis_block_hash: genesis(
+                "12a765e31ffd4059bada1e25190f6e98c99d9714d334efa41a195a7e7e04bfe2",
+            ),
+            target_block_interval: Duration::from_secs(150),
+            pow_algorithm: PowAlgorithm::Scrypt,
+            pow_limit_bits: 0x1e0fffff,
+            difficulty_adjustment: DifficultyAdjustment::Retarget {
+                interval: 2016,
+            },
+            address: address_params(Some("ltc"), None, &[0x30], &[0x32, 0x05]),
+            default_rpc_port: 9332,
+        }
+    }
+
+    /// Litecoin testnet (testnet4)
+    pub fn litecoin_testnet() -> Self {
+        Self {
+            network: ChainNetwork::Testnet,
+            genesis_block_hash: genesis(
+                "4966625a4b2851d9fdee139e56211a0d88575f59ed816ff5e6a63deb4e3e29a0",
+            ),
+            target_block_interval: Duration::from_secs(150),
+            pow_algorithm: PowAlgorithm::Scrypt,
+            pow_limit_bits: 0x1e0fffff,
+            difficulty_adjustment: DifficultyAdjustment::Retarget {
+                interval: 2016,
+            },
+            address: address_params(Some("tltc"), None, &[0x6f], &[0x3a, 0xc4]),
+            default_rpc_port: 19332,
+        }
+    }
+
+    /// Litecoin regtest
+    pub fn litecoin_regtest() -> Self {
+        Self {
+            network: ChainNetwork::Regtest,
+            genesis_block_hash: genesis(
+                "530827f38f93b43ed12af0b3ad25a288dc02ed74d6d7857862df51fc56c416f9",
+            ),
+            target_block_interval: Duration::from_secs(150),
+            pow_algorithm: PowAlgorithm::Scrypt,
+            pow_limit_bits: 0x207fffff,
+            difficulty_adjustment: DifficultyAdjustment::Fixed,
+            address: address_params(Some("rltc"), None, &[0x6f], &[0x3a, 0xc4]),
+            default_rpc_port: 19443,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::hashes::Hash as _;
+
+    use super::{AddressError, ChainParams};
+
+    /// Encodings of the same 160-bit hash
+    const BTC_SEGWIT: &str = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+    const LTC_SEGWIT: &str = "ltc1qw508d6qejxtdg4y5r3zarvary0c5xw7kgmn4n9";
+    const LTC_TESTNET_SEGWIT: &str =
+        "tltc1qw508d6qejxtdg4y5r3zarvary0c5xw7klfsuq0";
+    const LTC_P2PKH: &str = "LW3ByJXVHpiJsuy3u2sdieFQkXHtuk93Yi";
+    const LTC_P2SH: &str = "MJiPwX84iBe4WnFDwsYGgtnz1XonPhUqhf";
+    const LEGACY_P2SH: &str = "3CWFddi6m4ndiGyKqzYvsFYagqDLPVMTzC";
+    const TESTNET_P2PKH: &str = "mrLC19Je2BuWQDkWSTriGYPyQJXKkkBmCx";
+    const TESTNET_P2SH: &str = "2N44ThNe8NXHyv4bsX8AoVCXquBRW94Ls7W";
+    const BCH_CASHADDR: &str =
+        "bitcoincash:qpm2qsznhks23z7629mms6s4cwef74vcwvy22gdx6a";
+    const BCH_TESTNET_CASHADDR: &str =
+        "bchtest:qpm2qsznhks23z7629mms6s4cwef74vcwvqcw003ap";
+
+    #[test]
+    fn bitcoin_genesis_hashes_match_rust_bitcoin() {
+        for (params, network) in [
+            (ChainParams::bitcoin(), bitcoin::Network::Bitcoin),
+            (ChainParams::bitcoin_signet(), bitcoin::Network::Signet),
+            (ChainParams::bitcoin_regtest(), bitcoin::Network::Regtest),
+            // Bitcoin Cash forked from Bitcoin, and its regtest is the same
+            (ChainParams::bitcoin_cash(), bitcoin::Network::Bitcoin),
+            (
+                ChainParams::bitcoin_cash_regtest(),
+                bitcoin::Network::Regtest,
+            ),
+        ] {
+            assert_eq!(
+                params.genesis_block_hash,
+                bitcoin::constants::genesis_block(network).block_hash()
+            );
+            assert_eq!(
+                params.pow_limit(),
+                bitcoin::params::Params::new(network).max_attainable_target
+            );
+        }
+        for params in [
+            ChainParams::bitcoin_cash_testnet4(),
+            ChainParams::litecoin(),
+            ChainParams::litecoin_testnet(),
+            ChainParams::litecoin_regtest(),
+        ] {
+            assert_ne!(
+                params.genesis_block_hash,
+                bitcoin::BlockHash::all_zeros()
+            );
+        }
+    }
+
+    #[test]
+    fn litecoin_addresses() {
+        let litecoin = ChainParams::litecoin().address;
+        let bitcoin = ChainParams::bitcoin().address;
+        let segwit = litecoin.script_pubkey(LTC_SEGWIT).unwrap();
+        assert!(segwit.is_p2wpkh());
+        assert_eq!(bitcoin.script_pubkey(BTC_SEGWIT).unwrap(), segwit);
+        // Upper case is allowed
+        assert_eq!(
+            litecoin
+                .script_pubkey(&LTC_SEGWIT.to_ascii_uppercase())
+                .unwrap(),
+            segwit
+        );
+        let p2pkh = litecoin.script_pubkey(LTC_P2PKH).unwrap();
+        assert!(p2pkh.is_p2pkh());
+        // Both P2SH version bytes encode the same script
+        let p2sh = litecoin.script_pubkey(LTC_P2SH).unwrap();
+        assert!(p2sh.is_p2sh());
+        assert_eq!(litecoin.script_pubkey(LEGACY_P2SH).unwrap(), p2sh);
+    }
+
+    #[test]
+    fn bitcoin_cash_addresses() {
+        let params = ChainParams::bitcoin_cash().address;
+        let cashaddr = params.script_pubkey(BCH_CASHADDR).unwrap();
+        assert!(cashaddr.is_p2pkh());
+        assert!(params.script_pubkey(LEGACY_P2SH).unwrap().is_p2sh());
+        assert!(matches!(
+            params.script_pubkey(BCH_TESTNET_CASHADDR),
+            Err(AddressError::CashAddr(_))
+        ));
+        assert!(matches!(
+            params.script_pubkey(TESTNET_P2PKH),
+            Err(AddressError::WrongVersion(0x6f))
+        ));
+    }
+
+    #[test]
+    fn litecoin_test_network_addresses() {
+        let testnet = ChainParams::litecoin_testnet().address;
+        let regtest = ChainParams::litecoin_regtest().address;
+        assert!(
+            testnet
+                .script_pubkey(LTC_TESTNET_SEGWIT)
+                .unwrap()
+                .is_p2wpkh()
+        );
+        assert!(testnet.script_pubkey(TESTNET_P2PKH).unwrap().is_p2pkh());
+        assert!(testnet.script_pubkey(TESTNET_P2SH).unwrap().is_p2sh());
+        assert!(matches!(
+            regtest.script_pubkey(LTC_TESTNET_SEGWIT),
+            Err(AddressError::WrongSegwitHrp(hrp)) if hrp == "tltc"
+        ));
+        assert!(matches!(
+            testnet.script_pubkey(LTC_P2PKH),
+            Err(AddressError::WrongVersion(0x30))
+        ));
+    }
+
+    #[test]
+    fn bitcoin_cash_testnet_addresses() {
+        let params = ChainParams::bitcoin_cash_testnet4().address;
+        let cashaddr = params.script_pubkey(BCH_TESTNET_CASHADDR).unwrap();
+        assert_eq!(params.script_pubkey(TESTNET_P2PKH).unwrap(), cashaddr);
+        assert!(params.script_pubkey(TESTNET_P2SH).unwrap().is_p2sh());
+    }
+
+    #[test]
+    fn addresses_of_other_networks_are_rejected() {
+        let litecoin = ChainParams::litecoin().address;
+        let bitcoin = ChainParams::bitcoin().address;
+        let bitcoin_cash = ChainParams::bitcoin_cash_testnet4().address;
+        assert!(matches!(
+            litecoin.script_pubkey(BTC_SEGWIT),
+            Err(AddressError::WrongSegwitHrp(hrp)) if hrp == "bc"
+        ));
+        assert!(matches!(
+            litecoin.script_pubkey(LTC_TESTNET_SEGWIT),
+            Err(AddressError::WrongSegwitHrp(_))
+        ));
+        assert!(matches!(
+            litecoin.script_pubkey(TESTNET_P2PKH),
+            Err(AddressError::WrongVersion(0x6f))
+        ));
+        assert!(matches!(
+            bitcoin.script_pubkey(LTC_P2PKH),
+            Err(AddressError::WrongVersion(0x30))
+        ));
+        // Segwit is not used on Bitcoin Cash
+        assert!(matches!(
+            bitcoin_cash.script_pubkey(BTC_SEGWIT),
+            Err(AddressError::WrongSegwitHrp(_))
+        ));
+        // Mainnet CashAddr and legacy addresses
+        assert!(matches!(
+            bitcoin_cash.script_pubkey(
+                "bitcoincash:qpm2qsznhks23z7629mms6s4cwef74vcwvy22gdx6a"
+            ),
+            Err(AddressError::CashAddr(_))
+        ));
+        assert!(matches!(
+            bitcoin_cash
+                .script_pubkey("qpm2qsznhks23z7629mms6s4cwef74vcwvy22gdx6a"),
+            Err(AddressError::CashAddr(_))
+        ));
+        assert!(matches!(
+            bitcoin_cash.script_pubkey(LEGACY_P2SH),
+            Err(AddressError::WrongVersion(0x05))
+        ));
+        // Corrupted checksum of an address with the chain's segwit prefix
+        let mut corrupted = LTC_SEGWIT.to_owned();
+        corrupted.pop();
+        corrupted.push('q');
+        assert!(matches!(
+            litecoin.script_pubkey(&corrupted),
+            Err(AddressError::Segwit(_))
+        ));
+    }
+}
@@ -0,0 +1,582 @@
+//! SPV inclusion proofs for L1 swap fills
+//!
+//! A `SwapClaim` may carry an [`L1InclusionProof`] in its `proof_data`.
+//! The proof contains the raw L1 transaction, the merkle branch from that
+//! transaction to the merkle root, and the L1 block header that commits to
+//! the root. Verification is deterministic: every node reaches the same
+//! result without talking to a parent chain node.
+//!
+//! Claims for partially fillable swaps carry one proof per fill (see
+//! [`L1InclusionProof::encode_many`]). The payer of a fill binds it to an L2
+//! address with an `OP_RETURN` output (see [`l2_claimer_declaration`]).
+
+use bitcoin::{
+    self,
+    block::Header,
+    hashes::{Hash as _, sha256d},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use thiserror::Error;
+
+use super::{Address, ParentChainType};
+
+/// Serialized size of an L1 block header
+pub const L1_HEADER_SIZE: usize = 80;
+
+/// Maximum merkle branch length accepted in a proof.
+///
+/// A branch of this length can address 2^32 transactions, which is far more
+/// than any parent chain block can hold.
+pub const MAX_MERKLE_BRANCH_LEN: usize = 32;
+
+/// Maximum number of proofs accepted in a single `SwapClaim`
+pub const MAX_PROOFS_PER_CLAIM: usize = 16;
+
+#[derive(Debug, Error)]
+pub enum L1ProofError {
+    #[error("failed to decode L1 inclusion proof: {0}")]
+    Decode(String),
+    #[error("invalid L1 block header: {0}")]
+    InvalidHeader(String),
+    #[error("invalid L1 transaction: {0}")]
+    InvalidTransaction(String),
+    #[error("L1 block header does not satisfy its proof of work target")]
+    InsufficientWork,
+    #[error("L1 block header target is above the {0:?} proof of work limit")]
+    TargetAboveLimit(ParentChainType),
+    #[error("merkle branch too long ({0} > {MAX_MERKLE_BRANCH_LEN})")]
+    MerkleBranchTooLong(usize),
+    #[error("merkle branch does not commit {txid} to header merkle root")]
+    MerkleRootMismatch { txid: bitcoin::Txid },
+    #[error(
+        "L1 transaction {txid} has no output paying {min} to {max} to {script}"
+    )]
+    NoMatchingOutput {
+        txid: bitcoin::Txid,
+        script: bitcoin::ScriptBuf,
+        min: bitcoin::Amount,
+        max: bitcoin::Amount,
+    },
+    #[error("too many L1 inclusion proofs ({0} > {MAX_PROOFS_PER_CLAIM})")]
+    TooManyProofs(usize),
+    #[error("invalid L1 recipient address `{address}`: {reason}")]
+    InvalidRecipientAddress { address: String, reason: String },
+    #[error("SPV proofs are not supported for {0:?}")]
+    UnsupportedParentChain(ParentChainType),
+}
+
+/// An L1 fill that was proven by an [`L1InclusionProof`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct VerifiedL1Fill {
+    pub txid: bitcoin::Txid,
+    pub vout: u32,
+    /// Amount paid to the recipient script
+    pub amount: bitcoin::Amount,
+    /// L2 address declared by the payer (see [`declared_l2_claimer`])
+    pub l2_claimer_address: Option<Address>,
+    pub block_hash: bitcoin::BlockHash,
+    pub header: Header,
+}
+
+/// Proof that an L1 transaction is included in an L1 block
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Eq, PartialEq)]
+pub struct L1InclusionProof {
+    /// Consensus-encoded L1 transaction
+    pub raw_tx: Vec<u8>,
+    /// Sibling hashes from the transaction up to the merkle root, in
+    /// internal byte order
+    pub merkle_branch: Vec<[u8; 32]>,
+    /// Position of the transaction in the block
+    pub tx_index: u32,
+    /// Consensus-encoded 80-byte L1 block header
+    pub header: Vec<u8>,
+}
+
+impl L1InclusionProof {
+    pub fn new(
+        tx: &bitcoin::Transaction,
+        merkle_branch: Vec<bitcoin::TxMerkleNode>,
+        tx_index: u32,
+        header: &Header,
+    ) -> Self {
+        Self {
+            raw_tx: bitcoin::consensus::serialize(tx),
+            merkle_branch: merkle_branch
+                .into_iter()
+                .map(|node| node.to_byte_array())
+                .collect(),
+            tx_index,
+            header: bitcoin::consensus::serialize(header),
+        }
+    }
+
+    /// Proof for the transaction at `tx_index` in `block`. The last node of
+    /// each odd-length level of the merkle tree is paired with itself.
+    pub fn from_block(block: &bitcoin::Block, tx_index: usize) -> Option<Self> {
+        let tx = block.txdata.get(tx_index)?;
+        let mut level: Vec<[u8; 32]> = block
+            .txdata
+            .iter()
+            .map(|tx| tx.compute_txid().to_byte_array())
+            .collect();
+        let mut index = tx_index;
+        let mut merkle_branch = Vec::new();
+        let mut buf = [0u8; 64];
+        while level.len() > 1 {
+            if level.len() % 2 == 1 {
+                level.push(level[level.len() - 1]);
+            }
+            merkle_branch.push(level[index ^ 1]);
+            level = level
+                .chunks_exact(2)
+                .map(|pair| {
+                    buf[..32].copy_from_slice(&pair[0]);
+                    buf[32..].copy_from_slice(&pair[1]);
+                    sha256d::Hash::hash(&buf).to_byte_array()
+                })
+                .collect();
+            index >>= 1;
+        }
+        Some(Self {
+            raw_tx: bitcoin::consensus::serialize(tx),
+            merkle_branch,
+            tx_index: tx_index as u32,
+            header: bitcoin::consensus::serialize(&block.header),
+        })
+    }
+
+    /// Decode a proof from `SwapClaim::proof_data`
+    pub fn decode(bytes: &[u8]) -> Result<Self, L1ProofError> {
+        borsh::from_slice(bytes)
+            .map_err(|err| L1ProofError::Decode(err.to_string()))
+    }
+
+    /// Encode a proof for use as `SwapClaim::proof_data`
+    pub fn encode(&self) -> Vec<u8> {
+        borsh::to_vec(self).expect("serializing to a Vec should not fail")
+    }
+
+    /// Decode the proofs of several fills from `SwapClaim::proof_data`
+    pub fn decode_many(bytes: &[u8]) -> Result<Vec<Self>, L1ProofError> {
+        let proofs: Vec<Self> = borsh::from_slice(bytes)
+            .map_err(|err| L1ProofError::Decode(err.to_string()))?;
+        if proofs.len() > MAX_PROOFS_PER_CLAIM {
+            return Err(L1ProofError::TooManyProofs(proofs.len()));
+        }
+        Ok(proofs)
+    }
+
+    /// Encode the proofs of several fills for use as
+    /// `SwapClaim::proof_data`
+    pub fn encode_many(proofs: &[Self]) -> Vec<u8> {
+        borsh::to_vec(proofs).expect("serializing to a Vec should not fail")
+    }
+
+    pub fn decode_header(&self) -> Result<Header, L1ProofError> {
+        if self.header.len() != L1_HEADER_SIZE {
+            return Err(L1ProofError::InvalidHeader(format!(
+                "expected {L1_HEADER_SIZE} bytes, got {}",
+                self.header.len()
+            )));
+        }
+        bitcoin::consensus::deserialize(&self.header)
+            .map_err(|err| L1ProofError::InvalidHeader(err.to_string()))
+    }
+
+    pub fn decode_transaction(
+        &self,
+    ) -> Result<bitcoin::Transaction, L1ProofError> {
+        // A 64-byte transaction can be confused with an inner merkle node
+        if self.raw_tx.len() == 64 {
+            return Err(L1ProofError::InvalidTransaction(
+                "64-byte transactions are ambiguous with merkle nodes"
+                    .to_string(),
+            ));
+        }
+        bitcoin::consensus::deserialize(&self.raw_tx)
+            .map_err(|err| L1ProofError::InvalidTransaction(err.to_string()))
+    }
+
+    /// Compute the merkle root committed to by the branch for `txid`
+    fn compute_merkle_root(
+        &self,
+        txid: bitcoin::Txid,
+    ) -> Result<bitcoin::TxMerkleNode, L1ProofError> {
+        let branch_len = self.merkle_branch.len();
+        if branch_len > MAX_MERKLE_BRANCH_LEN {
+            return Err(L1ProofError::MerkleBranchTooLong(branch_len));
+        }
+        if branch_len < 32 && (self.tx_index >> branch_len) != 0 {
+            return Err(L1ProofError::MerkleRootMismatch { txid });
+        }
+        let mut node = txid.to_byte_array();
+        let mut index = self.tx_index;
+        let mut buf = [0u8; 64];
+        for sibling in &self.merkle_branch {
+            if index & 1 == 0 {
+                buf[..32].copy_from_slice(&node);
+                buf[32..].copy_from_slice(sibling);
+            } else {
+                buf[..32].copy_from_slice(sibling);
+                buf[32..].copy_from_slice(&node);
+            }
+            node = sha256d::Hash::hash(&buf).to_byte_array();
+            index >>= 1;
+        }
+        Ok(bitcoin::TxMerkleNode::from_byte_array(node))
+    }
+
+    /// Verify that the proof shows a payment of exactly `amount` to
+    /// `recipient_script` in a block header with valid proof of work.
+    ///
+    /// This does not check that the header is part of the parent chain's
+    /// best chain; callers that need confirmations must anchor the returned
+    /// block hash separately.
+    pub fn verify(
+        &self,
+        parent_chain: ParentChainType,
+        recipient_script: &bitcoin::Script,
+        amount: bitcoin::Amount,
+    ) -> Result<VerifiedL1Fill, L1ProofError> {
+        self.verify_payment(parent_chain, recipient_script, amount, amount)
+    }
+
+    /// Like [`Self::verify`], for a payment of between `min_amount` and
+    /// `max_amount`. The first matching output is the fill.
+    pub fn verify_payment(
+        &self,
+        parent_chain: ParentChainType,
+        recipient_script: &bitcoin::Script,
+        min_amount: bitcoin::Amount,
+        max_amount: bitcoin::Amount,
+    ) -> Result<VerifiedL1Fill, L1ProofError> {
+        let params = parent_chain.params();
+        if !params.verifiable_headers() {
+            return Err(L1ProofError::UnsupportedParentChain(parent_chain));
+        }
+        let header = self.decode_header()?;
+        let target = header.target();
+        if target > params.pow_limit() {
+            return Err(L1ProofError::TargetAboveLimit(parent_chain));
+        }
+        let block_hash = header
+            .validate_pow(target)
+            .map_err(|_| L1ProofError::InsufficientWork)?;
+        let tx = self.decode_transaction()?;
+        let txid = tx.compute_txid();
+        if self.compute_merkle_root(txid)? != header.merkle_root {
+            return Err(L1ProofError::MerkleRootMismatch { txid });
+        }
+        let vout = tx
+            .output
+            .iter()
+            .position(|txout| {
+                txout.script_pubkey.as_script() == recipient_script
+                    && (min_amount..=max_amount).contains(&txout.value)
+            })
+            .ok_or_else(|| L1ProofError::NoMatchingOutput {
+                txid,
+                script: recipient_script.to_owned(),
+                min: min_amount,
+                max: max_amount,
+            })?;
+        Ok(VerifiedL1Fill {
+            txid,
+            vout: vout as u32,
+            amount: tx.output[vout].value,
+            l2_claimer_address: declared_l2_claimer(&tx),
+            block_hash,
+            header,
+        })
+    }
+}
+
+/// `OP_RETURN` output script that binds an L1 payment to `l2_address`.
+/// Only `l2_address` can claim the L2 share of a fill paid by a transaction
+/// with this output.
+pub fn l2_claimer_declaration(l2_address: &Address) -> bitcoin::ScriptBuf {
+    bitcoin::ScriptBuf::new_op_return(l2_address.0)
+}
+
+/// L2 address declared by the first `OP_RETURN` output of `tx` that pushes
+/// exactly 20 bytes (see [`l2_claimer_declaration`])
+pub fn declared_l2_claimer(tx: &bitcoin::Transaction) -> Option<Address> {
+    tx.output.iter().find_map(|txout| {
+        let script = txout.script_pubkey.as_script();
+        if !script.is_op_return() {
+            return None;
+        }
+        let mut instructions = script.instructions().skip(1);
+        let Some(Ok(bitcoin::script::Instruction::PushBytes(data))) =
+            instructions.next()
+        else {
+            return None;
+        };
+        if instructions.next().is_some() {
+            return None;
+        }
+        <[u8; 20]>::try_from(data.as_bytes()).ok().map(Address)
+    })
+}
+
+/// Parse an L1 recipient address into the script it must be paid to, in
+/// any of the address encodings of the parent chain (see
+/// [`AddressParams`](super::AddressParams)).
+pub fn l1_recipient_script(
+    parent_chain: ParentChainType,
+    address: &str,
+) -> Result<bitcoin::ScriptBuf, L1ProofError> {
+    parent_chain
+        .params()
+        .address
+        .script_pubkey(address)
+        .map_err(|err| L1ProofError::InvalidRecipientAddress {
+            address: address.to_owned(),
+            reason: format!("{err}"),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{
+        Amount, ScriptBuf, Transaction, TxMerkleNode, TxOut,
+        absolute::LockTime,
+        block::{Header, Version},
+        hashes::{Hash as _, sha256d},
+        pow::CompactTarget,
+        transaction,
+    };
+
+    use super::{
+        L1InclusionProof, L1ProofError, MAX_PROOFS_PER_CLAIM,
+        declared_l2_claimer, l1_recipient_script, l2_claimer_declaration,
+    };
+    use crate::{
+        chain_registry::{BCH_TESTNET4_ID, builtin_network},
+        types::{Address, ParentChainType},
+    };
+
+    const REGTEST_ADDRESS: &str =
+        "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+
+    fn make_tx(
+        script: &ScriptBuf,
+        amount: Amount,
+        lock_time: u32,
+    ) -> Transaction {
+        Transaction {
+            version: transaction::Version::TWO,
+            lock_time: LockTime::from_consensus(lock_time),
+            input: Vec::new(),
+            output: vec![TxOut {
+                value: amount,
+                script_pubkey: script.clone(),
+            }],
+        }
+    }
+
+    fn parent(left: TxMerkleNode, right: TxMerkleNode) -> TxMerkleNode {
+        let mut buf = [0u8; 64];
+        buf[..32].copy_from_slice(left.as_byte_array());
+        buf[32..].copy_from_slice(right.as_byte_array());
+        TxMerkleNode::from_byte_array(sha256d::Hash::hash(&buf).to_byte_array())
+    }
+
+    /// Mine a regtest header committing to `merkle_root`
+    fn mine_header(merkle_root: TxMerkleNode) -> Header {
+        let mut header = Header {
+            version: Version::TWO,
+            prev_blockhash: bitcoin::BlockHash::all_zeros(),
+            merkle_root,
+            time: 1_700_000_000,
+            bits: CompactTarget::from_consensus(0x207fffff),
+            nonce: 0,
+        };
+        while header.validate_pow(header.target()).is_err() {
+            header.nonce += 1;
+        }
+        header
+    }
+
+    /// Build a two-transaction block and a proof for the second transaction
+    fn make_proof(amount: Amount) -> (L1InclusionProof, ScriptBuf) {
+        let script =
+            l1_recipient_script(ParentChainType::Regtest, REGTEST_ADDRESS)
+                .unwrap();
+        let other = make_tx(&ScriptBuf::new(), Amount::from_sat(1), 0);
+        let fill = make_tx(&script, amount, 1);
+        let other_node =
+            TxMerkleNode::from_raw_hash(other.compute_txid().to_raw_hash());
+        let fill_node =
+            TxMerkleNode::from_raw_hash(fill.compute_txid().to_raw_hash());
+        let header = mine_header(parent(other_node, fill_node));
+        let proof = L1InclusionProof::new(&fill, vec![other_node], 1, &header);
+        (proof, script)
+    }
+
+    #[test]
+    fn valid_proof_verifies() {
+        let amount = Amount::from_sat(5_000_000);
+        let (proof, script) = make_proof(amount);
+        let decoded = L1InclusionProof::decode(&proof.encode()).unwrap();
+        assert_eq!(decoded, proof);
+        let fill = decoded
+            .verify(ParentChainType::Regtest, &script, amount)
+            .unwrap();
+        assert_eq!(fill.vout, 0);
+        assert_eq!(fill.block_hash, fill.header.block_hash());
+    }
+
+    #[test]
+    fn proofs_from_block_verify() {
+        let script =
+            l1_recipient_script(ParentChainType::Regtest, REGTEST_ADDRESS)
+                .unwrap();
+        let amount = Amount::from_sat(5_000_000);
+        // An odd number of transactions, so the last node is duplicated
+        let mut block = bitcoin::Block {
+            header: mine_header(TxMerkleNode::all_zeros()),
+            txdata: (0..5).map(|i| make_tx(&script, amount, i)).collect(),
+        };
+        block.header = mine_header(block.compute_merkle_root().unwrap());
+        for tx_index in 0..5 {
+            let proof = L1InclusionProof::from_block(&block, tx_index).unwrap();
+            let fill = proof
+                .verify(ParentChainType::Regtest, &script, amount)
+                .unwrap();
+            assert_eq!(fill.txid, block.txdata[tx_index].compute_txid());
+            assert_eq!(fill.block_hash, block.block_hash());
+        }
+        assert!(L1InclusionProof::from_block(&block, 5).is_none());
+    }
+
+    #[test]
+    fn proof_with_wrong_amount_is_rejected() {
+        let amount = Amount::from_sat(5_000_000);
+        let (proof, script) = make_proof(amount);
+        let res = proof.verify(
+            ParentChainType::Regtest,
+            &script,
+            Amount::from_sat(5_000_001),
+        );
+        assert!(matches!(res, Err(L1ProofError::NoMatchingOutput { .. })));
+    }
+
+    #[test]
+    fn partial_payment_verifies_within_range() {
+        let amount = Amount::from_sat(5_000_000);
+        let (proof, script) = make_proof(amount);
+        let fill = proof
+            .verify_payment(
+                ParentChainType::Regtest,
+                &script,
+                Amount::from_sat(1_000_000),
+                Amount::from_sat(8_000_000),
+            )
+            .unwrap();
+        assert_eq!(fill.amount, amount);
+        assert_eq!(fill.l2_claimer_address, None);
+        let res = proof.verify_payment(
+            ParentChainType::Regtest,
+            &script,
+            Amount::from_sat(6_000_000),
+            Amount::from_sat(8_000_000),
+        );
+        assert!(matches!(res, Err(L1ProofError::NoMatchingOutput { .. })));
+    }
+
+    #[test]
+    fn proofs_encode_and_decode_many() {
+        let (proof, _) = make_proof(Amount::from_sat(5_000_000));
+        let proofs = vec![proof; 2];
+        let decoded = L1InclusionProof::decode_many(
+            &L1InclusionProof::encode_many(&proofs),
+        )
+        .unwrap();
+        assert_eq!(decoded, proofs);
+        let too_many = vec![proofs[0].clone(); MAX_PROOFS_PER_CLAIM + 1];
+        assert!(matches!(
+            L1InclusionProof::decode_many(&L1InclusionProof::encode_many(
+                &too_many
+            )),
+            Err(L1ProofError::TooManyProofs(_))
+        ));
+    }
+
+    #[test]
+    fn l2_claimer_is_declared_by_op_return() {
+        let alice = Address([1u8; 20]);
+        let mut tx = make_tx(&ScriptBuf::new(), Amount::from_sat(1), 0);
+        assert_eq!(declared_l2_claimer(&tx), None);
+        // Pushes of other sizes declare nothing
+        tx.output.push(TxOut {
+            value: Amount::ZERO,
+            script_pubkey: ScriptBuf::new_op_return([1u8; 32]),
+        });
+        assert_eq!(declared_l2_claimer(&tx), None);
+        tx.output.push(TxOut {
+            value: Amount::ZERO,
+            script_pubkey: l2_claimer_declaration(&alice),
+        });
+        assert_eq!(declared_l2_claimer(&tx), Some(alice));
+    }
+
+    #[test]
+    fn proof_with_wrong_index_is_rejected() {
+        let amount = Amount::from_sat(5_000_000);
+        let (mut proof, script) = make_proof(amount);
+        proof.tx_index = 0;
+        let res = proof.verify(ParentChainType::Regtest, &script, amount);
+        assert!(matches!(res, Err(L1ProofError::MerkleRootMismatch { .. })));
+        proof.tx_index = 3;
+        let res = proof.verify(ParentChainType::Regtest, &script, amount);
+        assert!(matches!(res, Err(L1ProofError::MerkleRootMismatch { .. })));
+    }
+
+    #[test]
+    fn regtest_header_is_rejected_for_mainnet() {
+        let amount = Amount::from_sat(5_000_000);
+        let (proof, script) = make_proof(amount);
+        let res = proof.verify(ParentChainType::BTC, &script, amount);
+        assert!(matches!(res, Err(L1ProofError::TargetAboveLimit(_))));
+    }
+
+    #[test]
+    fn recipient_address_must_match_chain() {
+        assert!(
+            l1_recipient_script(ParentChainType::BTC, REGTEST_ADDRESS).is_err()
+        );
+        const LTC_ADDRESS: &str = "ltc1qw508d6qejxtdg4y5r3zarvary0c5xw7kgmn4n9";
+        assert!(l1_recipient_script(ParentChainType::LTC, LTC_ADDRESS).is_ok());
+        assert!(
+            l1_recipient_script(ParentChainType::BTC, LTC_ADDRESS).is_err()
+        );
+    }
+
+    #[test]
+    fn bch_recipient_address_formats_share_a_script() {
+        let testnet4 = builtin_network(BCH_TESTNET4_ID);
+        let cashaddr = l1_recipient_script(
+            testnet4,
+            "bchtest:qpm2qsznhks23z7629mms6s4cwef74vcwvqcw003ap",
+        )
+        .unwrap();
+        let legacy =
+            l1_recipient_script(testnet4, "mrLC19Je2BuWQDkWSTriGYPyQJXKkkBmCx")
+                .unwrap();
+        assert_eq!(cashaddr, legacy);
+        assert!(l1_recipient_script(testnet4, REGTEST_ADDRESS).is_err());
+        // Mainnet addresses of the same key are for another network
+        for mainnet_address in [
+            "bitcoincash:qpm2qsznhks23z7629mms6s4cwef74vcwvy22gdx6a",
+            "1BpEi6DfDAUFd7GtittLSdBeYJvcoaVggu",
+        ] {
+            assert_eq!(
+                l1_recipient_script(ParentChainType::BCH, mainnet_address)
+                    .unwrap(),
+                cashaddr
+            );
+            assert!(l1_recipient_script(testnet4, mainnet_address).is_err());
+        }
+    }
+}
@@ -19,19 +19,32 @@ use crate::{
 };
 
 mod address;
+mod cashaddr;
+mod chain_params;
 pub mod hashes;
+mod l1_proof;
 pub mod proto;
+pub mod quote;
 pub mod schema;
 mod swap;
 mod transaction;
 
 pub use address::Address;
+pub use chain_params::{
+    AddressError, AddressParams, AsertAnchor, ChainNetwork, ChainParams,
+    DifficultyAdjustment, PowAlgorithm,
+};
 pub use hashes::{
     BlockHash, Hash, M6id, MerkleRoot, Txid, hash, hash_with_scratch_buffer,
 };
+pub use l1_proof::{
+    L1InclusionProof, L1ProofError, MAX_PROOFS_PER_CLAIM, VerifiedL1Fill,
+    declared_l2_claimer, l1_recipient_script, l2_claimer_declaration,
+};
+pub use quote::{QuoteFill, SwapOffer, SwapQuote};
 pub use swap::{
-    ParentChainType, Swap, SwapDirection, SwapError, SwapId, SwapState,
-    SwapTxId,
+    ChainId, InvalidChainId, ParentChainType, Swap, SwapDirection, SwapError,
+    SwapFill, SwapId, SwapReservation, SwapState, SwapTxId,
 };
 pub use transaction::{
     Authorized, AuthorizedTransaction, Content as OutputContent,
@@ -855,12 +868,35 @@ pub struct Tip {
     pub main_block_hash: bitcoin::BlockHash,
 }
 
-#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+/// Sidechain network, named after its mainchain network. Nodes only peer
+/// with nodes on the same network.
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize,
+)]
 #[cfg_attr(feature = "clap", derive(clap::ValueEnum, strum::Display))]
 pub enum Network {
     #[default]
     Signet,
     Regtest,
+    Testnet4,
+    Mainnet,
+}
+
+impl Network {
+    /// Mainchain network
+    pub fn mainchain_network(self) -> bitcoin::Network {
+        match self {
+            Self::Signet => bitcoin::Network::Signet,
+            Self::Regtest => bitcoin::Network::Regtest,
+            Self::Testnet4 => bitcoin::Network::Testnet4,
+            Self::Mainnet => bitcoin::Network::Bitcoin,
+        }
+    }
+
+    /// Genesis block of the mainchain network
+    pub fn mainchain_genesis_block_hash(self) -> bitcoin::BlockHash {
+        bitcoin::constants::genesis_block(self.mainchain_network()).block_hash()
+    }
 }
 
 /// Semver-compatible version
@@ -0,0 +1,397 @@
+//! Swap prices and quotes
+//!
+//! Prices are in L2 sats per L1 coin (see
+//! [`ParentChainType::sats_per_coin`]). Open L2 → L1 swaps are offers to
+//! buy L1 coins with L2 coins: a taker pays the swap's L1 amount and
+//! receives its L2 amount, so offers with a higher price are better for the
+//! taker.
+
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    ParentChainType, Swap, SwapDirection, SwapFill, SwapId, SwapState,
+    swap::l2_share_of_fill,
+};
+
+/// L2 amount of a swap of `l1_amount` at `price` (L2 sats per L1 coin),
+/// rounded down. `None` if it overflows.
+pub fn l2_amount_at_price(
+    parent_chain: ParentChainType,
+    l1_amount: bitcoin::Amount,
+    price: bitcoin::Amount,
+) -> Option<bitcoin::Amount> {
+    let l2_amount = l1_amount.to_sat() as u128 * price.to_sat() as u128
+        / parent_chain.sats_per_coin() as u128;
+    u64::try_from(l2_amount).ok().map(bitcoin::Amount::from_sat)
+}
+
+/// Price (L2 sats per L1 coin) of `l2_amount` for `l1_amount`, rounded
+/// down. `None` if `l1_amount` is zero or the price overflows.
+pub fn price_of(
+    parent_chain: ParentChainType,
+    l1_amount: bitcoin::Amount,
+    l2_amount: bitcoin::Amount,
+) -> Option<bitcoin::Amount> {
+    let price = (l2_amount.to_sat() as u128
+        * parent_chain.sats_per_coin() as u128)
+        .checked_div(l1_amount.to_sat() as u128)?;
+    u64::try_from(price).ok().map(bitcoin::Amount::from_sat)
+}
+
+/// An open L2 → L1 swap that a taker can fill
+#[derive(
+    Clone, Debug, Deserialize, Eq, PartialEq, Serialize, utoipa::ToSchema,
+)]
+pub struct SwapOffer {
+    pub swap_id: SwapId,
+    pub parent_chain: ParentChainType,
+    /// L2 sats per L1 coin, rounded down
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    #[schema(value_type = u64)]
+    pub price: bitcoin::Amount,
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    #[schema(value_type = u64)]
+    pub l1_amount: bitcoin::Amount,
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    #[schema(value_type = u64)]
+    pub l2_amount: bitcoin::Amount,
+    /// L1 amount paid by the fills recorded so far
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    #[schema(value_type = u64)]
+    pub filled_l1_amount: bitcoin::Amount,
+    /// Smallest L1 payment accepted as the next fill. Equal to the
+    /// outstanding L1 amount if the swap is filled by a single payment.
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    #[schema(value_type = u64)]
+    pub min_fill_l1_amount: bitcoin::Amount,
+    /// Whether the swap can be filled by several L1 payments. Otherwise, it
+    /// must be reserved (see [`Swap::reservation`]) before it is paid.
+    pub allows_partial_fills: bool,
+    pub required_confirmations: u32,
+    pub expires_at_height: Option<u32>,
+}
+
+impl SwapOffer {
+    /// Offer of `swap` at sidechain height `height`, given the `fills`
+    /// recorded for it. `None` if the swap is not an open L2 → L1 swap that
+    /// can be filled at that height: unfilled, or partially fillable with L1
+    /// still outstanding, and neither expired nor reserved by a taker.
+    pub fn new(swap: &Swap, fills: &[SwapFill], height: u32) -> Option<Self> {
+        if swap.direction != SwapDirection::L2ToL1
+            || swap.l2_recipient.is_some()
+            || swap.l1_recipient_address.is_none()
+            || swap
+                .expires_at_height
+                .is_some_and(|expiry| height >= expiry)
+            || swap.live_reservation(height).is_some()
+        {
+            return None;
+        }
+        let l1_amount = swap.l1_amount?;
+        let filled_l1_amount: bitcoin::Amount =
+            fills.iter().map(|fill| fill.l1_amount).sum();
+        let min_fill_l1_amount = if swap.allows_partial_fills() {
+            if !matches!(
+                swap.state,
+                SwapState::Pending | SwapState::PartiallyFilled(..)
+            ) {
+                return None;
+            }
+            swap.next_fill_range(filled_l1_amount)?.0
+        } else {
+            if swap.state != SwapState::Pending
+                || swap.has_l1_txid()
+                || swap.l2_claimer_address.is_some()
+                    && swap.reservation.is_none()
+            {
+                return None;
+            }
+            l1_amount
+        };
+        Some(Self {
+            swap_id: swap.id,
+            parent_chain: swap.parent_chain,
+            price: price_of(swap.parent_chain, l1_amount, swap.l2_amount)?,
+            l1_amount,
+            l2_amount: swap.l2_amount,
+            filled_l1_amount,
+            min_fill_l1_amount,
+            allows_partial_fills: swap.allows_partial_fills(),
+            required_confirmations: swap.required_confirmations,
+            expires_at_height: swap.expires_at_height,
+        })
+    }
+
+    /// L1 amount not yet filled
+    pub fn outstanding_l1_amount(&self) -> bitcoin::Amount {
+        self.l1_amount
+            .checked_sub(self.filled_l1_amount)
+            .unwrap_or(bitcoin::Amount::ZERO)
+    }
+
+    /// L2 amount released by filling the outstanding L1 amount
+    pub fn outstanding_l2_amount(&self) -> bitcoin::Amount {
+        self.l2_share_of_fill(self.outstanding_l1_amount())
+    }
+
+    /// L2 share released by an L1 fill of `fill_l1_amount`, as the next fill
+    pub fn l2_share_of_fill(
+        &self,
+        fill_l1_amount: bitcoin::Amount,
+    ) -> bitcoin::Amount {
+        l2_share_of_fill(
+            self.l1_amount,
+            self.l2_amount,
+            self.filled_l1_amount,
+            fill_l1_amount,
+        )
+    }
+
+    /// Order offers best first: by descending price, compared exactly
+    /// rather than by the rounded `price`, then by descending outstanding
+    /// L1 amount, then by swap ID
+    pub fn cmp_best_first(&self, other: &Self) -> Ordering {
+        let price = |offer: &Self, other: &Self| {
+            offer.l2_amount.to_sat() as u128 * other.l1_amount.to_sat() as u128
+        };
+        price(other, self)
+            .cmp(&price(self, other))
+            .then_with(|| {
+                other
+                    .outstanding_l1_amount()
+                    .cmp(&self.outstanding_l1_amount())
+            })
+            .then_with(|| self.swap_id.cmp(&other.swap_id))
+    }
+}
+
+/// A fill of one offer in a [`SwapQuote`]
+#[derive(
+    Clone, Debug, Deserialize, Eq, PartialEq, Serialize, utoipa::ToSchema,
+)]
+pub struct QuoteFill {
+    pub swap_id: SwapId,
+    /// L1 amount to pay to the swap's L1 recipient
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    #[schema(value_type = u64)]
+    pub l1_amount: bitcoin::Amount,
+    /// L2 amount released by the fill
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    #[schema(value_type = u64)]
+    pub l2_amount: bitcoin::Amount,
+}
+
+/// Best fill of a desired L1 amount from the open offers of a parent chain
+#[derive(
+    Clone, Debug, Deserialize, Eq, PartialEq, Serialize, utoipa::ToSchema,
+)]
+pub struct SwapQuote {
+    pub parent_chain: ParentChainType,
+    /// Total L1 amount to pay, at most the desired amount
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    #[schema(value_type = u64)]
+    pub l1_amount: bitcoin::Amount,
+    /// Total L2 amount received
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    #[schema(value_type = u64)]
+    pub l2_amount: bitcoin::Amount,
+    /// Average price of the fills in L2 sats per L1 coin, rounded down.
+    /// `None` if no offer can be filled.
+    #[serde(with = "bitcoin::amount::serde::as_sat::opt")]
+    #[schema(value_type = Option<u64>)]
+    pub price: Option<bitcoin::Amount>,
+    /// Fills, best price first
+    pub fills: Vec<QuoteFill>,
+}
+
+impl SwapQuote {
+    /// Best fill of up to `l1_amount` from the offers of `parent_chain`.
+    /// Offers are taken best price first, each filled with as much of the
+    /// remaining amount as it accepts. Offers that must be filled by more
+    /// than the remaining amount are skipped.
+    pub fn new(
+        parent_chain: ParentChainType,
+        offers: &[SwapOffer],
+        l1_amount: bitcoin::Amount,
+    ) -> Self {
+        let mut offers: Vec<&SwapOffer> = offers
+            .iter()
+            .filter(|offer| offer.parent_chain == parent_chain)
+            .collect();
+        offers.sort_by(|lhs, rhs| lhs.cmp_best_first(rhs));
+        let mut remaining = l1_amount;
+        let mut fills = Vec::new();
+        for offer in offers {
+            let fill_l1_amount = remaining.min(offer.outstanding_l1_amount());
+            if fill_l1_amount == bitcoin::Amount::ZERO
+                || fill_l1_amount < offer.min_fill_l1_amount
+            {
+                continue;
+            }
+            fills.push(QuoteFill {
+                swap_id: offer.swap_id,
+                l1_amount: fill_l1_amount,
+                l2_amount: offer.l2_share_of_fill(fill_l1_amount),
+            });
+            remaining -= fill_l1_amount;
+        }
+        let filled_l1_amount: bitcoin::Amount =
+            fills.iter().map(|fill| fill.l1_amount).sum();
+        let filled_l2_amount: bitcoin::Amount =
+            fills.iter().map(|fill| fill.l2_amount).sum();
+        Self {
+            parent_chain,
+            l1_amount: filled_l1_amount,
+            l2_amount: filled_l2_amount,
+            price: price_of(parent_chain, filled_l1_amount, filled_l2_amount),
+            fills,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Address, SwapTxId};
+
+    fn btc(amount: f64) -> bitcoin::Amount {
+        bitcoin::Amount::from_btc(amount).unwrap()
+    }
+
+    /// Open Regtest swap of `l1_amount` for `l2_amount`
+    fn open_swap(
+        id: u8,
+        l1_amount: bitcoin::Amount,
+        l2_amount: bitcoin::Amount,
+        min_fill_l1_amount: Option<bitcoin::Amount>,
+    ) -> Swap {
+        let mut swap = Swap::new(
+            SwapId([id; 32]),
+            SwapDirection::L2ToL1,
+            ParentChainType::Regtest,
+            SwapTxId::from_bytes(&[0u8; 32]),
+            None,
+            None,
+            l2_amount,
+            Some("bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080".to_owned()),
+            Some(l1_amount),
+            100,
+            Some(200),
+            Some(Address([1u8; 20])),
+        );
+        swap.min_fill_l1_amount = min_fill_l1_amount;
+        swap
+    }
+
+    #[test]
+    fn price_conversions() {
+        let chain = ParentChainType::BTC;
+        assert_eq!(
+            l2_amount_at_price(chain, btc(0.5), btc(2.0)),
+            Some(btc(1.0))
+        );
+        assert_eq!(price_of(chain, btc(0.5), btc(1.0)), Some(btc(2.0)));
+        assert_eq!(price_of(chain, bitcoin::Amount::ZERO, btc(1.0)), None);
+        // Rounded down
+        assert_eq!(
+            price_of(
+                chain,
+                bitcoin::Amount::from_sat(3),
+                bitcoin::Amount::from_sat(1)
+            ),
+            Some(bitcoin::Amount::from_sat(33_333_333))
+        );
+        assert_eq!(
+            l2_amount_at_price(chain, bitcoin::Amount::MAX, btc(2.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn only_fillable_open_swaps_are_offers() {
+        let swap = open_swap(1, btc(1.0), btc(2.0), None);
+        let offer = SwapOffer::new(&swap, &[], 150).unwrap();
+        assert_eq!(offer.price, btc(2.0));
+        assert_eq!(offer.min_fill_l1_amount, btc(1.0));
+        assert_eq!(offer.outstanding_l2_amount(), btc(2.0));
+        // Expired
+        assert!(SwapOffer::new(&swap, &[], 200).is_none());
+        // Not open
+        let mut with_recipient = swap.clone();
+        with_recipient.l2_recipient = Some(Address([2u8; 20]));
+        assert!(SwapOffer::new(&with_recipient, &[], 150).is_none());
+        // Reserved by a taker
+        let mut reserved = swap.clone();
+        reserved.reservation = Some(crate::types::SwapReservation {
+            l2_claimer_address: Address([3u8; 20]),
+            expires_at_height: 160,
+            bond_outpoints: Vec::new(),
+            bond: reserved.reservation_bond(),
+        });
+        assert!(SwapOffer::new(&reserved, &[], 150).is_none());
+        assert!(SwapOffer::new(&reserved, &[], 160).is_some());
+        // Filled
+        let mut filled = swap;
+        filled.state = SwapState::WaitingConfirmations(1, 3);
+        assert!(SwapOffer::new(&filled, &[], 150).is_none());
+    }
+
+    #[test]
+    fn partially_filled_offer_has_outstanding_amount() {
+        let mut swap = open_swap(1, btc(1.0), btc(3.0), Some(btc(0.1)));
+        let fills = [SwapFill {
+            l1_txid: SwapTxId::from_bytes(&[5u8; 32]),
+            l1_amount: btc(0.95),
+            l2_amount: swap.l2_share_of_fill(btc(0.0), btc(0.95)),
+            l2_claimer_address: Address([1u8; 20]),
+        }];
+        swap.state = swap.state_from_fills(&fills);
+        let offer = SwapOffer::new(&swap, &fills, 150).unwrap();
+        assert_eq!(offer.outstanding_l1_amount(), btc(0.05));
+        assert_eq!(offer.min_fill_l1_amount, btc(0.05));
+        assert_eq!(
+            offer.outstanding_l2_amount() + fills[0].l2_amount,
+            btc(3.0)
+        );
+    }
+
+    #[test]
+    fn quote_takes_best_prices_first() {
+        let offers: Vec<SwapOffer> = [
+            open_swap(1, btc(1.0), btc(2.0), None),
+            open_swap(2, btc(1.0), btc(3.0), Some(btc(0.5))),
+            open_swap(3, btc(0.5), btc(1.25), None),
+            open_swap(4, btc(2.0), btc(5.0), None),
+        ]
+        .iter()
+        .map(|swap| SwapOffer::new(swap, &[], 150).unwrap())
+        .collect();
+        let mut sorted = offers.clone();
+        sorted.sort_by(SwapOffer::cmp_best_first);
+        let ids: Vec<u8> =
+            sorted.iter().map(|offer| offer.swap_id.0[0]).collect();
+        assert_eq!(ids, [2, 4, 3, 1]);
+
+        // Swap 4 must be filled whole, so it is skipped for swap 3
+        let quote = SwapQuote::new(ParentChainType::Regtest, &offers, btc(1.6));
+        let fills: Vec<(u8, bitcoin::Amount)> = quote
+            .fills
+            .iter()
+            .map(|fill| (fill.swap_id.0[0], fill.l1_amount))
+            .collect();
+        assert_eq!(fills, [(2, btc(1.0)), (3, btc(0.5))]);
+        assert_eq!(quote.l1_amount, btc(1.5));
+        assert_eq!(quote.l2_amount, btc(4.25));
+
+        // Partial fills must be at least the minimum fill
+        let quote = SwapQuote::new(ParentChainType::Regtest, &offers, btc(0.4));
+        assert!(quote.fills.is_empty());
+        assert_eq!(quote.price, None);
+
+        // Offers of other chains are ignored
+        let quote = SwapQuote::new(ParentChainType::BTC, &offers, btc(10.0));
+        assert!(quote.fills.is_empty());
+    }
+}
@@ -4,9 +4,13 @@ use bitcoin::{self, hashes::Hash as _};
 use blake3;
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
+use serde_with::{DeserializeFromStr, SerializeDisplay};
 use thiserror::Error;
 
-use super::{Address, BlockHash};
+use super::{
+    Address, BlockHash, OutPoint, chain_params::ChainParams, l1_proof,
+};
+use crate::chain_registry::{self, ChainDescriptor};
 
 /// 32-byte swap identifier
 #[derive(
@@ -50,13 +54,18 @@ impl SwapId {
         Self(*hash.as_bytes())
     }
 
-    /// Generate swap ID for L1 → L2 swaps (for future use)
+    /// Generate swap ID for L1 → L2 swap offers
     pub fn from_l1_to_l2(
-        l1_txid: &bitcoin::Txid,
+        l1_amount: bitcoin::Amount,
+        l2_amount: bitcoin::Amount,
+        l2_creator_address: &Address,
         l2_recipient_address: &Address,
     ) -> Self {
         let mut id_data = Vec::new();
-        id_data.extend_from_slice(l1_txid.as_ref());
+        id_data.extend_from_slice(b"L1_TO_L2");
+        id_data.extend_from_slice(&l1_amount.to_sat().to_le_bytes());
+        id_data.extend_from_slice(&l2_amount.to_sat().to_le_bytes());
+        id_data.extend_from_slice(&l2_creator_address.0);
         id_data.extend_from_slice(&l2_recipient_address.0);
         let hash = blake3::hash(&id_data);
         Self(*hash.as_bytes())
@@ -87,21 +96,102 @@ pub enum SwapDirection {
     L2ToL1,
 }
 
-/// Parent chain type for swaps
-/// Note: This can be different from the sidechain's mainchain network.
-/// For example, sidechain may be on Regtest, but swaps can target Signet, Mainnet, etc.
+/// ID of a parent chain defined in the [chain registry](crate::chain_registry)
+/// rather than built in: 1 to [`ChainId::MAX_LEN`] ASCII letters, digits,
+/// `-` or `_`.
 #[derive(
     BorshSerialize,
-    BorshDeserialize,
     Clone,
     Copy,
-    Debug,
-    Deserialize,
+    DeserializeFromStr,
     Eq,
     Hash,
+    Ord,
     PartialEq,
-    Serialize,
-    utoipa::ToSchema,
+    PartialOrd,
+    SerializeDisplay,
+)]
+pub struct ChainId([u8; ChainId::MAX_LEN]);
+
+impl ChainId {
+    pub const MAX_LEN: usize = 16;
+
+    pub fn as_str(&self) -> &str {
+        let len = self.0.iter().position(|b| *b == 0).unwrap_or(Self::MAX_LEN);
+        std::str::from_utf8(&self.0[..len]).expect("chain IDs are ASCII")
+    }
+}
+
+#[derive(Debug, Error)]
+#[error(
+    "invalid chain ID `{0}`: expected 1 to {max} ASCII letters, digits, `-` or `_`",
+    max = ChainId::MAX_LEN
+)]
+pub struct InvalidChainId(String);
+
+impl std::str::FromStr for ChainId {
+    type Err = InvalidChainId;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty()
+            || s.len() > Self::MAX_LEN
+            || !s
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+        {
+            return Err(InvalidChainId(s.to_owned()));
+        }
+        let mut bytes = [0; Self::MAX_LEN];
+        bytes[..s.len()].copy_from_slice(s.as_bytes());
+        Ok(Self(bytes))
+    }
+}
+
+impl BorshDeserialize for ChainId {
+    fn deserialize_reader<R: std::io::Read>(
+        reader: &mut R,
+    ) -> std::io::Result<Self> {
+        let bytes = <[u8; Self::MAX_LEN]>::deserialize_reader(reader)?;
+        let len = bytes.iter().position(|b| *b == 0).unwrap_or(Self::MAX_LEN);
+        // Reject non-canonical padding
+        if bytes[len..].iter().any(|b| *b != 0) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "invalid chain ID padding",
+            ));
+        }
+        std::str::from_utf8(&bytes[..len])
+            .ok()
+            .and_then(|id| id.parse().ok())
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "invalid chain ID",
+                )
+            })
+    }
+}
+
+impl std::fmt::Debug for ChainId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ChainId").field(&self.as_str()).finish()
+    }
+}
+
+impl std::fmt::Display for ChainId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Parent chain type for swaps, and the stable ID by which swaps reference
+/// their parent chain. Its parameters are looked up in the
+/// [chain registry](crate::chain_registry).
+///
+/// Note: This can be different from the sidechain's mainchain network.
+/// For example, sidechain may be on Regtest, but swaps can target Signet, Mainnet, etc.
+#[derive(
+    BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Eq, Hash, PartialEq,
 )]
 pub enum ParentChainType {
     /// Bitcoin Mainnet
@@ -114,15 +204,165 @@ pub enum ParentChainType {
     Signet,
     /// Bitcoin Regtest (for testing)
     Regtest,
+    /// Chain loaded from the chain registry
+    Custom(ChainId),
+}
+
+mod parent_chain_type_serde {
+    use serde::{Deserialize, Serialize};
+    use utoipa::{
+        PartialSchema, ToSchema,
+        openapi::{self, RefOr, Schema},
+    };
+
+    use super::{ChainId, ParentChainType};
+
+    /// Default representation for Serde, as derived before custom chains
+    /// were added
+    #[derive(Deserialize, Serialize)]
+    enum DefaultRepr {
+        BTC,
+        BCH,
+        LTC,
+        Signet,
+        Regtest,
+        Custom(ChainId),
+    }
+
+    impl From<ParentChainType> for DefaultRepr {
+        fn from(parent_chain: ParentChainType) -> Self {
+            match parent_chain {
+                ParentChainType::BTC => Self::BTC,
+                ParentChainType::BCH => Self::BCH,
+                ParentChainType::LTC => Self::LTC,
+                ParentChainType::Signet => Self::Signet,
+                ParentChainType::Regtest => Self::Regtest,
+                ParentChainType::Custom(id) => Self::Custom(id),
+            }
+        }
+    }
+
+    impl From<DefaultRepr> for ParentChainType {
+        fn from(repr: DefaultRepr) -> Self {
+            match repr {
+                DefaultRepr::BTC => Self::BTC,
+                DefaultRepr::BCH => Self::BCH,
+                DefaultRepr::LTC => Self::LTC,
+                DefaultRepr::Signet => Self::Signet,
+                DefaultRepr::Regtest => Self::Regtest,
+                DefaultRepr::Custom(id) => Self::Custom(id),
+            }
+        }
+    }
+
+    /// Human-readable formats use the chain ID string
+    type SerdeRepr = serde_with::IfIsHumanReadable<
+        serde_with::FromInto<DefaultRepr>,
+        serde_with::DisplayFromStr,
+    >;
+
+    impl<'de> Deserialize<'de> for ParentChainType {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            <SerdeRepr as serde_with::DeserializeAs<'de, _>>::deserialize_as(
+                deserializer,
+            )
+        }
+    }
+
+    impl Serialize for ParentChainType {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            <SerdeRepr as serde_with::SerializeAs<_>>::serialize_as(
+                self, serializer,
+            )
+        }
+    }
+
+    impl PartialSchema for ParentChainType {
+        fn schema() -> RefOr<Schema> {
+            // Chain ID string
+            let obj = openapi::Object::with_type(openapi::Type::String);
+            RefOr::T(Schema::Object(obj))
+        }
+    }
+
+    impl ToSchema for ParentChainType {
+        fn name() -> std::borrow::Cow<'static, str> {
+            std::borrow::Cow::Borrowed("ParentChainType")
+        }
+    }
+}
+
+impl std::fmt::Display for ParentChainType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BTC => f.write_str("BTC"),
+            Self::BCH => f.write_str("BCH"),
+            Self::LTC => f.write_str("LTC"),
+            Self::Signet => f.write_str("Signet"),
+            Self::Regtest => f.write_str("Regtest"),
+            Self::Custom(id) => f.write_str(id.as_str()),
+        }
+    }
+}
+
+impl std::str::FromStr for ParentChainType {
+    type Err = InvalidChainId;
+
+    /// Parse a chain ID. The IDs of built-in chains are the names of their
+    /// variants.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "BTC" => Ok(Self::BTC),
+            "BCH" => Ok(Self::BCH),
+            "LTC" => Ok(Self::LTC),
+            "Signet" => Ok(Self::Signet),
+            "Regtest" => Ok(Self::Regtest),
+            _ => s.parse().map(Self::Custom),
+        }
+    }
 }
 
 impl ParentChainType {
+    /// Built-in parent chains
+    pub const BUILTIN: [Self; 5] =
+        [Self::BTC, Self::BCH, Self::LTC, Self::Signet, Self::Regtest];
+
+    /// Descriptor of this chain, or `None` for a custom chain that is not
+    /// in the chain registry
+    pub fn descriptor(&self) -> Option<&'static ChainDescriptor> {
+        chain_registry::registry().descriptor(*self)
+    }
+
+    /// Descriptor of this chain, or that of an unregistered chain (see
+    /// [`ChainDescriptor::unregistered`])
+    fn descriptor_or_unregistered(&self) -> &'static ChainDescriptor {
+        self.descriptor()
+            .unwrap_or_else(ChainDescriptor::unregistered)
+    }
+
     /// Get default required confirmations for this chain
     pub fn default_confirmations(&self) -> u32 {
-        match self {
-            Self::BTC => 6,
-            Self::BCH | Self::LTC | Self::Signet | Self::Regtest => 3,
-        }
+        self.descriptor_or_unregistered().default_confirmations
+    }
+
+    /// Minimum required confirmations for a swap of `l1_amount` on this
+    /// chain, from the chain's confirmation tiers. Enforced by consensus.
+    pub fn min_confirmations(&self, l1_amount: bitcoin::Amount) -> u32 {
+        self.descriptor_or_unregistered()
+            .min_confirmations(l1_amount)
+    }
+
+    /// Required confirmations suggested for a swap of `l1_amount`: the
+    /// chain's default, or the minimum for the swap's size if higher
+    pub fn suggested_confirmations(&self, l1_amount: bitcoin::Amount) -> u32 {
+        self.default_confirmations()
+            .max(self.min_confirmations(l1_amount))
     }
 
     /// Get default swap expiration in L2 blocks for this chain.
@@ -130,14 +370,36 @@ impl ParentChainType {
     /// After this many L2 blocks, an unclaimed swap is automatically cancelled
     /// and its locked outputs are returned to the creator.
     pub fn default_swap_expiration_blocks(&self) -> u32 {
-        match self {
-            // ~1 week at 10min L2 blocks
-            Self::BTC => 1008,
-            // ~3 days for faster chains / testnets
-            Self::BCH | Self::LTC | Self::Signet => 432,
-            // Short expiration for testing
-            Self::Regtest => 50,
-        }
+        self.descriptor_or_unregistered()
+            .swap_expiration_blocks
+            .default
+    }
+
+    /// Minimum swap expiration in L2 blocks that a swap creator may choose
+    pub fn min_swap_expiration_blocks(&self) -> u32 {
+        self.descriptor_or_unregistered().swap_expiration_blocks.min
+    }
+
+    /// Maximum swap expiration in L2 blocks that a swap creator may choose
+    pub fn max_swap_expiration_blocks(&self) -> u32 {
+        self.descriptor_or_unregistered().swap_expiration_blocks.max
+    }
+
+    /// Swap expiration in L2 blocks, as chosen by the swap creator or the
+    /// default for this chain
+    pub fn swap_expiration_blocks(
+        &self,
+        expires_in_blocks: Option<u32>,
+    ) -> u32 {
+        expires_in_blocks
+            .unwrap_or_else(|| self.default_swap_expiration_blocks())
+    }
+
+    /// Maximum number of L2 blocks for which a taker may reserve an open
+    /// swap
+    pub fn max_swap_reservation_blocks(&self) -> u32 {
+        self.descriptor_or_unregistered()
+            .max_swap_reservation_blocks
     }
 
     /// Maximum L1 confirmation age (in L1 blocks) for an L1 transaction
@@ -146,52 +408,30 @@ impl ParentChainType {
     /// Prevents using old, unrelated L1 transactions that happen to match
     /// the swap's address and amount.
     pub fn max_l1_tx_age_blocks(&self) -> u32 {
-        match self {
-            // ~2 weeks of Bitcoin blocks
-            Self::BTC => 2016,
-            // ~2 weeks equivalent for other chains
-            Self::BCH => 2016,
-            Self::LTC => 8064,
-            Self::Signet => 2016,
-            // Generous for testing
-            Self::Regtest => 500,
-        }
+        self.descriptor_or_unregistered().max_l1_tx_age_blocks
     }
 
-    /// Get the Bitcoin network enum for this chain type
-    pub fn to_bitcoin_network(&self) -> bitcoin::Network {
-        match self {
-            Self::BTC => bitcoin::Network::Bitcoin,
-            Self::Signet => bitcoin::Network::Signet,
-            Self::Regtest => bitcoin::Network::Regtest,
-            Self::BCH | Self::LTC => {
-                // BCH and LTC are separate networks, would need separate handling
-                bitcoin::Network::Bitcoin // Placeholder
-            }
-        }
+    /// Consensus and address parameters of this chain's network
+    pub fn params(&self) -> &'static ChainParams {
+        &self.descriptor_or_unregistered().params
     }
 
-    /// Get the default RPC port for this chain
-    ///
-    /// These are the standard mainnet RPC ports. Testnet/regtest ports differ.
+    /// Hash of the genesis block of this chain's network. L1 endpoints must
+    /// serve this block at height 0.
+    pub fn genesis_block_hash(&self) -> bitcoin::BlockHash {
+        self.params().genesis_block_hash
+    }
+
+    /// Get the default RPC port of this chain's network
     pub fn default_rpc_port(&self) -> u16 {
-        match self {
-            Self::BTC => 8332,
-            Self::BCH => 8332, // Bitcoin Cash ABC/BCHN default
-            Self::LTC => 9332, // Litecoin Core default
-            Self::Signet => 38332, // Bitcoin Signet default
-            Self::Regtest => 18443, // Bitcoin Regtest default
-        }
+        self.params().default_rpc_port
     }
 
     /// Get the human-readable coin name for display
-    pub fn coin_name(&self) -> &'static str {
-        match self {
-            Self::BTC => "Bitcoin",
-            Self::BCH => "Bitcoin Cash",
-            Self::LTC => "Litecoin",
-            Self::Signet => "Bitcoin Signet",
-            Self::Regtest => "Bitcoin Regtest",
+    pub fn coin_name(&self) -> String {
+        match self.descriptor() {
+            Some(descriptor) => descriptor.coin_name.clone(),
+            None => self.to_string(),
         }
     }
 
@@ -203,30 +443,26 @@ impl ParentChainType {
     }
 
     /// Get the ticker symbol for this chain
-    pub fn ticker(&self) -> &'static str {
-        match self {
-            Self::BTC => "BTC",
-            Self::BCH => "BCH",
-            Self::LTC => "LTC",
-            Self::Signet => "sBTC",
-            Self::Regtest => "rBTC",
+    pub fn ticker(&self) -> String {
+        match self.descriptor() {
+            Some(descriptor) => descriptor.ticker.clone(),
+            None => self.to_string(),
         }
     }
 
+    /// Coin name and ticker, for display
+    pub fn display_name(&self) -> String {
+        format!("{} ({})", self.coin_name(), self.ticker())
+    }
+
     /// Get the default RPC URL hint for this chain
-    pub fn default_rpc_url_hint(&self) -> &'static str {
-        match self {
-            Self::BTC => "http://localhost:8332",
-            Self::BCH => "http://localhost:8332",
-            Self::LTC => "http://localhost:9332",
-            Self::Signet => "http://localhost:38332",
-            Self::Regtest => "http://localhost:18443",
-        }
+    pub fn default_rpc_url_hint(&self) -> String {
+        format!("http://localhost:{}", self.default_rpc_port())
     }
 
-    /// Get all supported parent chain types
+    /// Get all parent chains in the chain registry
     pub fn all() -> &'static [ParentChainType] {
-        &[Self::BTC, Self::BCH, Self::LTC, Self::Signet, Self::Regtest]
+        chain_registry::registry().chains()
     }
 }
 
@@ -256,6 +492,10 @@ pub enum SwapState {
     Completed,
     /// Swap expired or cancelled
     Cancelled,
+    /// Partially fillable swap with some fills claimed, and more L1 still
+    /// outstanding.
+    /// Tuple format: (filled_l1_sats, l1_amount_sats)
+    PartiallyFilled(u64, u64),
 }
 
 impl SwapState {
@@ -472,6 +712,182 @@ mod tests {
         assert_eq!(swap.state, SwapState::Pending);
     }
 
+    #[test]
+    fn l1_to_l2_offer_awaits_acceptance() {
+        let mut swap = Swap::new(
+            SwapId([1u8; 32]),
+            SwapDirection::L1ToL2,
+            ParentChainType::Regtest,
+            SwapTxId::Hash32([0u8; 32]),
+            None,
+            Some(Address([2u8; 20])),
+            bitcoin::Amount::from_sat(1_000_000),
+            None,
+            Some(bitcoin::Amount::from_sat(500_000)),
+            100,
+            None,
+            Some(Address([3u8; 20])),
+        );
+        assert!(swap.is_awaiting_acceptance());
+        swap.accept("bcrt1qtest".to_string());
+        assert!(!swap.is_awaiting_acceptance());
+        assert_eq!(swap.l1_recipient_address.as_deref(), Some("bcrt1qtest"));
+        // L2 → L1 swaps are never awaiting acceptance
+        assert!(
+            !make_swap(ParentChainType::Regtest, 100, None)
+                .is_awaiting_acceptance()
+        );
+    }
+
+    #[test]
+    fn l1_recipient_script_is_derived_from_address() {
+        let address = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+        let mut swap = Swap::new(
+            SwapId([1u8; 32]),
+            SwapDirection::L1ToL2,
+            ParentChainType::Regtest,
+            SwapTxId::Hash32([0u8; 32]),
+            None,
+            Some(Address([2u8; 20])),
+            bitcoin::Amount::from_sat(1_000_000),
+            None,
+            Some(bitcoin::Amount::from_sat(500_000)),
+            100,
+            None,
+            Some(Address([3u8; 20])),
+        );
+        assert!(swap.l1_recipient_script.is_none());
+        swap.accept(address.to_string());
+        let script = swap.l1_recipient_script.clone().unwrap();
+        assert!(script.is_p2wpkh());
+        // Unparseable addresses have no script
+        assert!(
+            make_swap(ParentChainType::Regtest, 100, None)
+                .l1_recipient_script
+                .is_none()
+        );
+        // The script survives a Borsh round trip
+        let bytes = borsh::to_vec(&swap).unwrap();
+        let decoded: Swap = borsh::from_slice(&bytes).unwrap();
+        assert_eq!(decoded.l1_recipient_script, Some(script));
+    }
+
+    #[test]
+    fn l1_to_l2_swap_id_differs_from_l2_to_l1() {
+        let creator = Address([3u8; 20]);
+        let recipient = Address([2u8; 20]);
+        let l1_amount = bitcoin::Amount::from_sat(500_000);
+        let l2_amount = bitcoin::Amount::from_sat(1_000_000);
+        let offer =
+            SwapId::from_l1_to_l2(l1_amount, l2_amount, &creator, &recipient);
+        assert_eq!(
+            offer,
+            SwapId::from_l1_to_l2(l1_amount, l2_amount, &creator, &recipient)
+        );
+        assert_ne!(
+            offer,
+            SwapId::from_l1_to_l2(
+                l1_amount,
+                bitcoin::Amount::from_sat(1_000_001),
+                &creator,
+                &recipient
+            )
+        );
+        assert_ne!(
+            offer,
+            SwapId::from_l2_to_l1("", l1_amount, &creator, Some(&recipient))
+        );
+    }
+
+    fn make_fill(l1_sats: u64, l2_sats: u64) -> SwapFill {
+        SwapFill {
+            l1_txid: SwapTxId::Hash32([l1_sats as u8; 32]),
+            l1_amount: bitcoin::Amount::from_sat(l1_sats),
+            l2_amount: bitcoin::Amount::from_sat(l2_sats),
+            l2_claimer_address: Address([0u8; 20]),
+        }
+    }
+
+    #[test]
+    fn partial_fill_shares_sum_to_l2_amount() {
+        // 1_000_000 L2 for 300_000 L1: shares do not divide evenly
+        let mut swap = make_swap(ParentChainType::Regtest, 100, None);
+        swap.l1_amount = Some(bitcoin::Amount::from_sat(300_000));
+        swap.min_fill_l1_amount = Some(bitcoin::Amount::from_sat(100_000));
+        let mut filled = bitcoin::Amount::ZERO;
+        let mut released = bitcoin::Amount::ZERO;
+        for _ in 0..3 {
+            let fill = bitcoin::Amount::from_sat(100_000);
+            released += swap.l2_share_of_fill(filled, fill);
+            filled += fill;
+        }
+        assert_eq!(released, swap.l2_amount);
+        assert_eq!(
+            swap.l2_share_of_fill(
+                bitcoin::Amount::ZERO,
+                bitcoin::Amount::from_sat(100_000)
+            ),
+            bitcoin::Amount::from_sat(333_333)
+        );
+    }
+
+    #[test]
+    fn next_fill_range_shrinks_to_remaining() {
+        let mut swap = make_swap(ParentChainType::Regtest, 100, None);
+        assert_eq!(swap.next_fill_range(bitcoin::Amount::ZERO), None);
+        swap.min_fill_l1_amount = Some(bitcoin::Amount::from_sat(200_000));
+        assert_eq!(
+            swap.next_fill_range(bitcoin::Amount::ZERO),
+            Some((
+                bitcoin::Amount::from_sat(200_000),
+                bitcoin::Amount::from_sat(500_000)
+            ))
+        );
+        // Less than the minimum fill remains: it can be filled at once
+        assert_eq!(
+            swap.next_fill_range(bitcoin::Amount::from_sat(400_000)),
+            Some((
+                bitcoin::Amount::from_sat(100_000),
+                bitcoin::Amount::from_sat(100_000)
+            ))
+        );
+        assert_eq!(
+            swap.next_fill_range(bitcoin::Amount::from_sat(500_000)),
+            None
+        );
+    }
+
+    #[test]
+    fn state_from_fills() {
+        let mut swap = make_swap(ParentChainType::Regtest, 100, None);
+        swap.min_fill_l1_amount = Some(bitcoin::Amount::from_sat(100_000));
+        assert_eq!(swap.state_from_fills(&[]), SwapState::Pending);
+
+        let mut fills = vec![make_fill(200_000, 400_000)];
+        assert_eq!(
+            swap.state_from_fills(&fills),
+            SwapState::PartiallyFilled(200_000, 500_000)
+        );
+
+        fills.push(make_fill(300_000, 600_000));
+        assert_eq!(swap.state_from_fills(&fills), SwapState::Completed);
+    }
+
+    #[test]
+    fn fill_claimer_requires_declaration_on_open_swaps() {
+        let mut swap = make_swap(ParentChainType::Regtest, 100, None);
+        swap.min_fill_l1_amount = Some(bitcoin::Amount::from_sat(100_000));
+        let alice = Address([1u8; 20]);
+        let bob = Address([2u8; 20]);
+        // Open swap: undeclared fills cannot be claimed by anyone
+        assert_eq!(swap.fill_claimer(Some(alice)), Some(alice));
+        assert_eq!(swap.fill_claimer(None), None);
+        // Undeclared fills of a pre-specified swap go to its recipient
+        swap.l2_recipient = Some(bob);
+        assert_eq!(swap.fill_claimer(Some(alice)), Some(alice));
+        assert_eq!(swap.fill_claimer(None), Some(bob));
+    }
+
     #[test]
     fn swap_without_expiration_has_none() {
         let swap = make_swap(ParentChainType::BTC, 100, None);
@@ -481,7 +897,7 @@ mod tests {
     #[test]
     fn default_swap_expiration_blocks_per_chain() {
         assert_eq!(ParentChainType::BTC.default_swap_expiration_blocks(), 1008);
-        assert_eq!(ParentChainType::BCH.default_swap_expiration_blocks(), 432);
+        assert_eq!(ParentChainType::BCH.default_swap_expiration_blocks(), 1008);
         assert_eq!(ParentChainType::LTC.default_swap_expiration_blocks(), 432);
         assert_eq!(
             ParentChainType::Signet.default_swap_expiration_blocks(),
@@ -493,6 +909,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn default_swap_expiration_within_limits() {
+        for chain in [
+            ParentChainType::BTC,
+            ParentChainType::BCH,
+            ParentChainType::LTC,
+            ParentChainType::Signet,
+            ParentChainType::Regtest,
+        ] {
+            let default = chain.default_swap_expiration_blocks();
+            assert!(chain.min_swap_expiration_blocks() <= default);
+            assert!(default <= chain.max_swap_expiration_blocks());
+            assert_eq!(chain.swap_expiration_blocks(None), default);
+            assert_eq!(chain.swap_expiration_blocks(Some(7)), 7);
+        }
+    }
+
     #[test]
     fn max_l1_tx_age_blocks_per_chain() {
         assert_eq!(ParentChainType::BTC.max_l1_tx_age_blocks(), 2016);
@@ -502,6 +935,28 @@ mod tests {
         assert_eq!(ParentChainType::Regtest.max_l1_tx_age_blocks(), 500);
     }
 
+    #[test]
+    fn confirmations_scale_with_l1_amount() {
+        let btc = |amount| bitcoin::Amount::from_btc(amount).unwrap();
+        let chain = ParentChainType::BTC;
+        assert_eq!(chain.min_confirmations(btc(0.05)), 3);
+        assert_eq!(chain.suggested_confirmations(btc(0.05)), 6);
+        assert_eq!(chain.min_confirmations(btc(50.0)), 12);
+        assert_eq!(chain.suggested_confirmations(btc(50.0)), 12);
+        // Litecoin blocks are 4x as frequent as Bitcoin blocks
+        let ltc = ParentChainType::LTC;
+        assert_eq!(ltc.min_confirmations(btc(500.0)), 12);
+        // Test networks have lower tiers, and small regtest swaps may be
+        // tested with a single confirmation
+        let signet = ParentChainType::Signet;
+        assert_eq!(signet.min_confirmations(btc(0.05)), 2);
+        assert_eq!(signet.suggested_confirmations(btc(0.05)), 3);
+        assert_eq!(signet.min_confirmations(btc(1000.0)), 6);
+        let regtest = ParentChainType::Regtest;
+        assert_eq!(regtest.min_confirmations(btc(0.05)), 1);
+        assert_eq!(regtest.min_confirmations(btc(1000.0)), 6);
+    }
+
     #[test]
     fn swap_expiration_is_relative_to_creation_height() {
         let chain = ParentChainType::BTC;
@@ -528,6 +983,66 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn reservation_is_live_until_expiry() {
+        let mut swap = make_swap(ParentChainType::Regtest, 100, Some(150));
+        assert_eq!(swap.reservation_bond(), bitcoin::Amount::from_sat(10_000));
+        assert!(swap.live_reservation(100).is_none());
+
+        swap.reservation = Some(SwapReservation {
+            l2_claimer_address: Address([4u8; 20]),
+            expires_at_height: 110,
+            bond_outpoints: Vec::new(),
+            bond: swap.reservation_bond(),
+        });
+        assert!(swap.live_reservation(109).is_some());
+        assert!(swap.live_reservation(110).is_none());
+    }
+
+    #[test]
+    fn chain_id_validation() {
+        assert_eq!("DOGE".parse::<ChainId>().unwrap().as_str(), "DOGE");
+        assert!("regtest-b_2".parse::<ChainId>().is_ok());
+        assert!("".parse::<ChainId>().is_err());
+        assert!("a".repeat(ChainId::MAX_LEN + 1).parse::<ChainId>().is_err());
+        assert!("Dogecoin Testnet".parse::<ChainId>().is_err());
+        assert!("DOGE\0".parse::<ChainId>().is_err());
+    }
+
+    #[test]
+    fn parent_chain_type_serde_roundtrip() {
+        let doge = ParentChainType::Custom("DOGE".parse().unwrap());
+        for chain in [ParentChainType::BCH, doge] {
+            let json = serde_json::to_string(&chain).unwrap();
+            assert_eq!(json, format!("\"{chain}\""));
+            let decoded: ParentChainType = serde_json::from_str(&json).unwrap();
+            assert_eq!(decoded, chain);
+            let bytes = bincode::serialize(&chain).unwrap();
+            let decoded: ParentChainType =
+                bincode::deserialize(&bytes).unwrap();
+            assert_eq!(decoded, chain);
+            let bytes = borsh::to_vec(&chain).unwrap();
+            let decoded: ParentChainType = borsh::from_slice(&bytes).unwrap();
+            assert_eq!(decoded, chain);
+        }
+        // Built-in chains are encoded as before custom chains were added
+        assert_eq!(
+            bincode::serialize(&ParentChainType::BCH).unwrap(),
+            1u32.to_le_bytes()
+        );
+        assert_eq!(borsh::to_vec(&ParentChainType::Signet).unwrap(), [3]);
+    }
+
+    #[test]
+    fn parent_chain_type_rejects_non_canonical_chain_ids() {
+        let mut bytes =
+            borsh::to_vec(&ParentChainType::Custom("DOGE".parse().unwrap()))
+                .unwrap();
+        // Non-zero byte after the padding starts
+        *bytes.last_mut().unwrap() = b'X';
+        assert!(borsh::from_slice::<ParentChainType>(&bytes).is_err());
+    }
 }
 
 // Custom serde module for Option<Amount> that serializes as Option<u64>
@@ -560,6 +1075,73 @@ mod amount_opt_serde {
     }
 }
 
+/// An L1 payment towards a partially fillable swap, proven by a claim of its
+/// L2 share
+#[derive(
+    Clone, Debug, Deserialize, Eq, PartialEq, Serialize, utoipa::ToSchema,
+)]
+pub struct SwapFill {
+    pub l1_txid: SwapTxId,
+    /// Amount paid to the swap's L1 recipient
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    #[schema(value_type = u64)]
+    pub l1_amount: bitcoin::Amount,
+    /// Pro-rata share of the swap's L2 amount released by this fill
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    #[schema(value_type = u64)]
+    pub l2_amount: bitcoin::Amount,
+    /// L2 address the share was claimed to (see [`Swap::fill_claimer`])
+    pub l2_claimer_address: Address,
+}
+
+/// A taker's exclusive right to fill an open swap, until
+/// `expires_at_height`
+#[derive(
+    Clone, Debug, Deserialize, Eq, PartialEq, Serialize, utoipa::ToSchema,
+)]
+pub struct SwapReservation {
+    /// The only L2 address the swap can be claimed to
+    pub l2_claimer_address: Address,
+    /// L2 height at which the reservation lapses, if the swap has not been
+    /// filled by then
+    pub expires_at_height: u32,
+    /// Bond outputs locked to the swap by the taker. They are owned by the
+    /// swap creator: the claim returns them to the taker, and they are
+    /// forfeited to the creator if the reservation lapses.
+    pub bond_outpoints: Vec<OutPoint>,
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    #[schema(value_type = u64)]
+    pub bond: bitcoin::Amount,
+}
+
+impl BorshSerialize for SwapReservation {
+    fn serialize<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+    ) -> std::io::Result<()> {
+        BorshSerialize::serialize(&self.l2_claimer_address, writer)?;
+        BorshSerialize::serialize(&self.expires_at_height, writer)?;
+        BorshSerialize::serialize(&self.bond_outpoints, writer)?;
+        BorshSerialize::serialize(&self.bond.to_sat(), writer)?;
+        Ok(())
+    }
+}
+
+impl BorshDeserialize for SwapReservation {
+    fn deserialize_reader<R: std::io::Read>(
+        reader: &mut R,
+    ) -> std::io::Result<Self> {
+        Ok(Self {
+            l2_claimer_address: BorshDeserialize::deserialize_reader(reader)?,
+            expires_at_height: BorshDeserialize::deserialize_reader(reader)?,
+            bond_outpoints: BorshDeserialize::deserialize_reader(reader)?,
+            bond: bitcoin::Amount::from_sat(
+                BorshDeserialize::deserialize_reader(reader)?,
+            ),
+        })
+    }
+}
+
 /// Swap data structure
 #[derive(
     Clone, Debug, Deserialize, Eq, PartialEq, Serialize, utoipa::ToSchema,
@@ -577,6 +1159,12 @@ pub struct Swap {
     #[schema(value_type = u64)]
     pub l2_amount: bitcoin::Amount,
     pub l1_recipient_address: Option<String>,
+    /// Output script that L1 payments to `l1_recipient_address` must pay,
+    /// derived from the address when it is set. L1 payments are matched on
+    /// this script, so that any encoding of the address is accepted.
+    #[serde(default)]
+    #[schema(value_type = Option<String>)]
+    pub l1_recipient_script: Option<bitcoin::ScriptBuf>,
     #[serde(with = "amount_opt_serde")]
     #[schema(value_type = Option<u64>)]
     pub l1_amount: Option<bitcoin::Amount>,
@@ -596,6 +1184,40 @@ pub struct Swap {
     /// L2 address that created the swap (first input of SwapCreate). Used to restrict cancel/delete to creator.
     #[serde(default)]
     pub l2_creator_address: Option<Address>,
+    /// If set, the swap can be filled by several L1 payments of at least
+    /// this amount (or of the whole outstanding amount, if smaller), each
+    /// releasing a pro-rata share of the L2 amount. If `None`, the swap is
+    /// filled by a single L1 payment of exactly `l1_amount`.
+    #[serde(default, with = "amount_opt_serde")]
+    #[schema(value_type = Option<u64>)]
+    pub min_fill_l1_amount: Option<bitcoin::Amount>,
+    /// Taker reservation of an open swap. Kept once the swap is filled, and
+    /// cleared if it lapses first.
+    #[serde(default)]
+    pub reservation: Option<SwapReservation>,
+}
+
+/// L2 share released by an L1 fill of `fill_l1_amount` of a swap of
+/// `l1_amount` for `l2_amount`, given that `filled_l1_amount` was already
+/// filled (see [`Swap::l2_share_of_fill`])
+pub(super) fn l2_share_of_fill(
+    l1_amount: bitcoin::Amount,
+    l2_amount: bitcoin::Amount,
+    filled_l1_amount: bitcoin::Amount,
+    fill_l1_amount: bitcoin::Amount,
+) -> bitcoin::Amount {
+    if l1_amount == bitcoin::Amount::ZERO {
+        return bitcoin::Amount::ZERO;
+    }
+    let l1_amount = l1_amount.to_sat() as u128;
+    let l2_amount = l2_amount.to_sat() as u128;
+    let cumulative_share = |filled: bitcoin::Amount| {
+        let filled = (filled.to_sat() as u128).min(l1_amount);
+        (l2_amount * filled / l1_amount) as u64
+    };
+    let before = cumulative_share(filled_l1_amount);
+    let after = cumulative_share(filled_l1_amount + fill_l1_amount);
+    bitcoin::Amount::from_sat(after - before)
 }
 
 // Custom Borsh serialization for Swap (needed for integration tests)
@@ -630,6 +1252,18 @@ impl BorshSerialize for Swap {
         )?;
         BorshSerialize::serialize(&self.l1_txid_validated_at_height, writer)?;
         BorshSerialize::serialize(&self.l2_creator_address, writer)?;
+        BorshSerialize::serialize(
+            &self.min_fill_l1_amount.map(|amt| amt.to_sat()),
+            writer,
+        )?;
+        BorshSerialize::serialize(&self.reservation, writer)?;
+        BorshSerialize::serialize(
+            &self
+                .l1_recipient_script
+                .as_ref()
+                .map(|script| script.as_bytes()),
+            writer,
+        )?;
         Ok(())
     }
 }
@@ -666,6 +1300,11 @@ impl BorshDeserialize for Swap {
                 reader,
             )?,
             l2_creator_address: BorshDeserialize::deserialize_reader(reader)?,
+            min_fill_l1_amount: Option::<u64>::deserialize_reader(reader)?
+                .map(bitcoin::Amount::from_sat),
+            reservation: BorshDeserialize::deserialize_reader(reader)?,
+            l1_recipient_script: Option::<Vec<u8>>::deserialize_reader(reader)?
+                .map(bitcoin::ScriptBuf::from_bytes),
         })
     }
 }
@@ -686,8 +1325,17 @@ impl Swap {
         expires_at_height: Option<u32>,
         l2_creator_address: Option<Address>,
     ) -> Self {
-        let required_confirmations = required_confirmations
-            .unwrap_or_else(|| parent_chain.default_confirmations());
+        let required_confirmations =
+            required_confirmations.unwrap_or_else(|| match l1_amount {
+                Some(l1_amount) => {
+                    parent_chain.suggested_confirmations(l1_amount)
+                }
+                None => parent_chain.default_confirmations(),
+            });
+        let l1_recipient_script =
+            l1_recipient_address.as_deref().and_then(|address| {
+                l1_proof::l1_recipient_script(parent_chain, address).ok()
+            });
         Self {
             id,
             direction,
@@ -698,6 +1346,7 @@ impl Swap {
             l2_recipient,
             l2_amount,
             l1_recipient_address,
+            l1_recipient_script,
             l1_amount,
             l1_claimer_address: None,
             l2_claimer_address: None,
@@ -706,6 +1355,8 @@ impl Swap {
             l1_txid_validated_at_block_hash: None,
             l1_txid_validated_at_height: None,
             l2_creator_address,
+            min_fill_l1_amount: None,
+            reservation: None,
         }
     }
 
@@ -733,6 +1384,120 @@ impl Swap {
         self.l2_claimer_address = Some(l2_address);
     }
 
+    /// Whether the swap can be filled by several L1 payments
+    pub fn allows_partial_fills(&self) -> bool {
+        self.min_fill_l1_amount.is_some()
+    }
+
+    /// L2 share released by an L1 fill of `fill_l1_amount`, given that
+    /// `filled_l1_amount` was already filled. Shares are computed from
+    /// cumulative totals, so that they sum to exactly `l2_amount` once the
+    /// swap is fully filled.
+    pub fn l2_share_of_fill(
+        &self,
+        filled_l1_amount: bitcoin::Amount,
+        fill_l1_amount: bitcoin::Amount,
+    ) -> bitcoin::Amount {
+        let Some(l1_amount) = self.l1_amount else {
+            return bitcoin::Amount::ZERO;
+        };
+        l2_share_of_fill(
+            l1_amount,
+            self.l2_amount,
+            filled_l1_amount,
+            fill_l1_amount,
+        )
+    }
+
+    /// Smallest and largest L1 payment accepted as the next fill, given that
+    /// `filled_l1_amount` was already filled. `None` if the swap does not
+    /// allow partial fills, or is fully filled.
+    pub fn next_fill_range(
+        &self,
+        filled_l1_amount: bitcoin::Amount,
+    ) -> Option<(bitcoin::Amount, bitcoin::Amount)> {
+        let min_fill = self.min_fill_l1_amount?;
+        let remaining = self.l1_amount?.checked_sub(filled_l1_amount)?;
+        if remaining == bitcoin::Amount::ZERO {
+            return None;
+        }
+        Some((min_fill.min(remaining), remaining))
+    }
+
+    /// Derive the state of a partially fillable swap from its fill ledger
+    pub fn state_from_fills(&self, fills: &[SwapFill]) -> SwapState {
+        let l1_amount = self.l1_amount.unwrap_or(bitcoin::Amount::ZERO);
+        let filled: bitcoin::Amount =
+            fills.iter().map(|fill| fill.l1_amount).sum();
+        if fills.is_empty() {
+            SwapState::Pending
+        } else if filled < l1_amount {
+            SwapState::PartiallyFilled(filled.to_sat(), l1_amount.to_sat())
+        } else {
+            SwapState::Completed
+        }
+    }
+
+    /// L2 address that may claim the share of a fill, given the L2 address
+    /// declared by the L1 payer (see [`l1_proof::declared_l2_claimer`]). A
+    /// fill without a declared address goes to the swap's `l2_recipient`;
+    /// on an open swap it cannot be claimed, as nothing ties it to a taker.
+    pub fn fill_claimer(&self, declared: Option<Address>) -> Option<Address> {
+        declared.or(self.l2_recipient)
+    }
+
+    /// Whether the swap is partially fillable and has L1 still outstanding
+    pub fn is_accepting_fills(&self, fills: &[SwapFill]) -> bool {
+        let filled: bitcoin::Amount =
+            fills.iter().map(|fill| fill.l1_amount).sum();
+        self.next_fill_range(filled).is_some()
+    }
+
+    /// Whether the swap has been claimed or cancelled. Unlike its other
+    /// states, which follow the L1 transactions seen by this node, these are
+    /// only reached by connecting L2 blocks.
+    pub fn is_closed(&self) -> bool {
+        matches!(self.state, SwapState::Completed | SwapState::Cancelled)
+    }
+
+    /// Whether an L1 transaction has been recorded for the swap
+    pub fn has_l1_txid(&self) -> bool {
+        match &self.l1_txid {
+            SwapTxId::Hash32(hash) => *hash != [0u8; 32],
+            SwapTxId::Hash(bytes) => bytes.iter().any(|&b| b != 0),
+        }
+    }
+
+    /// Bond a taker must lock to reserve this swap: 1% of the L2 amount
+    pub fn reservation_bond(&self) -> bitcoin::Amount {
+        bitcoin::Amount::from_sat(self.l2_amount.to_sat() / 100)
+    }
+
+    /// The reservation of the swap, if it is live at `height`
+    pub fn live_reservation(&self, height: u32) -> Option<&SwapReservation> {
+        self.reservation
+            .as_ref()
+            .filter(|reservation| height < reservation.expires_at_height)
+    }
+
+    /// Whether this is an L1 → L2 offer that has not yet been accepted
+    pub fn is_awaiting_acceptance(&self) -> bool {
+        self.direction == SwapDirection::L1ToL2
+            && self.l1_recipient_address.is_none()
+            && self.state == SwapState::Pending
+    }
+
+    /// Accept an L1 → L2 offer. The swap creator must send the L1 coins to
+    /// `l1_recipient_address`.
+    pub fn accept(&mut self, l1_recipient_address: String) {
+        self.l1_recipient_script = l1_proof::l1_recipient_script(
+            self.parent_chain,
+            &l1_recipient_address,
+        )
+        .ok();
+        self.l1_recipient_address = Some(l1_recipient_address);
+    }
+
     /// Set the sidechain block reference where L1 txid was validated
     pub fn set_l1_txid_validation_block(
         &mut self,
@@ -300,6 +300,12 @@ mod content {
             value: bitcoin::Amount,
             swap_id: [u8; 32],
         },
+        Htlc {
+            value: bitcoin::Amount,
+            payment_hash: [u8; 32],
+            refund_height: u32,
+            refund_address: crate::types::Address,
+        },
     }
 
     /// Human-readable representation for Serde
@@ -331,6 +337,17 @@ mod content {
             #[schema(value_type = String)]
             swap_id: [u8; 32],
         },
+        Htlc {
+            #[serde(with = "bitcoin::amount::serde::as_sat")]
+            #[serde(rename = "value_sats")]
+            #[schema(value_type = u64)]
+            value: bitcoin::Amount,
+            #[serde(with = "hex::serde")]
+            #[schema(value_type = String)]
+            payment_hash: [u8; 32],
+            refund_height: u32,
+            refund_address: crate::types::Address,
+        },
     }
 
     type SerdeRepr = serde_with::IfIsHumanReadable<
@@ -357,6 +374,17 @@ mod content {
             value: bitcoin::Amount,
             swap_id: [u8; 32],
         },
+        /// Hashed timelock contract. Spendable by the output's address,
+        /// revealing a SHA256 preimage of `payment_hash` in an `HtlcRedeem`
+        /// transaction, before `refund_height`; and by `refund_address` from
+        /// `refund_height` onwards.
+        Htlc {
+            #[borsh(serialize_with = "super::borsh_serialize_bitcoin_amount")]
+            value: bitcoin::Amount,
+            payment_hash: [u8; 32],
+            refund_height: u32,
+            refund_address: crate::types::Address,
+        },
     }
 
     impl Content {
@@ -369,6 +397,9 @@ mod content {
         pub fn is_swap_pending(&self) -> bool {
             matches!(self, Self::SwapPending { .. })
         }
+        pub fn is_htlc(&self) -> bool {
+            matches!(self, Self::Htlc { .. })
+        }
 
         pub(in crate::types) fn schema_ref() -> utoipa::openapi::Ref {
             utoipa::openapi::Ref::new("OutputContent")
@@ -382,6 +413,7 @@ mod content {
                 Self::Value(value) => *value,
                 Self::Withdrawal { value, .. } => *value,
                 Self::SwapPending { value, .. } => *value,
+                Self::Htlc { value, .. } => *value,
             }
         }
     }
@@ -402,6 +434,17 @@ mod content {
                 Content::SwapPending { value, swap_id } => {
                     Self::SwapPending { value, swap_id }
                 }
+                Content::Htlc {
+                    value,
+                    payment_hash,
+                    refund_height,
+                    refund_address,
+                } => Self::Htlc {
+                    value,
+                    payment_hash,
+                    refund_height,
+                    refund_address,
+                },
             }
         }
     }
@@ -422,6 +465,17 @@ mod content {
                 Content::SwapPending { value, swap_id } => {
                     Self::SwapPending { value, swap_id }
                 }
+                Content::Htlc {
+                    value,
+                    payment_hash,
+                    refund_height,
+                    refund_address,
+                } => Self::Htlc {
+                    value,
+                    payment_hash,
+                    refund_height,
+                    refund_address,
+                },
             }
         }
     }
@@ -442,6 +496,17 @@ mod content {
                 DefaultRepr::SwapPending { value, swap_id } => {
                     Self::SwapPending { value, swap_id }
                 }
+                DefaultRepr::Htlc {
+                    value,
+                    payment_hash,
+                    refund_height,
+                    refund_address,
+                } => Self::Htlc {
+                    value,
+                    payment_hash,
+                    refund_height,
+                    refund_address,
+                },
             }
         }
     }
@@ -462,6 +527,17 @@ mod content {
                 HumanReadableRepr::SwapPending { value, swap_id } => {
                     Self::SwapPending { value, swap_id }
                 }
+                HumanReadableRepr::Htlc {
+                    value,
+                    payment_hash,
+                    refund_height,
+                    refund_address,
+                } => Self::Htlc {
+                    value,
+                    payment_hash,
+                    refund_height,
+                    refund_address,
+                },
             }
         }
     }
@@ -518,7 +594,10 @@ pub enum TxData {
     /// Regular transaction (no special data)
     #[default]
     Regular,
-    /// Swap creation transaction
+    /// Swap creation transaction. For L2 → L1 swaps, `l1_recipient_address`
+    /// and `l1_amount` are both set. An L1 → L2 swap offer sets only
+    /// `l1_amount` and `l2_recipient`; the L1 recipient is set when the offer
+    /// is accepted.
     SwapCreate {
         swap_id: [u8; 32],
         parent_chain: ParentChainType,
@@ -529,14 +608,54 @@ pub enum TxData {
         l2_amount: u64,
         l1_recipient_address: Option<String>,
         l1_amount: Option<u64>,
+        /// If set, the swap can be filled by several L1 payments of at least
+        /// this many sats, each releasing a pro-rata share of `l2_amount`
+        min_fill_l1_amount: Option<u64>,
+        /// Number of L2 blocks after creation at which the swap expires.
+        /// `None` uses the parent chain's default.
+        expires_in_blocks: Option<u32>,
     },
     /// Swap claim transaction
     SwapClaim {
         swap_id: [u8; 32],
         /// L2 address of the claimer (required for open swaps)
         l2_claimer_address: Option<Address>,
+        /// Borsh-encoded `L1InclusionProof` of the L1 fill, or a list of
+        /// proofs of the claimed fills if the swap allows partial fills.
         proof_data: Option<Vec<u8>>,
     },
+    /// Acceptance of an L1 → L2 swap offer. The accepting party locks
+    /// `l2_amount` in SwapPending outputs and names the L1 address where the
+    /// swap creator must send the L1 coins.
+    SwapAccept {
+        swap_id: [u8; 32],
+        l1_recipient_address: String,
+    },
+    /// Cancellation of a Pending swap by its creator, who signs it by
+    /// spending an output owned by the swap's `l2_creator_address` as the
+    /// first input. Unlocks the swap's outputs.
+    SwapCancel { swap_id: [u8; 32] },
+    /// Reservation of an open swap by a taker, who gets the exclusive right
+    /// to fill it for `reserve_blocks` L2 blocks. The taker locks the swap's
+    /// reservation bond in SwapPending outputs owned by the swap creator.
+    SwapReserve {
+        swap_id: [u8; 32],
+        /// The only L2 address the swap can then be claimed to
+        l2_claimer_address: Address,
+        reserve_blocks: u32,
+    },
+    /// Redemption of HTLC outputs, revealing the SHA256 preimage of their
+    /// payment hash. Every HTLC input spent before its refund height must
+    /// match the preimage.
+    HtlcRedeem { preimage: [u8; 32] },
+    /// Relay of parent chain headers into the consensus L1 header chain of
+    /// `parent_chain` (see [`crate::l1_headers`]). Has no inputs or outputs.
+    /// A block may carry at most one relay per parent chain.
+    L1Headers {
+        parent_chain: ParentChainType,
+        /// Consensus-encoded 80-byte headers, in ascending height order
+        headers: Vec<Vec<u8>>,
+    },
 }
 
 // Manual ToSchema implementation for TxData
@@ -27,8 +27,8 @@ pub use crate::{
 };
 use crate::{
     types::{
-        Accumulator, AmountOverflowError, AmountUnderflowError, PointedOutput,
-        UtreexoError, VERSION, Version, hash,
+        Accumulator, AmountOverflowError, AmountUnderflowError,
+        L1InclusionProof, PointedOutput, UtreexoError, VERSION, Version, hash,
     },
     util::Watchable,
 };
@@ -64,6 +64,8 @@ pub enum Error {
     DbEnv(#[from] EnvError),
     #[error("Database write error")]
     DbWrite(#[from] RwTxnError),
+    #[error("preimage does not match the HTLC payment hash")]
+    HtlcPreimageMismatch,
     #[error("io error")]
     Io(#[from] std::io::Error),
     #[error(
@@ -72,6 +74,10 @@ pub enum Error {
     AddressNotRecoverable { address: Address, max_index: u32 },
     #[error("no index for address {address}")]
     NoIndex { address: Address },
+    #[error("output {outpoint} is not an HTLC")]
+    NotHtlc { outpoint: OutPoint },
+    #[error("no spendable output owned by swap creator {address}")]
+    NoSwapCreatorOutput { address: Address },
     #[error(
         "wallet does not have a seed (set with RPC `set-seed-from-mnemonic`)"
     )]
@@ -291,6 +297,10 @@ impl Wallet {
 
     /// Create a SwapCreate transaction for L2 → L1 swaps
     /// If l2_recipient is None, creates an open swap (anyone can fill it)
+    /// If min_fill_l1_amount is set, the swap can be filled by several L1 payments
+    /// If expires_in_blocks is None, the parent chain's default expiry is used
+    /// If required_confirmations is None, the confirmations suggested for the
+    /// L1 amount are used
     /// `is_locked` is an optional function that returns true if an outpoint is locked to a swap
     #[allow(clippy::too_many_arguments)]
     pub fn create_swap_create_tx<F>(
@@ -302,6 +312,8 @@ impl Wallet {
         l2_recipient: Option<Address>, // Optional - None = open swap
         l2_amount: bitcoin::Amount,
         required_confirmations: Option<u32>,
+        min_fill_l1_amount: Option<bitcoin::Amount>,
+        expires_in_blocks: Option<u32>,
         fee: bitcoin::Amount,
         is_locked: F,
     ) -> Result<(Transaction, SwapId), Error>
@@ -377,15 +389,17 @@ impl Wallet {
                     swap_id: swap_id.0,
                 },
             },
+            // Change goes back to the creator address, so that the creator
+            // keeps an output to sign a SwapCancel with
             Output {
-                address: self.get_new_address()?,
+                address: l2_sender_address,
                 content: OutputContent::Value(change),
             },
         ];
 
         // 5. Create transaction with SwapCreate data
         let required_confirmations = required_confirmations
-            .unwrap_or_else(|| parent_chain.default_confirmations());
+            .unwrap_or_else(|| parent_chain.suggested_confirmations(l1_amount));
         let tx = Transaction {
             inputs,
             proof,
@@ -399,15 +413,464 @@ impl Wallet {
                 l2_amount: l2_amount.to_sat(),
                 l1_recipient_address: Some(l1_recipient_address),
                 l1_amount: Some(l1_amount.to_sat()),
+                min_fill_l1_amount: min_fill_l1_amount.map(|amt| amt.to_sat()),
+                expires_in_blocks,
             },
         };
 
         Ok((tx, swap_id))
     }
 
+    /// Create a SwapCreate transaction offering L1 coins for L2 coins
+    /// (L1 → L2). Nothing is locked: the L2 coins are locked by whoever
+    /// accepts the offer, and paid to `l2_recipient` when the swap is claimed.
+    /// `is_locked` is an optional function that returns true if an outpoint is locked to a swap
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_l1_to_l2_swap_create_tx<F>(
+        &self,
+        accumulator: &Accumulator,
+        parent_chain: ParentChainType,
+        l1_amount: bitcoin::Amount,
+        l2_recipient: Address,
+        l2_amount: bitcoin::Amount,
+        required_confirmations: Option<u32>,
+        fee: bitcoin::Amount,
+        is_locked: F,
+    ) -> Result<(Transaction, SwapId), Error>
+    where
+        F: Fn(&OutPoint) -> bool,
+    {
+        tracing::trace!(
+            ?parent_chain,
+            l1_amount = %l1_amount.display_dynamic(),
+            %l2_recipient,
+            l2_amount = %l2_amount.display_dynamic(),
+            fee = %fee.display_dynamic(),
+            "Creating L1 → L2 swap create transaction"
+        );
+
+        // The inputs only pay the fee. As for L2 → L1 swaps, validation
+        // computes the swap ID from the address of the first input's UTXO.
+        let (total, coins) = self.select_coins_with_filter(fee, is_locked)?;
+        let change = total - fee;
+        let l2_creator_address =
+            coins.values().next().ok_or(Error::NotEnoughFunds)?.address;
+        let swap_id = SwapId::from_l1_to_l2(
+            l1_amount,
+            l2_amount,
+            &l2_creator_address,
+            &l2_recipient,
+        );
+
+        let inputs: Vec<_> = coins
+            .into_iter()
+            .map(|(outpoint, output)| {
+                let utxo_hash = hash(&PointedOutput { outpoint, output });
+                (outpoint, utxo_hash)
+            })
+            .collect();
+        let input_utxo_hashes: Vec<BitcoinNodeHash> =
+            inputs.iter().map(|(_, hash)| hash.into()).collect();
+        let proof = accumulator.prove(&input_utxo_hashes)?;
+        // Change goes back to the creator address, as for L2 → L1 swaps
+        let outputs = vec![Output {
+            address: l2_creator_address,
+            content: OutputContent::Value(change),
+        }];
+
+        let required_confirmations = required_confirmations
+            .unwrap_or_else(|| parent_chain.suggested_confirmations(l1_amount));
+        let tx = Transaction {
+            inputs,
+            proof,
+            outputs,
+            data: TxData::SwapCreate {
+                swap_id: swap_id.0,
+                parent_chain,
+                l1_txid_bytes: vec![0u8; 32],
+                required_confirmations,
+                l2_recipient: Some(l2_recipient),
+                l2_amount: l2_amount.to_sat(),
+                l1_recipient_address: None, // Set on acceptance
+                l1_amount: Some(l1_amount.to_sat()),
+                min_fill_l1_amount: None,
+                expires_in_blocks: None,
+            },
+        };
+
+        Ok((tx, swap_id))
+    }
+
+    /// Create a SwapAccept transaction for an L1 → L2 swap offer, locking
+    /// `l2_amount` to the swap. The swap creator must then send the L1 coins
+    /// to `l1_recipient_address`. If the swap expires first, the locked
+    /// outputs are unlocked and remain ours.
+    /// `is_locked` is an optional function that returns true if an outpoint is locked to a swap
+    pub fn create_swap_accept_tx<F>(
+        &self,
+        accumulator: &Accumulator,
+        swap_id: SwapId,
+        l1_recipient_address: String,
+        l2_amount: bitcoin::Amount,
+        fee: bitcoin::Amount,
+        is_locked: F,
+    ) -> Result<Transaction, Error>
+    where
+        F: Fn(&OutPoint) -> bool,
+    {
+        tracing::trace!(
+            %swap_id,
+            %l1_recipient_address,
+            l2_amount = %l2_amount.display_dynamic(),
+            fee = %fee.display_dynamic(),
+            "Creating swap accept transaction"
+        );
+
+        let required_total =
+            l2_amount.checked_add(fee).ok_or(AmountOverflowError)?;
+        let (total, coins) =
+            self.select_coins_with_filter(required_total, is_locked)?;
+        let change = total - l2_amount - fee;
+
+        let inputs: Vec<_> = coins
+            .into_iter()
+            .map(|(outpoint, output)| {
+                let utxo_hash = hash(&PointedOutput { outpoint, output });
+                (outpoint, utxo_hash)
+            })
+            .collect();
+        let input_utxo_hashes: Vec<BitcoinNodeHash> =
+            inputs.iter().map(|(_, hash)| hash.into()).collect();
+        let proof = accumulator.prove(&input_utxo_hashes)?;
+        let outputs = vec![
+            Output {
+                address: self.get_new_address()?,
+                content: OutputContent::SwapPending {
+                    value: l2_amount,
+                    swap_id: swap_id.0,
+                },
+            },
+            Output {
+                address: self.get_new_address()?,
+                content: OutputContent::Value(change),
+            },
+        ];
+
+        Ok(Transaction {
+            inputs,
+            proof,
+            outputs,
+            data: TxData::SwapAccept {
+                swap_id: swap_id.0,
+                l1_recipient_address,
+            },
+        })
+    }
+
+    /// Create a SwapReserve transaction for an open swap. The bond is locked
+    /// to the swap in an output owned by `creator_address`, so that it is
+    /// forfeited to the swap creator if the reservation lapses.
+    /// `is_locked` is an optional function that returns true if an outpoint is locked to a swap
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_swap_reserve_tx<F>(
+        &self,
+        accumulator: &Accumulator,
+        swap_id: SwapId,
+        creator_address: Address,
+        l2_claimer_address: Address,
+        reserve_blocks: u32,
+        bond: bitcoin::Amount,
+        fee: bitcoin::Amount,
+        is_locked: F,
+    ) -> Result<Transaction, Error>
+    where
+        F: Fn(&OutPoint) -> bool,
+    {
+        tracing::trace!(
+            %swap_id,
+            %creator_address,
+            %l2_claimer_address,
+            reserve_blocks,
+            bond = %bond.display_dynamic(),
+            fee = %fee.display_dynamic(),
+            "Creating swap reserve transaction"
+        );
+
+        let required_total =
+            bond.checked_add(fee).ok_or(AmountOverflowError)?;
+        let (total, coins) =
+            self.select_coins_with_filter(required_total, is_locked)?;
+        let change = total - bond - fee;
+
+        let inputs: Vec<_> = coins
+            .into_iter()
+            .map(|(outpoint, output)| {
+                let utxo_hash = hash(&PointedOutput { outpoint, output });
+                (outpoint, utxo_hash)
+            })
+            .collect();
+        let input_utxo_hashes: Vec<BitcoinNodeHash> =
+            inputs.iter().map(|(_, hash)| hash.into()).collect();
+        let proof = accumulator.prove(&input_utxo_hashes)?;
+        let outputs = vec![
+            Output {
+                address: creator_address,
+                content: OutputContent::SwapPending {
+                    value: bond,
+                    swap_id: swap_id.0,
+                },
+            },
+            Output {
+                address: self.get_new_address()?,
+                content: OutputContent::Value(change),
+            },
+        ];
+
+        Ok(Transaction {
+            inputs,
+            proof,
+            outputs,
+            data: TxData::SwapReserve {
+                swap_id: swap_id.0,
+                l2_claimer_address,
+                reserve_blocks,
+            },
+        })
+    }
+
+    /// Create a SwapCancel transaction for a Pending swap that we created.
+    /// The swap creator signs the cancellation by spending an output owned by
+    /// `creator_address` as the first input; further inputs are selected if
+    /// that output does not cover `fee`.
+    /// `is_locked` is an optional function that returns true if an outpoint is locked to a swap
+    pub fn create_swap_cancel_tx<F>(
+        &self,
+        accumulator: &Accumulator,
+        swap_id: SwapId,
+        creator_address: Address,
+        fee: bitcoin::Amount,
+        is_locked: F,
+    ) -> Result<Transaction, Error>
+    where
+        F: Fn(&OutPoint) -> bool,
+    {
+        tracing::trace!(
+            %swap_id,
+            %creator_address,
+            fee = %fee.display_dynamic(),
+            "Creating swap cancel transaction"
+        );
+
+        let (creator_outpoint, creator_output) = self
+            .get_utxos()?
+            .into_iter()
+            .filter(|(outpoint, output)| {
+                output.address == creator_address
+                    && output.content.is_value()
+                    && !is_locked(outpoint)
+            })
+            .max_by_key(|(_, output)| output.get_value())
+            .ok_or(Error::NoSwapCreatorOutput {
+                address: creator_address,
+            })?;
+        let mut total = creator_output.get_value();
+        let mut coins = vec![(creator_outpoint, creator_output)];
+        if total < fee {
+            let (extra_total, extra_coins) = self
+                .select_coins_with_filter(fee - total, |outpoint| {
+                    *outpoint == creator_outpoint || is_locked(outpoint)
+                })?;
+            total =
+                total.checked_add(extra_total).ok_or(AmountOverflowError)?;
+            coins.extend(extra_coins);
+        }
+        let change = total.checked_sub(fee).ok_or(Error::NotEnoughFunds)?;
+
+        let inputs: Vec<_> = coins
+            .into_iter()
+            .map(|(outpoint, output)| {
+                let utxo_hash = hash(&PointedOutput { outpoint, output });
+                (outpoint, utxo_hash)
+            })
+            .collect();
+        let input_utxo_hashes: Vec<BitcoinNodeHash> =
+            inputs.iter().map(|(_, hash)| hash.into()).collect();
+        let proof = accumulator.prove(&input_utxo_hashes)?;
+        let outputs = vec![Output {
+            address: creator_address,
+            content: OutputContent::Value(change),
+        }];
+
+        Ok(Transaction {
+            inputs,
+            proof,
+            outputs,
+            data: TxData::SwapCancel { swap_id: swap_id.0 },
+        })
+    }
+
+    /// Create a transaction locking `value` in an HTLC paying `recipient`,
+    /// who can redeem it by revealing the preimage of `payment_hash` before
+    /// `refund_height`. From `refund_height` onwards, it can be refunded to
+    /// a new wallet address, which is returned with the transaction.
+    /// `is_locked` is an optional function that returns true if an outpoint is locked to a swap
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_htlc_tx<F>(
+        &self,
+        accumulator: &Accumulator,
+        recipient: Address,
+        value: bitcoin::Amount,
+        payment_hash: [u8; 32],
+        refund_height: u32,
+        fee: bitcoin::Amount,
+        is_locked: F,
+    ) -> Result<(Transaction, Address), Error>
+    where
+        F: Fn(&OutPoint) -> bool,
+    {
+        tracing::trace!(
+            %recipient,
+            value = %value.display_dynamic(),
+            payment_hash = %hex::encode(payment_hash),
+            refund_height,
+            fee = %fee.display_dynamic(),
+            "Creating HTLC transaction"
+        );
+
+        let required_total =
+            value.checked_add(fee).ok_or(AmountOverflowError)?;
+        let (total, coins) =
+            self.select_coins_with_filter(required_total, is_locked)?;
+        let change = total - value - fee;
+
+        let inputs: Vec<_> = coins
+            .into_iter()
+            .map(|(outpoint, output)| {
+                let utxo_hash = hash(&PointedOutput { outpoint, output });
+                (outpoint, utxo_hash)
+            })
+            .collect();
+        let input_utxo_hashes: Vec<BitcoinNodeHash> =
+            inputs.iter().map(|(_, hash)| hash.into()).collect();
+        let proof = accumulator.prove(&input_utxo_hashes)?;
+        let refund_address = self.get_new_address()?;
+        let outputs = vec![
+            Output {
+                address: recipient,
+                content: OutputContent::Htlc {
+                    value,
+                    payment_hash,
+                    refund_height,
+                    refund_address,
+                },
+            },
+            Output {
+                address: self.get_new_address()?,
+                content: OutputContent::Value(change),
+            },
+        ];
+
+        let tx = Transaction {
+            inputs,
+            proof,
+            outputs,
+            data: TxData::Regular,
+        };
+        Ok((tx, refund_address))
+    }
+
+    /// Create an HtlcRedeem transaction spending an HTLC paid to us, before
+    /// its refund height, by revealing the preimage of its payment hash
+    pub fn create_htlc_redeem_tx(
+        &self,
+        accumulator: &Accumulator,
+        htlc: (OutPoint, Output),
+        preimage: [u8; 32],
+        fee: bitcoin::Amount,
+    ) -> Result<Transaction, Error> {
+        use bitcoin::hashes::{Hash as _, sha256};
+
+        let (outpoint, output) = htlc;
+        let OutputContent::Htlc { payment_hash, .. } = &output.content else {
+            return Err(Error::NotHtlc { outpoint });
+        };
+        if sha256::Hash::hash(&preimage).to_byte_array() != *payment_hash {
+            return Err(Error::HtlcPreimageMismatch);
+        }
+        tracing::trace!(
+            ?outpoint,
+            fee = %fee.display_dynamic(),
+            "Creating HTLC redeem transaction"
+        );
+        let tx = self.create_htlc_spend_tx(
+            accumulator,
+            outpoint,
+            output,
+            fee,
+            TxData::HtlcRedeem { preimage },
+        )?;
+        Ok(tx)
+    }
+
+    /// Create a transaction refunding an HTLC that we created, from its
+    /// refund height onwards
+    pub fn create_htlc_refund_tx(
+        &self,
+        accumulator: &Accumulator,
+        htlc: (OutPoint, Output),
+        fee: bitcoin::Amount,
+    ) -> Result<Transaction, Error> {
+        let (outpoint, output) = htlc;
+        if !output.content.is_htlc() {
+            return Err(Error::NotHtlc { outpoint });
+        }
+        tracing::trace!(
+            ?outpoint,
+            fee = %fee.display_dynamic(),
+            "Creating HTLC refund transaction"
+        );
+        self.create_htlc_spend_tx(
+            accumulator,
+            outpoint,
+            output,
+            fee,
+            TxData::Regular,
+        )
+    }
+
+    /// Spend a single HTLC to a new wallet address
+    fn create_htlc_spend_tx(
+        &self,
+        accumulator: &Accumulator,
+        outpoint: OutPoint,
+        output: Output,
+        fee: bitcoin::Amount,
+        data: TxData,
+    ) -> Result<Transaction, Error> {
+        let value = output
+            .get_value()
+            .checked_sub(fee)
+            .ok_or(Error::NotEnoughFunds)?;
+        let utxo_hash = hash(&PointedOutput { outpoint, output });
+        let utxo_node_hash: BitcoinNodeHash = (&utxo_hash).into();
+        let proof = accumulator.prove(&[utxo_node_hash])?;
+        let outputs = vec![Output {
+            address: self.get_new_address()?,
+            content: OutputContent::Value(value),
+        }];
+        Ok(Transaction {
+            inputs: vec![(outpoint, utxo_hash)],
+            proof,
+            outputs,
+            data,
+        })
+    }
+
     /// Create a SwapClaim transaction
     /// For pre-specified swaps: recipient should be swap.l2_recipient
     /// For open swaps: recipient should be the claimer's L2 address (l2_claimer_address)
+    /// `l1_proof` must prove the L1 fill in a block of the consensus L1
+    /// header chain, with the swap's required confirmations
     pub fn create_swap_claim_tx(
         &self,
         accumulator: &Accumulator,
@@ -415,6 +878,7 @@ impl Wallet {
         recipient: Address,
         locked_outputs: Vec<(OutPoint, Output)>,
         l2_claimer_address: Option<Address>, // Required for open swaps
+        l1_proof: &L1InclusionProof,
     ) -> Result<Transaction, Error> {
         tracing::trace!(
             swap_id = %swap_id,
@@ -462,13 +926,88 @@ impl Wallet {
             data: TxData::SwapClaim {
                 swap_id: swap_id.0,
                 l2_claimer_address, // For open swaps
-                proof_data: None,
+                proof_data: Some(l1_proof.encode()),
             },
         };
 
         Ok(tx)
     }
 
+    /// Create a SwapClaim transaction for a partially fillable swap.
+    /// Pays `release` (the L2 share of the fills proven by `l1_proofs`) to
+    /// `recipient`, and re-locks the rest of `locked_outputs` to the swap.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_partial_swap_claim_tx(
+        &self,
+        accumulator: &Accumulator,
+        swap_id: SwapId,
+        recipient: Address,
+        locked_outputs: Vec<(OutPoint, Output)>,
+        release: bitcoin::Amount,
+        l2_claimer_address: Option<Address>,
+        l1_proofs: &[L1InclusionProof],
+    ) -> Result<Transaction, Error> {
+        tracing::trace!(
+            swap_id = %swap_id,
+            ?recipient,
+            %release,
+            num_outputs = locked_outputs.len(),
+            "Creating partial swap claim transaction"
+        );
+
+        let inputs: Vec<_> = locked_outputs
+            .iter()
+            .map(|(outpoint, output)| {
+                let utxo_hash = hash(&PointedOutput {
+                    outpoint: *outpoint,
+                    output: output.clone(),
+                });
+                (*outpoint, utxo_hash)
+            })
+            .collect();
+        let input_utxo_hashes: Vec<BitcoinNodeHash> =
+            inputs.iter().map(|(_, hash)| hash.into()).collect();
+        let proof = accumulator.prove(&input_utxo_hashes)?;
+
+        use crate::types::GetValue;
+        let mut total_value = bitcoin::Amount::ZERO;
+        for (_, output) in &locked_outputs {
+            total_value = total_value
+                .checked_add(output.get_value())
+                .ok_or(AmountOverflowError)?;
+        }
+        let remainder = total_value
+            .checked_sub(release)
+            .ok_or(Error::NotEnoughFunds)?;
+
+        let mut outputs = vec![Output {
+            address: recipient,
+            content: OutputContent::Value(release),
+        }];
+        if remainder > bitcoin::Amount::ZERO {
+            // Re-lock the rest to the swap, owned by the swap creator
+            let (_, first_locked) = &locked_outputs[0];
+            outputs.push(Output {
+                address: first_locked.address,
+                content: OutputContent::SwapPending {
+                    value: remainder,
+                    swap_id: swap_id.0,
+                },
+            });
+        }
+
+        Ok(Transaction {
+            inputs,
+            proof,
+            outputs,
+            data: TxData::SwapClaim {
+                swap_id: swap_id.0,
+                l2_claimer_address,
+                proof_data: Some(L1InclusionProof::encode_many(l1_proofs)),
+            },
+        })
+    }
+
     pub fn create_transaction(
         &self,
         accumulator: &Accumulator,
@@ -542,6 +1081,7 @@ impl Wallet {
         let mut total = bitcoin::Amount::ZERO;
         let mut skipped_withdrawal = 0;
         let mut skipped_swap_pending = 0;
+        let mut skipped_htlc = 0;
         let mut skipped_locked = 0;
 
         for (outpoint_key, output) in &utxos {
@@ -550,6 +1090,8 @@ impl Wallet {
                 "swap_pending"
             } else if output.content.is_withdrawal() {
                 "withdrawal"
+            } else if output.content.is_htlc() {
+                "htlc"
             } else {
                 "value"
             };
@@ -567,6 +1109,11 @@ impl Wallet {
                 skipped_withdrawal += 1;
                 continue;
             }
+            // HTLCs can only be spent by redeeming or refunding them
+            if output.content.is_htlc() {
+                skipped_htlc += 1;
+                continue;
+            }
             // Filter out SwapPending outputs - they are locked and should only be spent in SwapClaim transactions
             if output.content.is_swap_pending() {
                 skipped_swap_pending += 1;
@@ -619,6 +1166,7 @@ impl Wallet {
             total_selected = %total,
             skipped_withdrawal = skipped_withdrawal,
             skipped_swap_pending = skipped_swap_pending,
+            skipped_htlc = skipped_htlc,
             skipped_locked = skipped_locked,
             "Coin selection completed"
         );
@@ -691,7 +1239,7 @@ impl Wallet {
                     .total
                     .checked_add(value)
                     .ok_or(AmountOverflowError)?;
-                if !utxo.content.is_withdrawal() {
+                if !utxo.content.is_withdrawal() && !utxo.content.is_htlc() {
                     balance.available = balance
                         .available
                         .checked_add(value)
@@ -743,22 +1291,34 @@ impl Wallet {
     ) -> Result<AuthorizedTransaction, Error> {
         let is_swap_claim =
             matches!(transaction.data, TxData::SwapClaim { .. });
+        let is_htlc_redeem =
+            matches!(transaction.data, TxData::HtlcRedeem { .. });
         let mut authorizations = Vec::with_capacity(transaction.inputs.len());
         for (outpoint, _) in &transaction.inputs {
             let key = OutPointKey::from(outpoint);
             loop {
-                let (spent_utxo, index) = {
+                let (spent_utxo, signer, index) = {
                     let txn = self.env.read_txn().map_err(EnvError::from)?;
                     let spent_utxo = self
                         .utxos
                         .try_get(&txn, &key)
                         .map_err(DbError::from)?
                         .ok_or(Error::NoUtxo)?;
+                    // An HTLC that is not being redeemed is being refunded,
+                    // which is signed for by its refund address
+                    let signer = match &spent_utxo.content {
+                        OutputContent::Htlc { refund_address, .. }
+                            if !is_htlc_redeem =>
+                        {
+                            *refund_address
+                        }
+                        _ => spent_utxo.address,
+                    };
                     let index = self
                         .address_to_index
-                        .try_get(&txn, &spent_utxo.address)
+                        .try_get(&txn, &signer)
                         .map_err(DbError::from)?;
-                    (spent_utxo, index)
+                    (spent_utxo, signer, index)
                 };
                 let index = match index {
                     Some(idx) => BigEndian::read_u32(&idx),
@@ -781,7 +1341,7 @@ impl Wallet {
                             });
                             break;
                         }
-                        self.ensure_address_indexed(&spent_utxo.address)?;
+                        self.ensure_address_indexed(&signer)?;
                         continue;
                     }
                 };
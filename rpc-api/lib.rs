@@ -2,13 +2,16 @@
 
 #![allow(clippy::too_many_arguments)]
 
-use std::net::SocketAddr;
+use std::{collections::HashMap, net::SocketAddr};
 
 use coinshift::{
+    l1_headers::L1Tip,
     net::Peer,
+    parent_chain_rpc::{L1ConfigEntry, L1Endpoint},
     types::{
         Address, MerkleRoot, OutPoint, Output, OutputContent, ParentChainType,
-        PointedOutput, Swap, SwapId, SwapState, Txid, WithdrawalBundle,
+        PointedOutput, QuoteFill, Swap, SwapFill, SwapId, SwapOffer, SwapQuote,
+        SwapReservation, SwapState, Txid, WithdrawalBundle,
         schema as coinshift_schema,
     },
     wallet::Balance,
@@ -19,8 +22,10 @@ use l2l_openapi::open_api;
 mod schema;
 
 #[open_api(ref_schemas[
-    Address, MerkleRoot, OutPoint, Output, OutputContent, ParentChainType,
-    Swap, SwapId, SwapState, Txid, schema::BitcoinTxid,
+    Address, L1Tip, MerkleRoot, OutPoint, Output, OutputContent,
+    ParentChainType, QuoteFill, Swap, SwapFill, SwapId, SwapOffer, SwapQuote,
+    SwapReservation, SwapState, Txid,
+    schema::BitcoinTxid,
     coinshift_schema::BitcoinAddr, coinshift_schema::BitcoinOutPoint,
 ])]
 #[rpc(client, server)]
@@ -197,8 +202,46 @@ pub trait Rpc {
         mainchain_fee_sats: u64,
     ) -> RpcResult<Txid>;
 
+    /// Lock funds in an HTLC paying `recipient`, who can redeem it by
+    /// revealing the SHA256 preimage of `payment_hash_hex` before
+    /// `refund_height`. From `refund_height` onwards, this wallet can refund
+    /// it with `refund_htlc`.
+    #[method(name = "create_htlc")]
+    async fn create_htlc(
+        &self,
+        recipient: Address,
+        value_sats: u64,
+        payment_hash_hex: String,
+        refund_height: u32,
+        fee_sats: u64,
+    ) -> RpcResult<Txid>;
+
+    /// Redeem an HTLC paid to this wallet, by revealing the 32-byte preimage
+    /// of its payment hash. Only possible before the HTLC's refund height.
+    #[method(name = "redeem_htlc")]
+    async fn redeem_htlc(
+        &self,
+        outpoint: OutPoint,
+        preimage_hex: String,
+        fee_sats: u64,
+    ) -> RpcResult<Txid>;
+
+    /// Refund an HTLC created by this wallet. Only possible from the HTLC's
+    /// refund height onwards.
+    #[method(name = "refund_htlc")]
+    async fn refund_htlc(
+        &self,
+        outpoint: OutPoint,
+        fee_sats: u64,
+    ) -> RpcResult<Txid>;
+
     /// Create a swap (L2 → L1)
     /// If l2_recipient is None, creates an open swap (anyone can fill it)
+    /// If min_fill_l1_amount_sats is set, the swap can be filled by several
+    /// L1 payments of at least that amount, each releasing a pro-rata share
+    /// of the L2 amount
+    /// expires_in_blocks is the number of L2 blocks until the swap expires;
+    /// None uses the parent chain's default
     #[open_api_method(output_schema(
         PartialSchema = "schema::Tuple<SwapId, Txid>"
     ))]
@@ -211,9 +254,90 @@ pub trait Rpc {
         l2_recipient: Option<Address>, // Optional - None = open swap
         l2_amount_sats: u64,
         required_confirmations: Option<u32>,
+        min_fill_l1_amount_sats: Option<u64>,
+        expires_in_blocks: Option<u32>,
+        fee_sats: u64,
+    ) -> RpcResult<(SwapId, Txid)>;
+
+    /// Create an L1 → L2 swap offer: offer L1 coins in exchange for L2 coins.
+    /// The L2 coins are paid to l2_recipient, or to a new wallet address if
+    /// None.
+    #[open_api_method(output_schema(
+        PartialSchema = "schema::Tuple<SwapId, Txid>"
+    ))]
+    #[method(name = "create_l1_to_l2_swap")]
+    async fn create_l1_to_l2_swap(
+        &self,
+        parent_chain: ParentChainType,
+        l1_amount_sats: u64,
+        l2_recipient: Option<Address>,
+        l2_amount_sats: u64,
+        required_confirmations: Option<u32>,
+        fee_sats: u64,
+    ) -> RpcResult<(SwapId, Txid)>;
+
+    /// Get the confirmations suggested for a swap of l1_amount_sats on
+    /// parent_chain: the chain's default, or more if the swap's size
+    /// requires it. Used by create_swap and create_l1_to_l2_swap if
+    /// required_confirmations is None.
+    #[method(name = "get_swap_confirmations")]
+    async fn get_swap_confirmations(
+        &self,
+        parent_chain: ParentChainType,
+        l1_amount_sats: u64,
+    ) -> RpcResult<u32>;
+
+    /// Create a swap (L2 → L1) by price: the L2 amount offered is
+    /// l1_amount_sats at price_sats L2 sats per L1 coin, rounded down.
+    /// Other arguments are as for create_swap.
+    #[open_api_method(output_schema(
+        PartialSchema = "schema::Tuple<SwapId, Txid>"
+    ))]
+    #[method(name = "create_swap_at_price")]
+    async fn create_swap_at_price(
+        &self,
+        parent_chain: ParentChainType,
+        l1_recipient_address: String,
+        l1_amount_sats: u64,
+        l2_recipient: Option<Address>, // Optional - None = open swap
+        price_sats: u64,
+        required_confirmations: Option<u32>,
+        min_fill_l1_amount_sats: Option<u64>,
+        expires_in_blocks: Option<u32>,
         fee_sats: u64,
     ) -> RpcResult<(SwapId, Txid)>;
 
+    /// List the open L2 → L1 swaps of parent_chain that can be filled in
+    /// the next block, best price (most L2 per L1 coin) first
+    #[open_api_method(output_schema(ToSchema))]
+    #[method(name = "list_swap_offers")]
+    async fn list_swap_offers(
+        &self,
+        parent_chain: ParentChainType,
+    ) -> RpcResult<Vec<SwapOffer>>;
+
+    /// Get the best fill of up to l1_amount_sats from the open swaps of
+    /// parent_chain: the swaps to pay, best price first, and the L1 amount
+    /// to pay and L2 amount released by each. Swaps that must be filled by
+    /// a single payment have to be reserved before they are paid.
+    #[open_api_method(output_schema(ToSchema))]
+    #[method(name = "quote_swap")]
+    async fn quote_swap(
+        &self,
+        parent_chain: ParentChainType,
+        l1_amount_sats: u64,
+    ) -> RpcResult<SwapQuote>;
+
+    /// Accept an L1 → L2 swap offer, locking the swap's L2 amount.
+    /// The swap creator then sends the L1 coins to l1_recipient_address.
+    #[method(name = "accept_swap")]
+    async fn accept_swap(
+        &self,
+        swap_id: SwapId,
+        l1_recipient_address: String,
+        fee_sats: u64,
+    ) -> RpcResult<Txid>;
+
     /// Reconstruct all swaps from the blockchain
     /// This is useful for recovering from database corruption or verifying swap integrity
     /// Returns the number of swaps reconstructed
@@ -231,6 +355,12 @@ pub trait Rpc {
         l2_claimer_address: Option<Address>,
     ) -> RpcResult<()>;
 
+    /// Get the L1 payments claimed for a partially fillable swap
+    #[open_api_method(output_schema(ToSchema))]
+    #[method(name = "get_swap_fills")]
+    async fn get_swap_fills(&self, swap_id: SwapId)
+    -> RpcResult<Vec<SwapFill>>;
+
     /// Get swap status
     #[open_api_method(output_schema(ToSchema))]
     #[method(name = "get_swap_status")]
@@ -239,11 +369,17 @@ pub trait Rpc {
 
     /// Claim a swap (after L1 transaction has required confirmations)
     /// For open swaps, l2_claimer_address is required (the claimer's L2 address)
+    /// For partially fillable swaps, claims the share of every confirmed,
+    /// unclaimed fill that was declared for the recipient
+    /// Claims prove their L1 fills with l1_proof_hex: a hex-encoded L1
+    /// inclusion proof, or for partially fillable swaps a list of them. If
+    /// it is omitted, the proofs are built from the node's L1 backend.
     #[method(name = "claim_swap")]
     async fn claim_swap(
         &self,
         swap_id: SwapId,
         l2_claimer_address: Option<Address>, // Required for open swaps
+        l1_proof_hex: Option<String>,
     ) -> RpcResult<Txid>;
 
     /// List all swaps
@@ -259,13 +395,74 @@ pub trait Rpc {
         recipient: Address,
     ) -> RpcResult<Vec<Swap>>;
 
-    /// Cancel a swap (unlock outputs and mark as cancelled).
+    /// Cancel a swap that we created, by sending a SwapCancel transaction.
+    /// Once included in a block, the swap's outputs are unlocked and the
+    /// swap is cancelled on every node.
     /// Only allowed for Pending swaps (before L1 transaction is detected).
     #[method(name = "cancel_swap")]
-    async fn cancel_swap(&self, swap_id: SwapId) -> RpcResult<()>;
+    async fn cancel_swap(
+        &self,
+        swap_id: SwapId,
+        fee_sats: u64,
+    ) -> RpcResult<Txid>;
+
+    /// Reserve an open swap, by sending a SwapReserve transaction that locks
+    /// a bond of 1% of the swap's L2 amount.
+    /// While the reservation is live, only the reserving taker's L1 payment
+    /// fills the swap. If it lapses unfilled, the bond goes to the swap
+    /// creator.
+    /// Defaults to a new wallet address as claimer, and to the longest
+    /// reservation allowed for the swap's parent chain.
+    #[method(name = "reserve_swap")]
+    async fn reserve_swap(
+        &self,
+        swap_id: SwapId,
+        l2_claimer_address: Option<Address>,
+        reserve_blocks: Option<u32>,
+        fee_sats: u64,
+    ) -> RpcResult<Txid>;
 
     /// Delete a swap from the database.
     /// Only allowed for Pending or Cancelled swaps.
     #[method(name = "delete_swap")]
     async fn delete_swap(&self, swap_id: SwapId) -> RpcResult<()>;
+
+    /// Get the tip of the L1 header chain for a parent chain
+    #[open_api_method(output_schema(ToSchema))]
+    #[method(name = "get_l1_header_tip")]
+    async fn get_l1_header_tip(
+        &self,
+        parent_chain: ParentChainType,
+    ) -> RpcResult<Option<L1Tip>>;
+
+    /// Get the L1 config entry of each parent chain, or of `parent_chain`
+    /// if set. Stored passwords are hidden.
+    #[open_api_method(output_schema(PartialSchema = "schema::L1Config"))]
+    #[method(name = "get_l1_config")]
+    async fn get_l1_config(
+        &self,
+        parent_chain: Option<ParentChainType>,
+    ) -> RpcResult<HashMap<ParentChainType, L1ConfigEntry>>;
+
+    /// Set the primary L1 endpoint of a parent chain, keeping its extra
+    /// endpoints. Fails if the endpoint does not serve the parent chain.
+    #[method(name = "set_l1_config")]
+    async fn set_l1_config(
+        &self,
+        parent_chain: ParentChainType,
+        #[open_api_method_arg(schema(PartialSchema = "schema::L1Endpoint"))]
+        endpoint: L1Endpoint,
+    ) -> RpcResult<()>;
+
+    /// Submit hex-encoded L1 headers for a parent chain, in ascending height
+    /// order, as a header relay transaction. The headers are committed to
+    /// the L1 header chain once the relay is included in a block. If the
+    /// header chain for the parent chain is empty, the first header must be
+    /// its anchor checkpoint.
+    #[method(name = "submit_l1_headers")]
+    async fn submit_l1_headers(
+        &self,
+        parent_chain: ParentChainType,
+        headers_hex: Vec<String>,
+    ) -> RpcResult<Txid>;
 }
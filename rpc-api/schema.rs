@@ -22,6 +22,38 @@ impl ToSchema for BitcoinTxid {
     }
 }
 
+/// L1 config file entries, by parent chain
+pub struct L1Config;
+
+impl PartialSchema for L1Config {
+    fn schema() -> RefOr<Schema> {
+        let obj = utoipa::openapi::Object::new();
+        RefOr::T(Schema::Object(obj))
+    }
+}
+
+/// L1 config file endpoint (see `coinshift::parent_chain_rpc::L1Endpoint`)
+pub struct L1Endpoint;
+
+impl PartialSchema for L1Endpoint {
+    fn schema() -> RefOr<Schema> {
+        let obj = utoipa::openapi::Object::builder()
+            .property(
+                "url",
+                utoipa::openapi::Object::with_type(openapi::Type::String),
+            )
+            .required("url")
+            .build();
+        RefOr::T(Schema::Object(obj))
+    }
+}
+
+impl ToSchema for L1Endpoint {
+    fn name() -> std::borrow::Cow<'static, str> {
+        std::borrow::Cow::Borrowed("L1Endpoint")
+    }
+}
+
 pub struct OpenApi;
 
 impl PartialSchema for OpenApi {